@@ -0,0 +1,442 @@
+//! armv6m.rs
+//!
+//! cortex-m0/m0+ emulation context
+//!
+//! armv6m is a subset of armv7m (see ARM DDI 0419), so the backend wraps
+//! an armv7m backend and restricts the system control space to what
+//! armv6m implements:
+//! - the nvic has at most 32 interrupts with 2-bit priorities, and no
+//!   priority grouping or active bit registers
+//! - VTOR and the MPU are optional
+//! - there is no BASEPRI or FAULTMASK, and no configurable faults, so
+//!   every fault is taken as HardFault
+//!
+//! the backend uses the same cortex language as armv7m, so the taint
+//! ArchPlugin for the exception frame is the same. instructions that are
+//! only defined by armv7m, and accesses to BASEPRI and FAULTMASK, are
+//! undefined and taken as HardFault.
+use fugue_ir::{
+    disassembly::IRBuilderArena,
+    VarnodeData,
+};
+use fugue_core::prelude::*;
+
+use crate::types::*;
+use crate::utils::*;
+use crate::peripheral::Peripheral;
+use crate::backend::{
+    self,
+    armv7m,
+    ThreadSwitch,
+    HaltReason,
+    ResetPolicy,
+    Backend as BackendTrait,
+    semihosting::Semihosting,
+};
+use armv7m::{SysCtrlConfig, SCRegType, NVICRegType, MPURegType};
+
+use super::mmap::*;
+
+/// userops that access BASEPRI or FAULTMASK
+const UNDEFINED_USEROPS: &[&str] = &[
+    "getBasePriority",
+    "setBasePriority",
+    "enableFIQinterrupts",
+    "disableFIQinterrupts",
+    "isFIQinterruptsEnabled",
+];
+
+/// CCR is read-only with UNALIGN_TRP and STKALIGN set (see B3.2.8)
+const CCR_RESET: u32 = 0x208;
+
+const SCS_BASE: u64 = 0xe000e000;
+
+/// the cortex-m0/m0+ execution context
+#[derive(Debug, Clone)]
+pub struct Backend {
+    inner: armv7m::Backend,
+    vtor_present: bool,
+    mpu_present: bool,
+    /// indices of the userops that are undefined on armv6m
+    undefined_userops: Vec<usize>,
+}
+
+impl Backend {
+
+    pub fn new_with(
+        builder: &LanguageBuilder,
+        scs_config: Option<SysCtrlConfig>,
+    ) -> Result<Self, backend::Error> {
+        let scs_config = scs_config.unwrap_or_default()
+            .with_reset(SCRegType::CCR, CCR_RESET);
+        let inner = armv7m::Backend::new_with(builder, Some(scs_config))?;
+        let undefined_userops = inner.lang().translator().user_ops().iter()
+            .enumerate()
+            .filter(|(_, name)| UNDEFINED_USEROPS.contains(&&**name))
+            .map(|(index, _)| index)
+            .collect();
+        Ok(Self { inner, vtor_present: false, mpu_present: false, undefined_userops })
+    }
+
+    /// implement VTOR (optional on cortex-m0+, absent on cortex-m0)
+    pub fn with_vtor(mut self, present: bool) -> Self {
+        self.vtor_present = present;
+        self
+    }
+
+    /// implement the MPU (optional on cortex-m0+, absent on cortex-m0)
+    pub fn with_mpu(mut self, present: bool) -> Self {
+        self.mpu_present = present;
+        self
+    }
+
+    pub fn vtor_present(&self) -> bool {
+        self.vtor_present
+    }
+
+    pub fn mpu_present(&self) -> bool {
+        self.mpu_present
+    }
+
+    /// the wrapped armv7m backend
+    pub fn inner(&self) -> &armv7m::Backend {
+        &self.inner
+    }
+}
+
+impl BackendTrait for Backend {
+    fn lang(&self) -> &Language {
+        self.inner.lang()
+    }
+
+    fn current_thread(&self) -> EmuThread {
+        self.inner.current_thread()
+    }
+
+    fn tick(&mut self) -> Result<(), backend::Error> {
+        self.inner.tick()
+    }
+
+    fn maybe_thread_switch(&mut self) -> Option<ThreadSwitch> {
+        self.inner.maybe_thread_switch()
+    }
+
+    fn process_events(&mut self) -> Result<(), backend::Error> {
+        self.inner.process_events()
+    }
+
+    fn fault(&mut self, cause: backend::FaultCause) -> Result<bool, backend::Error> {
+        // the configurable faults can't be enabled through SHCSR,
+        // so the armv7m backend escalates them to HardFault
+        self.inner.fault(cause)
+    }
+
+    fn check_fetch(&mut self, address: &Address) -> Result<(), backend::Error> {
+        self.inner.check_fetch(address)?;
+        if !self._insn_defined(address) {
+            return Err(backend::Error::UndefinedInsn(address.clone()));
+        }
+        Ok(())
+    }
+
+    fn traps_divide_by_zero(&self) -> bool {
+        self.inner.traps_divide_by_zero()
+    }
+
+    fn map_mem(&mut self, base: &Address, size: usize) -> Result<(), backend::Error> {
+        self.inner.map_mem(base, size)
+    }
+
+    fn mmap(&self) -> &MemoryMap {
+        self.inner.mmap()
+    }
+
+    fn map_mmio(&mut self, peripheral: Peripheral) -> Result<(), backend::Error> {
+        self.inner.map_mmio(peripheral)
+    }
+
+    fn fetch<'irb>(&mut self, address: &Address, arena: &'irb IRBuilderArena) -> LiftResult<'irb> {
+        self.inner.fetch(address, arena)
+    }
+
+    fn read(&mut self, vnd: &VarnodeData) -> Result<BitVec, backend::Error> {
+        if vnd.space().is_default() {
+            self.load(&Address::from(vnd.offset()), vnd.size())
+        } else {
+            self.inner.read(vnd)
+        }
+    }
+
+    fn write(&mut self, vnd: &VarnodeData, val: &BitVec) -> Result<(), backend::Error> {
+        if vnd.space().is_default() {
+            self.store(&Address::from(vnd.offset()), val)
+        } else {
+            self.inner.write(vnd, val)
+        }
+    }
+
+    fn read_pc(&self) -> Result<Address, backend::Error> {
+        self.inner.read_pc()
+    }
+
+    fn write_pc(&mut self, address: &Address) -> Result<(), backend::Error> {
+        self.inner.write_pc(address)
+    }
+
+    fn read_sp(&self) -> Result<Address, backend::Error> {
+        self.inner.read_sp()
+    }
+
+    fn write_sp(&mut self, address: &Address) -> Result<(), backend::Error> {
+        self.inner.write_sp(address)
+    }
+
+    fn load(&mut self, address: &Address, size: usize) -> Result<BitVec, backend::Error> {
+        let Some(masks) = self._scs_masks(address, size, Permission::R) else {
+            return self.inner.load(address, size);
+        };
+        let mut dst = vec![0u8; size];
+        if masks.iter().any(|mask| *mask != 0) {
+            self.inner.load(address, size)?.to_le_bytes(&mut dst);
+            _apply_masks(&mut dst, &masks);
+        }
+        Ok(BitVec::from_le_bytes(&dst))
+    }
+
+    fn store(&mut self, address: &Address, val: &BitVec) -> Result<(), backend::Error> {
+        let Some(masks) = self._scs_masks(address, val.bytes(), Permission::W) else {
+            return self.inner.store(address, val);
+        };
+        let mut src = vec![0u8; val.bytes()];
+        val.to_le_bytes(&mut src);
+        match self._merge_write(address, &src, &masks)? {
+            Some(src) => { self.inner.store(address, &BitVec::from_le_bytes(&src)) }
+            None => { Ok(()) }
+        }
+    }
+
+    fn load_bytes(&mut self, address: &Address, dst: &mut [u8]) -> Result<(), backend::Error> {
+        let Some(masks) = self._scs_masks(address, dst.len(), Permission::R) else {
+            return self.inner.load_bytes(address, dst);
+        };
+        if masks.iter().all(|mask| *mask == 0) {
+            dst.fill(0);
+            return Ok(());
+        }
+        self.inner.load_bytes(address, dst)?;
+        _apply_masks(dst, &masks);
+        Ok(())
+    }
+
+    fn store_bytes<'a>(&mut self, address: &Address, bytes: &'a [u8]) -> Result<(), backend::Error> {
+        let Some(masks) = self._scs_masks(address, bytes.len(), Permission::W) else {
+            return self.inner.store_bytes(address, bytes);
+        };
+        match self._merge_write(address, bytes, &masks)? {
+            Some(src) => { self.inner.store_bytes(address, &src) }
+            None => { Ok(()) }
+        }
+    }
+
+    fn userop(
+        &mut self,
+        output: Option<&VarnodeData>,
+        inputs: &[VarnodeData],
+    ) -> Result<Option<fugue_core::ir::Location>, backend::Error> {
+        let (index, _, _) = get_userop_params(output, inputs);
+        if self.undefined_userops.contains(&index) {
+            // FAULTMASK and BASEPRI are not implemented
+            debug!("undefined userop {index} on armv6m");
+            return Err(backend::Error::UndefinedInsn(self.read_pc()?));
+        }
+        self.inner.userop(output, inputs)
+    }
+
+    fn enabled_interrupts(&self) -> Vec<u32> {
+        self.inner.enabled_interrupts()
+    }
+
+    fn fire_interrupt(&mut self, int_num: u32) -> Result<(), backend::Error> {
+        self.inner.fire_interrupt(int_num)
+    }
+
+    fn interrupts_masked(&self) -> bool {
+        self.inner.interrupts_masked()
+    }
+
+    fn interrupts_possible(&self) -> bool {
+        self.inner.interrupts_possible()
+    }
+
+    fn is_sleeping(&self) -> bool {
+        self.inner.is_sleeping()
+    }
+
+    fn fast_forward(&mut self) -> Result<usize, backend::Error> {
+        self.inner.fast_forward()
+    }
+
+    fn halt_reason(&self) -> Option<HaltReason> {
+        self.inner.halt_reason()
+    }
+
+    fn attach_semihosting(&mut self, semihosting: Semihosting) -> Result<(), backend::Error> {
+        self.inner.attach_semihosting(semihosting)
+    }
+
+    fn take_input_writes(&mut self) -> Vec<VarnodeData> {
+        self.inner.take_input_writes()
+    }
+
+    fn set_reset_policy(&mut self, policy: ResetPolicy) {
+        self.inner.set_reset_policy(policy)
+    }
+
+    fn take_reset(&mut self) -> bool {
+        self.inner.take_reset()
+    }
+
+    fn snapshot(&mut self) -> backend::Snapshot {
+        // vtor and mpu presence are fixed, so the armv7m state is enough
+        self.inner.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &backend::Snapshot) -> Result<(), backend::Error> {
+        self.inner.restore(snapshot)
+    }
+}
+
+impl Backend {
+    /// false if the thumb instruction at the address is only defined by
+    /// armv7m. the only 32-bit instructions in armv6m are BL, MSR, MRS,
+    /// and the barriers, and there is no CBZ, CBNZ, or IT (see A5.2).
+    fn _insn_defined(&self, address: &Address) -> bool {
+        let halfword = |offset: u64| match self.mmap().mem_view_bytes(&(*address + offset), Some(2)) {
+            Ok(&[lo, hi]) => { Some(u16::from_le_bytes([lo, hi])) }
+            _ => { None }
+        };
+        let Some(hw1) = halfword(0) else {
+            // left to the fetch to fault
+            return true;
+        };
+        if (hw1 >> 11) < 0b11101 {
+            let cbz = (hw1 & 0xf500) == 0xb100;
+            // the hints share the IT encoding with a zero mask
+            let it = (hw1 & 0xff00) == 0xbf00 && (hw1 & 0xf) != 0;
+            return !cbz && !it;
+        }
+        let Some(hw2) = halfword(2) else {
+            return true;
+        };
+        if (hw1 & 0xf800) != 0xf000 || (hw2 & 0x8000) == 0 {
+            return false;
+        }
+        match hw2 & 0x5000 {
+            // BL
+            0x5000 => { true }
+            // MSR, MRS, DSB, DMB, ISB
+            0x0000 => {
+                matches!(hw1 & 0x7e0, 0x380 | 0x3e0) || (hw1 & 0x7f0) == 0x3b0
+            }
+            _ => { false }
+        }
+    }
+
+    /// the bits of an scs register word that armv6m implements, or None
+    /// if the register is the same as armv7m. a mask of 0 makes the
+    /// register RAZ/WI.
+    fn _scs_mask(&self, offset: usize, access: Permission) -> Option<u32> {
+        let reg = SCRegType::lookup_offset(offset & !0b11)?;
+        match reg {
+            // no PRIGROUP or VECTRESET
+            SCRegType::AIRCR => { Some(0xffff8006) }
+            SCRegType::VTOR if !self.vtor_present => { Some(0) }
+            SCRegType::CCR if access == Permission::W => { Some(0) }
+            SCRegType::SHPR2(_) => { Some(0xc0000000) }
+            SCRegType::SHPR3(_) => { Some(0xc0c00000) }
+            // only SVCALLPENDED
+            SCRegType::SHCSR => { Some(0x00008000) }
+            SCRegType::SHPR1(_)
+            | SCRegType::CFSR
+            | SCRegType::HFSR
+            | SCRegType::MMFAR
+            | SCRegType::BFAR
+            | SCRegType::AFSR
+            | SCRegType::CPACR
+            | SCRegType::FPCCR
+            | SCRegType::FPCAR
+            | SCRegType::FPDSCR
+            | SCRegType::MVFR0
+            | SCRegType::MVFR1
+            | SCRegType::MVFR2
+            | SCRegType::ICTR
+            | SCRegType::STIR => { Some(0) }
+            SCRegType::NVIC(nvic_reg) => {
+                match nvic_reg {
+                    NVICRegType::ISER(0)
+                    | NVICRegType::ICER(0)
+                    | NVICRegType::ISPR(0)
+                    | NVICRegType::ICPR(0) => { None }
+                    // 2-bit priorities for 32 interrupts
+                    NVICRegType::IPR(n) if n < 8 => { Some(0xc0c0c0c0) }
+                    _ => { Some(0) }
+                }
+            }
+            SCRegType::MPU(_) if !self.mpu_present => { Some(0) }
+            // no region alias registers
+            SCRegType::MPU(MPURegType::RBAR(1..=3) | MPURegType::RASR(1..=3)) => { Some(0) }
+            _ => { None }
+        }
+    }
+
+    /// per-byte masks for an scs access, or None if the access is
+    /// outside the scs or no byte of it is restricted
+    fn _scs_masks(&self, address: &Address, size: usize, access: Permission) -> Option<Vec<u8>> {
+        let start = address.offset();
+        if start < SCS_BASE || start + size as u64 > SCS_BASE + 0x1000 {
+            return None;
+        }
+        let start = (start - SCS_BASE) as usize;
+        let masks = (start..start + size)
+            .map(|offset| {
+                self._scs_mask(offset, access)
+                    .map(|mask| (mask >> (8 * (offset & 0b11))) as u8)
+            })
+            .collect::<Vec<_>>();
+        if masks.iter().all(Option::is_none) {
+            return None;
+        }
+        Some(masks.into_iter().map(|mask| mask.unwrap_or(0xff)).collect())
+    }
+
+    /// merge the implemented bits of an scs write with the current value,
+    /// returning None if the write is ignored entirely
+    fn _merge_write(&mut self,
+        address: &Address,
+        src: &[u8],
+        masks: &[u8],
+    ) -> Result<Option<Vec<u8>>, backend::Error> {
+        if masks.iter().all(|mask| *mask == 0) {
+            debug!("ignored write to unimplemented scs register @ {address:#x?}");
+            return Ok(None);
+        }
+        let mut merged = vec![0u8; src.len()];
+        if masks.iter().any(|mask| *mask != 0xff) {
+            self.inner.load_bytes(address, &mut merged)?;
+        }
+        for ((byte, new), mask) in merged.iter_mut().zip(src).zip(masks) {
+            *byte = (new & mask) | (*byte & !mask);
+        }
+        Ok(Some(merged))
+    }
+}
+
+fn _apply_masks(bytes: &mut [u8], masks: &[u8]) {
+    for (byte, mask) in bytes.iter_mut().zip(masks) {
+        *byte &= mask;
+    }
+}
+
+
+#[cfg(test)]
+mod tests;
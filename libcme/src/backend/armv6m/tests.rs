@@ -0,0 +1,97 @@
+//! tests.rs
+
+use super::*;
+use crate::backend;
+use armv7m::AIRCR;
+
+#[test]
+fn test_armv6m_scs() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?
+        .with_vtor(false)
+        .with_mpu(false);
+    backend.map_mem(&Address::from(0x0u64), 0x1000usize)?;
+    let scs_reg = |reg: SCRegType| Address::from(SCS_BASE + reg.offset() as u64);
+    let read_word = |backend: &mut Backend, address: Address| {
+        let mut word = [0u8; 4];
+        backend.load_bytes(&address, &mut word).map(|_| u32::from_le_bytes(word))
+    };
+
+    info!("checking priorities...");
+    let ipr0 = scs_reg(SCRegType::NVIC(NVICRegType::IPR(0)));
+    backend.store_bytes(&ipr0, &u32::to_le_bytes(0xffffffff))?;
+    assert_eq!(read_word(&mut backend, ipr0)?, 0xc0c0c0c0, "2-bit priorities");
+    let ipr8 = scs_reg(SCRegType::NVIC(NVICRegType::IPR(8)));
+    backend.store_bytes(&ipr8, &u32::to_le_bytes(0xffffffff))?;
+    assert_eq!(read_word(&mut backend, ipr8)?, 0, "at most 32 interrupts");
+    let shpr3 = Address::from(SCS_BASE + 0xd20);
+    backend.store(&shpr3, &BitVec::from_u32(0xffffffff, 32))?;
+    assert_eq!(read_word(&mut backend, shpr3)?, 0xc0c00000);
+
+    info!("checking AIRCR...");
+    let aircr = AIRCR::new().with_vectkey_stat(0x05fa).with_prigroup(5);
+    backend.store_bytes(&scs_reg(SCRegType::AIRCR), &aircr.into_bits().to_le_bytes())?;
+    assert_eq!(read_word(&mut backend, scs_reg(SCRegType::AIRCR))?, 0xfa050000, "no PRIGROUP");
+
+    info!("checking optional and fixed registers...");
+    backend.store_bytes(&scs_reg(SCRegType::VTOR), &u32::to_le_bytes(0x800))?;
+    assert_eq!(read_word(&mut backend, scs_reg(SCRegType::VTOR))?, 0, "no VTOR");
+    backend.store_bytes(&scs_reg(SCRegType::CCR), &u32::to_le_bytes(0))?;
+    assert_eq!(read_word(&mut backend, scs_reg(SCRegType::CCR))?, CCR_RESET, "CCR read-only");
+    assert_eq!(read_word(&mut backend, scs_reg(SCRegType::MPU(MPURegType::TYPE)))?, 0, "no MPU");
+    assert_eq!(read_word(&mut backend, scs_reg(SCRegType::CFSR))?, 0);
+
+    info!("checking interrupt enables...");
+    let iser0 = scs_reg(SCRegType::NVIC(NVICRegType::ISER(0)));
+    let iser1 = scs_reg(SCRegType::NVIC(NVICRegType::ISER(1)));
+    backend.store_bytes(&iser0, &u32::to_le_bytes(0b1000))?;
+    backend.store_bytes(&iser1, &u32::to_le_bytes(0b1))?;
+    backend.process_events()?;
+    assert_eq!(backend.enabled_interrupts(), vec![3]);
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_armv6m_undefined_insns() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x1000usize)?;
+    let insn = Address::from(0x40u64);
+
+    info!("checking armv7m-only instructions...");
+    for (bytes, name) in [
+        (u32::to_le_bytes(0xf0f2fb91), "sdiv r0, r1, r2"),
+        (u32::to_le_bytes(0x0f00e851), "ldrex r0, [r1]"),
+        (u32::to_le_bytes(0x0000f240), "movw r0, #0"),
+        (u32::to_le_bytes(0x0000b100), "cbz r0, 0x44"),
+        (u32::to_le_bytes(0x0000bf08), "it eq"),
+    ] {
+        backend.store_bytes(&insn, &bytes)?;
+        assert!(matches!(backend.check_fetch(&insn), Err(backend::Error::UndefinedInsn(_))), "{name}");
+    }
+
+    info!("checking armv6m instructions...");
+    for (bytes, name) in [
+        (u32::to_le_bytes(0xf800f000), "bl 0x44"),
+        (u32::to_le_bytes(0x8811f381), "msr BASEPRI, r1"),
+        (u32::to_le_bytes(0x8f4ff3bf), "dsb sy"),
+        (u32::to_le_bytes(0x0000bf30), "wfi"),
+    ] {
+        backend.store_bytes(&insn, &bytes)?;
+        backend.check_fetch(&insn).unwrap_or_else(|err| panic!("{name}: {err}"));
+    }
+
+    info!("checking undefined instructions fault...");
+    assert!(backend.fault(backend::FaultCause::UndefinedInsn)?);
+
+    info!("done.");
+    Ok(())
+}
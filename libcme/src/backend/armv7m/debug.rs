@@ -0,0 +1,225 @@
+//! debug.rs
+//!
+//! debug event handling
+//!
+//! debug events halt the processor if halting debug is enabled
+//! (DHCSR.C_DEBUGEN), otherwise they are taken as a DebugMonitor
+//! exception if it is enabled and has sufficient priority.
+//! a breakpoint that can't be taken either way escalates to HardFault,
+//! and other debug events are ignored (see C1.4).
+use crate::backend::{self, HaltReason};
+use super::*;
+
+impl Backend {
+    /// true if the address is in the dwt register block
+    pub(crate) fn _is_dwt_region(&self, address: &Address, size: usize) -> bool {
+        let start = address.offset();
+        start >= DWT_BASE as u64 && start + size as u64 <= (DWT_BASE + 0x1000) as u64
+    }
+
+    /// true if the address is in the fpb register block
+    pub(crate) fn _is_fpb_region(&self, address: &Address, size: usize) -> bool {
+        let start = address.offset();
+        start >= FPB_BASE as u64 && start + size as u64 <= (FPB_BASE + 0x1000) as u64
+    }
+
+    /// generate a breakpoint error if the fpb matches the instruction address
+    pub(crate) fn _check_breakpoint(&mut self, address: &Address) -> Result<(), backend::Error> {
+        if std::mem::take(&mut self.step_over_breakpoint) {
+            return Ok(());
+        }
+        if self.fpb.breakpoint(address.offset() as u32) {
+            info!("fpb breakpoint @ {address:#x?}");
+            return Err(backend::Error::Breakpoint(address.clone()));
+        }
+        Ok(())
+    }
+
+    /// generate a debug event if a dwt instruction address comparator matches
+    pub(crate) fn _check_insn_watchpoint(&mut self, address: &Address) {
+        if !self.scs.debug_regs().get_demcr().trcena() {
+            return;
+        }
+        if let Some(n) = self.dwt.match_insn(address.offset() as u32) {
+            info!("dwt comparator {n} matched pc @ {address:#x?}");
+            self.events.push_back(Event::Debug(DebugEvent::Watchpoint(n)));
+        }
+    }
+
+    /// generate a debug event if a dwt data address comparator matches
+    pub(crate) fn _check_watchpoint(&mut self,
+        address: &Address,
+        size: usize,
+        access: Permission,
+    ) {
+        if !self.scs.debug_regs().get_demcr().trcena() {
+            return;
+        }
+        if let Some(n) = self.dwt.match_data(address.offset() as u32, size, access) {
+            info!("dwt comparator {n} matched {access:?} @ {address:#x?}");
+            self.events.push_back(Event::Debug(DebugEvent::Watchpoint(n)));
+        }
+    }
+
+    /// advance the dwt cycle counter
+    pub(crate) fn _tick_dwt(&mut self) {
+        if !self.scs.debug_regs().get_demcr().trcena() {
+            return;
+        }
+        if let Some(n) = self.dwt.tick() {
+            info!("dwt comparator {n} matched cycle count");
+            self.events.push_back(Event::Debug(DebugEvent::Watchpoint(n)));
+        }
+    }
+
+    /// handle a debug event
+    pub(crate) fn _debug_event(&mut self, evt: DebugEvent) -> Result<(), super::Error> {
+        if let DebugEvent::RegisterTransfer(dcrsr) = evt {
+            return self._register_transfer(dcrsr);
+        }
+        let dfsr = self.scs.get_dfsr_mut();
+        match &evt {
+            DebugEvent::RegisterTransfer(_) => { }
+            DebugEvent::HaltRequest => { dfsr.set_halted(true); }
+            DebugEvent::Breakpoint(_) => { dfsr.set_bkpt(true); }
+            DebugEvent::Watchpoint(_) => { dfsr.set_dwttrap(true); }
+        }
+
+        if self.scs.debug_regs().get_dhcsr().c_debugen() {
+            info!("entering debug state on {evt:?}");
+            let mut dbgregs = self.scs.debug_regs();
+            let dhcsr = dbgregs.get_dhcsr_mut();
+            dhcsr.set_c_halt(true);
+            dhcsr.set_s_halt(true);
+            self._halt(HaltReason::Debug);
+        } else if self._debug_monitor_ready() {
+            info!("DebugMonitor on {evt:?}");
+            self._pend_debug_monitor();
+        } else if let DebugEvent::Breakpoint(_) = evt {
+            // a breakpoint that can't be taken escalates
+            self.raise_fault(HardFault::DebugEvent.into(), None)?;
+        } else {
+            debug!("ignored {evt:?}");
+        }
+        Ok(())
+    }
+
+    /// handle a BKPT instruction that isn't a semihosting call
+    /// 
+    /// without a debugger or debug monitor to take it, the breakpoint
+    /// halts the processor rather than escalating to HardFault, so that
+    /// it's reported as a distinct halt reason.
+    pub(crate) fn _breakpoint_insn(&mut self, imm: u32) -> Result<(), super::Error> {
+        self.scs.get_dfsr_mut().set_bkpt(true);
+        if self.scs.debug_regs().get_dhcsr().c_debugen() {
+            let pc = self.read_pc()
+                .map_err(|_| super::Error::System("failed to read pc"))?;
+            return self._debug_event(DebugEvent::Breakpoint(pc.offset() as u32));
+        } else if self._debug_monitor_ready() {
+            info!("DebugMonitor on bkpt #{imm:#x}");
+            self._pend_debug_monitor();
+        } else {
+            info!("halted on bkpt #{imm:#x}");
+            self._halt(HaltReason::Breakpoint(imm));
+        }
+        Ok(())
+    }
+
+    /// stop executing instructions
+    pub(crate) fn _halt(&mut self, reason: HaltReason) {
+        self.status = Status::Halted;
+        self.halt_reason = Some(reason);
+    }
+
+    /// true if a debug event would be taken as a DebugMonitor exception
+    fn _debug_monitor_ready(&mut self) -> bool {
+        let monitor = ExceptionType::DebugMonitor;
+        let mon_en = self.scs.debug_regs().get_demcr().mon_en();
+        mon_en && self.scs.get_exception_priority(monitor).0 < self.current_priority()
+    }
+
+    fn _pend_debug_monitor(&mut self) {
+        let monitor = ExceptionType::DebugMonitor;
+        if !self.scs.exceptions.pending().contains(&monitor) {
+            self.scs.set_exception_pending(monitor);
+        }
+        self.scs.debug_regs().get_demcr_mut().set_mon_pend(true);
+    }
+
+    /// transfer a core register to or from DCRDR (see C1.6.3)
+    fn _register_transfer(&mut self, dcrsr: DCRSR) -> Result<(), super::Error> {
+        if !self.scs.debug_regs().get_debug_state() {
+            warn!("core register transfer outside debug state ignored");
+            return Ok(());
+        }
+        let regsel = dcrsr.regsel();
+        let name = match regsel {
+            0..=12 => { Some(format!("r{regsel}")) }
+            13 => { Some("sp".to_string()) }
+            14 => { Some("lr".to_string()) }
+            15 => { Some("pc".to_string()) }
+            33 => { Some("fpscr".to_string()) }
+            64..=95 => { Some(format!("s{}", regsel - 64)) }
+            _ => { None }
+        };
+        let vnd = name.and_then(|name| self.lang.translator().register_by_name(&name));
+        let write_val = self.scs.debug_regs().get_dcrdr().dbgtmp();
+
+        let read_val = match (regsel, vnd) {
+            (_, Some(vnd)) => {
+                if dcrsr.regwnr() {
+                    let val = BitVec::from_u32(write_val, vnd.bits());
+                    self.regs.write_val_with(vnd.offset() as usize, &val, self.endian)
+                        .map_err(|_| super::Error::System("failed to write register"))?;
+                    None
+                } else {
+                    self.regs.read_val_with(vnd.offset() as usize, vnd.size(), self.endian)
+                        .ok()
+                        .and_then(|val| val.to_u32())
+                }
+            }
+            (17, None) => {
+                if dcrsr.regwnr() {
+                    self.set_main_sp(&Address::from(write_val))?;
+                    None
+                } else {
+                    Some(self.get_main_sp()?.offset() as u32)
+                }
+            }
+            (18, None) => {
+                if dcrsr.regwnr() {
+                    self.set_proc_sp(&Address::from(write_val))?;
+                    None
+                } else {
+                    Some(self.get_proc_sp()?.offset() as u32)
+                }
+            }
+            (20, None) => {
+                // CONTROL[31:24], FAULTMASK[23:16], BASEPRI[15:8], PRIMASK[7:0]
+                if dcrsr.regwnr() {
+                    self.control = super::system::CONTROL::from_bits((write_val >> 24) & 0b111);
+                    self.faultmask.set_fm((write_val >> 16) & 1 != 0);
+                    self.basepri.set_basepri((write_val >> 8) as u8);
+                    self.primask.set_pm(write_val & 1 != 0);
+                    None
+                } else {
+                    Some((self.control.into_bits() << 24)
+                        | ((self.faultmask.fm() as u32) << 16)
+                        | ((self.basepri.basepri() as u32) << 8)
+                        | (self.primask.pm() as u32))
+                }
+            }
+            _ => {
+                warn!("core register transfer of regsel {regsel:#x} unsupported");
+                None
+            }
+        };
+
+        let mut dbgregs = self.scs.debug_regs();
+        if let Some(val) = read_val {
+            dbgregs.get_dcrdr_mut().set_dbgtmp(val);
+        }
+        dbgregs.get_dhcsr_mut().set_s_regrdy(true);
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+//! dwt.rs
+//!
+//! data watchpoint and trace unit
+//!
+//! implements the cycle counter and the comparators used for instruction
+//! address, data address, and cycle count watchpoints. trace packets,
+//! external triggers, pc sampling, and the profiling counters are not
+//! implemented, which DWT_CTRL reports through its NO* bits.
+//!
+//! the dwt is only active while DEMCR.TRCENA is set (see C1.8)
+use bitfield_struct::bitfield;
+
+use crate::backend;
+use super::*;
+
+/// dwt base address
+pub const DWT_BASE: u32 = 0xe0001000;
+/// number of implemented comparators
+const DWT_NUMCOMP: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DWTRegType {
+    /// control register
+    CTRL,
+    /// cycle count register
+    CYCCNT,
+    /// cpi count register
+    CPICNT,
+    /// exception overhead count register
+    EXCCNT,
+    /// sleep count register
+    SLEEPCNT,
+    /// lsu count register
+    LSUCNT,
+    /// folded-instruction count register
+    FOLDCNT,
+    /// program counter sample register
+    PCSR,
+    /// comparator register n
+    COMP(u8),
+    /// comparator mask register n
+    MASK(u8),
+    /// comparator function register n
+    FUNCTION(u8),
+}
+
+impl DWTRegType {
+    /// lookup register corresponding to given byte offset from the dwt base
+    pub fn lookup_offset(offset: usize) -> Option<Self> {
+        match offset {
+            0x000 => { Some(DWTRegType::CTRL) }
+            0x004 => { Some(DWTRegType::CYCCNT) }
+            0x008 => { Some(DWTRegType::CPICNT) }
+            0x00c => { Some(DWTRegType::EXCCNT) }
+            0x010 => { Some(DWTRegType::SLEEPCNT) }
+            0x014 => { Some(DWTRegType::LSUCNT) }
+            0x018 => { Some(DWTRegType::FOLDCNT) }
+            0x01c => { Some(DWTRegType::PCSR) }
+            0x020..=0x0fc if offset & 0b11 == 0 => {
+                let n = ((offset - 0x020) / 0x10) as u8;
+                match (offset - 0x020) % 0x10 {
+                    0x0 => { Some(DWTRegType::COMP(n)) }
+                    0x4 => { Some(DWTRegType::MASK(n)) }
+                    0x8 => { Some(DWTRegType::FUNCTION(n)) }
+                    _ => { None /* Reserved. */ }
+                }
+            }
+            _ => { None }
+        }
+    }
+
+    /// returns the register's address
+    pub fn address(&self) -> Address {
+        (DWT_BASE + self.offset() as u32).into()
+    }
+
+    /// returns the byte offset from the dwt base of the register type
+    pub fn offset(&self) -> usize {
+        match self {
+            DWTRegType::CTRL        => { 0x000 }
+            DWTRegType::CYCCNT      => { 0x004 }
+            DWTRegType::CPICNT      => { 0x008 }
+            DWTRegType::EXCCNT      => { 0x00c }
+            DWTRegType::SLEEPCNT    => { 0x010 }
+            DWTRegType::LSUCNT      => { 0x014 }
+            DWTRegType::FOLDCNT     => { 0x018 }
+            DWTRegType::PCSR        => { 0x01c }
+            DWTRegType::COMP(n)     => { 0x020 + (0x10 * *n as usize) }
+            DWTRegType::MASK(n)     => { 0x024 + (0x10 * *n as usize) }
+            DWTRegType::FUNCTION(n) => { 0x028 + (0x10 * *n as usize) }
+        }
+    }
+}
+
+/// provides information about the dwt and controls its features.
+///
+/// see C1.8.7
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+#[allow(nonstandard_style)]
+pub struct DWT_CTRL {
+    /// enables the cycle counter
+    #[bits(1)]
+    pub cyccntena: bool,
+    /// reload value for the POSTCNT counter
+    #[bits(4)]
+    pub postpreset: u8,
+    /// initial value for the POSTCNT counter
+    #[bits(4)]
+    pub postinit: u8,
+    /// selects the CYCCNT tap bit for POSTCNT
+    #[bits(1)]
+    pub cyctap: bool,
+    /// selects the CYCCNT tap bit for synchronization packets
+    #[bits(2)]
+    pub synctap: u8,
+    /// enables use of POSTCNT as a timer for periodic pc sample packets
+    #[bits(1)]
+    pub pcsamplena: bool,
+    #[bits(3)]
+    __: u8,
+    /// enables exception trace
+    #[bits(1)]
+    pub exctrcena: bool,
+    /// enables the CPICNT overflow event
+    #[bits(1)]
+    pub cpievtena: bool,
+    /// enables the EXCCNT overflow event
+    #[bits(1)]
+    pub excevtena: bool,
+    /// enables the SLEEPCNT overflow event
+    #[bits(1)]
+    pub sleepevtena: bool,
+    /// enables the LSUCNT overflow event
+    #[bits(1)]
+    pub lsuevtena: bool,
+    /// enables the FOLDCNT overflow event
+    #[bits(1)]
+    pub foldevtena: bool,
+    /// enables the POSTCNT underflow event
+    #[bits(1)]
+    pub cycevtena: bool,
+    #[bits(1)]
+    __: bool,
+    /// profiling counters not supported. read-only.
+    #[bits(1)]
+    pub noprfcnt: bool,
+    /// cycle counter not supported. read-only.
+    #[bits(1)]
+    pub nocyccnt: bool,
+    /// external match signals not supported. read-only.
+    #[bits(1)]
+    pub noexttrig: bool,
+    /// trace sampling and exception tracing not supported. read-only.
+    #[bits(1)]
+    pub notrcpkt: bool,
+    /// number of comparators implemented. read-only.
+    #[bits(4)]
+    pub numcomp: u8,
+}
+
+impl DWT_CTRL {
+    /// the bits that can be written
+    const WRITE_MASK: u32 = 0x007f_1fff;
+}
+
+/// controls the operation of comparator n.
+///
+/// see C1.8.17
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+#[allow(nonstandard_style)]
+pub struct DWT_FUNCTION {
+    /// selects the action taken on a comparator match
+    /// - 0b0100: watchpoint on instruction address (or cycle count if CYCMATCH)
+    /// - 0b0101: watchpoint on data read
+    /// - 0b0110: watchpoint on data write
+    /// - 0b0111: watchpoint on data read or write
+    /// other values select trace and etm functions, which aren't implemented.
+    #[bits(4)]
+    pub function: u8,
+    #[bits(1)]
+    __: bool,
+    /// emit data trace address offset packets
+    #[bits(1)]
+    pub emitrange: bool,
+    #[bits(1)]
+    __: bool,
+    /// compare against the cycle counter instead of an address.
+    /// only supported by comparator 0.
+    #[bits(1)]
+    pub cycmatch: bool,
+    /// compare against a data value instead of an address
+    #[bits(1)]
+    pub datavmatch: bool,
+    /// a second comparator is linked for data value matching
+    #[bits(1)]
+    pub lnk1ena: bool,
+    /// size of the data value for data value matching
+    #[bits(2)]
+    pub datavsize: u8,
+    /// comparator used for linked address comparison
+    #[bits(4)]
+    pub datavaddr0: u8,
+    /// second comparator used for linked address comparison
+    #[bits(4)]
+    pub datavaddr1: u8,
+    #[bits(4)]
+    __: u8,
+    /// the comparator matched since the register was last read.
+    /// cleared to 0 on read. read-only.
+    #[bits(1)]
+    pub matched: bool,
+    #[bits(7)]
+    __: u8,
+}
+
+impl DWT_FUNCTION {
+    /// the bits that can be written
+    const WRITE_MASK: u32 = 0x000f_fdaf;
+}
+
+/// a dwt comparator and its mask and function
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DWTComparator {
+    /// reference value for the comparison
+    pub comp: u32,
+    /// number of low address bits ignored by the comparison
+    pub mask: u32,
+    pub function: DWT_FUNCTION,
+}
+
+impl DWTComparator {
+    /// true if the comparator address falls within [address, address + size)
+    /// with the masked bits ignored
+    pub fn matches(&self, address: u32, size: usize) -> bool {
+        let ignored = ((1u64 << self.mask.min(31)) - 1) as u32;
+        let comp = self.comp & !ignored;
+        let start = address & !ignored;
+        let end = address.wrapping_add(size.max(1) as u32 - 1) & !ignored;
+        start <= comp && comp <= end
+    }
+}
+
+/// data watchpoint and trace unit state
+#[derive(Debug, Clone)]
+pub struct DWT {
+    pub ctrl: DWT_CTRL,
+    pub cyccnt: u32,
+    pub comparators: Vec<DWTComparator>,
+}
+
+impl Default for DWT {
+    fn default() -> Self {
+        let ctrl = DWT_CTRL::new()
+            .with_numcomp(DWT_NUMCOMP)
+            .with_notrcpkt(true)
+            .with_noexttrig(true)
+            .with_noprfcnt(true);
+        let comparators = vec![DWTComparator::default(); DWT_NUMCOMP as usize];
+        Self { ctrl, cyccnt: 0, comparators }
+    }
+}
+
+impl DWT {
+    /// increment the cycle counter if enabled,
+    /// returns the comparator that matched the new count if any
+    pub fn tick(&mut self) -> Option<u8> {
+        if !self.ctrl.cyccntena() {
+            return None;
+        }
+        self.cyccnt = self.cyccnt.wrapping_add(1);
+        let cyccnt = self.cyccnt;
+        self._match(|comparator| {
+            comparator.function.cycmatch() && comparator.comp == cyccnt
+        })
+    }
+
+    /// cycles until a cycle count comparator matches,
+    /// or None if the counter is disabled or no comparator is set
+    pub fn cycles_to_match(&self) -> Option<usize> {
+        if !self.ctrl.cyccntena() {
+            return None;
+        }
+        self.comparators.iter()
+            .filter(|comparator| {
+                comparator.function.function() == 0b0100 && comparator.function.cycmatch()
+            })
+            .map(|comparator| match comparator.comp.wrapping_sub(self.cyccnt) {
+                // the count matches again after wrapping around
+                0 => { 1usize << 32 }
+                cycles => { cycles as usize }
+            })
+            .min()
+    }
+
+    /// advance the cycle counter by the given number of cycles,
+    /// which must end before the next match
+    pub fn skip(&mut self, cycles: usize) {
+        if self.ctrl.cyccntena() {
+            self.cyccnt = self.cyccnt.wrapping_add(cycles as u32);
+        }
+    }
+
+    /// check the instruction address watchpoints,
+    /// returns the comparator that matched if any
+    pub fn match_insn(&mut self, address: u32) -> Option<u8> {
+        self._match(|comparator| {
+            !comparator.function.cycmatch() && comparator.matches(address, 2)
+        })
+    }
+
+    /// check the data address watchpoints for an access,
+    /// returns the comparator that matched if any
+    pub fn match_data(&mut self, address: u32, size: usize, access: Permission) -> Option<u8> {
+        let function = match access {
+            Permission::R => { 0b0101 }
+            Permission::W => { 0b0110 }
+            _ => { return None; }
+        };
+        let mut matched = None;
+        for (n, comparator) in self.comparators.iter_mut().enumerate() {
+            let watch = comparator.function.function();
+            if (watch == function || watch == 0b0111)
+                && !comparator.function.datavmatch()
+                && comparator.matches(address, size)
+            {
+                comparator.function.set_matched(true);
+                matched = matched.or(Some(n as u8));
+            }
+        }
+        matched
+    }
+
+    /// perform an event-triggering read of dwt register bytes
+    pub fn read_bytes(&mut self,
+        offset: usize,
+        dst: &mut [u8],
+        _events: &mut VecDeque<Event>,
+    ) -> Result<(), backend::Error> {
+        let address = DWT_BASE + offset as u32;
+        check_alignment(address, dst.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let val = match DWTRegType::lookup_offset(offset) {
+            Some(DWTRegType::CTRL) => { self.ctrl.into_bits() }
+            Some(DWTRegType::CYCCNT) => { self.cyccnt }
+            // pc sampling isn't supported
+            Some(DWTRegType::PCSR) => { 0xffffffff }
+            Some(DWTRegType::COMP(n)) if n < DWT_NUMCOMP => {
+                self.comparators[n as usize].comp
+            }
+            Some(DWTRegType::MASK(n)) if n < DWT_NUMCOMP => {
+                self.comparators[n as usize].mask
+            }
+            Some(DWTRegType::FUNCTION(n)) if n < DWT_NUMCOMP => {
+                // matched is cleared on read
+                let function = &mut self.comparators[n as usize].function;
+                let val = function.into_bits();
+                function.set_matched(false);
+                val
+            }
+            // profiling counters and unimplemented comparators are RAZ/WI
+            _ => { 0 }
+        };
+        dst.copy_from_slice(&val.to_le_bytes());
+        Ok(())
+    }
+
+    /// perform an event-triggering write of dwt register bytes
+    pub fn write_bytes(&mut self,
+        offset: usize,
+        src: &[u8],
+        _events: &mut VecDeque<Event>,
+    ) -> Result<(), backend::Error> {
+        let address = DWT_BASE + offset as u32;
+        check_alignment(address, src.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let write_val = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        match DWTRegType::lookup_offset(offset) {
+            Some(DWTRegType::CTRL) => {
+                let ro_bits = self.ctrl.into_bits() & !DWT_CTRL::WRITE_MASK;
+                self.ctrl = DWT_CTRL::from_bits(ro_bits | (write_val & DWT_CTRL::WRITE_MASK));
+            }
+            Some(DWTRegType::CYCCNT) => {
+                self.cyccnt = write_val;
+            }
+            Some(DWTRegType::PCSR) => {
+                let err = Error::WriteAccessViolation(address.into());
+                return Err(backend::Error::from(err).into());
+            }
+            Some(DWTRegType::COMP(n)) if n < DWT_NUMCOMP => {
+                self.comparators[n as usize].comp = write_val;
+            }
+            Some(DWTRegType::MASK(n)) if n < DWT_NUMCOMP => {
+                self.comparators[n as usize].mask = write_val & 0x1f;
+            }
+            Some(DWTRegType::FUNCTION(n)) if n < DWT_NUMCOMP => {
+                let mut function = DWT_FUNCTION::from_bits(write_val & DWT_FUNCTION::WRITE_MASK);
+                if function.cycmatch() && n != 0 {
+                    warn!("cycle count matching only supported by comparator 0");
+                    function.set_cycmatch(false);
+                }
+                if function.datavmatch() {
+                    warn!("data value matching not supported");
+                }
+                let comparator = &mut self.comparators[n as usize];
+                function.set_matched(comparator.function.matched());
+                comparator.function = function;
+            }
+            _ => { }
+        }
+        Ok(())
+    }
+
+    fn _match(&mut self, pred: impl Fn(&DWTComparator) -> bool) -> Option<u8> {
+        let mut matched = None;
+        for (n, comparator) in self.comparators.iter_mut().enumerate() {
+            if comparator.function.function() == 0b0100 && pred(comparator) {
+                comparator.function.set_matched(true);
+                matched = matched.or(Some(n as u8));
+            }
+        }
+        matched
+    }
+}
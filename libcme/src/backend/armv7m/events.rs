@@ -59,7 +59,7 @@ pub enum Event {
     // special events
     SEVInstructionExecuted, // the execution of a SEV instruction on any processor in the multiprocessor system
 
-    // debug event placeholder
+    // DHCSR, DCRSR, DEMCR, and the dwt and fpb
     Debug(DebugEvent),
 
     // peripheral events
@@ -82,6 +82,10 @@ impl Backend {
         debug!("handling {evt:?}");
         match evt {
             Event::SetProcessorStatus(status) => {
+                if self.status == Status::Halted && status == Status::Alive {
+                    // resume from debug state
+                    self.step_over_breakpoint = true;
+                }
                 self.status = status;
                 Ok(())
             }
@@ -95,6 +99,12 @@ impl Backend {
             }
             Event::ExceptionSetPending(exception_type, val) => {
                 if val {
+                    if self.scs.get_scr().sevonpend()
+                        && !self.scs.exceptions.pending().contains(&exception_type)
+                    {
+                        // transitions to pending are wakeup events (B1.5.18)
+                        self.event.0 = true;
+                    }
                     self.scs.set_exception_pending(exception_type);
                 } else {
                     self.scs.clr_exception_pending(exception_type);
@@ -118,22 +128,33 @@ impl Backend {
                 // to ignore this event
                 Ok(())
             }
-            Event::ExternSysResetRequest => {
-                let err = super::Error::System("extern system reset").into();
-                let msg = "system reset unsupported";
-                Err(backend::Error::Arch(msg, Arc::new(err)))
-            }
-            Event::LocalSysResetRequest => {
-                let err = super::Error::System("local system reset").into();
-                let msg = "system reset unsupported";
-                Err(backend::Error::Arch(msg, Arc::new(err)))
+            Event::ExternSysResetRequest
+            | Event::LocalSysResetRequest => {
+                match self.reset_policy {
+                    ResetPolicy::Halt => {
+                        info!("halted on {evt:?}");
+                        self._halt(HaltReason::Reset);
+                    }
+                    ResetPolicy::Reset => {
+                        info!("reset on {evt:?}");
+                        self._take_reset()?;
+                        self.reset_taken = true;
+                    }
+                }
+                Ok(())
             }
             Event::ExceptionClrAllActive => {
-                // TODO: this must also clear the IPSR
-                todo!()
+                let active = self.scs.exceptions.active().to_vec();
+                for typ in active {
+                    self.scs.clr_exception_active(typ);
+                }
+                self.xpsr.ipsr_mut().set_exception_number(0);
+                self.mode = Mode::Thread;
+                Ok(())
             }
             Event::VectorKeyWrite => {
-                todo!()
+                // AIRCR writes are already gated on the vector key
+                Ok(())
             }
             Event::SetPriorityGrouping(group) => {
                 // right we rely on prigroup register itself for getting
@@ -142,53 +163,47 @@ impl Backend {
                 Ok(())
             }
             Event::SetTransitionWakupEvent(val) => {
-                todo!()
+                // SEVONPEND is read from the SCR when an exception is pended
+                Ok(())
             }
             Event::SetDeepSleep(val) => {
-                todo!()
+                // deep sleep is the same as sleep since clocks aren't gated
+                Ok(())
             }
             Event::SetSleepOnExit(val) => {
-                todo!()
-            }
-            Event::ThreadModeExceptionsEnabled(val) => {
-                todo!()
-            }
-            Event::STIRUnprivilegedAccessAllowed(val) => {
-                todo!()
-            }
-            Event::UnalignedAccessTrapEnabled(val) => {
-                todo!()
-            }
-            Event::DivideByZeroTrapEnabled(val) => {
-                todo!()
-            }
-            Event::PreciseDataAccessFaultIgnored(val) => {
-                todo!()
-            }
-            Event::Stack8ByteAligned(val) => {
-                todo!()
-            }
-            Event::DataCacheEnabled(val) => {
-                todo!()
+                // SLEEPONEXIT is read from the SCR on exception return
+                Ok(())
             }
-            Event::InsnCacheEnabled(val) => {
-                todo!()
+            Event::ThreadModeExceptionsEnabled(_)
+            | Event::STIRUnprivilegedAccessAllowed(_)
+            | Event::UnalignedAccessTrapEnabled(_)
+            | Event::DivideByZeroTrapEnabled(_)
+            | Event::PreciseDataAccessFaultIgnored(_)
+            | Event::Stack8ByteAligned(_) => {
+                // the CCR bit is already stored by the register write,
+                // and is read from the CCR where it takes effect
+                Ok(())
             }
-            Event::BranchPredictionEnabled(val) => {
-                todo!()
+            Event::DataCacheEnabled(_)
+            | Event::InsnCacheEnabled(_)
+            | Event::BranchPredictionEnabled(_) => {
+                // caches and branch prediction aren't modeled
+                Ok(())
             }
             Event::FaultStatusClr(fault) => {
-                todo!("need to implement fault handling for this to make sense")
-                // go through all the possible CFSR error conditions 
-                // some of them trap conditionally. rather annoying to implement.
+                // the status bit is already cleared by the register write,
+                // and clearing it doesn't affect pending or active faults
+                Ok(())
             }
             Event::SEVInstructionExecuted => {
-                // do nothing with this for now
+                // there is only one processor to signal
                 // see SEV A7.7.129
+                self.event.0 = true;
                 Ok(())
             }
-            Event::Debug(_evt) => {
-                todo!()
+            Event::Debug(evt) => {
+                self._debug_event(evt)
+                    .map_err(backend::Error::from)
             }
             Event::Peripheral(evt) => {
                 let mut nvicregs = self.scs.nvic_regs_mut();
@@ -1,8 +1,13 @@
 //! faults.rs
 //! 
 //! architecture-defined faults
+//! 
+//! a fault sets its status bits in the CFSR or HFSR and pends its exception,
+//! which is taken before the next instruction. configurable faults escalate
+//! to HardFault if they're disabled or can't preempt the current execution
+//! priority, and a fault that can't be taken as a HardFault locks up the
+//! processor (see B1.5.15).
 
-#[allow(unused)]
 use super::*;
 
 /// fault types
@@ -56,6 +61,18 @@ pub enum HardFault {
 
 
 
+impl Fault {
+    /// the exception that handles the fault if it isn't escalated
+    pub fn exception_type(&self) -> ExceptionType {
+        match self {
+            Fault::Mem(_) => { ExceptionType::MemFault }
+            Fault::Bus(_) => { ExceptionType::BusFault }
+            Fault::Usg(_) => { ExceptionType::UsageFault }
+            Fault::Hard(_) => { ExceptionType::HardFault }
+        }
+    }
+}
+
 impl From<MemFault> for Fault {
     fn from(value: MemFault) -> Self {
         Self::Mem(value)
@@ -78,4 +95,101 @@ impl From<HardFault> for Fault {
     fn from(value: HardFault) -> Self {
         Self::Hard(value)
     }
-}
\ No newline at end of file
+}
+
+impl Backend {
+    /// raise a synchronous fault in the current instruction
+    /// 
+    /// `address` is the faulting data address, which is recorded in the
+    /// MMFAR or BFAR for data access faults.
+    /// returns a lockup error if the fault can't be taken.
+    #[instrument(skip_all)]
+    pub fn raise_fault(&mut self, fault: Fault, address: Option<Address>) -> Result<(), super::Error> {
+        warn!("{fault:?} fault (address: {address:#x?})");
+        self._set_fault_status(&fault, address);
+        let priority = self.current_priority();
+        let mut typ = fault.exception_type();
+        if typ != ExceptionType::HardFault
+            && (!self.scs.exception_enabled(typ)
+                || self.scs.get_exception_priority(typ).0 >= priority)
+        {
+            info!("{typ:?} escalated to HardFault");
+            self.scs.get_hfsr_mut().set_forced(true);
+            typ = ExceptionType::HardFault;
+        }
+        if typ == ExceptionType::HardFault && priority <= -1 {
+            // a fault in the HardFault or NMI handler, or with FAULTMASK set
+            error!("lockup on {fault:?} at priority {priority}");
+            self.status = Status::Lockup;
+            self.scs.debug_regs().get_dhcsr_mut().set_s_lockup(true);
+            return Err(super::Error::Lockup(fault));
+        }
+        if !self.scs.exceptions.pending().contains(&typ) {
+            self.scs.set_exception_pending(typ);
+        }
+        Ok(())
+    }
+
+    /// set the status and address register bits for a fault
+    pub(crate) fn _set_fault_status(&mut self, fault: &Fault, address: Option<Address>) {
+        match fault {
+            Fault::Mem(fault) => {
+                let mmfsr = self.scs.get_cfsr().memmanage();
+                let mmfsr = match fault {
+                    MemFault::InsnAccessViolation => { mmfsr.with_iaccviol(true) }
+                    MemFault::DataAccessViolation => { mmfsr.with_daccviol(true) }
+                    MemFault::OnExceptionReturn => { mmfsr.with_munstkerr(true) }
+                    MemFault::OnExceptionEntry => { mmfsr.with_mstkerr(true) }
+                    MemFault::LazyStatePreservation => { mmfsr.with_mlsperr(true) }
+                };
+                let mmfsr = match address {
+                    Some(address) if *fault == MemFault::DataAccessViolation => {
+                        self.scs.get_mmfar_mut().set_address(address.offset() as u32);
+                        mmfsr.with_mmarvalid(true)
+                    }
+                    _ => { mmfsr }
+                };
+                self.scs.get_cfsr_mut().set_memmanage(mmfsr);
+            }
+            Fault::Bus(fault) => {
+                let bfsr = self.scs.get_cfsr().busfault();
+                let bfsr = match fault {
+                    BusFault::InsnPrefetch => { bfsr.with_ibuserr(true) }
+                    BusFault::PreciseDataAccess => { bfsr.with_preciserr(true) }
+                    BusFault::ImprciseDataAccess => { bfsr.with_impreciserr(true) }
+                    BusFault::OnExceptionReturn => { bfsr.with_unstkerr(true) }
+                    BusFault::OnExceptionEntry => { bfsr.with_stkerr(true) }
+                    BusFault::LazyStatePreservation => { bfsr.with_lsperr(true) }
+                };
+                let bfsr = match address {
+                    Some(address) if *fault == BusFault::PreciseDataAccess => {
+                        self.scs.get_bfar_mut().set_address(address.offset() as u32);
+                        bfsr.with_bfarvalid(true)
+                    }
+                    _ => { bfsr }
+                };
+                self.scs.get_cfsr_mut().set_busfault(bfsr);
+            }
+            Fault::Usg(fault) => {
+                let ufsr = self.scs.get_cfsr().usagefault();
+                let ufsr = match fault {
+                    UsgFault::UndefinedInsn => { ufsr.with_undefinstr(true) }
+                    UsgFault::InvalidState => { ufsr.with_invstate(true) }
+                    UsgFault::IntegrityCheck => { ufsr.with_invpc(true) }
+                    UsgFault::CoprocessorAccess => { ufsr.with_nocp(true) }
+                    UsgFault::UnalignedAccess => { ufsr.with_unaligned(true) }
+                    UsgFault::DivideByZero => { ufsr.with_divbyzero(true) }
+                };
+                self.scs.get_cfsr_mut().set_usagefault(ufsr);
+            }
+            Fault::Hard(fault) => {
+                let hfsr = self.scs.get_hfsr_mut();
+                match fault {
+                    HardFault::VectorTableRead => { hfsr.set_vecttbl(true) }
+                    HardFault::EscalatedException => { hfsr.set_forced(true) }
+                    HardFault::DebugEvent => { hfsr.set_debugevt(true) }
+                }
+            }
+        }
+    }
+}
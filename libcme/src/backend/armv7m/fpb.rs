@@ -0,0 +1,221 @@
+//! fpb.rs
+//!
+//! flash patch and breakpoint unit
+//!
+//! implements version 1 of the fpb, whose instruction comparators generate
+//! breakpoint debug events on instruction address matches in the code
+//! region. flash patch remapping is not implemented, which FP_REMAP
+//! reports, so comparators configured to remap never match.
+//!
+//! see C1.11
+use bitfield_struct::bitfield;
+
+use crate::backend;
+use super::*;
+
+/// fpb base address
+pub const FPB_BASE: u32 = 0xe0002000;
+/// number of instruction address comparators
+const FPB_NUM_CODE: u8 = 6;
+/// number of literal address comparators
+const FPB_NUM_LIT: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FPBRegType {
+    /// flash patch control register
+    CTRL,
+    /// flash patch remap register
+    REMAP,
+    /// flash patch comparator register n
+    COMP(u8),
+}
+
+impl FPBRegType {
+    /// lookup register corresponding to given byte offset from the fpb base
+    pub fn lookup_offset(offset: usize) -> Option<Self> {
+        match offset {
+            0x000 => { Some(FPBRegType::CTRL) }
+            0x004 => { Some(FPBRegType::REMAP) }
+            0x008..=0x084 if offset & 0b11 == 0 => {
+                Some(FPBRegType::COMP(((offset - 0x008) / 4) as u8))
+            }
+            _ => { None }
+        }
+    }
+
+    /// returns the register's address
+    pub fn address(&self) -> Address {
+        (FPB_BASE + self.offset() as u32).into()
+    }
+
+    /// returns the byte offset from the fpb base of the register type
+    pub fn offset(&self) -> usize {
+        match self {
+            FPBRegType::CTRL    => { 0x000 }
+            FPBRegType::REMAP   => { 0x004 }
+            FPBRegType::COMP(n) => { 0x008 + (4 * *n as usize) }
+        }
+    }
+}
+
+/// provides fpb implementation information, and the global enable.
+///
+/// see C1.11.3
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+#[allow(nonstandard_style)]
+pub struct FP_CTRL {
+    /// enables the fpb
+    #[bits(1)]
+    pub enable: bool,
+    /// writes to FP_CTRL are ignored unless this bit is written as 1.
+    /// reads as 0.
+    #[bits(1)]
+    pub key: bool,
+    #[bits(2)]
+    __: u8,
+    /// bits [3:0] of the number of instruction address comparators. read-only.
+    #[bits(4)]
+    pub num_code_lo: u8,
+    /// number of literal address comparators. read-only.
+    #[bits(4)]
+    pub num_lit: u8,
+    /// bits [6:4] of the number of instruction address comparators. read-only.
+    #[bits(3)]
+    pub num_code_hi: u8,
+    #[bits(13)]
+    __: u16,
+    /// fpb architecture revision. read-only.
+    #[bits(4)]
+    pub rev: u8,
+}
+
+impl FP_CTRL {
+    /// number of instruction address comparators
+    pub fn num_code(&self) -> u8 {
+        (self.num_code_hi() << 4) | self.num_code_lo()
+    }
+}
+
+/// holds an address for comparison and the action taken on a match.
+///
+/// see C1.11.5
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+#[allow(nonstandard_style)]
+pub struct FP_COMP {
+    /// enables the comparator
+    #[bits(1)]
+    pub enable: bool,
+    #[bits(1)]
+    __: bool,
+    /// bits [28:2] of the comparison address
+    #[bits(27)]
+    pub comp: u32,
+    #[bits(1)]
+    __: bool,
+    /// action on a match of an instruction address comparator
+    /// - 0b00: remap to the address in FP_REMAP
+    /// - 0b01: breakpoint on the lower halfword
+    /// - 0b10: breakpoint on the upper halfword
+    /// - 0b11: breakpoint on both halfwords
+    #[bits(2)]
+    pub replace: u8,
+}
+
+impl FP_COMP {
+    /// true if the comparator generates a breakpoint for the instruction address
+    pub fn breakpoint(&self, address: u32) -> bool {
+        let base = self.comp() << 2;
+        self.enable() && match self.replace() {
+            0b01 => { address & !0b1 == base }
+            0b10 => { address & !0b1 == (base | 0b10) }
+            0b11 => { address & !0b11 == base }
+            _ => { false }
+        }
+    }
+}
+
+/// flash patch and breakpoint unit state
+#[derive(Debug, Clone)]
+pub struct FPB {
+    pub ctrl: FP_CTRL,
+    pub comparators: Vec<FP_COMP>,
+}
+
+impl Default for FPB {
+    fn default() -> Self {
+        let ctrl = FP_CTRL::new()
+            .with_num_code_lo(FPB_NUM_CODE & 0xf)
+            .with_num_code_hi(FPB_NUM_CODE >> 4)
+            .with_num_lit(FPB_NUM_LIT);
+        let comparators = vec![FP_COMP::new(); (FPB_NUM_CODE + FPB_NUM_LIT) as usize];
+        Self { ctrl, comparators }
+    }
+}
+
+impl FPB {
+    /// true if an enabled instruction address comparator
+    /// generates a breakpoint for the address
+    pub fn breakpoint(&self, address: u32) -> bool {
+        // the fpb only matches addresses in the code region
+        self.ctrl.enable() && address < 0x20000000
+            && self.comparators.iter()
+                .take(self.ctrl.num_code() as usize)
+                .any(|comp| comp.breakpoint(address))
+    }
+
+    /// perform an event-triggering read of fpb register bytes
+    pub fn read_bytes(&mut self,
+        offset: usize,
+        dst: &mut [u8],
+        _events: &mut VecDeque<Event>,
+    ) -> Result<(), backend::Error> {
+        let address = FPB_BASE + offset as u32;
+        check_alignment(address, dst.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let val = match FPBRegType::lookup_offset(offset) {
+            Some(FPBRegType::CTRL) => { self.ctrl.into_bits() }
+            Some(FPBRegType::COMP(n)) => {
+                self.comparators.get(n as usize)
+                    .map(|comp| comp.into_bits())
+                    .unwrap_or(0)
+            }
+            // FP_REMAP.RMPSPT reads as 0 since remapping isn't supported
+            _ => { 0 }
+        };
+        dst.copy_from_slice(&val.to_le_bytes());
+        Ok(())
+    }
+
+    /// perform an event-triggering write of fpb register bytes
+    pub fn write_bytes(&mut self,
+        offset: usize,
+        src: &[u8],
+        _events: &mut VecDeque<Event>,
+    ) -> Result<(), backend::Error> {
+        let address = FPB_BASE + offset as u32;
+        check_alignment(address, src.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let write_val = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        match FPBRegType::lookup_offset(offset) {
+            Some(FPBRegType::CTRL) => {
+                let new_ctrl = FP_CTRL::from_bits(write_val);
+                if new_ctrl.key() {
+                    self.ctrl.set_enable(new_ctrl.enable());
+                }
+            }
+            Some(FPBRegType::COMP(n)) => {
+                if let Some(comp) = self.comparators.get_mut(n as usize) {
+                    *comp = FP_COMP::from_bits(write_val & 0xdfff_fffd);
+                }
+            }
+            _ => { }
+        }
+        Ok(())
+    }
+}
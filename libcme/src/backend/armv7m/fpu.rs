@@ -0,0 +1,225 @@
+//! fpu.rs
+//!
+//! floating point extension context handling
+//!
+//! the extension is implemented if the sleigh spec defines the fp
+//! registers. an fp instruction is recognized by its access to an fp
+//! register, which is where coprocessor access is checked and any lazily
+//! preserved context is saved.
+//!
+//! an extended exception frame holds s0-s15 and fpscr after the basic
+//! frame. with lazy preservation (FPCCR.LSPEN) the space is reserved on
+//! exception entry but only written if the handler executes an fp instruction.
+use std::ops::Range;
+
+use super::*;
+
+/// offset of s0 in an extended exception frame
+pub const FP_FRAME_OFFSET: u64 = 0x20;
+
+/// floating point extension registers
+#[derive(Debug, Clone)]
+pub struct FPRegs {
+    /// s0-s15, the registers saved in an extended frame
+    pub sregs: Vec<VarnodeData>,
+    pub fpscr: VarnodeData,
+    /// register space occupied by the s, d, and q registers
+    bank: Range<u64>,
+}
+
+impl FPRegs {
+    /// look up the fp registers, or None if the extension isn't implemented
+    pub fn new_from(t: &Translator) -> Option<Self> {
+        let fpscr = t.register_by_name("fpscr")?;
+        let sregs = (0..16)
+            .map(|i| t.register_by_name(&format!("s{i}")))
+            .collect::<Option<Vec<_>>>()?;
+        let bank = ["s", "d", "q"].into_iter()
+            .flat_map(|prefix| (0..32).map(move |i| format!("{prefix}{i}")))
+            .filter_map(|name| t.register_by_name(&name))
+            .fold(sregs[0].offset()..sregs[0].offset(), |bank, reg| {
+                let end = reg.offset() + reg.size() as u64;
+                bank.start.min(reg.offset())..bank.end.max(end)
+            });
+        Some(Self { sregs, fpscr, bank })
+    }
+
+    /// the registers saved in an extended frame, in frame order
+    pub fn context(&self) -> impl Iterator<Item=&VarnodeData> {
+        self.sregs.iter().chain(std::iter::once(&self.fpscr))
+    }
+
+    /// true if the varnode overlaps an fp register
+    pub fn contains(&self, vnd: &VarnodeData) -> bool {
+        let start = vnd.offset();
+        let end = start + vnd.size() as u64;
+        let fpscr_start = self.fpscr.offset();
+        let fpscr_end = fpscr_start + self.fpscr.size() as u64;
+        (start < self.bank.end && self.bank.start < end)
+            || (start < fpscr_end && fpscr_start < end)
+    }
+}
+
+impl Backend {
+    /// derived from HaveFPExt()
+    pub fn have_fp_ext(&self) -> bool {
+        self.fp.is_some()
+    }
+
+    /// true if the register varnode is an fp register
+    pub(crate) fn _is_fp_register(&self, vnd: &VarnodeData) -> bool {
+        self.fp.as_ref().is_some_and(|fp| fp.contains(vnd))
+    }
+
+    /// checks made before an fp instruction executes,
+    /// derived from ExecuteFPCheck()
+    ///
+    /// fails with a coprocessor access error if the fp extension is
+    /// disabled, so that the instruction is abandoned and the NOCP
+    /// UsageFault is taken with its address stacked.
+    pub(crate) fn _execute_fp_check(&mut self) -> Result<(), backend::Error> {
+        if !self._fp_enabled() {
+            warn!("floating point access denied");
+            return Err(backend::Error::CoprocessorAccess);
+        }
+        if self.scs.get_fpccr().lspact() {
+            // save the interrupted context before the handler modifies it
+            self._preserve_fp_state()?;
+        }
+        if self.scs.get_fpccr().aspen() && !self.control.fpca() {
+            // a new fp context starts with the default status
+            let default = self.scs.get_fpdscr().into_bits() & FPDSCR::FPSCR_MASK;
+            let fpscr = self._read_fpscr()? & !FPDSCR::FPSCR_MASK;
+            self._write_fpscr(fpscr | default)?;
+            self.control.set_fpca(true);
+        }
+        Ok(())
+    }
+
+    /// true if the fp extension is enabled at the current privilege level
+    pub(crate) fn _fp_enabled(&self) -> bool {
+        match self.scs.get_cpacr().fp_access() {
+            Some(CPACRAccess::Full) => { true }
+            Some(CPACRAccess::Privileged) => { self.current_mode_is_privileged() }
+            Some(CPACRAccess::Denied) => { false }
+            None => {
+                warn!("CPACR cp10 and cp11 access differs");
+                false
+            }
+        }
+    }
+
+    /// derived from CheckVFPEnabled() during exception entry and return
+    ///
+    /// raises a NOCP UsageFault and returns false if the fp extension
+    /// is disabled at the current privilege level. the fault is a derived
+    /// exception, so it is pended rather than abandoning the stacking.
+    pub(crate) fn _check_fp_enabled(&mut self) -> Result<bool, super::Error> {
+        let enabled = self._fp_enabled();
+        if !enabled {
+            warn!("floating point context access denied");
+            self.raise_fault(UsgFault::CoprocessorAccess.into(), None)?;
+        }
+        Ok(enabled)
+    }
+
+    /// derived from PreserveFPState()
+    ///
+    /// save the fp context to the space reserved by the last extended frame
+    pub(crate) fn _preserve_fp_state(&mut self) -> Result<(), super::Error> {
+        let address = Address::from(self.scs.get_fpcar().address() << 3);
+        // s0-s15, fpscr, and the reserved word
+        if self._check_stack_access(&address, 0x48, Permission::W, MemFault::LazyStatePreservation)? {
+            self._store_fp_context(&address)?;
+        }
+        self.scs.get_fpccr_mut().set_lspact(false);
+        Ok(())
+    }
+
+    /// derived from UpdateFPCCR()
+    ///
+    /// reserve the fp context space of an extended frame for lazy preservation
+    pub(crate) fn _update_fpccr(&mut self, frameptr: &Address) {
+        let priority = self.current_priority();
+        let ready = |backend: &Self, typ: ExceptionType| {
+            backend.scs.exceptions.enabled.contains(&typ)
+                && priority > backend.scs.get_exception_priority(typ).0
+        };
+        let mmrdy = ready(self, ExceptionType::MemFault);
+        let bfrdy = ready(self, ExceptionType::BusFault);
+        let monrdy = self.scs.debug_regs().get_demcr().mon_en()
+            && priority > self.scs.get_exception_priority(ExceptionType::DebugMonitor).0;
+        let user = !self.current_mode_is_privileged();
+        let thread = self.mode == Mode::Thread;
+
+        let address = (frameptr.offset() + FP_FRAME_OFFSET) as u32;
+        self.scs.get_fpcar_mut().set_address(address >> 3);
+        let fpccr = self.scs.get_fpccr_mut();
+        fpccr.set_lspact(true);
+        fpccr.set_user(user);
+        fpccr.set_thread(thread);
+        fpccr.set_hfrdy(priority > -1);
+        fpccr.set_mmrdy(mmrdy);
+        fpccr.set_bfrdy(bfrdy);
+        fpccr.set_monrdy(monrdy);
+    }
+
+    /// store s0-s15 and fpscr in extended frame layout
+    pub(crate) fn _store_fp_context(&mut self, address: &Address) -> Result<(), super::Error> {
+        let Some(fp) = self.fp.as_ref() else {
+            return Ok(());
+        };
+        for (i, reg) in fp.context().enumerate() {
+            let bytes = self.regs.view_bytes(reg.offset() as usize, reg.size())
+                .map_err(|_| {
+                    let msg = "failed to read bytes from fp register";
+                    error!("{msg}: {reg:#x?}");
+                    super::Error::System(msg)
+                })?;
+            self.mmap.store_bytes(&(*address + i as u64 * 4), bytes, &mut self.events)
+                .map_err(|_| {
+                    let msg = "failed to store fp context";
+                    error!("{msg}: {reg:#x?} @ {:#x?}", *address + i as u64 * 4);
+                    super::Error::System(msg)
+                })?;
+        }
+        Ok(())
+    }
+
+    /// load s0-s15 and fpscr from extended frame layout
+    pub(crate) fn _load_fp_context(&mut self, address: &Address) -> Result<(), super::Error> {
+        let Some(fp) = self.fp.as_ref() else {
+            return Ok(());
+        };
+        for (i, reg) in fp.context().enumerate() {
+            let bytes = self.mmap.mem_view_bytes(&(*address + i as u64 * 4), Some(4))
+                .map_err(|_| {
+                    let msg = "failed to load fp context";
+                    error!("{msg}: {:#x?}", *address + i as u64 * 4);
+                    super::Error::System(msg)
+                })?;
+            self.regs.write_bytes(reg.offset() as usize, bytes)
+                .map_err(|_| {
+                    let msg = "failed to write to fp register";
+                    error!("{msg}: {reg:#x?}");
+                    super::Error::System(msg)
+                })?;
+        }
+        Ok(())
+    }
+
+    fn _read_fpscr(&self) -> Result<u32, super::Error> {
+        let fpscr = &self.fp.as_ref().unwrap().fpscr;
+        self.regs.read_val_with(fpscr.offset() as usize, fpscr.size(), self.endian)
+            .ok()
+            .and_then(|val| val.to_u32())
+            .ok_or(super::Error::System("failed to read fpscr"))
+    }
+
+    fn _write_fpscr(&mut self, val: u32) -> Result<(), super::Error> {
+        let fpscr = &self.fp.as_ref().unwrap().fpscr;
+        let val = BitVec::from_u32(val, fpscr.bits());
+        self.regs.write_val_with(fpscr.offset() as usize, &val, self.endian)
+            .map_err(|_| super::Error::System("failed to write fpscr"))
+    }
+}
@@ -2,6 +2,7 @@
 //! 
 //! implementation of helper functions that require full backend context
 
+use crate::backend;
 use super::*;
 
 impl Backend {
@@ -52,6 +53,12 @@ impl Backend {
     /// from B1.5.4 page B1-529
     #[allow(unused)]
     pub fn current_priority(&self) -> i16 {
+        self._execution_priority(self.primask.pm())
+    }
+
+    /// execution priority with the given PRIMASK value,
+    /// which wfi uses to ignore PRIMASK (see B1.5.19)
+    pub(crate) fn _execution_priority(&self, primask: bool) -> i16 {
         // priority of thread mode with no active exceptions
         // this value is PriorityMax + 1 = 256
         // (configurable priority maximum bit field is 8 bits)
@@ -85,7 +92,7 @@ impl Backend {
             boostedpri -= boostedpri % groupvalue;
         }
 
-        if self.primask.pm() {
+        if primask {
             boostedpri = 0;
         }
 
@@ -100,6 +107,33 @@ impl Backend {
         }
     }
 
+    /// validate a memory access against the mpu regions
+    /// using the current privilege and execution priority (see B3.5.3)
+    pub(crate) fn _check_mpu(&self,
+        address: &Address,
+        size: usize,
+        access: Permission,
+    ) -> Result<(), backend::Error> {
+        let mpu = self.scs.mpu_regs();
+        if !mpu.get_ctrl().enable() {
+            return Ok(());
+        }
+        let privileged = self.current_mode_is_privileged();
+        let priority = self.current_priority();
+        // regions and subregions are at least 32 bytes, so permissions
+        // can only change on a 32 byte boundary
+        let start = address.offset();
+        let end = start + size.max(1) as u64;
+        let boundaries = ((start & !0x1f) + 0x20..end).step_by(0x20);
+        for check in std::iter::once(start).chain(boundaries) {
+            if !mpu.permits(check as u32, access, privileged, priority) {
+                warn!("mpu {access:?} access violation @ {check:#010x}");
+                return Err(backend::Error::AccessViolation(address.clone(), access));
+            }
+        }
+        Ok(())
+    }
+
     /// exception entry (see B1.5.6)
     #[instrument(skip_all)]
     pub fn exception_entry(&mut self, excp_typ: ExceptionType) -> Result<ThreadSwitch, super::Error> {
@@ -115,9 +149,12 @@ impl Backend {
         } else {
             self.get_main_sp()?
         };
+        let fp_frame = self.have_fp_ext() && self.control.fpca();
         let (return_address, new_frame_address) = self.push_stack(excp_typ)?;
         let vtor = Some(Address::from(self.scs.get_vtor().tbloff() << 7));
         let target_address = self.exception_taken(excp_typ)?;
+        // taking an exception wakes the processor from WFI/WFE
+        self.status = Status::Alive;
         let new_thread = self.current_thread();
         let return_address = Some(return_address);
         let typ = u32::from(&excp_typ);
@@ -132,6 +169,7 @@ impl Backend {
             target_address,
             return_address,
             vtor,
+            fp_frame,
         })
     }
 
@@ -139,12 +177,12 @@ impl Backend {
     /// returns the return address and frame address
     #[instrument(skip_all)]
     fn push_stack(&mut self, excp_typ: ExceptionType) -> Result<(Address, Address), super::Error> {
-        // if fp_enabled && self.control.fpca() {
-        //     framesize = 0x68;
-        //     forcealign = true;
-        // }
-        let framesize = 0x20u32;
-        let forcealign = self.scs.get_ccr().stkalign();
+        let fp_frame = self.have_fp_ext() && self.control.fpca();
+        let (framesize, forcealign) = if fp_frame {
+            (0x68u32, true)
+        } else {
+            (0x20u32, self.scs.get_ccr().stkalign())
+        };
 
         let spmask = !((forcealign as u32) << 2);
         
@@ -167,6 +205,68 @@ impl Backend {
                 super::Error::System(msg)
             })?;
 
+        // a stacking fault is a derived exception, so the frame is
+        // not written and the exception entry continues
+        let stackable = self._check_stack_access(
+            &frameptr, framesize as usize, Permission::W, MemFault::OnExceptionEntry)?;
+
+        let return_address = self.return_address(excp_typ)?;
+        let result = return_address.clone();
+        let xpsr = self.xpsr.0 & !(1 << 9) | (frameptralign << 9);
+        if stackable {
+            self._store_frame(&frameptr, &return_address, xpsr)?;
+        }
+
+        // EXC_RETURN.nofpext is clear if an extended frame was pushed
+        let lr = 0xFFFFFFE0 | ((!fp_frame as u32) << 4);
+        let lr = if matches!(self.mode, Mode::Handler(_)) {
+            lr | 0b0001
+        } else {
+            lr | 0b1001 | ((self.control.spsel() as u32) << 2)
+        };
+        let lr = BitVec::from_u32(lr, 32);
+        let lr_vnd = self.lang.translator().register_by_name("lr").unwrap();
+        self.regs.write_val_with(lr_vnd.offset() as usize, &lr, self.endian)
+            .map_err(|_| {
+                let msg = "failed to write to lr";
+                error!("{msg}: {lr}");
+                super::Error::System(msg)
+            })?;
+
+        if fp_frame {
+            if self.scs.get_fpccr().lspen() {
+                // reserve space for the fp context, which is saved
+                // when the handler first executes an fp instruction
+                self._update_fpccr(&frameptr);
+            } else if stackable && self._check_fp_enabled()? {
+                self._store_fp_context(&(frameptr + FP_FRAME_OFFSET))?;
+            }
+        }
+
+        Ok((result, frameptr))
+    }
+
+    /// validate a stack access against the mpu, raising the stacking fault
+    /// if it is not permitted. returns true if the access is permitted.
+    pub(crate) fn _check_stack_access(&mut self,
+        address: &Address,
+        size: usize,
+        access: Permission,
+        fault: MemFault,
+    ) -> Result<bool, super::Error> {
+        if self._check_mpu(address, size, access).is_ok() {
+            return Ok(true);
+        }
+        self.raise_fault(fault.into(), None)?;
+        Ok(false)
+    }
+
+    /// store r0-r3, r12, lr, the return address, and xpsr in a basic frame
+    fn _store_frame(&mut self,
+        frameptr: &Address,
+        return_address: &Address,
+        xpsr: u32,
+    ) -> Result<(), super::Error> {
         let t = self.lang.translator();
         let push_regs = ["r0", "r1", "r2", "r3", "r12", "lr"].into_iter()
             .map(|reg_str| {
@@ -180,62 +280,30 @@ impl Backend {
                     error!("{msg}: {reg:#x?}");
                     super::Error::System(msg)
                 })?;
-            self.mmap.store_bytes(&(frameptr + i * 4), bytes, &mut self.events)
+            self.mmap.store_bytes(&(*frameptr + i * 4), bytes, &mut self.events)
                 .map_err(|_| {
                     let msg = "failed to push register to stack";
-                    error!("{msg}: {reg:#x?} @ {:#x?}", frameptr + i * 4);
+                    error!("{msg}: {reg:#x?} @ {:#x?}", *frameptr + i * 4);
                     super::Error::System(msg)
                 })?;
         }
         // push return address
-        let return_address = self.return_address(excp_typ)?;
-        let result = return_address.clone();
         let return_address = u32::to_le_bytes(return_address.offset() as u32);
-        self.mmap.store_bytes(&(frameptr + 0x18u64), &return_address, &mut self.events)
+        self.mmap.store_bytes(&(*frameptr + 0x18u64), &return_address, &mut self.events)
             .map_err(|_| {
                 let msg = "failed to push return address to stack";
-                error!("{msg}: {:#x?}", frameptr + 0x18u64);
+                error!("{msg}: {:#x?}", *frameptr + 0x18u64);
                 super::Error::System(msg)
             })?;
         // push xpsr
-        let xpsr = self.xpsr.0 & !(1 << 9) | (frameptralign << 9);
         let xpsr = u32::to_le_bytes(xpsr);
-        self.mmap.store_bytes(&(frameptr + 0x1Cu64), &xpsr, &mut self.events)
+        self.mmap.store_bytes(&(*frameptr + 0x1Cu64), &xpsr, &mut self.events)
             .map_err(|_| {
                 let msg = "failed to push xpsr to stack";
                 error!("{msg}: {:#x?}", xpsr);
                 super::Error::System(msg)
             })?;
-        
-        // if fp_enabled && self.control.fpca() {
-        //     // load floating point registers.
-        //     // unimplemented.
-        //     // see reference manual for details
-        // }
-
-        // if fp_enabled {
-        //     if matches!(self.mode, Mode::Handler(_)) {
-        //         let lr = 0xFFFFFFE0 | (((!self.control.fpca()) as u32) << 4) | 0x1;
-        //     }
-        //     else {
-        //         let lr = 0xFFFFFFE0 | (((!self.control.fpca()) as u32) << 4) | (((!self.control.fpca()) as u32) << 2) | 0b1001;
-        //     }
-        // }
-        let lr = if matches!(self.mode, Mode::Handler(_)) {
-            0xFFFFFFF1
-        } else {
-            0xFFFFFFF1 | ((self.control.spsel() as u32) << 2)
-        };
-        let lr = BitVec::from_u32(lr, 32);
-        let lr_vnd = t.register_by_name("lr").unwrap();
-        self.regs.write_val_with(lr_vnd.offset() as usize, &lr, self.endian)
-            .map_err(|_| {
-                let msg = "failed to write to lr";
-                error!("{msg}: {lr}");
-                super::Error::System(msg)
-            })?;
-
-        Ok((result, frameptr))
+        Ok(())
     }
 
     /// get return address based on exception type
@@ -375,13 +443,71 @@ impl Backend {
             })
     }
 
-    /// clear exclusive local processor id
-    /// not sure what this should be doing.
-    /// see B1.5.6 ExceptionTaken
-    fn _clear_exclusive_local(&mut self) -> Result<(), super::Error> {
+    /// generate an unaligned access error for halfword and word accesses
+    /// that aren't size-aligned if CCR.UNALIGN_TRP is set, or if the
+    /// current instruction always requires aligned accesses (see A3.2.1)
+    pub(crate) fn _check_unaligned(&self, address: &Address, size: usize) -> Result<(), backend::Error> {
+        let align = match size {
+            0 | 1 => { 1 }
+            2 | 3 => { 2 }
+            _ => { 4 }
+        };
+        let trap = self.strict_alignment || self.scs.get_ccr().unalign_trp();
+        if trap && address.offset() % align != 0 {
+            return Err(backend::Error::Unaligned(address.clone(), size));
+        }
         Ok(())
     }
 
+    /// generate an unprivileged access error for unprivileged writes to
+    /// the STIR unless CCR.USERSETMPEND is set (see B3.4.4)
+    pub(crate) fn _check_stir_access(&self, address: &Address) -> Result<(), backend::Error> {
+        let stir = SCRegType::STIR.address();
+        if *address == stir
+            && !self.current_mode_is_privileged()
+            && !self.scs.get_ccr().usersetmpend()
+        {
+            return Err(backend::Error::Unprivileged(address.clone()));
+        }
+        Ok(())
+    }
+
+    /// tag the granule containing the address in the local exclusive monitor,
+    /// moving it to the exclusive access state (see A3.4.1)
+    pub(crate) fn _mark_exclusive_local(&mut self, address: u32) {
+        self.exclusive = Some(address & !(EXCLUSIVE_GRANULE - 1));
+    }
+
+    /// true if the local exclusive monitor is in the exclusive access state
+    /// and tagged with the granule containing the address
+    pub(crate) fn _is_exclusive_local(&self, address: u32) -> bool {
+        self.exclusive == Some(address & !(EXCLUSIVE_GRANULE - 1))
+    }
+
+    /// move the local exclusive monitor to the open access state.
+    /// 
+    /// called on CLREX, STREX, exception entry and return
+    /// (see B1.5.6 ExceptionTaken and B1.5.8 ExceptionReturn)
+    pub(crate) fn _clear_exclusive_local(&mut self) -> Result<(), super::Error> {
+        if let Some(tagged) = self.exclusive.take() {
+            debug!("cleared exclusive monitor tagged @ {tagged:#x}");
+        }
+        Ok(())
+    }
+
+    /// clear the local exclusive monitor if a store overlaps the tagged granule
+    pub(crate) fn _check_exclusive_store(&mut self, address: &Address, size: usize) {
+        let Some(tagged) = self.exclusive else {
+            return;
+        };
+        let start = address.offset();
+        let tagged = tagged as u64;
+        if start < tagged + EXCLUSIVE_GRANULE as u64 && tagged < start + size as u64 {
+            debug!("store @ {address:#x?} cleared exclusive monitor");
+            self.exclusive = None;
+        }
+    }
+
     /// perform exception return
     /// returns context switch information
     #[instrument(skip_all)]
@@ -394,9 +520,12 @@ impl Backend {
             error!("{msg}: {:#x}", exc_return.into_bits());
             return Err(super::Error::UnpredictableBehavior(msg));
         }
-        // if fp_enabled {
-        //     assert_eq!(exc_return.nofpext(), false, "unpredictable behavior")
-        // }
+        if !self.have_fp_ext() && !exc_return.nofpext() {
+            let msg = "extended frame return without floating point extension";
+            error!("{msg}: {:#x}", exc_return.into_bits());
+            return Err(super::Error::UnpredictableBehavior(msg));
+        }
+        let fp_frame = !exc_return.nofpext();
 
         let returning_excp = ExceptionType::from(self.xpsr.ipsr().exception_number());
         // used for Handler -> Thread check when value == 1
@@ -406,9 +535,7 @@ impl Backend {
         // that will preempt instead of returning
         if !self.scs.exceptions.active().contains(&returning_excp) {
             warn!("returning from inactive handler is a usagefault");
-            let ufsr = self.scs.get_cfsr().usagefault()
-                .with_invpc(true);
-            return self._return_usagefault(returning_excp, exc_return, ufsr)
+            return self._return_fault(returning_excp, exc_return, UsgFault::IntegrityCheck.into())
         }
 
         let frameptr = match exc_return.modebits() {
@@ -430,13 +557,17 @@ impl Backend {
             _ => {
                 // return to thread exception mismatch
                 // or illegal exc_return
-                let ufsr = self.scs.get_cfsr().usagefault()
-                    .with_invpc(true);
-                return self._return_usagefault(returning_excp, exc_return, ufsr)
+                return self._return_fault(returning_excp, exc_return, UsgFault::IntegrityCheck.into())
             }
         };
 
         self._deactivate_exception(returning_excp);
+        let framesize = if fp_frame { 0x68 } else { 0x20 };
+        if self._check_mpu(&frameptr, framesize, Permission::R).is_err() {
+            // unstacking fault, the frame is left on the stack
+            let fault = MemFault::OnExceptionReturn.into();
+            return self._return_fault(returning_excp, exc_return, fault);
+        }
         let old_thread = self.current_thread();
         let switch_address = self.read_pc()
             .map_err(|_| {
@@ -451,11 +582,9 @@ impl Backend {
             let excp_num = self.xpsr.ipsr().exception_number();
             if excp_num == 0 {
                 // return ipsr is inconsistent
-                let ufsr = self.scs.get_cfsr().usagefault()
-                    .with_invpc(true);
                 // push stack again to negate popstack
                 self.push_stack(ExceptionType::UsageFault)?;
-                return self._return_usagefault(returning_excp, exc_return, ufsr);
+                return self._return_fault(returning_excp, exc_return, UsgFault::IntegrityCheck.into());
             }
             let typ = ExceptionType::from(excp_num);
             Mode::Handler(typ)
@@ -464,11 +593,9 @@ impl Backend {
             let excp_num = self.xpsr.ipsr().exception_number();
             if excp_num != 0 {
                 // return ipsr is inconsistent
-                let ufsr = self.scs.get_cfsr().usagefault()
-                    .with_invpc(true);
                 // push stack again to negate popstack
                 self.push_stack(ExceptionType::UsageFault)?;
-                return self._return_usagefault(returning_excp, exc_return, ufsr);
+                return self._return_fault(returning_excp, exc_return, UsgFault::IntegrityCheck.into());
             }
             Mode::Thread
         };
@@ -484,7 +611,10 @@ impl Backend {
             && nested_activation == 0 
             && self.scs.get_scr().sleeponexit()
         {
-            return self.sleep_on_exit();
+            // sleeping without unstacking is optional, so the return
+            // completes and thread mode resumes after the next wakeup
+            debug!("sleep on exit");
+            self.status = Status::WaitingForInterrupt;
         }
 
         Ok(ThreadSwitch {
@@ -497,6 +627,7 @@ impl Backend {
             switch_address,
             target_address,
             return_address,
+            fp_frame,
         })
     }
 
@@ -513,18 +644,19 @@ impl Backend {
         }
     }
 
-    fn _return_usagefault(
+    /// tail-chain to a fault raised during exception return
+    fn _return_fault(
         &mut self,
         returning_excp: ExceptionType,
         exc_return: EXC_RETURN,
-        ufsr: UFSR,
+        fault: Fault,
     ) -> Result<ThreadSwitch, super::Error> {
         // get current context information
         let old_thread = self.current_thread();
         let switch_address = self.read_pc()
             .map_err(|_| {
                 let msg = concat!(
-                    "could not read pc while triggering usagefault ",
+                    "could not read pc while triggering fault ",
                     "during exception return",
                 );
                 error!(msg);
@@ -542,8 +674,8 @@ impl Backend {
                 u32::from_le_bytes(*(&slice[..4] as *const [u8] as *const[u8; 4]))
             }).map_err(|_| {
                 let msg = concat!(
-                    "failed to read stack frame while triggering",
-                    "usagefault during exception return",
+                    "failed to read stack frame while triggering ",
+                    "fault during exception return",
                 );
                 error!("{msg}: {pushed_frame_address:#x?}");
                 super::Error::System(msg)
@@ -551,13 +683,19 @@ impl Backend {
         let pushed_return_address = Address::from(pushed_return_address);
 
         self._deactivate_exception(returning_excp);
-        self.scs.get_cfsr_mut().set_usagefault(ufsr);
+        self._set_fault_status(&fault, None);
+        let mut typ = fault.exception_type();
+        if typ != ExceptionType::HardFault && !self.scs.exception_enabled(typ) {
+            info!("{typ:?} escalated to HardFault");
+            self.scs.get_hfsr_mut().set_forced(true);
+            typ = ExceptionType::HardFault;
+        }
         let value = BitVec::from_u32(exc_return.into_bits(), 32);
         let lr_vnd = self.lang.translator().register_by_name("lr").unwrap();
         self.regs.write_val_with(lr_vnd.offset() as usize, &value, self.endian)
             .map_err(|_| {
                 let msg = concat!(
-                    "failed to write lr while triggering usagefault ",
+                    "failed to write lr while triggering fault ",
                     "during exception return",
                 );
                 error!("{msg}: {value:#x}");
@@ -565,13 +703,14 @@ impl Backend {
             })?;
         
         let vtor = Some(Address::from(self.scs.get_vtor().tbloff() << 7));
-        let target_address = self.exception_taken(ExceptionType::UsageFault)?;
+        let target_address = self.exception_taken(typ)?;
         let new_thread = self.current_thread();
         let old_frame_address = pushed_frame_address;
         let new_frame_address = pushed_frame_address;
         let return_address = Some(pushed_return_address);
+        let fp_frame = !exc_return.nofpext();
         return Ok(ThreadSwitch {
-            typ: u32::from(&ExceptionType::UsageFault),
+            typ: u32::from(&typ),
             old_thread,
             new_thread,
             old_frame_address,
@@ -580,18 +719,19 @@ impl Backend {
             target_address,
             return_address,
             vtor,
+            fp_frame,
         })
     }
 
     /// pop the stack, returning the new frame address and the target pc address
     #[instrument(skip_all)]
     fn pop_stack(&mut self, frameptr: &Address, exc_return: EXC_RETURN) -> Result<(Address, Address), super::Error> {
-        // if fp_enabled && !exc_return.nofpext() {
-        //     framesize = 0x68;
-        //     forcealign = true;
-        // }
-        let framesize = 0x20u32;
-        let forcealign = self.scs.get_ccr().stkalign() as u32;
+        let fp_frame = self.have_fp_ext() && !exc_return.nofpext();
+        let (framesize, forcealign) = if fp_frame {
+            (0x68u32, 1u32)
+        } else {
+            (0x20u32, self.scs.get_ccr().stkalign() as u32)
+        };
 
         let t = self.lang.translator();
         let pop_regs = ["r0", "r1", "r2", "r3", "r12", "lr"].into_iter()
@@ -621,9 +761,18 @@ impl Backend {
                 u32::from_le_bytes(*(&slice[4..] as *const [u8] as *const [u8; 4])),
             )})?;
 
-        // if fp_enabled {
-        //     // see PopStack in  B1.5.8 for these implementation details
-        // }
+        if self.have_fp_ext() {
+            if fp_frame {
+                if self.scs.get_fpccr().lspact() {
+                    // the handler never executed an fp instruction,
+                    // so the registers still hold the fp context
+                    self.scs.get_fpccr_mut().set_lspact(false);
+                } else if self._check_fp_enabled()? {
+                    self._load_fp_context(&(*frameptr + FP_FRAME_OFFSET))?;
+                }
+            }
+            self.control.set_fpca(fp_frame);
+        }
 
         let spmask = (((psr >> 9) & 1) & forcealign) << 2;
 
@@ -650,10 +799,6 @@ impl Backend {
         Ok((frame_address, target_address))
     }
 
-    fn sleep_on_exit(&mut self) -> Result<ThreadSwitch, super::Error> {
-        unimplemented!("sleep on exit implementation defined")
-    }
-
     /// produces instruction synchronization barrier
     /// with 4 bit option
     /// see page D6-824
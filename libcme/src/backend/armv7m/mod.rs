@@ -35,7 +35,10 @@ use crate::peripheral::{
 use crate::backend::{
     self,
     ThreadSwitch,
+    HaltReason,
+    ResetPolicy,
     Backend as BackendTrait,
+    semihosting::Semihosting,
 };
 
 use super::mmap::*;
@@ -51,12 +54,25 @@ mod scs;
 pub use scs::*;
 mod faults;
 pub use faults::*;
+mod fpu;
+pub use fpu::*;
+mod dwt;
+pub use dwt::*;
+mod fpb;
+pub use fpb::*;
+mod debug;
+mod semihosting;
+pub use semihosting::*;
+mod sleep;
 
 
 /// largest expected instruction 16 bytes in x86, 4 in ARM
 const MAX_INSN_SIZE: usize = 4;
 /// default proc_sp value is UNKNOWN. pick 0 for simplicity.
 const DEFAULT_PROC_SP: u32 = 0;
+/// size of the block tagged by the local exclusive monitor
+/// is IMPLEMENTATION DEFINED. pick the smallest permitted (A3.4.3).
+const EXCLUSIVE_GRANULE: u32 = 8;
 
 
 #[derive(Debug, Error, Clone)]
@@ -79,6 +95,8 @@ pub enum Error {
     ReadAccessViolation(Address),
     #[error("illegal access alignment @ [{0:#x?}; {1}], expected: {2:?}")]
     AlignmentViolation(Address, usize, Alignment),
+    #[error("processor locked up on fault: {0:?}")]
+    Lockup(Fault),
 }
 
 impl From<Error> for backend::Error {
@@ -122,6 +140,8 @@ pub enum Status {
     WaitingForInterrupt,
     Halted,
     Killed,
+    /// an unrecoverable fault occurred (see B1.5.15)
+    Lockup,
 }
 
 /// EXC_RETURN (see B1.5.8)
@@ -156,6 +176,9 @@ pub struct Backend {
     mode: Mode,
     /// event register (B1.5.18)
     event: system::EVENT,
+    /// granule tagged by the local exclusive monitor,
+    /// or None in the open access state (A3.4.1)
+    exclusive: Option<u32>,
     /// armv7m xPSR is a combination of APSR, IPSR, and EPSR
     /// and is not defined as part of the ghidra sleigh spec.
     /// hence we must handle this manually
@@ -172,7 +195,29 @@ pub struct Backend {
 
     regs: FixedState,
     tmps: FixedState,
+    /// floating point extension registers, if implemented
+    fp: Option<FPRegs>,
     scs: SysCtrlSpace,
+    /// data watchpoint and trace unit
+    dwt: DWT,
+    /// flash patch and breakpoint unit
+    fpb: FPB,
+    /// semihosting call handler, if attached
+    semihosting: Option<Semihosting>,
+    /// locations written with input bytes by semihosting reads
+    input_writes: Vec<VarnodeData>,
+    /// the reason for the last halt
+    halt_reason: Option<HaltReason>,
+    /// set on leaving debug state, so that the first fetch
+    /// doesn't hit the breakpoint that halted the processor again
+    step_over_breakpoint: bool,
+    /// set while executing an instruction whose data accesses must be
+    /// aligned regardless of CCR.UNALIGN_TRP
+    strict_alignment: bool,
+    /// how system reset requests are handled
+    reset_policy: ResetPolicy,
+    /// set when a system reset request resets the processor
+    reset_taken: bool,
     mmap: MemoryMap,
 
     events: VecDeque<Event>,
@@ -204,8 +249,12 @@ impl Backend {
         let regs = FixedState::new(t.register_space_size());
         let tmps = FixedState::new(t.unique_space_size());
         let ctx_db = t.context_database();
+        let fp = FPRegs::new_from(t);
         let sp = lang.convention().stack_pointer().varnode().clone();
-        let scs_config = scs_config.unwrap_or_default();
+        let mut scs = SysCtrlSpace::new_from(scs_config.unwrap_or_default());
+        if fp.is_some() {
+            scs.reset_fp_regs();
+        }
 
         Ok(Self {
             id: 0,
@@ -215,6 +264,7 @@ impl Backend {
             endian,
             mode: Mode::Thread,
             event: system::EVENT::default(),
+            exclusive: None,
             xpsr: system::XPSR(0),
             main_sp: None,
             proc_sp: None,
@@ -225,8 +275,18 @@ impl Backend {
             apsr,
             regs,
             tmps,
+            fp,
             mmap: MemoryMap::default(),
-            scs: SysCtrlSpace::new_from(scs_config),
+            scs,
+            dwt: DWT::default(),
+            fpb: FPB::default(),
+            semihosting: None,
+            input_writes: vec![],
+            halt_reason: None,
+            step_over_breakpoint: false,
+            strict_alignment: false,
+            reset_policy: ResetPolicy::default(),
+            reset_taken: false,
             events: VecDeque::new(),
             lang,
             ctx_db,
@@ -262,6 +322,12 @@ impl BackendTrait for Backend {
     fn tick(&mut self) -> Result<(), backend::Error> {
         // systick in SCS
         self.scs.tick(&mut self.events)?;
+        // dwt cycle counter
+        self._tick_dwt();
+        // semihosting clock
+        if let Some(semihosting) = self.semihosting.as_mut() {
+            semihosting.tick();
+        }
         // time-dependent peripherals
         self.mmap.tick(&mut self.events)?;
         Ok(())
@@ -283,7 +349,6 @@ impl BackendTrait for Backend {
         // since 0xFxxxxxxx range has execute-never permissions.
         if exc_return.exc_value() == 0xF && self.mode != Mode::Thread {
             // this is EXC_RETURN.
-            assert!(exc_return.nofpext(), "floating point not supported");
             return self.exception_return(exc_return)
                 .map_err(|err| {
                     error!("exception return failed: {err:?}");
@@ -295,7 +360,7 @@ impl BackendTrait for Backend {
         // that is enabled and has a lower priority group value
         for typ in self.scs.exceptions.pending() {
             if self.scs.get_exception_priority(*typ).0 < self.current_priority()
-                && self.scs.exceptions.enabled.contains(typ)
+                && self.scs.exception_enabled(*typ)
             {
                 // a pending exception will preempt the current context
                 return self.exception_entry(*typ)
@@ -317,6 +382,58 @@ impl BackendTrait for Backend {
         Ok(())
     }
 
+    fn fault(&mut self, cause: backend::FaultCause) -> Result<bool, backend::Error> {
+        let (fault, address) = match cause {
+            backend::FaultCause::InsnFetch => {
+                (BusFault::InsnPrefetch.into(), None)
+            }
+            backend::FaultCause::UndefinedInsn => {
+                (UsgFault::UndefinedInsn.into(), None)
+            }
+            backend::FaultCause::DataAccess(address) => {
+                (BusFault::PreciseDataAccess.into(), Some(address))
+            }
+            backend::FaultCause::AccessViolation(_address, Permission::E) => {
+                (MemFault::InsnAccessViolation.into(), None)
+            }
+            backend::FaultCause::AccessViolation(address, _) => {
+                (MemFault::DataAccessViolation.into(), Some(address))
+            }
+            backend::FaultCause::Breakpoint(address) => {
+                let evt = DebugEvent::Breakpoint(address.offset() as u32);
+                self._debug_event(evt)?;
+                return Ok(true);
+            }
+            backend::FaultCause::UnalignedAccess(_address) => {
+                (UsgFault::UnalignedAccess.into(), None)
+            }
+            backend::FaultCause::DivideByZero => {
+                if !self.scs.get_ccr().div_0_trp() {
+                    return Ok(false);
+                }
+                (UsgFault::DivideByZero.into(), None)
+            }
+            backend::FaultCause::CoprocessorAccess => {
+                (UsgFault::CoprocessorAccess.into(), None)
+            }
+        };
+        self.raise_fault(fault, address)?;
+        Ok(true)
+    }
+
+    fn check_fetch(&mut self, address: &Address) -> Result<(), backend::Error> {
+        let size = self._insn_size(address);
+        self._check_mpu(address, size, Permission::E)?;
+        self.strict_alignment = self._insn_strict_alignment(address);
+        self._check_breakpoint(address)?;
+        self._check_insn_watchpoint(address);
+        Ok(())
+    }
+
+    fn traps_divide_by_zero(&self) -> bool {
+        self.scs.get_ccr().div_0_trp()
+    }
+
     fn map_mem(&mut self,
         base: &Address,
         size: usize,
@@ -382,6 +499,7 @@ impl BackendTrait for Backend {
 
     fn fetch<'irb>(&mut self, address: &Address, irb: &'irb IRBuilderArena) -> LiftResult<'irb> {
         // cloning the context db around is super inefficient, but for now we just need this to work...
+        self.strict_alignment = self._insn_strict_alignment(address);
         let mut lifter = Lifter::new_with(self.lang.translator(), self.ctx_db.clone());
         let bytes = self._mem_view_bytes(address, Some(MAX_INSN_SIZE))?;
         let pcode_result = lifter.lift(irb, address.clone(), bytes);
@@ -401,8 +519,11 @@ impl BackendTrait for Backend {
 
     fn load(&mut self, address: &Address, size: usize) -> Result<BitVec, backend::Error> {
         let big_endian = self.lang.translator().is_big_endian();
+        self._check_unaligned(address, size)?;
+        self._check_mpu(address, size, Permission::R)?;
+        self._check_watchpoint(address, size, Permission::R);
         let mut dst = vec![0u8; size];
-        self.load_bytes(address, &mut dst)?;
+        self._load_bytes(address, &mut dst)?;
 
         if big_endian {
             Ok(BitVec::from_be_bytes(&dst))
@@ -413,6 +534,11 @@ impl BackendTrait for Backend {
 
     fn store(&mut self, address: &Address, val: &BitVec) -> Result<(), backend::Error> {
         let size = val.bytes();
+        self._check_unaligned(address, size)?;
+        self._check_mpu(address, size, Permission::W)?;
+        self._check_stir_access(address)?;
+        self._check_watchpoint(address, size, Permission::W);
+        self._check_exclusive_store(address, size);
         let mut src = vec![0u8; size];
         if self.lang.translator().is_big_endian() {
             val.to_be_bytes(&mut src);
@@ -420,7 +546,7 @@ impl BackendTrait for Backend {
             val.to_le_bytes(&mut src);
         }
 
-        self.store_bytes(address, &src)
+        self._store_bytes(address, &src)
     }
 
     fn read(&mut self, vnd: &VarnodeData) -> Result<BitVec, backend::Error> {
@@ -428,6 +554,9 @@ impl BackendTrait for Backend {
         if spc.is_constant() {
             Ok(BitVec::from_u64(vnd.offset(), vnd.bits()))
         } else if spc.is_register() {
+            if self._is_fp_register(vnd) {
+                self._execute_fp_check()?;
+            }
             Ok(self.regs.read_val_with(vnd.offset() as usize, vnd.size(), self.endian)?)
         } else if spc.is_unique() {
             Ok(self.tmps.read_val_with(vnd.offset() as usize, vnd.size(), self.endian)?)
@@ -441,6 +570,9 @@ impl BackendTrait for Backend {
     fn write(&mut self, vnd: &VarnodeData, val: &BitVec) -> Result<(), backend::Error> {
         let spc = vnd.space();
         if spc.is_register() {
+            if self._is_fp_register(vnd) {
+                self._execute_fp_check()?;
+            }
             Ok(self.regs.write_val_with(vnd.offset() as usize, val, self.endian)?)
         } else if spc.is_unique() {
             Ok(self.tmps.write_val_with(vnd.offset() as usize, val, self.endian)?)
@@ -454,34 +586,184 @@ impl BackendTrait for Backend {
     }
 
     fn load_bytes(&mut self, address: &Address, dst: &mut [u8]) -> Result<(), backend::Error> {
+        self._check_unaligned(address, dst.len())?;
+        self._check_mpu(address, dst.len(), Permission::R)?;
+        self._load_bytes(address, dst)
+    }
+
+    fn store_bytes(&mut self, address: &Address, src: &[u8]) -> Result<(), backend::Error> {
+        self._check_unaligned(address, src.len())?;
+        self._check_mpu(address, src.len(), Permission::W)?;
+        self._check_stir_access(address)?;
+        self._check_exclusive_store(address, src.len());
+        self._store_bytes(address, src)
+    }
+
+    fn userop(
+        &mut self,
+        output: Option<&VarnodeData>,
+        inputs: &[VarnodeData],
+    ) -> Result<Option<fugue_core::ir::Location>, backend::Error> {
+        let (index, inputs, output) = get_userop_params(output, inputs);
+        self._userop(index, inputs, output)
+    }
+
+    fn enabled_interrupts(&self) -> Vec<u32> {
+        self.scs.exceptions.enabled().iter()
+            .filter_map(|typ| match typ {
+                ExceptionType::ExternalInterrupt(int_num) => { Some(*int_num) }
+                _ => { None }
+            })
+            .collect()
+    }
+
+    fn fire_interrupt(&mut self, int_num: u32) -> Result<(), backend::Error> {
+        let typ = ExceptionType::ExternalInterrupt(int_num);
+        // pending an already pending interrupt has no effect
+        if !self.scs.exceptions.pending().contains(&typ) {
+            self.scs.set_exception_pending(typ);
+        }
+        Ok(())
+    }
+
+    fn interrupts_masked(&self) -> bool {
+        self.primask.pm() || self.faultmask.fm()
+    }
+
+    fn interrupts_possible(&self) -> bool {
+        let systick_regs = self.scs.systick_regs();
+        let systick_csr = systick_regs.get_csr();
+        !self.scs.exceptions.pending().is_empty()
+            || !self.enabled_interrupts().is_empty()
+            || (systick_csr.enable() && systick_csr.tickint())
+    }
+
+    fn is_sleeping(&self) -> bool {
+        matches!(self.status, Status::WaitingForEvent | Status::WaitingForInterrupt)
+    }
+
+    fn fast_forward(&mut self) -> Result<usize, backend::Error> {
+        self._fast_forward()
+    }
+
+    fn halt_reason(&self) -> Option<HaltReason> {
+        match self.status {
+            Status::Halted => { self.halt_reason.clone() }
+            _ => { None }
+        }
+    }
+
+    fn attach_semihosting(&mut self, semihosting: Semihosting) -> Result<(), backend::Error> {
+        self.semihosting = Some(semihosting);
+        Ok(())
+    }
+
+    fn take_input_writes(&mut self) -> Vec<VarnodeData> {
+        std::mem::take(&mut self.input_writes)
+    }
+
+    fn set_reset_policy(&mut self, policy: ResetPolicy) {
+        self.reset_policy = policy;
+    }
+
+    fn take_reset(&mut self) -> bool {
+        std::mem::take(&mut self.reset_taken)
+    }
+
+    fn snapshot(&mut self) -> backend::Snapshot {
+        // take the memory map out so the clone of the remaining
+        // state doesn't copy all of memory
+        let mmap = std::mem::take(&mut self.mmap);
+        let state = Arc::new(self.clone());
+        self.mmap = mmap;
+        backend::Snapshot { mmap: self.mmap.snapshot(), state }
+    }
+
+    fn restore(&mut self, snapshot: &backend::Snapshot) -> Result<(), backend::Error> {
+        let Some(state) = snapshot.state.downcast_ref::<Self>() else {
+            return Err(Error::System("snapshot is not from an armv7m backend").into());
+        };
+        let mmap = std::mem::take(&mut self.mmap);
+        *self = state.clone();
+        self.mmap = mmap;
+        self.mmap.restore(&snapshot.mmap)
+    }
+}
+
+impl Backend {
+    /// load bytes without access checks
+    fn _load_bytes(&mut self, address: &Address, dst: &mut [u8]) -> Result<(), backend::Error> {
         if self._is_scs_region(address, dst.len()) {
             let offset = ((address.offset() as u32) - 0xe000e000u32) as usize;
             self.scs.read_bytes(offset, dst, &mut self.events)
+        } else if self._is_dwt_region(address, dst.len()) {
+            let offset = ((address.offset() as u32) - DWT_BASE) as usize;
+            self.dwt.read_bytes(offset, dst, &mut self.events)
+        } else if self._is_fpb_region(address, dst.len()) {
+            let offset = ((address.offset() as u32) - FPB_BASE) as usize;
+            self.fpb.read_bytes(offset, dst, &mut self.events)
         } else {
             self.mmap.load_bytes(address, dst, &mut self.events)
         }
     }
 
-    fn store_bytes(&mut self, address: &Address, src: &[u8]) -> Result<(), backend::Error> {
+    /// store bytes without access checks
+    fn _store_bytes(&mut self, address: &Address, src: &[u8]) -> Result<(), backend::Error> {
         if self._is_scs_region(address, src.len()) {
             let offset = ((address.offset() as u32) - 0xe000e000u32) as usize;
             self.scs.write_bytes(offset, src, &mut self.events)
+        } else if self._is_dwt_region(address, src.len()) {
+            let offset = ((address.offset() as u32) - DWT_BASE) as usize;
+            self.dwt.write_bytes(offset, src, &mut self.events)
+        } else if self._is_fpb_region(address, src.len()) {
+            let offset = ((address.offset() as u32) - FPB_BASE) as usize;
+            self.fpb.write_bytes(offset, src, &mut self.events)
         } else {
             self.mmap.store_bytes(address, src, &mut self.events)
         }
     }
 
-    fn userop(
-        &mut self,
-        output: Option<&VarnodeData>,
-        inputs: &[VarnodeData],
-    ) -> Result<Option<fugue_core::ir::Location>, backend::Error> {
-        let (index, inputs, output) = get_userop_params(output, inputs);
-        self._userop(index, inputs, output)
+    /// the size of the thumb instruction at the address.
+    /// the first halfword of a 32-bit instruction has bits [15:11]
+    /// of 0b11101, 0b11110, or 0b11111 (see A5.1)
+    fn _insn_size(&self, address: &Address) -> usize {
+        match self.mmap.mem_view_bytes(address, Some(2)) {
+            Ok(&[_, hi]) if (hi >> 3) >= 0b11101 => { 4 }
+            _ => { 2 }
+        }
+    }
+
+    /// true if the thumb instruction at the address faults on unaligned
+    /// accesses regardless of CCR.UNALIGN_TRP. these are the multiple,
+    /// dual, exclusive, and coprocessor loads and stores (see A3.2.1)
+    fn _insn_strict_alignment(&self, address: &Address) -> bool {
+        let halfword = |offset: u64| match self.mmap.mem_view_bytes(&(*address + offset), Some(2)) {
+            Ok(&[lo, hi]) => { Some(u16::from_le_bytes([lo, hi])) }
+            _ => { None }
+        };
+        let Some(hw1) = halfword(0) else {
+            return false;
+        };
+        if (hw1 >> 11) < 0b11101 {
+            // PUSH, POP, LDM, STM
+            return (hw1 & 0xfe00) == 0xb400
+                || (hw1 & 0xfe00) == 0xbc00
+                || (hw1 & 0xf000) == 0xc000;
+        }
+        match hw1 & 0xfe40 {
+            // LDM, STM, PUSH, POP
+            0xe800 => { true }
+            // LDRD, STRD, LDREX, STREX, except TBB and TBH
+            0xe840 => {
+                let table_branch = (hw1 & 0xfff0) == 0xe8d0
+                    && halfword(2).is_some_and(|hw2| (hw2 & 0xffe0) == 0xf000);
+                !table_branch
+            }
+            // LDC, STC, VLDR, VSTR, VLDM, VSTM, VPUSH, VPOP
+            _ => { (hw1 & 0xee00) == 0xec00 }
+        }
     }
-}
 
-impl Backend {
     fn _is_scs_region(&self, address: &Address, size: usize) -> bool {
         (*address + size as u64) < self.scs.range.end
         && *address >= self.scs.range.start
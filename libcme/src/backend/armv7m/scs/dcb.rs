@@ -1,6 +1,9 @@
 //! dcb.rs
 //! 
 //! debug control block
+//! 
+//! writes can't be told apart from debugger writes through the dap,
+//! so halting debug can be enabled by any write with the debug key.
 
 use derive_more::From;
 use bitfield_struct::bitfield;
@@ -9,9 +12,18 @@ use crate::types::RegInfo;
 use crate::backend;
 use super::*;
 
+/// debug events, handled by entering debug state if halting debug
+/// is enabled, or otherwise by the DebugMonitor exception (see C1.5)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DebugEvent {
-    // todo
+    /// a halt request through DHCSR.C_HALT
+    HaltRequest,
+    /// a breakpoint at the instruction address
+    Breakpoint(u32),
+    /// a dwt comparator matched
+    Watchpoint(u8),
+    /// a core register transfer requested through DCRSR
+    RegisterTransfer(DCRSR),
 }
 
 
@@ -437,23 +449,109 @@ impl<'a> DebugRegs<'a> {
     }
 
     /// perform an event-triggering read of debug register bytes
-    #[allow(unused)]
     pub fn read_bytes(&mut self,
         offset: usize,
         dst: &mut [u8],
-        events: &mut VecDeque<Event>,
+        _events: &mut VecDeque<Event>,
     ) -> Result<(), backend::Error> {
-        todo!()
+        let address = BASE + offset as u32;
+        check_alignment(address, dst.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let reg = DebugRegType::lookup_offset(offset)
+            .ok_or_else(| | {
+                backend::Error::from(Error::InvalidSysCtrlReg(address.into()))
+            })?;
+        let word_offset = offset / 4;
+        match reg {
+            DebugRegType::DCRSR => {
+                // write-only register
+                let err = Error::ReadAccessViolation(address.into());
+                return Err(backend::Error::from(err).into());
+            }
+            DebugRegType::DHCSR => {
+                dst.copy_from_slice(&self.backing[word_offset].to_le_bytes());
+                // sticky status bits are cleared on read
+                let dhcsr = self.get_dhcsr_mut();
+                dhcsr.set_s_retire_st(false);
+                dhcsr.set_s_reset_st(false);
+            }
+            _ => {
+                dst.copy_from_slice(&self.backing[word_offset].to_le_bytes());
+            }
+        }
+        Ok(())
     }
 
     /// perform an event-triggering write to debug register bytes
-    #[allow(unused)]
     pub fn write_bytes(&mut self,
         offset: usize,
         src: &[u8],
         events: &mut VecDeque<Event>,
     ) -> Result<(), backend::Error> {
-        todo!()
+        let address = BASE + offset as u32;
+        check_alignment(address, src.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let reg = DebugRegType::lookup_offset(offset)
+            .ok_or_else(| | {
+                backend::Error::from(Error::InvalidSysCtrlReg(address.into()))
+            })?;
+        let write_val = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        match reg {
+            DebugRegType::DHCSR => {
+                if write_val >> 16 != 0xa05f {
+                    warn!("DHCSR write without DBGKEY ignored");
+                    return Ok(());
+                }
+                // only the control bits are writable
+                let new_dhcsr = DHCSR::from_bits(write_val & 0x2f);
+                let dhcsr = self.get_dhcsr_mut();
+                let halted = dhcsr.s_halt();
+                dhcsr.set_c_debugen(new_dhcsr.c_debugen());
+                dhcsr.set_c_halt(new_dhcsr.c_halt());
+                dhcsr.set_c_step(new_dhcsr.c_step());
+                dhcsr.set_c_maskints(new_dhcsr.c_maskints());
+                dhcsr.set_c_snapstall(new_dhcsr.c_snapstall());
+                if new_dhcsr.c_step() {
+                    warn!("halting debug single-step not supported");
+                }
+
+                if new_dhcsr.c_debugen() && new_dhcsr.c_halt() && !halted {
+                    events.push_back(Event::Debug(DebugEvent::HaltRequest));
+                } else if halted && !new_dhcsr.c_halt() {
+                    // leave debug state
+                    dhcsr.set_s_halt(false);
+                    events.push_back(Event::SetProcessorStatus(Status::Alive));
+                }
+            }
+            DebugRegType::DCRSR => {
+                let dcrsr = DCRSR::from_bits(write_val & 0x1007f);
+                *self.get_dcrsr_mut() = dcrsr;
+                // set again once the transfer completes
+                self.get_dhcsr_mut().set_s_regrdy(false);
+                events.push_back(Event::Debug(DebugEvent::RegisterTransfer(dcrsr)));
+            }
+            DebugRegType::DCRDR => {
+                self.get_dcrdr_mut().set_dbgtmp(write_val);
+            }
+            DebugRegType::DEMCR => {
+                let demcr = *self.get_demcr();
+                let new_demcr = DEMCR::from_bits(write_val & 0x010f07f1);
+                *self.get_demcr_mut() = new_demcr;
+
+                let excp = ExceptionType::DebugMonitor;
+                if demcr.mon_en() != new_demcr.mon_en() {
+                    events.push_back(Event::ExceptionEnabled(excp, new_demcr.mon_en()));
+                }
+                if demcr.mon_pend() != new_demcr.mon_pend() {
+                    events.push_back(Event::ExceptionSetPending(excp, new_demcr.mon_pend()));
+                }
+            }
+        }
+        Ok(())
     }
 }
 
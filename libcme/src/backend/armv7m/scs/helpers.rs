@@ -310,7 +310,7 @@ impl SysCtrlSpace {
     }
 
     #[instrument(skip_all)]
-    pub fn exception_enabled(&mut self, typ: ExceptionType) ->  bool {
+    pub fn exception_enabled(&self, typ: ExceptionType) ->  bool {
         match typ {
             ExceptionType::Reset
             | ExceptionType::NMI
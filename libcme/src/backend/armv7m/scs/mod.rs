@@ -36,7 +36,7 @@ mod helpers;
 static BASE: u32 = 0xe000e000;
 
 /// config containing reset values for scs registers
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SysCtrlConfig {
     map: AHashMap<SCRegType, u32>,
 }
@@ -49,6 +49,14 @@ impl Default for SysCtrlConfig {
     }
 }
 
+impl SysCtrlConfig {
+    /// set the reset value of a register
+    pub fn with_reset(mut self, scregtype: SCRegType, reset_val: u32) -> Self {
+        self.map.insert(scregtype, reset_val);
+        self
+    }
+}
+
 /// system control space
 /// 
 /// memory-mapped 4kb address space containing 32-bit registers for
@@ -61,6 +69,7 @@ pub struct SysCtrlSpace {
     backing: Box<[u32; 0x400]>,
     pub exceptions: ExceptionState,
     pub mpu: MPUState,
+    config: Arc<SysCtrlConfig>,
 }
 // TODO: refactor backing into separate registers struct...
 
@@ -84,15 +93,57 @@ impl fmt::Debug for SysCtrlSpace {
 
 impl SysCtrlSpace {
     pub fn new_from(config: SysCtrlConfig) -> Self {
+        Self::_new_from(Arc::new(config))
+    }
+
+    fn _new_from(config: Arc<SysCtrlConfig>) -> Self {
         let range = Address::from(0xe000e000u64)..Address::from(0xe000f000u64);
         let mut backing = Box::new([0u32; 0x400]);
         let exceptions = ExceptionState::default();
-        let mpu = MPUState::default();
-        for (scregtype, reset_val) in config.map {
+        let mpu_type = SCRegType::MPU(MPURegType::TYPE);
+        backing[mpu_type.offset() / 4] = mpu_type.reset().unwrap_or(0);
+        let ictr = SCRegType::ICTR;
+        backing[ictr.offset() / 4] = ictr.reset().unwrap_or(0);
+        for (scregtype, reset_val) in config.map.iter() {
             let offset = scregtype.offset();
-            backing[offset] = reset_val;
+            backing[offset / 4] = *reset_val;
+        }
+        let dregion = TYPE::from_bits(backing[mpu_type.offset() / 4]).dregion();
+        let mpu = MPUState::new(dregion);
+        Self { range, backing, exceptions, mpu, config }
+    }
+
+    /// reset the scs registers and exception state,
+    /// following ResetSCSRegs() in B1.5.5.
+    /// 
+    /// the debug registers are only reset by a power-on reset,
+    /// so they keep their values.
+    pub fn reset(&mut self) {
+        let debug_regs = [
+            DebugRegType::DHCSR,
+            DebugRegType::DCRSR,
+            DebugRegType::DCRDR,
+            DebugRegType::DEMCR,
+        ].map(|reg| (reg.offset() / 4, self.backing[reg.offset() / 4]));
+        *self = Self::_new_from(self.config.clone());
+        for (word_offset, val) in debug_regs {
+            self.backing[word_offset] = val;
+        }
+    }
+
+    /// reset the floating point extension registers (see B1.5.5)
+    pub fn reset_fp_regs(&mut self) {
+        let fp_regs = [
+            SCRegType::CPACR,
+            SCRegType::FPCCR,
+            SCRegType::FPDSCR,
+            SCRegType::MVFR0,
+            SCRegType::MVFR1,
+            SCRegType::MVFR2,
+        ];
+        for reg in fp_regs {
+            self.backing[reg.offset() / 4] = reg.reset().unwrap_or(0);
         }
-        Self { range, backing, exceptions, mpu }
     }
 
     /// direct view into the scs as transmuted bytes
@@ -129,7 +180,6 @@ impl SysCtrlSpace {
         match reg_type {
             SCRegType::ICSR
             | SCRegType::VTOR
-            | SCRegType::AIRCR
             | SCRegType::SCR
             | SCRegType::CCR
             | SCRegType::SHCSR
@@ -138,6 +188,13 @@ impl SysCtrlSpace {
             | SCRegType::MMFAR
             | SCRegType::BFAR
             | SCRegType::AFSR
+            | SCRegType::CPACR
+            | SCRegType::FPCCR
+            | SCRegType::FPCAR
+            | SCRegType::FPDSCR
+            | SCRegType::MVFR0
+            | SCRegType::MVFR1
+            | SCRegType::MVFR2
             | SCRegType::ICTR => {
                 check_alignment(address, dst.len(), Alignment::Word)
                     .map_err(|(address, size, expected)| {
@@ -148,13 +205,15 @@ impl SysCtrlSpace {
                 };
                 dst.copy_from_slice(slice);
             }
-            // SCRegType::CPACR => todo!(),
-            // SCRegType::FPCCR => todo!(),
-            // SCRegType::FPCAR => todo!(),
-            // SCRegType::FPDSCR => todo!(),
-            // SCRegType::MVFR0 => todo!(),
-            // SCRegType::MVFR1 => todo!(),
-            // SCRegType::MVFR2 => todo!(),
+            SCRegType::AIRCR => {
+                check_alignment(address, dst.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                // VECTKEYSTAT always reads as 0xfa05
+                let val = (self.backing[word_offset] & 0x0000ffff) | 0xfa050000;
+                dst.copy_from_slice(&val.to_le_bytes());
+            }
             // SCRegType::MCR => todo!(),
             // SCRegType::ACTLR => todo!(),
             SCRegType::STIR => {
@@ -175,6 +234,10 @@ impl SysCtrlSpace {
                 let mut mpuregs = self.mpu_regs_mut();
                 return mpuregs.read_bytes(offset, dst, events);
             }
+            SCRegType::Debug(_dbgreg_type) => {
+                let mut dbgregs = self.debug_regs();
+                return dbgregs.read_bytes(offset, dst, events);
+            }
             SCRegType::SHPR1(_)
             | SCRegType::SHPR2(_)
             | SCRegType::SHPR3(_)
@@ -187,7 +250,7 @@ impl SysCtrlSpace {
                 let slice = unsafe {
                     &*(&self.backing[word_offset] as *const u32 as *const [u8; 4])
                 };
-                dst.copy_from_slice(&slice[byte_offset..]);
+                dst.copy_from_slice(&slice[byte_offset..byte_offset + dst.len()]);
             }
         }
         Ok(())
@@ -214,7 +277,7 @@ impl SysCtrlSpace {
         let write_val = src.iter()
             .enumerate().take(4)
             .fold(0u32, |val, (i, &byte)| {
-                val | ((byte as u32) << i)
+                val | ((byte as u32) << (i * 8))
             });
         match reg_type {
             SCRegType::ICSR => {
@@ -291,6 +354,13 @@ impl SysCtrlSpace {
                 let new_prigroup = new_aircr.prigroup();
                 let new_vectkey = new_aircr.vectkey_stat();
 
+                if new_vectkey != 0x05FA {
+                    // writes without the vector key are ignored
+                    warn!("AIRCR write ignored without VECTKEY: {write_val:#x}");
+                    return Ok(());
+                }
+                events.push_back(Event::VectorKeyWrite);
+
                 let dbg_state = self.debug_regs().get_debug_state();
 
                 let aircr = self.get_aircr_mut();
@@ -315,21 +385,22 @@ impl SysCtrlSpace {
                 }
                 if new_sysresetreq ^ aircr.sysresetreq() {
                     if new_sysresetreq {
-                        // make local system reset request
-                        events.push_back(Event::LocalSysResetRequest);
+                        // request a system reset from the external system,
+                        // which clears SYSRESETREQ when it resets the processor
+                        events.push_back(Event::ExternSysResetRequest);
                     } else {
-                        // clear local system reset request
+                        // clear system reset request
                         // assuming it hasn't happened yet.
                         // don't know if this is actually correct behavior.
                         // arch doesn't specify.
                         let maybe_idx = events.iter()
                             .enumerate()
                             .find(|&(_, evt)| {
-                                *evt == Event::LocalSysResetRequest
+                                *evt == Event::ExternSysResetRequest
                             }).map(|(i, _)| i);
                         if let Some(idx) = maybe_idx {
                             let removed = events.remove(idx).unwrap();
-                            assert_eq!(removed, Event::LocalSysResetRequest,
+                            assert_eq!(removed, Event::ExternSysResetRequest,
                                 "removed the wrong event!");
                         }
                     }
@@ -339,9 +410,6 @@ impl SysCtrlSpace {
                     events.push_back(Event::SetPriorityGrouping(new_prigroup));
                     aircr.set_prigroup(new_prigroup);
                 }
-                if new_vectkey == 0x05FA {
-                    events.push_back(Event::VectorKeyWrite);
-                }
             }
             SCRegType::SCR => {
                 let masked_write_val = write_val & 0b10110;
@@ -412,7 +480,7 @@ impl SysCtrlSpace {
                     ccr.set_bfhfnmign(new_bfhfnmign);
                 }
                 if changed.stkalign() {
-                    let evt = Event::PreciseDataAccessFaultIgnored(new_stkalign);
+                    let evt = Event::Stack8ByteAligned(new_stkalign);
                     events.push_back(evt);
                     // TODO: have some configuration that makes this RO/RW
                     ccr.set_stkalign(new_stkalign);
@@ -559,23 +627,108 @@ impl SysCtrlSpace {
                 // note that we do not update SHCSR here, but in handling generated events
                 // since there are multiple ways to change interrupt state in software.
             }
-            SCRegType::CFSR => todo!(),
-            SCRegType::HFSR => todo!(),
-            SCRegType::DFSR => todo!(),
-            SCRegType::MMFAR => todo!(),
-            SCRegType::BFAR => todo!(),
-            SCRegType::AFSR => todo!(),
-            // SCRegType::CPACR => todo!(),
-            // SCRegType::FPCCR => todo!(),
-            // SCRegType::FPCAR => todo!(),
-            // SCRegType::FPDSCR => todo!(),
-            // SCRegType::MVFR0 => todo!(),
-            // SCRegType::MVFR1 => todo!(),
-            // SCRegType::MVFR2 => todo!(),
+            SCRegType::CFSR => {
+                // MMFSR, BFSR, and UFSR can be accessed separately
+                check_alignment(address, src.len(), Alignment::Any)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                // status bits are write-one-to-clear
+                let clr_mask = write_val << (byte_offset * 8);
+                let cfsr = self.get_cfsr_mut();
+                let cleared = CFSR::from_bits(cfsr.into_bits() & clr_mask);
+                *cfsr = CFSR::from_bits(cfsr.into_bits() & !clr_mask);
+                events.extend(cleared.write_evt());
+            }
+            SCRegType::HFSR => {
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                let hfsr = self.get_hfsr_mut();
+                let cleared = HFSR::from_bits(hfsr.into_bits() & write_val);
+                *hfsr = HFSR::from_bits(hfsr.into_bits() & !write_val);
+                events.extend(cleared.write_evt());
+            }
+            SCRegType::DFSR => {
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                self.backing[word_offset] &= !(write_val & 0x1f);
+            }
+            SCRegType::MMFAR
+            | SCRegType::BFAR
+            | SCRegType::AFSR => {
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                self.backing[word_offset] = write_val;
+            }
+            SCRegType::CPACR => {
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                // only cp10 and cp11 are implemented, the rest are RAZ/WI
+                let mut masked_write_val = write_val & 0x00f00000;
+                for shift in [20, 22] {
+                    if (masked_write_val >> shift) & 0b11 == 0b10 {
+                        // reserved encoding behaves as access denied
+                        warn!("reserved CPACR access value");
+                        masked_write_val &= !(0b11 << shift);
+                    }
+                }
+                self.backing[word_offset] = masked_write_val;
+            }
+            SCRegType::FPCCR => {
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                self.backing[word_offset] = write_val & 0xc000017b;
+            }
+            SCRegType::FPCAR => {
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                self.backing[word_offset] = write_val & !0b111;
+            }
+            SCRegType::FPDSCR => {
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                self.backing[word_offset] = write_val & FPDSCR::FPSCR_MASK;
+            }
+            SCRegType::MVFR0
+            | SCRegType::MVFR1
+            | SCRegType::MVFR2
+            | SCRegType::ICTR => {
+                // read-only registers
+                let address: Address = (BASE + offset as u32).into();
+                let err = Error::WriteAccessViolation(address);
+                return Err(backend::Error::from(err).into());
+            }
             // SCRegType::MCR => todo!(),
-            SCRegType::ICTR => todo!(),
             // SCRegType::ACTLR => todo!(),
-            SCRegType::STIR => todo!(),
+            SCRegType::STIR => {
+                // unprivileged access is checked by the backend,
+                // which knows the current privilege
+                check_alignment(address, src.len(), Alignment::Word)
+                    .map_err(|(address, size, expected)| {
+                        Error::AlignmentViolation(address, size, expected)
+                    })?;
+                let stir = STIR::from_bits(write_val);
+                if stir.intid() as usize >= self.get_ictr().num_int_lines() {
+                    // writes for unimplemented interrupts are ignored
+                    warn!("STIR write ignored for unimplemented interrupt {}", stir.intid());
+                    return Ok(());
+                }
+                events.extend(stir.write_evt());
+            }
             SCRegType::SysTick(_streg_type) => {
                 let mut stregs = self.systick_regs_mut();
                 return stregs.write_bytes(offset, src, events);
@@ -588,6 +741,10 @@ impl SysCtrlSpace {
                 let mut mpuregs = self.mpu_regs_mut();
                 return mpuregs.write_bytes(offset, src, events);
             }
+            SCRegType::Debug(_dbgreg_type) => {
+                let mut dbgregs = self.debug_regs();
+                return dbgregs.write_bytes(offset, src, events);
+            }
             _ => {
                 check_alignment(address, src.len(), Alignment::Any)
                     .map_err(|(address, size, expected)| {
@@ -660,30 +817,30 @@ impl SysCtrlSpace {
 
     /// get wrapper for interacting with mpu registers
     pub fn mpu_regs_mut(&mut self) -> MPURegsMut {
-        let slice = &mut self.backing[..0xdec];
-        Self::_mpu_regs_mut(slice)
+        let slice = &mut self.backing[..0x37c];
+        Self::_mpu_regs_mut(slice, &mut self.mpu)
     }
 
-    fn _mpu_regs_mut<'a>(backing: &'a mut [u32]) -> MPURegsMut<'a> {
-        assert!(backing.len() >= 0xdec, "backing not long enough");
+    fn _mpu_regs_mut<'a>(backing: &'a mut [u32], state: &'a mut MPUState) -> MPURegsMut<'a> {
+        assert!(backing.len() >= 0x37c, "backing not long enough");
         let backing = unsafe {
-            &mut *(backing as *mut [u32] as *mut [u32; 0xdec])
+            &mut *(backing as *mut [u32] as *mut [u32; 0x37c])
         };
-        MPURegsMut::new(backing)
+        MPURegsMut::new(backing, state)
     }
 
     /// get wrapper for reading mpu registers
     pub fn mpu_regs(&self) -> MPURegs {
-        let slice = &self.backing[..0xdec];
-        Self::_mpu_regs(slice)
+        let slice = &self.backing[..0x37c];
+        Self::_mpu_regs(slice, &self.mpu)
     }
 
-    fn _mpu_regs<'a>(backing: &'a [u32]) -> MPURegs<'a> {
-        assert!(backing.len() >= 0xdec, "backing not long enough");
+    fn _mpu_regs<'a>(backing: &'a [u32], state: &'a MPUState) -> MPURegs<'a> {
+        assert!(backing.len() >= 0x37c, "backing not long enough");
         let backing = unsafe {
-            &*(backing as *const [u32] as *const [u32; 0xdec])
+            &*(backing as *const [u32] as *const [u32; 0x37c])
         };
-        MPURegs::new(backing)
+        MPURegs::new(backing, state)
     }
 
     /// get wrapper for interacting with dbg registers
@@ -699,12 +856,7 @@ impl SysCtrlSpace {
 
 impl Default for SysCtrlSpace {
     fn default() -> Self {
-        Self {
-            range: Address::from(0xe000e000u64)..Address::from(0xe000f000u64),
-            backing: Box::new([0u32; 0x400]),
-            exceptions: ExceptionState::default(),
-            mpu: MPUState::default(),
-        }
+        Self::new_from(SysCtrlConfig::default())
     }
 }
 
@@ -808,41 +960,41 @@ impl SysCtrlSpace {
         unsafe { &*(&self.backing[word_offset] as *const u32 as *const CPACR) }
     }
 
-    // pub fn get_fpccr(&self) -> &FPCCR {
-    //     let byte_offset = SCRegType::FPCCR.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &*(&self.backing[word_offset] as *const u32 as *const FPCCR) }
-    // }
+    pub fn get_fpccr(&self) -> &FPCCR {
+        let byte_offset = SCRegType::FPCCR.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &*(&self.backing[word_offset] as *const u32 as *const FPCCR) }
+    }
 
-    // pub fn get_fpcar(&self) -> &FPCAR {
-    //     let byte_offset = SCRegType::FPCAR.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &*(&self.backing[word_offset] as *const u32 as *const FPCAR) }
-    // }
+    pub fn get_fpcar(&self) -> &FPCAR {
+        let byte_offset = SCRegType::FPCAR.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &*(&self.backing[word_offset] as *const u32 as *const FPCAR) }
+    }
 
-    // pub fn get_fpdscr(&self) -> &FPDSCR {
-    //     let byte_offset = SCRegType::FPDSCR.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &*(&self.backing[word_offset] as *const u32 as *const FPDSCR) }
-    // }
+    pub fn get_fpdscr(&self) -> &FPDSCR {
+        let byte_offset = SCRegType::FPDSCR.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &*(&self.backing[word_offset] as *const u32 as *const FPDSCR) }
+    }
 
-    // pub fn get_mvfr0(&self) -> &MVFR0 {
-    //     let byte_offset = SCRegType::MVFR0.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &*(&self.backing[word_offset] as *const u32 as *const MVFR0) }
-    // }
+    pub fn get_mvfr0(&self) -> &MVFR0 {
+        let byte_offset = SCRegType::MVFR0.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &*(&self.backing[word_offset] as *const u32 as *const MVFR0) }
+    }
 
-    // pub fn get_mvfr1(&self) -> &MVFR1 {
-    //     let byte_offset = SCRegType::MVFR1.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &*(&self.backing[word_offset] as *const u32 as *const MVFR1) }
-    // }
+    pub fn get_mvfr1(&self) -> &MVFR1 {
+        let byte_offset = SCRegType::MVFR1.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &*(&self.backing[word_offset] as *const u32 as *const MVFR1) }
+    }
 
-    // pub fn get_mvfr2(&self) -> &MVFR2 {
-    //     let byte_offset = SCRegType::MVFR2.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &*(&self.backing[word_offset] as *const u32 as *const MVFR2) }
-    // }
+    pub fn get_mvfr2(&self) -> &MVFR2 {
+        let byte_offset = SCRegType::MVFR2.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &*(&self.backing[word_offset] as *const u32 as *const MVFR2) }
+    }
 
     // pub fn get_mcr(&self) -> &MCR {
     //     let byte_offset = SCRegType::MCR.offset();
@@ -964,41 +1116,41 @@ impl SysCtrlSpace {
         unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut CPACR) }
     }
 
-    // pub fn get_fpccr_mut(&mut self) -> &mut FPCCR {
-    //     let byte_offset = SCRegType::FPCCR.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut FPCCR) }
-    // }
+    pub fn get_fpccr_mut(&mut self) -> &mut FPCCR {
+        let byte_offset = SCRegType::FPCCR.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut FPCCR) }
+    }
 
-    // pub fn get_fpcar_mut(&mut self) -> &mut FPCAR {
-    //     let byte_offset = SCRegType::FPCAR.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut FPCAR) }
-    // }
+    pub fn get_fpcar_mut(&mut self) -> &mut FPCAR {
+        let byte_offset = SCRegType::FPCAR.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut FPCAR) }
+    }
 
-    // pub fn get_fpdscr_mut(&mut self) -> &mut FPDSCR {
-    //     let byte_offset = SCRegType::FPDSCR.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut FPDSCR) }
-    // }
+    pub fn get_fpdscr_mut(&mut self) -> &mut FPDSCR {
+        let byte_offset = SCRegType::FPDSCR.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut FPDSCR) }
+    }
 
-    // pub fn get_mvfr0_mut(&mut self) -> &mut MVFR0 {
-    //     let byte_offset = SCRegType::MVFR0.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut MVFR0) }
-    // }
+    pub fn get_mvfr0_mut(&mut self) -> &mut MVFR0 {
+        let byte_offset = SCRegType::MVFR0.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut MVFR0) }
+    }
 
-    // pub fn get_mvfr1_mut(&mut self) -> &mut MVFR1 {
-    //     let byte_offset = SCRegType::MVFR1.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut MVFR1) }
-    // }
+    pub fn get_mvfr1_mut(&mut self) -> &mut MVFR1 {
+        let byte_offset = SCRegType::MVFR1.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut MVFR1) }
+    }
 
-    // pub fn get_mvfr2_mut(&mut self) -> &mut MVFR2 {
-    //     let byte_offset = SCRegType::MVFR2.offset();
-    //     let word_offset = byte_offset / 4;
-    //     unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut MVFR2) }
-    // }
+    pub fn get_mvfr2_mut(&mut self) -> &mut MVFR2 {
+        let byte_offset = SCRegType::MVFR2.offset();
+        let word_offset = byte_offset / 4;
+        unsafe { &mut *(&mut self.backing[word_offset] as *mut u32 as *mut MVFR2) }
+    }
 
     // pub fn get_mcr_mut(&mut self) -> &mut MCR {
     //     let byte_offset = SCRegType::MCR.offset();
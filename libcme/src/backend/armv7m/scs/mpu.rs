@@ -1,6 +1,11 @@
 //! mpu.rs
 //! 
 //! memory protection unit implementation
+//! 
+//! region base address and attributes are kept in the mpu state, and the
+//! RBAR and RASR register aliases mirror the region selected by RNR.
+//! when the mpu is enabled, every load, store, and instruction fetch
+//! is validated against the enabled regions (see B3.5).
 
 use bitfield_struct::bitfield;
 
@@ -23,20 +28,64 @@ pub enum MPURegType {
     RASR(u8),
 }
 
+/// the base address and attributes of an mpu region
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MPURegion {
+    pub rbar: RBAR,
+    pub rasr: RASR,
+}
+
+impl MPURegion {
+    /// true if the region is enabled and contains the address
+    /// in one of its enabled subregions
+    pub fn contains(&self, address: u32) -> bool {
+        if !self.rasr.enable() {
+            return false;
+        }
+        // sizes below 32 bytes are reserved
+        let lsbit = (self.rasr.size() + 1).max(5);
+        if lsbit < 32 && (address >> lsbit) != (self.rbar.into_bits() >> lsbit) {
+            return false;
+        }
+        // regions smaller than 256 bytes have no subregions
+        if lsbit < 8 {
+            return true;
+        }
+        let subregion = (address >> (lsbit - 3)) & 0b111;
+        self.rasr.srd() & (1 << subregion) == 0
+    }
+}
+
+/// mpu region state
 #[derive(Clone, Debug)]
 pub struct MPUState {
-
+    regions: Vec<MPURegion>,
 }
 
-impl Default for MPUState {
-    fn default() -> Self {
-        Self { }
+impl MPUState {
+    /// an mpu with `dregion` regions, which are disabled on reset
+    pub fn new(dregion: u8) -> Self {
+        Self { regions: vec![MPURegion::default(); dregion as usize] }
+    }
+
+    pub fn regions(&self) -> &[MPURegion] {
+        &self.regions[..]
+    }
+
+    /// the region that determines the attributes of an address.
+    /// where regions overlap, the highest numbered region takes priority.
+    pub fn lookup(&self, address: u32) -> Option<&MPURegion> {
+        self.regions.iter().rev().find(|region| region.contains(address))
     }
 }
 
 impl MPURegType {
     pub fn lookup_offset(offset: usize) -> Option<MPURegType> {
-        assert!((offset >= 0xd90) && (offset <= 0xdec), "offset not in mpu");
+        assert!((offset >= 0xd90) && (offset <= 0xdef), "offset not in mpu");
+        if offset & 0b11 != 0 {
+            // mpu registers are word-accessible only
+            return None;
+        }
         match offset {
             0xd90 => { Some(MPURegType::TYPE) }
             0xd94 => { Some(MPURegType::CTRL) }
@@ -49,8 +98,7 @@ impl MPURegType {
             0xdb0 => { Some(MPURegType::RASR(2)) }
             0xdb4 => { Some(MPURegType::RBAR(3)) }
             0xdb8 => { Some(MPURegType::RASR(3)) }
-            0xdbc..=0xdec => { None /* Reserved. */ }
-            _ => { unreachable!() }
+            _ => { None /* Reserved. */ }
         }
     }
 
@@ -72,18 +120,19 @@ impl MPURegType {
 
     fn _data(&self) -> &'static RegInfo {
         match self {
-            MPURegType::TYPE    => { &RegInfo { offset: 0xd90, perms: 0b100, reset: None } }
+            // 8 regions, as in the cortex-m3 and cortex-m4
+            MPURegType::TYPE    => { &RegInfo { offset: 0xd90, perms: 0b100, reset: Some(0x800) } }
             MPURegType::CTRL    => { &RegInfo { offset: 0xd94, perms: 0b110, reset: Some(0) } }
             MPURegType::RNR     => { &RegInfo { offset: 0xd98, perms: 0b110, reset: None } }
-            MPURegType::RBAR(0) => { &RegInfo { offset: 0xd9c, perms: 0b110, reset: None } }
-            MPURegType::RASR(0) => { &RegInfo { offset: 0xda0, perms: 0b110, reset: None } }
             MPURegType::RBAR(1) => { &RegInfo { offset: 0xda4, perms: 0b110, reset: None } }
             MPURegType::RASR(1) => { &RegInfo { offset: 0xda8, perms: 0b110, reset: None } }
             MPURegType::RBAR(2) => { &RegInfo { offset: 0xdac, perms: 0b110, reset: None } }
             MPURegType::RASR(2) => { &RegInfo { offset: 0xdb0, perms: 0b110, reset: None } }
             MPURegType::RBAR(3) => { &RegInfo { offset: 0xdb4, perms: 0b110, reset: None } }
             MPURegType::RASR(3) => { &RegInfo { offset: 0xdb8, perms: 0b110, reset: None } }
-            _ => { panic!("invalid reg type: {self:?}") }
+            // there are only 3 aliases, anything else is the original register
+            MPURegType::RBAR(_) => { &RegInfo { offset: 0xd9c, perms: 0b110, reset: None } }
+            MPURegType::RASR(_) => { &RegInfo { offset: 0xda0, perms: 0b110, reset: None } }
         }
     }
 }
@@ -93,11 +142,13 @@ impl MPURegType {
 /// used as temporary wrapper struct to interact with mpu registers
 /// and perform mpu-related register operations
 pub struct MPURegs<'a> {
-    backing: &'a [u32; 0xdec],
+    backing: &'a [u32; 0x37c],
+    state: &'a MPUState,
 }
 
 pub struct MPURegsMut<'a> {
-    backing: &'a mut [u32; 0xdec],
+    backing: &'a mut [u32; 0x37c],
+    state: &'a mut MPUState,
 }
 
 pub trait MPU {
@@ -186,34 +237,164 @@ impl<'a> MPUMut for MPURegsMut<'a> {
 }
 
 impl<'a> MPURegs<'a> {
-    pub fn new(backing: &'a [u32; 0xdec]) -> Self {
-        Self { backing }
+    pub fn new(backing: &'a [u32; 0x37c], state: &'a MPUState) -> Self {
+        Self { backing, state }
+    }
+
+    /// derived from ValidateAddress() (see B3.5.3)
+    /// 
+    /// returns true if the mpu permits the access. a negative execution
+    /// priority uses the default memory map unless HFNMIENA is set.
+    pub fn permits(&self,
+        address: u32,
+        access: Permission,
+        privileged: bool,
+        priority: i16,
+    ) -> bool {
+        let ctrl = self.get_ctrl();
+        if !ctrl.enable() || (!ctrl.hfnmiena() && priority < 0) {
+            return true;
+        }
+        // the private peripheral bus always uses the default memory map
+        if (0xe0000000..0xe0100000).contains(&address) {
+            return true;
+        }
+        let (ap, xn) = match self.state.lookup(address) {
+            Some(region) => { (region.rasr.ap(), region.rasr.xn()) }
+            // the default memory map is a background region for privileged accesses
+            None if ctrl.privdefena() && privileged => { (0b011, _default_xn(address)) }
+            None => { return false; }
+        };
+        let (read, write) = match (ap, privileged) {
+            (0b001 | 0b010 | 0b011, true) => { (true, true) }
+            (0b011, false) => { (true, true) }
+            (0b010, false) => { (true, false) }
+            (0b101, true) | (0b110 | 0b111, _) => { (true, false) }
+            // 0b100 is reserved
+            _ => { (false, false) }
+        };
+        match access {
+            Permission::W => { write }
+            Permission::E => { read && !xn }
+            _ => { read }
+        }
     }
 }
 
+/// execute never regions of the default memory map (see B3.1)
+fn _default_xn(address: u32) -> bool {
+    matches!(address >> 28, 0x4 | 0x5 | 0xa..=0xf)
+}
+
 impl<'a> MPURegsMut<'a> {
-    pub fn new(backing: &'a mut [u32; 0xdec]) -> Self {
-        Self { backing }
+    pub fn new(backing: &'a mut [u32; 0x37c], state: &'a mut MPUState) -> Self {
+        Self { backing, state }
     }
 
     /// perform an event-triggering read of mpu register bytes
-    #[allow(unused)]
     pub fn read_bytes(&mut self,
         offset: usize,
         dst: &mut [u8],
         _events: &mut VecDeque<Event>,
     ) -> Result<(), backend::Error> {
-        todo!()
+        let address = BASE + offset as u32;
+        check_alignment(address, dst.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let reg = MPURegType::lookup_offset(offset)
+            .ok_or_else(| | {
+                backend::Error::from(Error::InvalidSysCtrlReg(address.into()))
+            })?;
+        if reg != MPURegType::TYPE && self.get_type().dregion() == 0 {
+            // mpu not implemented
+            dst.fill(0);
+            return Ok(());
+        }
+        // region registers and their aliases mirror the selected region
+        dst.copy_from_slice(self.view_bytes(offset / 4));
+        Ok(())
     }
 
     /// perform an event-triggering write of mpu register bytes
-    #[allow(unused)]
     pub fn write_bytes(&mut self,
         offset: usize,
         src: &[u8],
-        events: &mut VecDeque<Event>,
+        _events: &mut VecDeque<Event>,
     ) -> Result<(), backend::Error> {
-        todo!()
+        let address = BASE + offset as u32;
+        check_alignment(address, src.len(), Alignment::Word)
+            .map_err(|(address, size, expected)| {
+                Error::AlignmentViolation(address, size, expected)
+            })?;
+        let reg = MPURegType::lookup_offset(offset)
+            .ok_or_else(| | {
+                backend::Error::from(Error::InvalidSysCtrlReg(address.into()))
+            })?;
+        let write_val = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        let dregion = self.get_type().dregion();
+        if dregion == 0 {
+            // mpu not implemented
+            return Ok(());
+        }
+        match reg {
+            MPURegType::CTRL => {
+                *self.get_ctrl_mut() = CTRL::from_bits(write_val & 0b111);
+            }
+            MPURegType::RNR => {
+                let region = RNR::from_bits(write_val).region();
+                if region >= dregion {
+                    warn!("unpredictable mpu region number: {region}");
+                }
+                self.get_rnr_mut().set_region(region);
+            }
+            MPURegType::RBAR(_) => {
+                let rbar = RBAR::from_bits(write_val);
+                if rbar.valid() {
+                    // update the region number before the base address
+                    self.get_rnr_mut().set_region(rbar.region());
+                }
+                let base = RBAR::new().with_addr(rbar.addr());
+                if let Some(region) = self._selected_region_mut() {
+                    region.rbar = base;
+                }
+            }
+            MPURegType::RASR(_) => {
+                let rasr = RASR::from_bits(write_val);
+                if let Some(region) = self._selected_region_mut() {
+                    region.rasr = rasr;
+                }
+            }
+            MPURegType::TYPE => {
+                // read-only, writes are ignored
+                return Ok(());
+            }
+        }
+        self._mirror_selected_region();
+        Ok(())
+    }
+
+    fn _selected_region_mut(&mut self) -> Option<&mut MPURegion> {
+        let region = self.get_rnr().region();
+        let selected = self.state.regions.get_mut(region as usize);
+        if selected.is_none() {
+            warn!("mpu region {region} not implemented");
+        }
+        selected
+    }
+
+    /// update the RBAR and RASR aliases to reflect the selected region
+    fn _mirror_selected_region(&mut self) {
+        let region = self.get_rnr().region();
+        let selected = self.state.regions.get(region as usize)
+            .copied()
+            .unwrap_or_default();
+        // RBAR.REGION reads as bits [3:0] of RNR
+        let rbar = selected.rbar.with_region(region & 0xf);
+        for alias in 0..4 {
+            *self.get_rbar_mut(alias) = rbar;
+            *self.get_rasr_mut(alias) = selected.rasr;
+        }
     }
 }
 
@@ -167,7 +167,7 @@ impl SCRegType {
             0xd1c_usize..=0xd1f => { Some(SCRegType::SHPR2((offset - 0xd1c +  8) as u8)) }
             0xd20_usize..=0xd23 => { Some(SCRegType::SHPR3((offset - 0xd20 + 12) as u8)) }
             0xd24_usize => { Some(SCRegType::SHCSR) }
-            0xd28_usize => { Some(SCRegType::CFSR) }
+            0xd28_usize..=0xd2b => { Some(SCRegType::CFSR) }
             0xd2c_usize => { Some(SCRegType::HFSR) }
             0xd30_usize => { Some(SCRegType::DFSR) }
             0xd34_usize => { Some(SCRegType::MMFAR) }
@@ -242,15 +242,15 @@ impl SCRegType {
             SCRegType::AFSR     => { &RegInfo { offset: 0xd3c_usize, perms: 0b110, reset: None } }
             SCRegType::CPACR    => { &RegInfo { offset: 0xd88_usize, perms: 0b110, reset: None } }
             
-            // SCRegType::FPCCR     => { &RegInfo { offset: 0xf34_usize, perms: 0b110, reset: Some(0x0) } }
-            // SCRegType::FPCAR     => { &RegInfo { offset: 0xf38_usize, perms: 0b110, reset: None } }
-            // SCRegType::FPDSCR    => { &RegInfo { offset: 0xf3c_usize, perms: 0b110, reset: Some(0x0) } }
-            // SCRegType::MVFR0     => { &RegInfo { offset: 0xf40_usize, perms: 0b100, reset: Some(0x0) } }
-            // SCRegType::MVFR1     => { &RegInfo { offset: 0xf44_usize, perms: 0b100, reset: Some(0x0) } }
-            // SCRegType::MVFR2     => { &RegInfo { offset: 0xf48_usize, perms: 0b100, reset: Some(0x0) } }
+            SCRegType::FPCCR    => { &RegInfo { offset: 0xf34_usize, perms: 0b110, reset: Some(0xc0000000) } }
+            SCRegType::FPCAR    => { &RegInfo { offset: 0xf38_usize, perms: 0b110, reset: None } }
+            SCRegType::FPDSCR   => { &RegInfo { offset: 0xf3c_usize, perms: 0b110, reset: Some(0x0) } }
+            SCRegType::MVFR0    => { &RegInfo { offset: 0xf40_usize, perms: 0b100, reset: Some(0x10110021) } }
+            SCRegType::MVFR1    => { &RegInfo { offset: 0xf44_usize, perms: 0b100, reset: Some(0x11000011) } }
+            SCRegType::MVFR2    => { &RegInfo { offset: 0xf48_usize, perms: 0b100, reset: Some(0x0) } }
             
             SCRegType::MCR      => { &RegInfo { offset: 0x000_usize, perms: 0b110, reset: Some(0x0) } }
-            SCRegType::ICTR     => { &RegInfo { offset: 0x004_usize, perms: 0b100, reset: None } }
+            SCRegType::ICTR     => { &RegInfo { offset: 0x004_usize, perms: 0b100, reset: Some(0xf) } }
             SCRegType::ACTLR    => { &RegInfo { offset: 0x008_usize, perms: 0b110, reset: None } }
             SCRegType::STIR     => { &RegInfo { offset: 0xf00_usize, perms: 0b010, reset: None } }
             SCRegType::PID4     => { &RegInfo { offset: 0xfd0_usize, perms: 0b100, reset: None } }
@@ -778,9 +778,7 @@ impl BFSR {
         if self.lsperr() {
             evts.push(Event::FaultStatusClr(BusFault::LazyStatePreservation.into()));
         }
-        if self.bfarvalid() {
-            unimplemented!("what happens on write to bfarvalid?");
-        }
+        // clearing BFARVALID only invalidates the BFAR
         evts
     }
 }
@@ -824,9 +822,7 @@ impl MMFSR {
         if self.mlsperr() {
             evts.push(Event::FaultStatusClr(MemFault::LazyStatePreservation.into()));
         }
-        if self.mmarvalid() {
-            unimplemented!("what happens on write to mmarvalid?");
-        }
+        // clearing MMARVALID only invalidates the MMFAR
         evts
     }
 }
@@ -943,7 +939,7 @@ pub struct CPACR {
     __: u8,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum CPACRAccess {
     Denied = 0b00,
@@ -965,6 +961,171 @@ impl CPACRAccess {
     }
 }
 
+impl CPACR {
+    /// access to the floating point extension, which is coprocessors 10 and 11.
+    /// returns None if the fields disagree, which is unpredictable.
+    pub fn fp_access(&self) -> Option<CPACRAccess> {
+        let (cp10, cp11) = (self.cp10(), self.cp11());
+        (cp10 == cp11).then_some(cp10)
+    }
+}
+
+/// floating point context control register
+/// 
+/// holds control data for the floating point extension
+/// and the state of lazy context preservation.
+/// 
+/// see B3.2.21
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+pub struct FPCCR {
+    /// lazy state preservation is active
+    #[bits(1)]
+    pub lspact: bool,
+    /// privilege level was user when the fp stack frame was allocated
+    #[bits(1)]
+    pub user: bool,
+    #[bits(1)]
+    __: bool,
+    /// mode was thread when the fp stack frame was allocated
+    #[bits(1)]
+    pub thread: bool,
+    /// priority permitted setting hardfault pending when the fp stack frame was allocated
+    #[bits(1)]
+    pub hfrdy: bool,
+    /// memmanage was enabled and priority permitted setting it pending
+    /// when the fp stack frame was allocated
+    #[bits(1)]
+    pub mmrdy: bool,
+    /// busfault was enabled and priority permitted setting it pending
+    /// when the fp stack frame was allocated
+    #[bits(1)]
+    pub bfrdy: bool,
+    #[bits(1)]
+    __: bool,
+    /// debugmonitor was enabled and priority permitted setting it pending
+    /// when the fp stack frame was allocated
+    #[bits(1)]
+    pub monrdy: bool,
+    #[bits(21)]
+    __: u32,
+    /// enable lazy context save of fp state
+    #[bits(1)]
+    pub lspen: bool,
+    /// execution of an fp instruction sets CONTROL.FPCA
+    #[bits(1)]
+    pub aspen: bool,
+}
+
+/// floating point context address register
+/// 
+/// holds the address of the space allocated on the stack
+/// for the lazily preserved fp state.
+/// 
+/// see B3.2.22
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+pub struct FPCAR {
+    #[bits(3)]
+    __: u8,
+    /// bits [31:3] of the address of the unpopulated fp register space
+    #[bits(29)]
+    pub address: u32,
+}
+
+/// floating point default status control register
+/// 
+/// holds the default values for the fpscr of a new context.
+/// 
+/// see B3.2.23
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+pub struct FPDSCR {
+    #[bits(22)]
+    __: u32,
+    /// default value for fpscr.rmode
+    #[bits(2)]
+    pub rmode: u8,
+    /// default value for fpscr.fz
+    #[bits(1)]
+    pub fz: bool,
+    /// default value for fpscr.dn
+    #[bits(1)]
+    pub dn: bool,
+    /// default value for fpscr.ahp
+    #[bits(1)]
+    pub ahp: bool,
+    #[bits(5)]
+    __: u8,
+}
+
+impl FPDSCR {
+    /// mask of the fpscr bits taken from the fpdscr
+    pub const FPSCR_MASK: u32 = 0x07c00000;
+}
+
+/// media and fp feature register 0
+/// 
+/// describes the fp features of the implementation. read-only.
+/// 
+/// see B4.6.3
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+pub struct MVFR0 {
+    #[bits(4)]
+    pub simd_registers: u8,
+    #[bits(4)]
+    pub single_precision: u8,
+    #[bits(4)]
+    pub double_precision: u8,
+    #[bits(4)]
+    pub fp_exception_trapping: u8,
+    #[bits(4)]
+    pub divide: u8,
+    #[bits(4)]
+    pub square_root: u8,
+    #[bits(4)]
+    pub short_vectors: u8,
+    #[bits(4)]
+    pub fp_rounding_modes: u8,
+}
+
+/// media and fp feature register 1
+/// 
+/// describes the fp features of the implementation. read-only.
+/// 
+/// see B4.6.4
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+pub struct MVFR1 {
+    #[bits(4)]
+    pub ftz_mode: u8,
+    #[bits(4)]
+    pub d_nan_mode: u8,
+    #[bits(16)]
+    __: u32,
+    #[bits(4)]
+    pub fp_hpfp: u8,
+    #[bits(4)]
+    pub fp_fused_mac: u8,
+}
+
+/// media and fp feature register 2
+/// 
+/// describes the fp features of the implementation. read-only.
+/// 
+/// see B4.6.5
+#[bitfield(u32)]
+#[derive(PartialEq, Eq)]
+pub struct MVFR2 {
+    #[bits(4)]
+    __: u8,
+    #[bits(4)]
+    pub vfp_misc: u8,
+    #[bits(24)]
+    __: u32,
+}
+
 /// Provides information about the interrupt controller. 
 /// Word-accessible only. Read-only.
 ///
@@ -983,11 +1144,18 @@ impl ICTR {
     pub fn num_int_lines(&self) -> usize {
         (self.intlinesnum() + 1) * 32
     }
+
+    /// the smallest ICTR that supports the given number of interrupts
+    pub fn from_num_interrupts(num_interrupts: usize) -> Self {
+        let intlinesnum = num_interrupts.div_ceil(32).saturating_sub(1).min(0xf);
+        Self::new().with_intlinesnum(intlinesnum)
+    }
 }
 
 /// software triggered interrupt
 /// 
-/// same efect as setting NVIC IPSR interrupt bit to 1
+/// same effect as setting the interrupt's NVIC ISPR bit to 1.
+/// unprivileged writes are only permitted if CCR.USERSETMPEND is set.
 #[bitfield(u32)]
 #[derive(PartialEq, Eq)]
 pub struct STIR {
@@ -1003,7 +1171,7 @@ impl STIR {
     }
 
     pub fn write_evt(&self) -> Vec<Event> {
-        vec![Event::ExceptionSetActive(ExceptionType::ExternalInterrupt(self.exception_number()), true)]
+        vec![Event::ExceptionSetPending(ExceptionType::ExternalInterrupt(self.intid()), true)]
     }
 }
 
@@ -175,6 +175,22 @@ pub trait SysTick {
         let word_offset = SysTickRegType::CALIB.offset() / 4;
         unsafe { &*(self.view_bytes(word_offset) as *const [u8; 4] as *const u32 as *const CALIB) }
     }
+
+    /// cycles until the counter triggers the systick exception,
+    /// or None if it never will
+    fn cycles_to_interrupt(&self) -> Option<usize> {
+        let csr = self.get_csr();
+        if !csr.enable() || !csr.tickint() {
+            return None;
+        }
+        let reload = self.get_rvr().reload() as usize;
+        match self.get_cvr().current() as usize {
+            0 if reload == 0 => { None }
+            // the counter reloads on the next tick
+            0 => { Some(reload + 1) }
+            current => { Some(current) }
+        }
+    }
 }
 
 /// SysTick register exclusive reference accessor trait
@@ -264,6 +280,31 @@ impl<'a> SysTickRegsMut<'a> {
         false
     }
 
+    /// advance the counter as if it was ticked the given number of times.
+    /// the cycles must end before the next systick exception.
+    pub fn skip(&mut self, cycles: usize) {
+        if !self.get_csr().enable() || cycles == 0 {
+            return;
+        }
+        let reload = self.get_rvr().reload() as usize;
+        let current = self.get_cvr().current() as usize;
+        let (current, wrapped) = if cycles <= current {
+            (current - cycles, cycles == current)
+        } else if reload == 0 {
+            (0, current > 0)
+        } else {
+            // from 0 the counter reloads, then counts down to 0 again
+            let period = reload + 1;
+            let rem = (cycles - current) % period;
+            let next = if rem == 0 { 0 } else { period - rem };
+            (next, current > 0 || cycles - current >= period)
+        };
+        self.get_cvr_mut().set_current(current as u32);
+        if wrapped {
+            self.get_csr_mut().set_countflag(true);
+        }
+    }
+
     /// perform an event-triggering read of systick register bytes
     pub fn read_bytes(&mut self,
         offset: usize,
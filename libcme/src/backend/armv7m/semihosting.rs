@@ -0,0 +1,221 @@
+//! semihosting.rs
+//!
+//! arm semihosting calls
+//!
+//! on m-profile a semihosting call is made with BKPT 0xAB. the operation
+//! number is in r0 and r1 holds either the single parameter or a pointer
+//! to a block of parameter words. the result is returned in r0.
+//! the host side of each operation is in backend::semihosting.
+use crate::backend::{self, HaltReason};
+use crate::backend::semihosting::{Operation, Semihosting, EINVAL};
+use crate::peripheral::{self, channel::ChannelStateError};
+use super::*;
+
+/// BKPT immediate that makes a semihosting call
+pub const SEMIHOSTING_BKPT: u8 = 0xab;
+/// longest null-terminated string read from a semihosting call
+const MAX_STR_LEN: usize = 0x1000;
+/// longest buffer filled by a single SYS_READ; longer reads are short
+const MAX_READ_LEN: usize = 0x10000;
+
+impl Backend {
+    /// true if semihosting calls are handled
+    pub fn have_semihosting(&self) -> bool {
+        self.semihosting.is_some()
+    }
+
+    /// perform the semihosting call in r0 and r1
+    pub(crate) fn _semihosting_call(&mut self) -> Result<(), backend::Error> {
+        let num = self._read_core_reg("r0")?;
+        let param = self._read_core_reg("r1")?;
+        let Some(op) = Operation::lookup(num) else {
+            warn!("unsupported semihosting operation: {num:#x}");
+            let result = self._semihosting()?.fail(EINVAL);
+            return self._write_core_reg("r0", result as u32);
+        };
+        debug!("semihosting {op:?} ({param:#x})");
+
+        let result = match op {
+            Operation::Open => {
+                let [name, mode, len] = self._read_params(param)?;
+                let name = self._read_str(name, len as usize)?;
+                self._semihosting()?.open(&name, mode) as u32
+            }
+            Operation::Close => {
+                let [handle] = self._read_params(param)?;
+                self._semihosting()?.close(handle) as u32
+            }
+            Operation::WriteC => {
+                let byte = self._read_mem(param, 1)?;
+                self._semihosting()?.write_console(&byte);
+                return Ok(());
+            }
+            Operation::Write0 => {
+                let s = self._read_cstr(param)?;
+                self._semihosting()?.write_console(&s);
+                return Ok(());
+            }
+            Operation::Write => {
+                let [handle, buf, len] = self._read_params(param)?;
+                let src = self._read_mem(buf, len as usize)?;
+                self._semihosting()?.write(handle, &src)
+            }
+            Operation::Read => {
+                let [handle, buf, len] = self._read_params(param)?;
+                // the length comes from the firmware, so don't trust it
+                let mut dst = vec![0u8; (len as usize).min(MAX_READ_LEN)];
+                let reads_input = self._semihosting()?.reads_input(handle);
+                let not_read = self._semihosting()?.read(handle, &mut dst)
+                    .map_err(|err| _input_error(buf, err))?;
+                let read = dst.len() - not_read as usize;
+                self.store_bytes(&Address::from(buf), &dst[..read])?;
+                if reads_input && read > 0 {
+                    let spc = self.lang.translator().manager().default_space();
+                    let mem = VarnodeData::new(spc.as_ref(), buf as u64, read);
+                    self.input_writes.push(mem);
+                }
+                len - read as u32
+            }
+            Operation::ReadC => {
+                let mut byte = [0u8; 1];
+                let read = self._semihosting()?.read_console(&mut byte)
+                    .map_err(|err| _input_error(param, err))?;
+                if read == 0 {
+                    u32::MAX
+                } else {
+                    let r0 = self.lang.translator().register_by_name("r0").unwrap();
+                    self.input_writes.push(r0);
+                    byte[0] as u32
+                }
+            }
+            Operation::IsError => {
+                let [status] = self._read_params(param)?;
+                ((status as i32) < 0) as u32
+            }
+            Operation::IsTTY => {
+                let [handle] = self._read_params(param)?;
+                self._semihosting()?.is_tty(handle) as u32
+            }
+            Operation::Seek => {
+                let [handle, pos] = self._read_params(param)?;
+                self._semihosting()?.seek(handle, pos) as u32
+            }
+            Operation::FLen => {
+                let [handle] = self._read_params(param)?;
+                self._semihosting()?.flen(handle) as u32
+            }
+            Operation::Remove => {
+                let [name, len] = self._read_params(param)?;
+                let name = self._read_str(name, len as usize)?;
+                self._semihosting()?.remove(&name) as u32
+            }
+            Operation::Rename => {
+                let [from, from_len, to, to_len] = self._read_params(param)?;
+                let from = self._read_str(from, from_len as usize)?;
+                let to = self._read_str(to, to_len as usize)?;
+                self._semihosting()?.rename(&from, &to) as u32
+            }
+            Operation::TmpNam | Operation::System => {
+                warn!("semihosting {op:?} unsupported");
+                self._semihosting()?.fail(EINVAL) as u32
+            }
+            Operation::Clock => { self._semihosting()?.clock() }
+            Operation::Time => { self._semihosting()?.time() }
+            Operation::Errno => { self._semihosting()?.errno() as u32 }
+            Operation::GetCmdline => {
+                let [buf, len] = self._read_params(param)?;
+                let mut cmdline = self._semihosting()?.cmdline().as_bytes().to_vec();
+                if cmdline.len() + 1 > len as usize {
+                    self._semihosting()?.fail(EINVAL) as u32
+                } else {
+                    let cmdline_len = cmdline.len() as u32;
+                    cmdline.push(0);
+                    self.store_bytes(&Address::from(buf), &cmdline)?;
+                    self.store_bytes(&Address::from(param + 4), &cmdline_len.to_le_bytes())?;
+                    0
+                }
+            }
+            Operation::HeapInfo => {
+                // zeros let the c library pick its own heap and stack
+                let [block] = self._read_params(param)?;
+                self.store_bytes(&Address::from(block), &[0u8; 16])?;
+                return Ok(());
+            }
+            Operation::Exit => {
+                // on aarch32 the parameter is the reason code itself
+                self._halt(HaltReason::Exit { reason: param, code: 0 });
+                return Ok(());
+            }
+            Operation::ExitExtended => {
+                let [reason, code] = self._read_params(param)?;
+                self._halt(HaltReason::Exit { reason, code });
+                return Ok(());
+            }
+            Operation::Elapsed => {
+                let elapsed = self._semihosting()?.elapsed();
+                self.store_bytes(&Address::from(param), &elapsed.to_le_bytes())?;
+                0
+            }
+            Operation::TickFreq => { self._semihosting()?.tick_freq() }
+        };
+        self._write_core_reg("r0", result)
+    }
+
+    fn _semihosting(&mut self) -> Result<&mut Semihosting, backend::Error> {
+        self.semihosting.as_mut()
+            .ok_or_else(| | Error::System("semihosting call without semihosting attached").into())
+    }
+
+    /// read a block of parameter words
+    fn _read_params<const N: usize>(&mut self, address: u32) -> Result<[u32; N], backend::Error> {
+        let mut params = [0u32; N];
+        for (i, param) in params.iter_mut().enumerate() {
+            let bytes = self._read_mem(address + i as u32 * 4, 4)?;
+            *param = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Ok(params)
+    }
+
+    fn _read_mem(&mut self, address: u32, len: usize) -> Result<Vec<u8>, backend::Error> {
+        let mut dst = vec![0u8; len];
+        self.load_bytes(&Address::from(address), &mut dst)?;
+        Ok(dst)
+    }
+
+    fn _read_str(&mut self, address: u32, len: usize) -> Result<String, backend::Error> {
+        let bytes = self._read_mem(address, len)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// read a null-terminated string
+    fn _read_cstr(&mut self, address: u32) -> Result<Vec<u8>, backend::Error> {
+        let mut s = vec![];
+        for i in 0..MAX_STR_LEN as u32 {
+            let byte = self._read_mem(address + i, 1)?[0];
+            if byte == 0 {
+                break;
+            }
+            s.push(byte);
+        }
+        Ok(s)
+    }
+
+    pub(crate) fn _read_core_reg(&self, name: &str) -> Result<u32, backend::Error> {
+        let reg = self.lang.translator().register_by_name(name).unwrap();
+        let val = self.regs.read_val_with(reg.offset() as usize, reg.size(), self.endian)?;
+        val.to_u32().ok_or_else(|| backend::Error::AddressInvalid(val))
+    }
+
+    pub(crate) fn _write_core_reg(&mut self, name: &str, val: u32) -> Result<(), backend::Error> {
+        let reg = self.lang.translator().register_by_name(name).unwrap();
+        let val = BitVec::from_u32(val, reg.bits());
+        Ok(self.regs.write_val_with(reg.offset() as usize, &val, self.endian)?)
+    }
+}
+
+/// a console read after the input is exhausted,
+/// reported the same way as a channel peripheral read
+fn _input_error(address: u32, err: crossbeam::channel::TryRecvError) -> backend::Error {
+    let err = ChannelStateError::Recv(Address::from(address), err);
+    backend::Error::from(peripheral::Error::from(err))
+}
@@ -0,0 +1,104 @@
+//! sleep.rs
+//!
+//! sleep state handling
+//!
+//! WFI and WFE suspend execution until a wakeup event (see B1.5.18 and
+//! B1.5.19). while suspended no instructions execute, and the clock is
+//! fast-forwarded to the next wakeup source in one step so that low-power
+//! event loops don't spend their cycle budget idling. SCR.SLEEPDEEP selects the same sleep
+//! state since clocks aren't gated.
+use crate::backend;
+use super::*;
+
+/// most cycles to fast-forward at once when no wakeup source is scheduled,
+/// so that control returns to the caller periodically while waiting on
+/// interrupts that may never come
+const MAX_SLEEP_CYCLES: usize = 0x10000;
+
+impl Backend {
+    /// advance the clock while sleeping, until a wakeup event occurs,
+    /// no wakeup is possible, or the cycle limit is reached.
+    /// returns the number of cycles that elapsed.
+    pub(crate) fn _fast_forward(&mut self) -> Result<usize, backend::Error> {
+        let mut cycles = 0;
+        while self.is_sleeping() {
+            if self._should_wake() {
+                debug!("woke from {:?} after {cycles} cycles", self.status);
+                self.status = Status::Alive;
+                break;
+            }
+            if cycles == MAX_SLEEP_CYCLES || !self.interrupts_possible() {
+                break;
+            }
+            // skip to the cycle that may raise the next event
+            let remaining = MAX_SLEEP_CYCLES - cycles;
+            let skip = self._cycles_until_event()
+                .map_or(remaining, |n| n.clamp(1, remaining));
+            self._skip_cycles(skip - 1)?;
+            self.tick()?;
+            self.process_events()?;
+            cycles += skip;
+        }
+        Ok(cycles)
+    }
+
+    /// cycles until the next tick that may raise an event,
+    /// or None if no time-dependent event is scheduled
+    fn _cycles_until_event(&mut self) -> Option<usize> {
+        let systick = self.scs.systick_regs().cycles_to_interrupt();
+        let dwt = if self.scs.debug_regs().get_demcr().trcena() {
+            self.dwt.cycles_to_match()
+        } else {
+            None
+        };
+        let peripherals = self.mmap.next_event();
+        [systick, dwt, peripherals].into_iter().flatten().min()
+    }
+
+    /// advance every clock by the given number of cycles without
+    /// ticking. the cycles must end before the next event.
+    fn _skip_cycles(&mut self, cycles: usize) -> Result<(), backend::Error> {
+        if cycles == 0 {
+            return Ok(());
+        }
+        self.scs.systick_regs_mut().skip(cycles);
+        if self.scs.debug_regs().get_demcr().trcena() {
+            self.dwt.skip(cycles);
+        }
+        if let Some(semihosting) = self.semihosting.as_mut() {
+            semihosting.skip(cycles);
+        }
+        self.mmap.skip(cycles)?;
+        Ok(())
+    }
+
+    /// true if the processor wakes from its current sleep state
+    fn _should_wake(&mut self) -> bool {
+        match self.status {
+            Status::WaitingForInterrupt => {
+                // pending exceptions wake the processor if they would
+                // preempt with PRIMASK clear, even if they aren't taken
+                let priority = self._execution_priority(false);
+                self._preempting_exception_pending(priority)
+            }
+            Status::WaitingForEvent => {
+                if self.event.0 {
+                    // the event is consumed by the WFE
+                    self.event.0 = false;
+                    return true;
+                }
+                self._preempting_exception_pending(self.current_priority())
+            }
+            _ => { true }
+        }
+    }
+
+    /// true if an enabled exception is pending with higher priority
+    /// than the given execution priority
+    fn _preempting_exception_pending(&self, priority: i16) -> bool {
+        self.scs.exceptions.pending().iter().any(|typ| {
+            self.scs.exception_enabled(*typ)
+                && self.scs.get_exception_priority(*typ).0 < priority
+        })
+    }
+}
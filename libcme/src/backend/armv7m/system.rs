@@ -31,8 +31,10 @@ impl Backend {
     }
 
     /// reset processor following pseudocode in B1.5.5
+    /// 
+    /// memory and peripherals are left as they are, as are the debug
+    /// components which are only reset on power-on.
     pub(crate) fn _take_reset(&mut self) -> Result<(), backend::Error> {
-        todo!("many more things need implementation before this will work!");
         /* 
          * TakeReset() pseudocode B1.5.5
          * CurrentMode = Mode_Thread;
@@ -88,8 +90,41 @@ impl Backend {
          * 
          * // see B1.4.7 for register-related global pseudocode definitions
          */
-
-
+        self.mode = Mode::Thread;
+        self.primask.set_pm(false);
+        self.faultmask.set_fm(false);
+        self.basepri.set_basepri(0);
+        self.control = CONTROL::default();
+        self.scs.reset();
+        if self.fp.is_some() {
+            self.scs.reset_fp_regs();
+        }
+        self._clear_exclusive_local()?;
+        self.event = EVENT::default();
+        self.events.clear();
+        self.status = Status::Alive;
+        self.halt_reason = None;
+        self.step_over_breakpoint = false;
+        self.strict_alignment = false;
+
+        let vtor = Address::from(self.scs.get_vtor().tbloff() << 7);
+        let vt = self.mmap.mem_view_bytes(&vtor, Some(8))
+            .map_err(|_| {
+                let msg = "failed to view vector table";
+                error!("{msg}: {vtor:#x?}");
+                super::Error::System(msg)
+            })?;
+        let sp_main = u32::from_le_bytes([vt[0], vt[1], vt[2], vt[3]]);
+        let reset_entry = u32::from_le_bytes([vt[4], vt[5], vt[6], vt[7]]);
+        self.main_sp = None;
+        self.proc_sp = None;
+        self.write_sp(&Address::from(sp_main & !0b11))?;
+        self._write_core_reg("lr", 0xffffffff)?;
+        self.xpsr.ipsr_mut().set_exception_number(0);
+        self.xpsr.epsr_mut().set_t((reset_entry & 1) == 1);
+        self.xpsr.epsr_mut().itstate().set(0);
+        self.write_pc(&Address::from(reset_entry & !1))?;
+        Ok(())
     }
 }
 
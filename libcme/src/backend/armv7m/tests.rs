@@ -93,4 +93,599 @@ fn test_systick_registers() -> Result<(), backend::Error> {
 
     info!("done.");
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_fire_interrupt() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    assert!(backend.enabled_interrupts().is_empty());
+    assert!(!backend.interrupts_possible());
+
+    info!("enabling interrupt 3 in ISER0...");
+    let address = Address::from(0xe000e000u64 + NVICRegType::ISER(0).offset() as u64);
+    let bytes = u32::to_le_bytes(1 << 3);
+    backend.store_bytes(&address, &bytes)?;
+    backend.process_events()?;
+    assert_eq!(backend.enabled_interrupts(), vec![3]);
+    assert!(backend.interrupts_possible());
+
+    info!("firing interrupt 3...");
+    backend.fire_interrupt(3)?;
+    backend.fire_interrupt(3)?;
+    let typ = ExceptionType::ExternalInterrupt(3);
+    assert_eq!(backend.scs.exceptions.pending(), &[typ]);
+
+    info!("masking interrupts...");
+    assert!(!backend.interrupts_masked());
+    backend.primask.set_pm(true);
+    assert!(backend.interrupts_masked());
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_fp_extended_frame() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    assert!(backend.have_fp_ext(), "cortex spec should define fp registers");
+    assert!(backend.scs.get_fpccr().lspen(), "lazy preservation enabled on reset");
+    let s0 = backend.translator().register_by_name("s0").unwrap();
+    let one = BitVec::from_u32(0x3f800000, 32);
+
+    info!("setting up memory...");
+    backend.map_mem(&Address::from(0x0u64), 0x1000usize)?;
+    backend.store_bytes(&Address::from(0x200u64), test::programs::TEST_PROG_SQUARE)?;
+    backend.store_bytes(&Address::from(15u64 * 4), &u32::to_le_bytes(0x301))?;
+    backend.write_pc(&Address::from(0x200u64))?;
+    backend.write_sp(&Address::from(0xf00u64))?;
+
+    info!("fp access with coprocessor disabled...");
+    assert!(matches!(backend.write(&s0, &one), Err(backend::Error::CoprocessorAccess)));
+    assert_eq!(backend.regs.read_val_with(s0.offset() as usize, s0.size(), backend.endian)?,
+        BitVec::from_u32(0, 32), "faulting access should not execute");
+    assert!(backend.fault(backend::FaultCause::CoprocessorAccess)?);
+    assert!(backend.scs.get_cfsr().usagefault().nocp(), "expected NOCP fault");
+    assert!(backend.scs.exceptions.pending().contains(&ExceptionType::HardFault),
+        "disabled UsageFault should escalate");
+    assert!(!backend.control.fpca());
+    backend.scs.clr_exception_pending(ExceptionType::HardFault);
+
+    info!("enabling fp in CPACR...");
+    backend.store_bytes(&SCRegType::CPACR.address(), &u32::to_le_bytes(0x00f00000))?;
+    assert_eq!(backend.scs.get_cpacr().fp_access(), Some(CPACRAccess::Full));
+    backend.write(&s0, &one)?;
+    assert!(backend.control.fpca(), "fp instruction should set FPCA");
+
+    info!("taking exception with lazy preservation...");
+    let switch = backend.exception_entry(ExceptionType::SysTick)
+        .map_err(backend::Error::from)?;
+    assert!(switch.fp_frame);
+    assert_eq!(switch.new_frame_address, Address::from(0xf00u64 - 0x68));
+    let lr_vnd = backend.translator().register_by_name("lr").unwrap();
+    assert_eq!(backend.read(&lr_vnd)?, BitVec::from_u32(0xffffffe9, 32));
+    assert!(backend.scs.get_fpccr().lspact());
+    assert_eq!(backend.scs.get_fpcar().address() << 3, 0xf00 - 0x68 + 0x20);
+    assert!(!backend.control.fpca());
+
+    info!("handler fp access preserves the context...");
+    backend.write(&s0, &BitVec::from_u32(0, 32))?;
+    assert!(!backend.scs.get_fpccr().lspact());
+    let saved = backend.load(&Address::from(0xf00u64 - 0x68 + 0x20), 4)?;
+    assert_eq!(saved, one, "s0 should be saved to the reserved space");
+
+    info!("returning from exception...");
+    let switch = backend.exception_return(EXC_RETURN::from_bits(0xffffffe9))
+        .map_err(backend::Error::from)?;
+    assert!(switch.fp_frame);
+    assert_eq!(backend.read(&s0)?, one, "s0 should be restored");
+    assert_eq!(backend.read_sp()?, Address::from(0xf00u64));
+    assert!(backend.control.fpca());
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_fault_escalation() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x1000usize)?;
+    let address = Address::from(0x4000_0000u64);
+
+    info!("disabled BusFault escalates to HardFault...");
+    assert!(backend.fault(backend::FaultCause::DataAccess(address))?);
+    let bfsr = backend.scs.get_cfsr().busfault();
+    assert!(bfsr.preciserr() && bfsr.bfarvalid());
+    assert_eq!(backend.scs.get_bfar().address(), 0x4000_0000);
+    assert!(backend.scs.get_hfsr().forced());
+    assert_eq!(backend.scs.exceptions.pending(), &[ExceptionType::HardFault]);
+
+    info!("reading BFSR byte...");
+    let mut bfsr_byte = [0u8; 1];
+    backend.load_bytes(&Address::from(0xe000ed29u64), &mut bfsr_byte)?;
+    assert_eq!(bfsr_byte, [0x82]);
+
+    info!("clearing fault status...");
+    backend.store_bytes(&SCRegType::CFSR.address(), &u32::to_le_bytes(0x8200))?;
+    backend.store_bytes(&SCRegType::HFSR.address(), &u32::to_le_bytes(1 << 30))?;
+    backend.process_events()?;
+    assert_eq!(backend.scs.get_cfsr().into_bits(), 0);
+    assert!(!backend.scs.get_hfsr().forced());
+    backend.scs.clr_exception_pending(ExceptionType::HardFault);
+
+    info!("enabled BusFault is taken directly...");
+    backend.store_bytes(&SCRegType::SHCSR.address(), &u32::to_le_bytes(1 << 17))?;
+    backend.process_events()?;
+    assert!(backend.fault(backend::FaultCause::DataAccess(address))?);
+    assert!(!backend.scs.get_hfsr().forced());
+    assert_eq!(backend.scs.exceptions.pending(), &[ExceptionType::BusFault]);
+    backend.scs.clr_exception_pending(ExceptionType::BusFault);
+
+    info!("divide by zero only faults when trapped...");
+    assert!(!backend.fault(backend::FaultCause::DivideByZero)?);
+
+    info!("fault with FAULTMASK set locks up...");
+    backend.faultmask.set_fm(true);
+    assert!(backend.fault(backend::FaultCause::UndefinedInsn).is_err());
+    assert_eq!(backend.status, Status::Lockup);
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_mpu() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x4000usize)?;
+    let mpu_reg = |reg: MPURegType| SCRegType::MPU(reg).address();
+    let read_word = |backend: &mut Backend, address: Address| {
+        let mut word = [0u8; 4];
+        backend.load_bytes(&address, &mut word).map(|_| u32::from_le_bytes(word))
+    };
+    assert_eq!(read_word(&mut backend, mpu_reg(MPURegType::TYPE))?, 0x800, "8 regions");
+    backend.store_bytes(&mpu_reg(MPURegType::TYPE), &u32::to_le_bytes(0))?;
+    assert_eq!(read_word(&mut backend, mpu_reg(MPURegType::TYPE))?, 0x800, "writes ignored");
+
+    info!("configuring regions...");
+    // region 0: 1kb at 0x0, full access
+    let rasr = RASR::new().with_enable(true).with_size(9).with_ap(0b011);
+    backend.store_bytes(&mpu_reg(MPURegType::RBAR(0)), &u32::to_le_bytes(0x0000_0010))?;
+    backend.store_bytes(&mpu_reg(MPURegType::RASR(0)), &u32::to_le_bytes(rasr.into_bits()))?;
+    // region 1: 256 bytes at 0x800, privileged only, execute never
+    let rasr = RASR::new().with_enable(true).with_size(7).with_ap(0b001).with_xn(true);
+    backend.store_bytes(&mpu_reg(MPURegType::RBAR(1)), &u32::to_le_bytes(0x0000_0811))?;
+    backend.store_bytes(&mpu_reg(MPURegType::RASR(1)), &u32::to_le_bytes(rasr.into_bits()))?;
+    assert_eq!(backend.scs.mpu.regions()[1].rbar.addr() << 5, 0x800);
+    assert_eq!(read_word(&mut backend, mpu_reg(MPURegType::RNR))?, 1, "rnr updated by rbar");
+    assert_eq!(read_word(&mut backend, mpu_reg(MPURegType::RBAR(2)))?, 0x801, "rbar alias");
+    backend.store_bytes(&mpu_reg(MPURegType::CTRL), &u32::to_le_bytes(0b001))?;
+
+    info!("checking privileged accesses...");
+    let data = Address::from(0x800u64);
+    backend.load(&data, 4)?;
+    assert!(backend.load(&Address::from(0x2000u64), 4).is_err(), "no background region");
+    assert!(backend.check_fetch(&data).is_err(), "execute never");
+    backend.check_fetch(&Address::from(0x100u64))?;
+
+    info!("checking unprivileged accesses...");
+    backend.control.set_npriv(true);
+    assert!(matches!(backend.load(&data, 4),
+        Err(backend::Error::AccessViolation(_, Permission::R))));
+    backend.store(&Address::from(0x100u64), &BitVec::from_u32(1, 32))?;
+
+    info!("checking subregions...");
+    // disable the first 128 bytes of region 0
+    let rasr = RASR::new().with_enable(true).with_size(9).with_ap(0b011).with_srd(0b1);
+    backend.store_bytes(&mpu_reg(MPURegType::RNR), &u32::to_le_bytes(0))?;
+    backend.store_bytes(&mpu_reg(MPURegType::RASR(3)), &u32::to_le_bytes(rasr.into_bits()))?;
+    assert!(backend.load(&Address::from(0x10u64), 4).is_err());
+    backend.load(&Address::from(0x80u64), 4)?;
+    // disable the second 128 bytes, inside an access that starts and ends in enabled ones
+    let rasr = rasr.with_srd(0b10);
+    backend.store_bytes(&mpu_reg(MPURegType::RASR(0)), &u32::to_le_bytes(rasr.into_bits()))?;
+    let mut block = [0u8; 0xa0];
+    assert!(backend.load_bytes(&Address::from(0x70u64), &mut block).is_err(), "disabled subregion");
+    assert!(backend.store_bytes(&Address::from(0x70u64), &block).is_err(), "disabled subregion");
+
+    info!("checking background region...");
+    backend.store_bytes(&mpu_reg(MPURegType::CTRL), &u32::to_le_bytes(0b101))?;
+    assert!(backend.load(&Address::from(0x2000u64), 4).is_err(), "unprivileged");
+    backend.control.set_npriv(false);
+    backend.load(&Address::from(0x2000u64), 4)?;
+
+    info!("checking instruction length...");
+    // a 32-bit instruction that ends in the execute never region
+    backend.store_bytes(&Address::from(0x7fcu64), &u32::to_le_bytes(0xf000bf00))?;
+    backend.check_fetch(&Address::from(0x7fcu64))?;
+    assert!(backend.check_fetch(&Address::from(0x7feu64)).is_err(), "bl prefix spans 0x800");
+
+    info!("violation raises MemManage fault...");
+    assert!(backend.fault(backend::FaultCause::AccessViolation(data, Permission::R))?);
+    let mmfsr = backend.scs.get_cfsr().memmanage();
+    assert!(mmfsr.daccviol() && mmfsr.mmarvalid());
+    assert_eq!(backend.scs.get_mmfar().address(), 0x800);
+
+    info!("stacking violation raises MemManage fault...");
+    backend.store_bytes(&Address::from(0x100u64), &u32::to_le_bytes(0xbf00bf00))?;
+    backend.store_bytes(&Address::from(15u64 * 4), &u32::to_le_bytes(0x101))?;
+    backend.write_pc(&Address::from(0x100u64))?;
+    backend.write_sp(&Address::from(0x3000u64))?;
+    backend.store_bytes(&mpu_reg(MPURegType::CTRL), &u32::to_le_bytes(0b001))?;
+    backend.exception_entry(ExceptionType::SysTick)
+        .map_err(backend::Error::from)?;
+    assert!(backend.scs.get_cfsr().memmanage().mstkerr());
+    assert_eq!(backend.mmap.mem_view_bytes(&Address::from(0x3000u64 - 0x8), Some(4))?, &[0u8; 4],
+        "frame should not be written");
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_debug_units() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x4000usize)?;
+    let write_word = |backend: &mut Backend, address: Address, val: u32| {
+        backend.store_bytes(&address, &val.to_le_bytes())
+    };
+    let read_word = |backend: &mut Backend, address: Address| {
+        let mut word = [0u8; 4];
+        backend.load_bytes(&address, &mut word).map(|_| u32::from_le_bytes(word))
+    };
+    let demcr = SCRegType::Debug(DebugRegType::DEMCR).address();
+    let dhcsr = SCRegType::Debug(DebugRegType::DHCSR).address();
+
+    info!("counting cycles...");
+    write_word(&mut backend, DWTRegType::CTRL.address(), 0b1)?;
+    backend.tick()?;
+    assert_eq!(read_word(&mut backend, DWTRegType::CYCCNT.address())?, 0, "dwt disabled");
+    write_word(&mut backend, demcr, DEMCR::new().with_trcena(true).into_bits())?;
+    backend.tick()?;
+    backend.tick()?;
+    assert_eq!(read_word(&mut backend, DWTRegType::CYCCNT.address())?, 2);
+
+    info!("checking data watchpoint...");
+    write_word(&mut backend, DWTRegType::COMP(1).address(), 0x100)?;
+    write_word(&mut backend, DWTRegType::MASK(1).address(), 2)?;
+    write_word(&mut backend, DWTRegType::FUNCTION(1).address(), 0b0110)?;
+    backend.load(&Address::from(0x100u64), 4)?;
+    backend.store(&Address::from(0x102u64), &BitVec::from_u16(1, 16))?;
+    backend.process_events()?;
+    let function = read_word(&mut backend, DWTRegType::FUNCTION(1).address())?;
+    assert!(DWT_FUNCTION::from_bits(function).matched());
+    let function = read_word(&mut backend, DWTRegType::FUNCTION(1).address())?;
+    assert!(!DWT_FUNCTION::from_bits(function).matched(), "cleared on read");
+    assert!(backend.scs.get_dfsr().dwttrap());
+    assert!(!backend.is_halted(), "no debugger or monitor");
+
+    info!("checking breakpoint...");
+    let bkpt = Address::from(0x200u64);
+    write_word(&mut backend, FPBRegType::CTRL.address(), 0b1)?;
+    backend.check_fetch(&bkpt)?;
+    write_word(&mut backend, FPBRegType::CTRL.address(), 0b11)?;
+    let comp = FP_COMP::new().with_enable(true).with_comp(0x200 >> 2).with_replace(0b01);
+    write_word(&mut backend, FPBRegType::COMP(0).address(), comp.into_bits())?;
+    backend.check_fetch(&Address::from(0x202u64))?;
+    let Err(backend::Error::Breakpoint(address)) = backend.check_fetch(&bkpt) else {
+        panic!("expected breakpoint");
+    };
+    assert!(backend.fault(backend::FaultCause::Breakpoint(address.clone()))?);
+    assert!(backend.scs.get_hfsr().debugevt(), "breakpoint escalated");
+    assert!(backend.scs.exceptions.pending().contains(&ExceptionType::HardFault));
+
+    info!("halting on breakpoint...");
+    write_word(&mut backend, dhcsr, 0x0000_0001)?;
+    assert!(!backend.scs.debug_regs().get_dhcsr().c_debugen(), "no debug key");
+    write_word(&mut backend, dhcsr, 0xa05f_0001)?;
+    assert!(backend.fault(backend::FaultCause::Breakpoint(address))?);
+    assert!(backend.is_halted());
+    assert!(backend.scs.get_dfsr().bkpt());
+
+    info!("reading registers in debug state...");
+    let pc = backend.translator().program_counter().clone();
+    backend.regs.write_val_with(pc.offset() as usize, &BitVec::from_u32(0x200, 32), backend.endian)?;
+    write_word(&mut backend, SCRegType::Debug(DebugRegType::DCRSR).address(), 15)?;
+    backend.process_events()?;
+    assert!(backend.scs.debug_regs().get_dhcsr().s_regrdy());
+    assert_eq!(read_word(&mut backend, SCRegType::Debug(DebugRegType::DCRDR).address())?, 0x200);
+
+    info!("resuming...");
+    write_word(&mut backend, dhcsr, 0xa05f_0001)?;
+    backend.process_events()?;
+    assert!(!backend.is_halted());
+    backend.check_fetch(&bkpt)?;
+    assert!(matches!(backend.check_fetch(&bkpt), Err(backend::Error::Breakpoint(_))),
+        "only the first fetch after resuming steps over the breakpoint");
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_semihosting() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x4000usize)?;
+    let (stdin_src, stdin) = crossbeam::channel::unbounded();
+    let (stdout, stdout_dst) = crossbeam::channel::unbounded();
+    let semihosting = backend::semihosting::Semihosting::new()
+        .with_stdin(stdin)
+        .with_stdout(stdout);
+    backend.attach_semihosting(semihosting)?;
+    let call = |backend: &mut Backend, op: u32, param: u32| {
+        backend._write_core_reg("r0", op)?;
+        backend._write_core_reg("r1", param)?;
+        backend._semihosting_call()?;
+        backend._read_core_reg("r0")
+    };
+
+    info!("writing to console...");
+    backend.store_bytes(&Address::from(0x100u64), b"hi\0")?;
+    call(&mut backend, 0x04, 0x100)?;
+    assert_eq!(stdout_dst.try_iter().collect::<Vec<u8>>(), b"hi");
+
+    info!("reading from console...");
+    stdin_src.send(b'x').unwrap();
+    assert_eq!(call(&mut backend, 0x07, 0)?, b'x' as u32);
+    let r0 = backend.lang.translator().register_by_name("r0").unwrap();
+    assert_eq!(backend.take_input_writes(), vec![r0]);
+
+    info!("reading from stdin handle...");
+    backend.store_bytes(&Address::from(0x300u64), b":tt")?;
+    for (i, word) in [0x300u32, 0, 3].iter().enumerate() {
+        backend.store_bytes(&Address::from(0x310u64 + i as u64 * 4), &word.to_le_bytes())?;
+    }
+    let handle = call(&mut backend, 0x01, 0x310)?;
+    stdin_src.send(b'y').unwrap();
+    stdin_src.send(b'z').unwrap();
+    for (i, word) in [handle, 0x400, u32::MAX].iter().enumerate() {
+        backend.store_bytes(&Address::from(0x320u64 + i as u64 * 4), &word.to_le_bytes())?;
+    }
+    assert_eq!(call(&mut backend, 0x06, 0x320)?, u32::MAX - 2, "huge reads are short");
+    let writes = backend.take_input_writes();
+    assert_eq!(writes.len(), 1);
+    assert_eq!((writes[0].offset(), writes[0].size()), (0x400, 2));
+
+    info!("exiting...");
+    assert!(!backend.is_halted());
+    let block = [backend::semihosting::ADP_STOPPED_APPLICATION_EXIT, 3];
+    backend.store_bytes(&Address::from(0x200u64), &block[0].to_le_bytes())?;
+    backend.store_bytes(&Address::from(0x204u64), &block[1].to_le_bytes())?;
+    call(&mut backend, 0x20, 0x200)?;
+    assert_eq!(backend.halt_reason(), Some(backend::HaltReason::Exit {
+        reason: backend::semihosting::ADP_STOPPED_APPLICATION_EXIT,
+        code: 3,
+    }));
+
+    info!("halting on plain breakpoint...");
+    backend.status = Status::Alive;
+    backend._breakpoint_insn(0x12)?;
+    assert_eq!(backend.halt_reason(), Some(backend::HaltReason::Breakpoint(0x12)));
+    assert!(backend.scs.get_dfsr().bkpt());
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_exclusive_monitor() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x4000usize)?;
+
+    info!("checking open access state...");
+    assert!(!backend._is_exclusive_local(0x100));
+
+    info!("checking tagged granule...");
+    backend._mark_exclusive_local(0x104);
+    assert!(backend._is_exclusive_local(0x100), "same granule");
+    assert!(!backend._is_exclusive_local(0x108), "different granule");
+
+    info!("checking intervening stores...");
+    backend.store(&Address::from(0x108u64), &BitVec::from_u32(1, 32))?;
+    assert!(backend._is_exclusive_local(0x104), "store outside granule");
+    backend.store(&Address::from(0x106u64), &BitVec::from_u16(1, 16))?;
+    assert!(!backend._is_exclusive_local(0x104), "store inside granule");
+
+    info!("checking clear...");
+    backend._mark_exclusive_local(0x200);
+    backend._clear_exclusive_local()?;
+    assert!(!backend._is_exclusive_local(0x200));
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_sleep() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x1000usize)?;
+
+    info!("sleeping with no wakeup possible...");
+    backend.status = Status::WaitingForInterrupt;
+    assert_eq!(backend.fast_forward()?, 0);
+    assert!(backend.is_sleeping());
+
+    info!("fast-forwarding to systick...");
+    backend.store_bytes(&SysTickRegType::RVR.address(), &u32::to_le_bytes(100))?;
+    backend.store_bytes(&SysTickRegType::CSR.address(), &u32::to_le_bytes(0b11))?;
+    backend.process_events()?;
+    let cycles = backend.fast_forward()?;
+    // one cycle to reload from 0, then 100 to count down
+    assert_eq!(cycles, 101, "woke in one step at the underflow");
+    assert_eq!(backend.scs.systick_regs().get_cvr().current(), 0);
+    assert!(!backend.is_sleeping());
+    assert!(backend.scs.exceptions.pending().contains(&ExceptionType::SysTick));
+
+    info!("waking wfi with primask set...");
+    backend.primask.set_pm(true);
+    backend.status = Status::WaitingForInterrupt;
+    assert_eq!(backend.fast_forward()?, 0, "pending systick wakes");
+    assert!(!backend.is_sleeping());
+
+    info!("waking wfe on sev...");
+    backend.status = Status::WaitingForEvent;
+    backend.events.push_back(Event::SEVInstructionExecuted);
+    backend.process_events()?;
+    backend.fast_forward()?;
+    assert!(!backend.is_sleeping());
+    assert!(!backend.event.0, "event consumed");
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_ccr_traps() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x1000usize)?;
+
+    info!("checking defaults...");
+    assert!(!backend.traps_divide_by_zero());
+    backend.load(&Address::from(0x102u64), 4)?;
+
+    info!("checking multiple and dual load/stores are always aligned...");
+    let insn = Address::from(0x40u64);
+    // ldm r0!, {r1, r2}
+    backend.store_bytes(&insn, &u16::to_le_bytes(0xc806))?;
+    backend.check_fetch(&insn)?;
+    assert!(matches!(backend.load(&Address::from(0x102u64), 4),
+        Err(backend::Error::Unaligned(_, 4))), "ldm");
+    // ldrd r1, r2, [r0]
+    backend.store_bytes(&insn, &u32::to_le_bytes(0x1200e9d0))?;
+    backend.check_fetch(&insn)?;
+    assert!(matches!(backend.load_bytes(&Address::from(0x102u64), &mut [0u8; 4]),
+        Err(backend::Error::Unaligned(_, 4))), "ldrd");
+    // ldr r1, [r0]
+    backend.store_bytes(&insn, &u16::to_le_bytes(0x6801))?;
+    backend.check_fetch(&insn)?;
+    backend.load(&Address::from(0x102u64), 4)?;
+
+    info!("writing CCR...");
+    let ccr = CCR::new()
+        .with_unalign_trp(true)
+        .with_div_0_trp(true)
+        .with_stkalign(true);
+    backend.store_bytes(&SCRegType::CCR.address(), &ccr.into_bits().to_le_bytes())?;
+    assert!(backend.events.contains(&Event::Stack8ByteAligned(true)));
+    backend.process_events()?;
+    assert!(backend.traps_divide_by_zero());
+
+    info!("checking unaligned accesses...");
+    backend.load(&Address::from(0x101u64), 1)?;
+    backend.load(&Address::from(0x104u64), 4)?;
+    let Err(backend::Error::Unaligned(address, 4)) = backend.load(&Address::from(0x102u64), 4) else {
+        panic!("expected unaligned access");
+    };
+    assert!(backend.fault(backend::FaultCause::UnalignedAccess(address))?);
+    assert!(backend.scs.get_cfsr().usagefault().unaligned());
+    assert!(matches!(backend.store_bytes(&Address::from(0x102u64), &[0u8; 4]),
+        Err(backend::Error::Unaligned(_, 4))), "store_bytes");
+
+    info!("done.");
+    Ok(())
+}
+
+#[test]
+fn test_aircr_stir_ictr() -> Result<(), backend::Error> {
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building backend...");
+    let mut backend = Backend::new_with(&builder, None)?;
+    backend.map_mem(&Address::from(0x0u64), 0x1000usize)?;
+    backend.store_bytes(&Address::from(0x0u64), &u32::to_le_bytes(0x800))?;
+    backend.store_bytes(&Address::from(0x4u64), &u32::to_le_bytes(0x101))?;
+
+    info!("reading ICTR...");
+    let mut ictr = [0u8; 4];
+    backend.load_bytes(&SCRegType::ICTR.address(), &mut ictr)?;
+    assert_eq!(ICTR::from_bits(u32::from_le_bytes(ictr)).num_int_lines(), 512);
+    assert_eq!(ICTR::from_num_interrupts(39).num_int_lines(), 64);
+
+    info!("writing AIRCR without VECTKEY...");
+    let aircr = AIRCR::new().with_sysresetreq(true);
+    backend.store_bytes(&SCRegType::AIRCR.address(), &aircr.into_bits().to_le_bytes())?;
+    assert!(backend.events.is_empty(), "write ignored");
+    let mut aircr_bytes = [0u8; 4];
+    backend.load_bytes(&SCRegType::AIRCR.address(), &mut aircr_bytes)?;
+    assert_eq!(u32::from_le_bytes(aircr_bytes), 0xfa050000);
+
+    info!("writing STIR...");
+    let stir = BitVec::from_u32(3, 32);
+    backend.store(&SCRegType::STIR.address(), &stir)?;
+    backend.process_events()?;
+    assert!(backend.scs.exceptions.pending().contains(&ExceptionType::ExternalInterrupt(3)));
+
+    info!("writing STIR unprivileged...");
+    backend.control.set_npriv(true);
+    let Err(backend::Error::Unprivileged(_)) = backend.store(&SCRegType::STIR.address(), &stir) else {
+        panic!("expected unprivileged access error");
+    };
+    assert!(matches!(backend.store_bytes(&SCRegType::STIR.address(), &3u32.to_le_bytes()),
+        Err(backend::Error::Unprivileged(_))), "store_bytes");
+    backend.control.set_npriv(false);
+    let ccr = CCR::new().with_usersetmpend(true);
+    backend.store_bytes(&SCRegType::CCR.address(), &ccr.into_bits().to_le_bytes())?;
+    backend.control.set_npriv(true);
+    backend.store(&SCRegType::STIR.address(), &stir)?;
+    backend.control.set_npriv(false);
+
+    info!("requesting system reset...");
+    backend.set_reset_policy(ResetPolicy::Reset);
+    backend.primask.set_pm(true);
+    let aircr = AIRCR::new().with_vectkey_stat(0x05fa).with_sysresetreq(true);
+    backend.store_bytes(&SCRegType::AIRCR.address(), &aircr.into_bits().to_le_bytes())?;
+    backend.process_events()?;
+    assert_eq!(backend.read_pc()?, Address::from(0x100u64));
+    assert_eq!(backend.read_sp()?, Address::from(0x800u64));
+    assert!(!backend.primask.pm());
+    assert!(backend.scs.exceptions.pending().is_empty());
+    assert!(!backend.scs.get_ccr().usersetmpend());
+    assert!(!backend.is_halted());
+    assert!(backend.take_reset());
+    assert!(!backend.take_reset(), "taken once");
+
+    info!("requesting system reset with halt policy...");
+    backend.set_reset_policy(ResetPolicy::Halt);
+    backend.store_bytes(&SCRegType::AIRCR.address(), &aircr.into_bits().to_le_bytes())?;
+    backend.process_events()?;
+    assert_eq!(backend.halt_reason(), Some(backend::HaltReason::Reset));
+    assert!(!backend.take_reset());
+
+    info!("done.");
+    Ok(())
+}
@@ -387,12 +387,30 @@ fn _software_interrupt(this: &mut Backend,
     Ok(None)
 }
 
+/// implements the BKPT instruction.
+/// (see BKPT instruction A7.7.17)
+/// 
+/// BKPT 0xAB is a semihosting call if semihosting is attached,
+/// otherwise a breakpoint debug event is generated.
+/// 
+/// inputs:
+/// - breakpoint immediate
+/// output:
+/// - none
 fn _software_bkpt(this: &mut Backend,
     index: usize,
     inputs: &[VarnodeData],
     output: Option<&VarnodeData>,
 ) -> Result<Option<Location>, backend::Error> {
-    todo!("unsupported userop: {}", _lookup_userop(index).name)
+    assert!(inputs.len() == 1, "software_bkpt expects exactly 1 input!");
+    assert!(output.is_none(), "software_bkpt has no output");
+    let imm = this.read(&inputs[0])?.to_u32().unwrap();
+    if imm == SEMIHOSTING_BKPT as u32 && this.have_semihosting() {
+        this._semihosting_call()?;
+    } else {
+        this._breakpoint_insn(imm)?;
+    }
+    Ok(None)
 }
 
 fn _software_udf(this: &mut Backend,
@@ -640,9 +658,13 @@ fn _has_exclusive_access(this: &mut Backend,
 ) -> Result<Option<Location>, backend::Error> {
     assert!(inputs.len() == 1, "has_exclusive_access expects exactly 1 input");
     assert!(output.is_some(), "has_exclusive_access has an output");
-    warn!("has_exclusive_access() is currently a stub that always returns 1");
+    let in0 = this.read(&inputs[0])?;
+    let address = in0.to_u32()
+        .ok_or_else(|| Error::InvalidAddress(in0))?;
     let out = output.unwrap();
-    let bv = bool2bv(true);
+    let bv = bool2bv(this._is_exclusive_local(address));
+    // the store exclusive clears the monitor whether or not it succeeds
+    this._clear_exclusive_local()?;
     this.write(out, &bv)?;
     Ok(None)
 }
@@ -719,12 +741,22 @@ fn _jazelle_branch(this: &mut Backend,
     unimplemented!("unsupported userop: {}", _lookup_userop(index).name)
 }
 
+/// implementation of CLREX instruction.
+/// (see CLREX instruction A7.7.23)
+/// 
+/// moves the local exclusive monitor to the open access state.
+/// 
+/// inputs:
+/// - none
+/// output:
+/// - none
 fn _clear_exclusive_local(this: &mut Backend,
     index: usize,
     inputs: &[VarnodeData],
     output: Option<&VarnodeData>,
 ) -> Result<Option<Location>, backend::Error> {
-    unimplemented!("unsupported userop: {}", _lookup_userop(index).name)
+    this._clear_exclusive_local()?;
+    Ok(None)
 }
 
 fn _hint_debug(this: &mut Backend,
@@ -933,12 +965,22 @@ fn _reverse_bit_order(this: &mut Backend,
     todo!("unsupported userop: {}", _lookup_userop(index).name)
 }
 
+/// implementation of SEV instruction.
+/// (see SEV instruction A7.7.129)
+/// 
+/// signals an event to all processors, setting the event register.
+/// 
+/// inputs:
+/// - none
+/// output:
+/// - none
 fn _send_event(this: &mut Backend,
     index: usize,
     inputs: &[VarnodeData],
     output: Option<&VarnodeData>,
 ) -> Result<Option<Location>, backend::Error> {
-    unimplemented!("unsupported userop: {}", _lookup_userop(index).name)
+    this.events.push_back(Event::SEVInstructionExecuted);
+    Ok(None)
 }
 
 fn _set_endian_state(this: &mut Backend,
@@ -997,9 +1039,9 @@ fn _index_check(this: &mut Backend,
     todo!("unsupported userop: {}", _lookup_userop(index).name)
 }
 
-/// if address has shareable memory attribute, mark the physical
-/// address as exclusive access for the executing processor in the
-/// global monitor.
+/// mark the address as exclusive access for the executing processor
+/// in the local monitor. there is only one processor, so the global
+/// monitor for shareable memory is not modeled.
 /// used in sleigh definitions of "ldrex" instructions.
 /// see A7.7.52 for LDREX instruction.
 /// see A3.4 for synchronization and semaphores.
@@ -1018,11 +1060,11 @@ fn _exclusive_access(this: &mut Backend,
 ) -> Result<Option<Location>, backend::Error> {
     assert!(inputs.len() == 1, "exclusive_access expects exactly 1 input");
     assert!(output.is_none(), "exclusive_address has no output");
-    warn!("exclusive_access() is currently a stub that does nothing");
     let in0 = this.read(&inputs[0])?;
-    let address = bv2addr(in0.clone())
+    let address = in0.to_u32()
         .ok_or_else(|| Error::InvalidAddress(in0))?;
-    info!("exclusive_access({address:#x?})");
+    debug!("exclusive_access({address:#x})");
+    this._mark_exclusive_local(address);
     Ok(None)
 }
 
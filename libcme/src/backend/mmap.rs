@@ -29,8 +29,30 @@ pub struct MemoryMap {
     mmap: IntervalMap<Address, MapIx>,
     mem: Vec<FixedState>,
     mmio: Vec<Peripheral>,
+    /// pages written since the last snapshot or restore
+    dirty: Vec<DirtyPages>,
+    /// id of the snapshot the dirty pages are tracked against
+    snapshot_id: Option<usize>,
 }
 
+/// a snapshot of the memory map
+/// 
+/// memory regions are copied in full when the snapshot is taken,
+/// but only pages dirtied since are copied back on restore.
+#[derive(Clone)]
+pub struct MemorySnapshot {
+    id: usize,
+    mmap: IntervalMap<Address, MapIx>,
+    mem: Vec<FixedState>,
+    mmio: Vec<Peripheral>,
+    dirty: Vec<DirtyPages>,
+}
+
+impl MemorySnapshot {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
 
 
 impl MemoryMap {
@@ -54,6 +76,7 @@ impl MemoryMap {
         let mem = FixedState::new(size);
         let idx = MapIx::Mem(self.mem.len());
         self.mem.push(mem);
+        self.dirty.push(DirtyPages::new(size));
         self.mmap.insert(range, idx);
 
         Ok(())
@@ -102,6 +125,22 @@ impl MemoryMap {
         Ok(())
     }
 
+    /// cycles until the next peripheral event, if any
+    pub fn next_event(&self) -> Option<usize> {
+        self.mmio.iter()
+            .filter_map(|peripheral| peripheral.next_event())
+            .min()
+    }
+
+    /// advance peripherals by the given number of cycles,
+    /// which end before the next peripheral event
+    pub fn skip(&mut self, cycles: usize) -> Result<(), backend::Error> {
+        for peripheral in self.mmio.iter_mut() {
+            peripheral.skip(cycles)?;
+        }
+        Ok(())
+    }
+
     pub fn mapped(&self) -> impl Iterator<Item=MappedRange> + use<'_> {
         self.mmap.iter(..)
             .map(|(range, ix)| {
@@ -171,7 +210,9 @@ impl MemoryMap {
                 let state = self.mem.get_mut(idx).unwrap();
                 let offset = (*address - range.start).offset() as usize;
                 state.write_bytes(offset, src)
-                    .map_err(backend::Error::from)
+                    .map_err(backend::Error::from)?;
+                self.dirty[idx].mark(offset, src.len());
+                Ok(())
             }
             MapIx::Mmio(idx) => {
                 let peripheral = self.mmio.get_mut(idx).unwrap();
@@ -214,6 +255,8 @@ impl MemoryMap {
             MapIx::Mem(idx) => {
                 let state = self.mem.get_mut(idx).unwrap();
                 let offset = (*address - range.start).offset() as usize;
+                // can't see what is written through the view, so assume all of it
+                self.dirty[idx].mark(offset, size);
                 state.view_bytes_mut(offset, size)
                     .map_err(backend::Error::from)
             }
@@ -223,6 +266,58 @@ impl MemoryMap {
         }
     }
 
+    /// take a snapshot of the memory map
+    /// 
+    /// dirty page tracking is reset relative to the new snapshot
+    pub fn snapshot(&mut self) -> MemorySnapshot {
+        let id = next_snapshot_id();
+        for dirty in self.dirty.iter_mut() {
+            dirty.clear();
+        }
+        self.snapshot_id = Some(id);
+        MemorySnapshot {
+            id,
+            mmap: self.mmap.clone(),
+            mem: self.mem.clone(),
+            mmio: self.mmio.clone(),
+            dirty: self.dirty.clone(),
+        }
+    }
+
+    /// restore the memory map from a snapshot
+    /// 
+    /// if the snapshot is the one dirty pages are being tracked against,
+    /// only the dirty pages are copied. otherwise all memory is copied.
+    /// peripherals are always restored in full.
+    #[instrument(skip_all)]
+    pub fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<(), backend::Error> {
+        if self.snapshot_id == Some(snapshot.id) && self.mem.len() == snapshot.mem.len() {
+            for (idx, dirty) in self.dirty.iter_mut().enumerate() {
+                let state = &mut self.mem[idx];
+                let saved = &snapshot.mem[idx];
+                for range in dirty.ranges() {
+                    let size = range.end - range.start;
+                    let src = saved.view_bytes(range.start, size)?;
+                    state.view_bytes_mut(range.start, size)?
+                        .copy_from_slice(src);
+                }
+                dirty.clear();
+            }
+        } else {
+            debug!("restoring from untracked snapshot {}, copying all memory", snapshot.id);
+            self.mmap = snapshot.mmap.clone();
+            self.mem = snapshot.mem.clone();
+            self.dirty = snapshot.dirty.clone();
+            self.snapshot_id = Some(snapshot.id);
+        }
+        self.mmio = snapshot.mmio.clone();
+        Ok(())
+    }
+
+    /// number of memory pages written since the last snapshot or restore
+    pub fn dirty_page_count(&self) -> usize {
+        self.dirty.iter().map(DirtyPages::count).sum()
+    }
 
     #[instrument(skip_all)]
     fn _get_mapped_region(&self, address: impl Into<Address>) -> Result<(Range<Address>, MapIx), backend::Error> {
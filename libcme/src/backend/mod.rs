@@ -1,6 +1,7 @@
 //! backend.rs
 //! 
 //! architecture backends must implement this trait to be used in context
+use std::any::Any;
 use std::sync::Arc;
 use std::ops::Range;
 use std::fmt;
@@ -19,9 +20,11 @@ use crate::types::*;
 use crate::peripheral::{self, Peripheral};
 
 pub mod mmap;
+pub mod semihosting;
 pub mod armv7m;
+pub mod armv6m;
 
-pub use mmap::MemoryMap;
+pub use mmap::{MemoryMap, MemorySnapshot};
 
 /// backend errors
 #[derive(Debug, Error, Clone)]
@@ -38,6 +41,18 @@ pub enum Error {
     AddressNotLifted(Address),
     #[error("address in unmapped memory: {0}")]
     Unmapped(Address),
+    #[error("{1:?} access violation @ {0}")]
+    AccessViolation(Address, Permission),
+    #[error("breakpoint @ {0}")]
+    Breakpoint(Address),
+    #[error("unaligned {1} byte access @ {0}")]
+    Unaligned(Address, usize),
+    #[error("unprivileged access @ {0}")]
+    Unprivileged(Address),
+    #[error("coprocessor access denied")]
+    CoprocessorAccess,
+    #[error("undefined instruction @ {0}")]
+    UndefinedInsn(Address),
     #[error("mapped regions conflict: {0:#x?} and {1:#x?}")]
     MapConflict(Range<Address>, Range<Address>),
     // #[error("out of bounds fixedstate read: [{offset:#x}; {size}]")]
@@ -62,6 +77,67 @@ pub struct ThreadSwitch {
     pub target_address: Address,
     pub return_address: Option<Address>,
     pub vtor: Option<Address>,
+    /// the exception frame includes the floating point context
+    pub fp_frame: bool,
+}
+
+/// an architectural fault condition detected while evaluating an instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaultCause {
+    /// the instruction couldn't be fetched
+    InsnFetch,
+    /// the instruction couldn't be decoded
+    UndefinedInsn,
+    /// a load or store accessed an unmapped address,
+    /// or one that isn't accessible at the current privilege
+    DataAccess(Address),
+    /// an access wasn't permitted by the memory protection
+    AccessViolation(Address, Permission),
+    /// the instruction matched a hardware breakpoint
+    Breakpoint(Address),
+    /// an unaligned access while unaligned accesses are trapped
+    UnalignedAccess(Address),
+    /// an integer division by zero
+    DivideByZero,
+    /// a coprocessor instruction while the coprocessor is disabled
+    CoprocessorAccess,
+}
+
+/// the reason the processor stopped executing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HaltReason {
+    /// entered debug state from a debug event or halt request
+    Debug,
+    /// executed a breakpoint instruction with the given immediate
+    /// that wasn't handled by a debugger or debug monitor
+    Breakpoint(u32),
+    /// the program exited through semihosting with a reason code
+    /// and subcode (see semihosting::ADP_STOPPED_APPLICATION_EXIT)
+    Exit { reason: u32, code: u32 },
+    /// the program requested a system reset
+    /// while the reset policy is to halt
+    Reset,
+}
+
+/// how a system reset requested by the program is handled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResetPolicy {
+    /// halt the processor with HaltReason::Reset
+    #[default]
+    Halt,
+    /// reset the processor and continue from the reset vector
+    Reset,
+}
+
+/// a backend state snapshot
+/// 
+/// the memory map is snapshotted separately so that it can be
+/// restored incrementally. the rest of the backend state is
+/// arch-specific and left to the backend to interpret.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub mmap: MemorySnapshot,
+    pub state: Arc<dyn Any>,
 }
 
 
@@ -90,6 +166,23 @@ pub trait Backend: fmt::Debug + DynClone {
     /// processes any events in backend event queue
     fn process_events(&mut self) -> Result<(), Error>;
 
+    /// raise the architectural fault for a fault condition in the current
+    /// instruction, to be taken on the next thread switch
+    /// 
+    /// returns false if the backend doesn't fault on the condition,
+    /// in which case the evaluator reports it as an error.
+    fn fault(&mut self, _cause: FaultCause) -> Result<bool, Error> { Ok(false) }
+
+    /// check that the instruction at the given address may be executed
+    /// 
+    /// called before every instruction, since lifted instructions
+    /// are cached and not fetched from the backend again.
+    fn check_fetch(&mut self, _address: &Address) -> Result<(), Error> { Ok(()) }
+
+    /// true if integer division by zero is a fault condition,
+    /// otherwise the result of the division is 0
+    fn traps_divide_by_zero(&self) -> bool { true }
+
     /// initialize a memory region in the context's memory map
     fn map_mem(&mut self, base: &Address, size: usize) -> Result<(), Error>;
 
@@ -138,6 +231,62 @@ pub trait Backend: fmt::Debug + DynClone {
     /// no implemented userops currently branch at all,
     /// but this is left as is for future support if necessry.
     fn userop(&mut self, output: Option<&VarnodeData>, inputs: &[VarnodeData]) -> Result<Option<Location>, Error>;
+
+    /// get the interrupt numbers of all currently enabled external interrupts
+    fn enabled_interrupts(&self) -> Vec<u32>;
+
+    /// pend an external interrupt, as if a peripheral had fired it
+    fn fire_interrupt(&mut self, int_num: u32) -> Result<(), Error>;
+
+    /// true if exceptions with configurable priority are currently masked
+    fn interrupts_masked(&self) -> bool;
+
+    /// true if any exception could still be raised without software
+    /// intervention, i.e. one is pending, enabled, or will be triggered by a timer
+    fn interrupts_possible(&self) -> bool;
+
+    /// true if the processor is suspended waiting for an interrupt or event
+    fn is_sleeping(&self) -> bool;
+
+    /// while the processor is sleeping, advance the clock until it wakes
+    /// instead of stepping through idle cycles.
+    /// 
+    /// returns the number of cycles that elapsed. the processor may still
+    /// be sleeping afterwards if no wakeup occurred within the backend's limit.
+    fn fast_forward(&mut self) -> Result<usize, Error> { Ok(0) }
+
+    /// the reason the processor is halted, if it is
+    fn halt_reason(&self) -> Option<HaltReason> { None }
+
+    /// true if the processor is halted
+    fn is_halted(&self) -> bool { self.halt_reason().is_some() }
+
+    /// attach a semihosting implementation to handle semihosting calls
+    /// 
+    /// backends that don't support semihosting ignore it.
+    fn attach_semihosting(&mut self, _semihosting: semihosting::Semihosting) -> Result<(), Error> { Ok(()) }
+
+    /// take the locations written with input bytes since the last call,
+    /// e.g. by semihosting console reads, so that they can be tainted
+    /// like reads from an input peripheral.
+    fn take_input_writes(&mut self) -> Vec<VarnodeData> { vec![] }
+
+    /// set how system reset requests are handled
+    /// 
+    /// backends that don't support system reset requests ignore it.
+    fn set_reset_policy(&mut self, _policy: ResetPolicy) { }
+
+    /// true if the program reset the backend since the last call,
+    /// so that state kept alongside it can be reset too
+    fn take_reset(&mut self) -> bool { false }
+
+    /// take a snapshot of the backend state
+    fn snapshot(&mut self) -> Snapshot;
+
+    /// restore the backend state from a snapshot
+    /// 
+    /// only memory pages written since the snapshot was taken are restored
+    fn restore(&mut self, snapshot: &Snapshot) -> Result<(), Error>;
 }
 clone_trait_object!(Backend);
 
@@ -191,6 +340,9 @@ impl<'backend> Backend for Box<dyn Backend + 'backend> {
     fn tick(&mut self) -> Result<(), Error> { (**self).tick() }
     fn maybe_thread_switch(&mut self) -> Option<ThreadSwitch> { (**self).maybe_thread_switch() }
     fn process_events(&mut self) -> Result<(), Error> { (**self).process_events() }
+    fn fault(&mut self, cause: FaultCause) -> Result<bool, Error> { (**self).fault(cause) }
+    fn check_fetch(&mut self, address: &Address) -> Result<(), Error> { (**self).check_fetch(address) }
+    fn traps_divide_by_zero(&self) -> bool { (**self).traps_divide_by_zero() }
     fn map_mem(&mut self, base: &Address, size: usize) -> Result<(), Error> { (**self).map_mem(base, size) }
     fn map_mmio(&mut self, peripheral: Peripheral) -> Result<(), Error> { (**self).map_mmio(peripheral) }
     fn mmap(&self) -> &MemoryMap { (**self).mmap() }
@@ -206,4 +358,18 @@ impl<'backend> Backend for Box<dyn Backend + 'backend> {
     fn load_bytes(&mut self, address: &Address, dst: &mut [u8]) -> Result<(), Error> { (**self).load_bytes(address, dst) }
     fn store_bytes<'a>(&mut self, address: &Address, bytes: &'a [u8]) -> Result<(), Error> { (**self).store_bytes(address, bytes) }
     fn userop(&mut self, output: Option<&VarnodeData>, inputs: &[VarnodeData]) -> Result<Option<Location>, Error> { (**self).userop(output, inputs) }
+    fn enabled_interrupts(&self) -> Vec<u32> { (**self).enabled_interrupts() }
+    fn fire_interrupt(&mut self, int_num: u32) -> Result<(), Error> { (**self).fire_interrupt(int_num) }
+    fn interrupts_masked(&self) -> bool { (**self).interrupts_masked() }
+    fn interrupts_possible(&self) -> bool { (**self).interrupts_possible() }
+    fn is_sleeping(&self) -> bool { (**self).is_sleeping() }
+    fn fast_forward(&mut self) -> Result<usize, Error> { (**self).fast_forward() }
+    fn halt_reason(&self) -> Option<HaltReason> { (**self).halt_reason() }
+    fn is_halted(&self) -> bool { (**self).is_halted() }
+    fn attach_semihosting(&mut self, semihosting: semihosting::Semihosting) -> Result<(), Error> { (**self).attach_semihosting(semihosting) }
+    fn take_input_writes(&mut self) -> Vec<VarnodeData> { (**self).take_input_writes() }
+    fn set_reset_policy(&mut self, policy: ResetPolicy) { (**self).set_reset_policy(policy) }
+    fn take_reset(&mut self) -> bool { (**self).take_reset() }
+    fn snapshot(&mut self) -> Snapshot { (**self).snapshot() }
+    fn restore(&mut self, snapshot: &Snapshot) -> Result<(), Error> { (**self).restore(snapshot) }
 }
\ No newline at end of file
@@ -0,0 +1,707 @@
+//! semihosting.rs
+//!
+//! host side of arm semihosting
+//!
+//! semihosting lets a program use the host's i/o through a trap
+//! instruction. the backend decodes the call and its parameter block,
+//! and the operation is carried out here.
+//!
+//! console reads come from the fuzz input, following the same end of
+//! input policy as the channel peripherals. console writes are sent to
+//! an optional channel, or logged. file i/o is confined to a sandbox
+//! directory, and without one every file open fails.
+//!
+//! files are read from the sandbox the first time they are used and
+//! are kept in memory from then on. the host directory is never changed,
+//! so file state is cloned and snapshotted with the backend and runs
+//! don't see each other's writes. the clock is derived from the cycle
+//! count so that runs are repeatable.
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
+use thiserror::Error;
+
+use crate::backend;
+use crate::utils::*;
+use crate::peripheral::channel::{EndOfInput, EndOfInputPolicy, InputCounter};
+
+/// SYS_EXIT reason for a normal application exit
+pub const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+/// SYS_EXIT reason for an unknown runtime error, e.g. abort()
+pub const ADP_STOPPED_RUNTIME_ERROR_UNKNOWN: u32 = 0x20023;
+
+/// contents of the `:semihosting-features` file,
+/// reporting support for SYS_EXIT_EXTENDED and separate stdout/stderr
+const FEATURES: [u8; 5] = [b'S', b'H', b'F', b'B', 0b11];
+
+const EBADF: i32 = 9;
+const ENOENT: i32 = 2;
+const EACCES: i32 = 13;
+pub const EINVAL: i32 = 22;
+const EIO: i32 = 5;
+const ENOSPC: i32 = 28;
+
+/// largest file that can be written in the sandbox
+const MAX_FILE_LEN: usize = 0x100_0000;
+
+#[derive(Debug, Error, Clone)]
+pub enum Error {
+    #[error("invalid clock rate: {0}")]
+    InvalidClockRate(u64),
+}
+
+impl From<Error> for backend::Error {
+    fn from(err: Error) -> Self {
+        backend::Error::Arch("semihosting", Arc::new(err.into()))
+    }
+}
+
+/// semihosting operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Open,
+    Close,
+    WriteC,
+    Write0,
+    Write,
+    Read,
+    ReadC,
+    IsError,
+    IsTTY,
+    Seek,
+    FLen,
+    TmpNam,
+    Remove,
+    Rename,
+    Clock,
+    Time,
+    System,
+    Errno,
+    GetCmdline,
+    HeapInfo,
+    Exit,
+    ExitExtended,
+    Elapsed,
+    TickFreq,
+}
+
+impl Operation {
+    /// lookup the operation for an operation number
+    pub fn lookup(num: u32) -> Option<Self> {
+        match num {
+            0x01 => { Some(Operation::Open) }
+            0x02 => { Some(Operation::Close) }
+            0x03 => { Some(Operation::WriteC) }
+            0x04 => { Some(Operation::Write0) }
+            0x05 => { Some(Operation::Write) }
+            0x06 => { Some(Operation::Read) }
+            0x07 => { Some(Operation::ReadC) }
+            0x08 => { Some(Operation::IsError) }
+            0x09 => { Some(Operation::IsTTY) }
+            0x0a => { Some(Operation::Seek) }
+            0x0c => { Some(Operation::FLen) }
+            0x0d => { Some(Operation::TmpNam) }
+            0x0e => { Some(Operation::Remove) }
+            0x0f => { Some(Operation::Rename) }
+            0x10 => { Some(Operation::Clock) }
+            0x11 => { Some(Operation::Time) }
+            0x12 => { Some(Operation::System) }
+            0x13 => { Some(Operation::Errno) }
+            0x15 => { Some(Operation::GetCmdline) }
+            0x16 => { Some(Operation::HeapInfo) }
+            0x18 => { Some(Operation::Exit) }
+            0x20 => { Some(Operation::ExitExtended) }
+            0x30 => { Some(Operation::Elapsed) }
+            0x31 => { Some(Operation::TickFreq) }
+            _ => { None }
+        }
+    }
+}
+
+/// an open semihosting file
+#[derive(Debug, Clone)]
+enum Handle {
+    Stdin,
+    Stdout,
+    Stderr,
+    /// the `:semihosting-features` file
+    Features { pos: usize },
+    /// a file in the sandbox, by its path relative to the sandbox
+    File { path: PathBuf, pos: u64, read: bool, write: bool, append: bool },
+}
+
+/// semihosting host state
+#[derive(Debug, Clone)]
+pub struct Semihosting {
+    stdin: Option<Receiver<u8>>,
+    end_of_input: EndOfInputPolicy,
+    /// counts the console bytes taken from the input
+    input_count: InputCounter,
+    stdout: Option<Sender<u8>>,
+    sandbox: Option<PathBuf>,
+    /// sandbox files that have been used, where None is a removed file
+    files: Files,
+    cmdline: String,
+    /// open handles, where handle n is at index n - 1
+    handles: Vec<Option<Handle>>,
+    errno: i32,
+    cycles: u64,
+    /// cycles per second
+    clock_rate: u64,
+    /// seconds since the unix epoch at cycle 0
+    epoch: u64,
+}
+
+impl Default for Semihosting {
+    fn default() -> Self {
+        Self {
+            stdin: None,
+            end_of_input: EndOfInputPolicy::default(),
+            input_count: InputCounter::new(),
+            stdout: None,
+            sandbox: None,
+            files: BTreeMap::new(),
+            cmdline: String::new(),
+            handles: vec![],
+            errno: 0,
+            cycles: 0,
+            clock_rate: 1_000_000,
+            epoch: 0,
+        }
+    }
+}
+
+impl Semihosting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// read console input from the given channel
+    pub fn with_stdin(mut self, stdin: Receiver<u8>) -> Self {
+        self.stdin = Some(stdin);
+        self
+    }
+
+    /// set the policy for console reads after the input runs dry
+    pub fn with_end_of_input(mut self, end_of_input: EndOfInputPolicy) -> Self {
+        self.end_of_input = end_of_input;
+        self
+    }
+
+    /// count console bytes with the given counter, e.g. one shared with
+    /// the channel peripheral that feeds the input
+    pub fn with_input_counter(mut self, input_count: InputCounter) -> Self {
+        self.input_count = input_count;
+        self
+    }
+
+    /// send console output to the given channel instead of the log
+    pub fn with_stdout(mut self, stdout: Sender<u8>) -> Self {
+        self.stdout = Some(stdout);
+        self
+    }
+
+    /// allow file i/o relative to the given directory.
+    /// its files can be read, but writes are only kept in memory.
+    pub fn with_sandbox(mut self, sandbox: impl Into<PathBuf>) -> Self {
+        self.sandbox = Some(sandbox.into());
+        self
+    }
+
+    /// set the command line returned by SYS_GET_CMDLINE
+    pub fn with_cmdline(mut self, cmdline: impl Into<String>) -> Self {
+        self.cmdline = cmdline.into();
+        self
+    }
+
+    /// set the number of cycles per second used by the clock operations
+    pub fn with_clock_rate(mut self, clock_rate: u64) -> Result<Self, Error> {
+        if clock_rate == 0 {
+            return Err(Error::InvalidClockRate(clock_rate));
+        }
+        self.clock_rate = clock_rate;
+        Ok(self)
+    }
+
+    /// set the time reported by SYS_TIME at cycle 0
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// advance the clock by one cycle
+    pub fn tick(&mut self) {
+        self.cycles += 1;
+    }
+
+    /// advance the clock by the given number of cycles
+    pub fn skip(&mut self, cycles: usize) {
+        self.cycles += cycles as u64;
+    }
+
+    pub fn cmdline(&self) -> &str {
+        &self.cmdline
+    }
+
+    /// the errno of the last failed operation (SYS_ERRNO)
+    pub fn errno(&self) -> i32 {
+        self.errno
+    }
+
+    /// centiseconds since execution started (SYS_CLOCK)
+    pub fn clock(&self) -> u32 {
+        (self.cycles * 100 / self.clock_rate) as u32
+    }
+
+    /// seconds since the unix epoch (SYS_TIME)
+    pub fn time(&self) -> u32 {
+        (self.epoch + self.cycles / self.clock_rate) as u32
+    }
+
+    /// ticks since execution started (SYS_ELAPSED)
+    pub fn elapsed(&self) -> u64 {
+        self.cycles
+    }
+
+    /// ticks per second (SYS_TICKFREQ)
+    pub fn tick_freq(&self) -> u32 {
+        self.clock_rate as u32
+    }
+
+    /// write bytes to the console
+    pub fn write_console(&mut self, bytes: &[u8]) {
+        match self.stdout {
+            Some(ref stdout) => {
+                for byte in bytes {
+                    // a disconnected receiver just discards the output
+                    let _ = stdout.try_send(*byte);
+                }
+            }
+            None => {
+                info!("semihosting: {}", String::from_utf8_lossy(bytes).trim_end());
+            }
+        }
+    }
+
+    /// read bytes from the console, returning the number of bytes read.
+    ///
+    /// once the input is exhausted, reads are short unless the end of input
+    /// policy fills them. under the exit and timeout policies, a read that
+    /// can't return any bytes is an error.
+    pub fn read_console(&mut self, dst: &mut [u8]) -> Result<usize, TryRecvError> {
+        let Some(ref stdin) = self.stdin else {
+            return Ok(0);
+        };
+        let len = dst.len();
+        for (i, dst) in dst.iter_mut().enumerate() {
+            *dst = match stdin.try_recv() {
+                Ok(byte) => {
+                    self.input_count.add(1);
+                    byte
+                }
+                Err(TryRecvError::Empty) => {
+                    match self.end_of_input.get() {
+                        EndOfInput::Fill(byte) => { byte }
+                        EndOfInput::Stall => { return Ok(i) }
+                        EndOfInput::Exit | EndOfInput::Timeout => {
+                            if i > 0 {
+                                return Ok(i);
+                            }
+                            return Err(TryRecvError::Empty);
+                        }
+                    }
+                }
+                Err(err) => { return Err(err) }
+            };
+        }
+        Ok(len)
+    }
+
+    /// open a file (SYS_OPEN), returning the handle or -1.
+    ///
+    /// `mode` is the index of the fopen mode in
+    /// r, rb, r+, r+b, w, wb, w+, w+b, a, ab, a+, a+b
+    pub fn open(&mut self, name: &str, mode: u32) -> i32 {
+        if mode > 11 {
+            return self.fail(EINVAL);
+        }
+        let handle = match name {
+            ":tt" => {
+                match mode {
+                    0..=3 => { Handle::Stdin }
+                    4..=7 => { Handle::Stdout }
+                    _ => { Handle::Stderr }
+                }
+            }
+            ":semihosting-features" => {
+                if mode > 1 {
+                    return self.fail(EACCES);
+                }
+                Handle::Features { pos: 0 }
+            }
+            _ => {
+                let Some(path) = self._resolve(name) else {
+                    warn!("semihosting open of {name:?} outside sandbox");
+                    return self.fail(EACCES);
+                };
+                let file = match self._file(&path) {
+                    Ok(file) => { file }
+                    Err(errno) => { return self.fail(errno) }
+                };
+                if file.is_none() && mode < 4 {
+                    return self.fail(ENOENT);
+                }
+                if file.is_none() || (4..8).contains(&mode) {
+                    self.files.insert(path.clone(), Some(Arc::default()));
+                }
+                let read = mode < 4 || mode & 0b10 != 0;
+                let write = mode >= 2;
+                let append = mode >= 8;
+                Handle::File { path, pos: 0, read, write, append }
+            }
+        };
+        let index = match self.handles.iter().position(Option::is_none) {
+            Some(index) => { self.handles[index] = Some(handle); index }
+            None => { self.handles.push(Some(handle)); self.handles.len() - 1 }
+        };
+        index as i32 + 1
+    }
+
+    /// close a handle (SYS_CLOSE), returning 0 or -1
+    pub fn close(&mut self, handle: u32) -> i32 {
+        match self._handle_index(handle) {
+            Some(index) => { self.handles[index] = None; 0 }
+            None => { self.fail(EBADF) }
+        }
+    }
+
+    /// write to a handle (SYS_WRITE), returning the number of bytes not written
+    pub fn write(&mut self, handle: u32, src: &[u8]) -> u32 {
+        let Some(index) = self._handle_index(handle) else {
+            self.fail(EBADF);
+            return src.len() as u32;
+        };
+        if matches!(self.handles[index], Some(Handle::Stdout | Handle::Stderr)) {
+            self.write_console(src);
+            return 0;
+        }
+        let result = match &mut self.handles[index] {
+            Some(Handle::File { path, pos, write: true, append, .. }) => {
+                _write_file(&mut self.files, path, pos, *append, src)
+            }
+            _ => { Err(EBADF) }
+        };
+        match result {
+            Ok(written) => { (src.len() - written) as u32 }
+            Err(errno) => {
+                self.fail(errno);
+                src.len() as u32
+            }
+        }
+    }
+
+    /// read from a handle (SYS_READ), returning the number of bytes not read
+    pub fn read(&mut self, handle: u32, dst: &mut [u8]) -> Result<u32, TryRecvError> {
+        let Some(index) = self._handle_index(handle) else {
+            self.fail(EBADF);
+            return Ok(dst.len() as u32);
+        };
+        if let Some(Handle::Stdin) = self.handles[index] {
+            return self.read_console(dst).map(|n| (dst.len() - n) as u32);
+        }
+        let result = match &mut self.handles[index] {
+            Some(Handle::Features { pos }) => {
+                let src = &FEATURES[(*pos).min(FEATURES.len())..];
+                let n = src.len().min(dst.len());
+                dst[..n].copy_from_slice(&src[..n]);
+                *pos += n;
+                Ok(n)
+            }
+            Some(Handle::File { path, pos, read: true, .. }) => {
+                _read_file(&self.files, path, pos, dst)
+            }
+            _ => { Err(EBADF) }
+        };
+        match result {
+            Ok(n) => { Ok((dst.len() - n) as u32) }
+            Err(errno) => {
+                self.fail(errno);
+                Ok(dst.len() as u32)
+            }
+        }
+    }
+
+    /// true if reads from the handle take bytes from the console input
+    pub fn reads_input(&self, handle: u32) -> bool {
+        self.stdin.is_some() && self._handle_index(handle)
+            .is_some_and(|index| matches!(self.handles[index], Some(Handle::Stdin)))
+    }
+
+    /// true if the handle is a console (SYS_ISTTY), returning 1, 0, or -1
+    pub fn is_tty(&mut self, handle: u32) -> i32 {
+        let Some(index) = self._handle_index(handle) else {
+            return self.fail(EBADF);
+        };
+        let tty = matches!(self.handles[index], Some(Handle::Stdin | Handle::Stdout | Handle::Stderr));
+        tty as i32
+    }
+
+    /// seek to an absolute position (SYS_SEEK), returning 0 or -1
+    pub fn seek(&mut self, handle: u32, position: u32) -> i32 {
+        let Some(index) = self._handle_index(handle) else {
+            return self.fail(EBADF);
+        };
+        match &mut self.handles[index] {
+            Some(Handle::Features { pos }) => { *pos = position as usize; 0 }
+            Some(Handle::File { pos, .. }) => { *pos = position as u64; 0 }
+            Some(_) => { self.fail(EINVAL) }
+            None => { self.fail(EBADF) }
+        }
+    }
+
+    /// the length of a file (SYS_FLEN), or -1
+    pub fn flen(&mut self, handle: u32) -> i32 {
+        let Some(index) = self._handle_index(handle) else {
+            return self.fail(EBADF);
+        };
+        let result = match &self.handles[index] {
+            Some(Handle::Features { .. }) => { Ok(FEATURES.len() as u64) }
+            Some(Handle::File { path, .. }) => {
+                match self.files.get(path) {
+                    Some(Some(data)) => { Ok(data.len() as u64) }
+                    _ => { Err(ENOENT) }
+                }
+            }
+            Some(_) => { Err(EINVAL) }
+            None => { Err(EBADF) }
+        };
+        match result {
+            Ok(len) => { len as i32 }
+            Err(errno) => { self.fail(errno) }
+        }
+    }
+
+    /// delete a file (SYS_REMOVE), returning 0 or a host error number
+    pub fn remove(&mut self, name: &str) -> i32 {
+        let Some(path) = self._resolve(name) else {
+            return self.fail(EACCES);
+        };
+        match self._file(&path) {
+            Ok(Some(_)) => {
+                self.files.insert(path, None);
+                0
+            }
+            Ok(None) => { self.fail(ENOENT); ENOENT }
+            Err(errno) => { self.fail(errno); errno }
+        }
+    }
+
+    /// rename a file (SYS_RENAME), returning 0 or a host error number
+    pub fn rename(&mut self, from: &str, to: &str) -> i32 {
+        let (Some(from), Some(to)) = (self._resolve(from), self._resolve(to)) else {
+            return self.fail(EACCES);
+        };
+        let result = self._file(&to).and_then(|_| self._file(&from));
+        match result {
+            Ok(Some(data)) => {
+                self.files.insert(from, None);
+                self.files.insert(to, Some(data));
+                0
+            }
+            Ok(None) => { self.fail(ENOENT); ENOENT }
+            Err(errno) => { self.fail(errno); errno }
+        }
+    }
+
+    /// record an error number for SYS_ERRNO, returning -1
+    pub fn fail(&mut self, errno: i32) -> i32 {
+        self.errno = errno;
+        -1
+    }
+
+    fn _handle_index(&self, handle: u32) -> Option<usize> {
+        let index = (handle as usize).checked_sub(1)?;
+        self.handles.get(index)?.as_ref().map(|_| index)
+    }
+
+    /// the path of a file name relative to the sandbox,
+    /// or None if there is no sandbox or the name escapes it
+    fn _resolve(&self, name: &str) -> Option<PathBuf> {
+        self.sandbox.as_ref()?;
+        let mut path = PathBuf::new();
+        for component in Path::new(name).components() {
+            match component {
+                Component::Normal(part) => { path.push(part); }
+                Component::CurDir => {  }
+                _ => { return None }
+            }
+        }
+        (!path.as_os_str().is_empty()).then_some(path)
+    }
+
+    /// the contents of a sandbox file, or None if it doesn't exist.
+    /// files are read from the host the first time they are used.
+    fn _file(&mut self, path: &Path) -> Result<Option<Arc<Vec<u8>>>, i32> {
+        if let Some(file) = self.files.get(path) {
+            return Ok(file.clone());
+        }
+        let sandbox = self.sandbox.as_ref().ok_or(EACCES)?;
+        let file = _read_host(sandbox, path)?.map(Arc::new);
+        self.files.insert(path.to_path_buf(), file.clone());
+        Ok(file)
+    }
+}
+
+/// read a file from the sandbox directory on the host,
+/// refusing any that symlinks resolve to outside of it
+fn _read_host(sandbox: &Path, path: &Path) -> Result<Option<Vec<u8>>, i32> {
+    let host_path = match sandbox.join(path).canonicalize() {
+        Ok(host_path) => { host_path }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => { return Ok(None) }
+        Err(err) => { return Err(_errno(&err)) }
+    };
+    let sandbox = sandbox.canonicalize().map_err(|err| _errno(&err))?;
+    if !host_path.starts_with(&sandbox) {
+        warn!("semihosting file {} links outside sandbox", path.display());
+        return Err(EACCES);
+    }
+    fs::read(&host_path).map(Some).map_err(|err| _errno(&err))
+}
+
+/// the host error number of an io error
+fn _errno(err: &io::Error) -> i32 {
+    match err.raw_os_error() {
+        Some(errno) => { errno }
+        None if err.kind() == io::ErrorKind::NotFound => { ENOENT }
+        None => { EIO }
+    }
+}
+
+type Files = BTreeMap<PathBuf, Option<Arc<Vec<u8>>>>;
+
+fn _read_file(files: &Files, path: &Path, pos: &mut u64, dst: &mut [u8]) -> Result<usize, i32> {
+    let Some(Some(data)) = files.get(path) else {
+        return Err(ENOENT);
+    };
+    let start = (*pos).min(data.len() as u64) as usize;
+    let n = (data.len() - start).min(dst.len());
+    dst[..n].copy_from_slice(&data[start..start + n]);
+    *pos = (start + n) as u64;
+    Ok(n)
+}
+
+fn _write_file(files: &mut Files, path: &Path, pos: &mut u64, append: bool, src: &[u8]) -> Result<usize, i32> {
+    let Some(Some(data)) = files.get_mut(path) else {
+        return Err(ENOENT);
+    };
+    let start = if append { data.len() } else { *pos as usize };
+    let end = start.saturating_add(src.len());
+    if end > MAX_FILE_LEN {
+        return Err(ENOSPC);
+    }
+    // only copies the contents if a snapshot still shares them
+    let data = Arc::make_mut(data);
+    if data.len() < end {
+        data.resize(end, 0);
+    }
+    data[start..end].copy_from_slice(src);
+    *pos = end as u64;
+    Ok(src.len())
+}
+
+#[cfg(test)]
+mod test {
+    use crossbeam::channel::unbounded;
+    use super::*;
+
+    #[test]
+    fn test_console() {
+        let (stdin_tx, stdin_rx) = unbounded();
+        let (stdout_tx, stdout_rx) = unbounded();
+        let mut semihosting = Semihosting::new()
+            .with_stdin(stdin_rx)
+            .with_stdout(stdout_tx)
+            .with_end_of_input(EndOfInput::Exit.into());
+        let stdin = semihosting.open(":tt", 0) as u32;
+        let stdout = semihosting.open(":tt", 4) as u32;
+        assert_eq!(semihosting.is_tty(stdout), 1);
+
+        assert_eq!(semihosting.write(stdout, b"hi"), 0);
+        assert_eq!(stdout_rx.try_iter().collect::<Vec<u8>>(), b"hi");
+
+        stdin_tx.send(b'x').unwrap();
+        let mut buf = [0u8; 4];
+        assert_eq!(semihosting.read(stdin, &mut buf), Ok(3), "short read");
+        assert_eq!(buf[0], b'x');
+        assert!(semihosting.read(stdin, &mut buf).is_err(), "input exhausted");
+
+        let features = semihosting.open(":semihosting-features", 0) as u32;
+        assert_eq!(semihosting.flen(features), 5);
+        assert_eq!(semihosting.read(features, &mut buf), Ok(0));
+        assert_eq!(&buf, b"SHFB");
+
+        assert_eq!(semihosting.close(stdin), 0);
+        assert_eq!(semihosting.close(stdin), -1);
+        assert_eq!(semihosting.errno(), EBADF);
+        assert_eq!(semihosting.seek(stdin, 0), -1, "closed handle");
+        assert_eq!(semihosting.flen(stdin), -1, "closed handle");
+
+        assert!(Semihosting::new().with_clock_rate(0).is_err());
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let sandbox = std::env::temp_dir().join(format!("semihosting-{}", std::process::id()));
+        fs::create_dir_all(&sandbox).unwrap();
+        let mut semihosting = Semihosting::new();
+        assert_eq!(semihosting.open("out.txt", 4), -1, "no sandbox");
+
+        let mut semihosting = semihosting.with_sandbox(&sandbox);
+        assert_eq!(semihosting.open("../out.txt", 4), -1, "escapes sandbox");
+        assert_eq!(semihosting.open("/tmp/out.txt", 4), -1, "absolute path");
+
+        fs::write(sandbox.join("in.txt"), b"input").unwrap();
+        let handle = semihosting.open("in.txt", 0) as u32;
+        let mut buf = [0u8; 8];
+        assert_eq!(semihosting.read(handle, &mut buf), Ok(3));
+        assert_eq!(&buf[..5], b"input");
+        assert_eq!(semihosting.close(handle), 0);
+
+        let handle = semihosting.open("out.txt", 6) as u32;
+        assert_eq!(semihosting.write(handle, b"hello"), 0);
+        assert_eq!(semihosting.flen(handle), 5);
+        assert_eq!(semihosting.seek(handle, 1), 0);
+        assert_eq!(semihosting.read(handle, &mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"ello");
+        assert_eq!(semihosting.close(handle), 0);
+
+        assert_eq!(semihosting.rename("out.txt", "moved.txt"), 0);
+        assert_eq!(semihosting.open("out.txt", 0), -1);
+        assert_eq!(semihosting.remove("moved.txt"), 0);
+        assert_eq!(semihosting.remove("in.txt"), 0);
+        assert_eq!(semihosting.open("in.txt", 0), -1);
+        assert!(!sandbox.join("out.txt").exists(), "writes stay in memory");
+        assert!(sandbox.join("in.txt").exists(), "removes stay in memory");
+        fs::remove_dir_all(&sandbox).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sandbox_symlink() {
+        let root = std::env::temp_dir().join(format!("semihosting-link-{}", std::process::id()));
+        let sandbox = root.join("sandbox");
+        fs::create_dir_all(&sandbox).unwrap();
+        fs::write(root.join("secret.txt"), b"secret").unwrap();
+        std::os::unix::fs::symlink(root.join("secret.txt"), sandbox.join("link.txt")).unwrap();
+
+        let mut semihosting = Semihosting::new().with_sandbox(&sandbox);
+        assert_eq!(semihosting.open("link.txt", 0), -1, "links outside sandbox");
+        assert_eq!(semihosting.errno(), EACCES);
+        assert_eq!(semihosting.open("link.txt", 4), -1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
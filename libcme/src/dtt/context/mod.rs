@@ -18,14 +18,15 @@ use fugue_core::eval::fixed_state::FixedStateError;
 use fugue_bv::BitVec;
 
 use crate::types::*;
-use crate::peripheral::{self, Peripheral};
+use crate::peripheral::{self, Peripheral, channel::InputCounter};
 use crate::utils;
 
 use crate::backend::{self, Backend};
-use super::tag::{self, Tag};
+use super::tag::{Tag, TaintTag};
+use super::tag::provenance::{Label, LabelSet};
 
 mod shadow;
-use shadow::ShadowState;
+use shadow::{ShadowState, ShadowSnapshot};
 mod plugin;
 use plugin::*;
 
@@ -55,6 +56,8 @@ pub enum Error {
     OOBRead { offset: usize, size: usize },
     #[error("out of bounds fixedstate write: [{offset:#x}; {size}]")]
     OOBWrite { offset: usize, size: usize },
+    #[error("language is missing registers: {0}")]
+    MissingRegisters(&'static str),
 }
 
 impl From<IRError> for Error {
@@ -92,18 +95,18 @@ impl From<peripheral::Error> for Error {
 /// all of these can can be handled in a single function and observers can be
 /// dispatched from a central location without having to litter them everywhere
 #[derive(Debug)]
-pub enum CtxRequest<'a> {
+pub enum CtxRequest<'a, T: TaintTag = Tag> {
     Fetch { address: Address, arena: &'a IRBuilderArena },
     Read { vnd: &'a VarnodeData },
-    Write { vnd: &'a VarnodeData, val: &'a BitVec, tag: &'a Tag },
+    Write { vnd: &'a VarnodeData, val: &'a BitVec, tag: &'a T },
     Load { address: Address, size: usize },
-    Store { address: Address, val: &'a BitVec, tag: &'a Tag },
+    Store { address: Address, val: &'a BitVec, tag: &'a T },
     LoadBytes { address: Address, dst: &'a mut [u8] },
-    StoreBytes { address: Address, bytes: &'a [u8], tag: &'a Tag },
+    StoreBytes { address: Address, bytes: &'a [u8], tag: &'a T },
     ReadPc,
-    WritePc { address: Address, tag: &'a Tag },
+    WritePc { address: Address, tag: &'a T },
     ReadSp,
-    WriteSp { address: Address, tag: &'a Tag },
+    WriteSp { address: Address, tag: &'a T },
     CallOther { output: Option<&'a VarnodeData>, inputs: &'a [VarnodeData] },
 }
 
@@ -111,17 +114,17 @@ pub enum CtxRequest<'a> {
 /// 
 /// contains the result of the context request
 #[derive(Debug)]
-pub enum CtxResponse<'irb> {
+pub enum CtxResponse<'irb, T: TaintTag = Tag> {
     Fetch { result: LiftResult<'irb> },
-    Read { result: Result<(BitVec, Tag), Error> },
+    Read { result: Result<(BitVec, T), Error> },
     Write { result: Result<(), Error> },
-    Load { result: Result<(BitVec, Tag), Error> },
+    Load { result: Result<(BitVec, T), Error> },
     Store { result: Result<(), Error> },
-    LoadBytes { result: Result<Tag, Error> },
+    LoadBytes { result: Result<T, Error> },
     StoreBytes { result: Result<(), Error> },
-    ReadPc { result: Result<(Address, Tag), Error> },
+    ReadPc { result: Result<(Address, T), Error> },
     WritePc { result: Result<(), Error> },
-    ReadSp { result: Result<(Address, Tag), Error> },
+    ReadSp { result: Result<(Address, T), Error> },
     WriteSp { result: Result<(), Error> },
     CallOther { result: Result<Option<Location>, Error> },
 }
@@ -130,26 +133,55 @@ pub enum CtxResponse<'irb> {
 /// 
 /// an architecture emulation context implementation should implement this trait to keep the
 /// actual evaluator architecture agnostic
+/// 
+/// the context is generic over the tag type held in its shadow state,
+/// which defaults to `Tag`.
 #[derive(Debug, Clone)]
-pub struct Context<'backend> {
+pub struct Context<'backend, T: TaintTag = Tag> {
     /// the architecture-specific backend for this context
     backend: Box<dyn Backend + 'backend>,
-    shadow: ShadowState,
-    arch_plugin: Box<dyn ArchPlugin + 'backend>,
+    shadow: ShadowState<T>,
+    arch_plugin: Box<dyn ArchPlugin<T> + 'backend>,
+    /// tag given to input bytes written by the backend, e.g. semihosting reads
+    input_tag: Option<T>,
+}
+
+/// a context snapshot
+/// 
+/// taking a snapshot copies all state once, restoring
+/// only copies memory and tag pages written since.
+#[derive(Clone)]
+pub struct Snapshot<T: TaintTag = Tag> {
+    backend: backend::Snapshot,
+    shadow: ShadowSnapshot<T>,
 }
 
 
 impl<'backend> Context<'backend> {
 
     pub fn new_with(backend: Box<dyn Backend + 'backend>) -> Self {
+        Self::new_tagged(backend)
+    }
+
+    pub fn from_backend(backend: impl Backend + 'backend) -> Result<Self, Error> {
+        Self::from_backend_tagged(backend)
+    }
+}
+
+impl<'backend, T: TaintTag> Context<'backend, T> {
+
+    /// create a context with a custom tag type
+    pub fn new_tagged(backend: Box<dyn Backend + 'backend>) -> Self {
         let shadow = ShadowState::new_with(backend.lang().clone());
         let arch = backend.lang().translator().architecture();
         let arch_plugin = plugin_from(arch);
-        Self { backend, shadow, arch_plugin }
+        Self { backend, shadow, arch_plugin, input_tag: None }
     }
 
-    pub fn from_backend(backend: impl Backend + 'backend) -> Result<Self, Error> {
-        let mut context = Self::new_with(Box::new(backend));
+    /// create a context with a custom tag type from a backend,
+    /// mapping shadow memory for the backend's mapped memory
+    pub fn from_backend_tagged(backend: impl Backend + 'backend) -> Result<Self, Error> {
+        let mut context = Self::new_tagged(Box::new(backend));
         let ranges: Vec<MappedRange> = context.backend.mmap().mapped().collect();
         for mapped_range in ranges {
             match mapped_range {
@@ -207,7 +239,7 @@ impl<'backend> Context<'backend> {
     pub fn map_mmio(
         &mut self,
         peripheral: Peripheral,
-        tag: Option<Tag>,
+        tag: Option<T>,
     ) -> Result<(), Error> {
         let base = peripheral.base_address();
         let size = peripheral.size() as usize;
@@ -219,9 +251,49 @@ impl<'backend> Context<'backend> {
     pub fn has_mapped(&self, address: &Address) -> bool {
         self.backend().mmap().has_mapped(address)
     }
+
+    /// start labelling tainted mmio reads with their input offsets
+    /// 
+    /// the counter must be shared with the peripherals reading the input
+    /// (see `ChannelPeripheral::input_counter`), so that labels are only
+    /// given to the bytes a read actually took from the input.
+    /// labels are unioned as taint propagates and can be queried with
+    /// `labels_of`, `mem_labels`, and `violation_labels`.
+    pub fn enable_provenance(&mut self, consumed: InputCounter) {
+        self.shadow.enable_provenance(consumed);
+    }
+
+    pub fn provenance_enabled(&self) -> bool {
+        self.shadow.provenance().is_some()
+    }
+
+    /// start tracking bit-precise taint masks
+    /// 
+    /// the evaluator enables this for bit-precise policies.
+    pub fn enable_bit_shadow(&mut self) {
+        self.shadow.enable_bits();
+    }
+
+    pub fn bit_shadow_enabled(&self) -> bool {
+        self.shadow.bits().is_some()
+    }
+
+    /// take a snapshot of the context state
+    pub fn snapshot(&mut self) -> Snapshot<T> {
+        let backend = self.backend.snapshot();
+        let shadow = self.shadow.snapshot();
+        Snapshot { backend, shadow }
+    }
+
+    /// restore the context state from a snapshot
+    pub fn restore(&mut self, snapshot: &Snapshot<T>) -> Result<(), Error> {
+        self.backend.restore(&snapshot.backend)?;
+        self.shadow.restore(&snapshot.shadow)?;
+        Ok(())
+    }
 }
 
-impl<'backend> Context<'backend> {
+impl<'backend, T: TaintTag> Context<'backend, T> {
     // interaction implementations
 
     /// tick processor clock
@@ -232,7 +304,7 @@ impl<'backend> Context<'backend> {
     /// check for and apply thread switches
     /// returns the thread switch if taken, as well as the tag 
     /// of the target address
-    pub fn maybe_thread_switch(&mut self) -> Result<Option<(backend::ThreadSwitch, Tag)>, Error> {
+    pub fn maybe_thread_switch(&mut self) -> Result<Option<(backend::ThreadSwitch, T)>, Error> {
         let Some(ctx) = self.backend.maybe_thread_switch() else {
             return Ok(None)
         };
@@ -240,86 +312,257 @@ impl<'backend> Context<'backend> {
         Ok(Some((ctx, tag)))
     }
 
+    /// get the interrupt numbers of all currently enabled external interrupts
+    pub fn enabled_interrupts(&self) -> Vec<u32> {
+        self.backend.enabled_interrupts()
+    }
+
+    /// pend an external interrupt, as if a peripheral had fired it
+    pub fn fire_interrupt(&mut self, int_num: u32) -> Result<(), Error> {
+        self.backend.fire_interrupt(int_num).map_err(Error::from)
+    }
+
+    /// true if exceptions with configurable priority are currently masked
+    pub fn interrupts_masked(&self) -> bool {
+        self.backend.interrupts_masked()
+    }
+
+    /// true if any exception could still be raised without software intervention
+    pub fn interrupts_possible(&self) -> bool {
+        self.backend.interrupts_possible()
+    }
+
+    /// true if the processor is suspended waiting for an interrupt or event
+    pub fn is_sleeping(&self) -> bool {
+        self.backend.is_sleeping()
+    }
+
+    /// advance the clock while the processor sleeps,
+    /// returning the number of cycles that elapsed
+    pub fn fast_forward(&mut self) -> Result<usize, Error> {
+        let cycles = self.backend.fast_forward()?;
+        self._maybe_reset_shadow();
+        Ok(cycles)
+    }
+
+    /// true if the processor is halted
+    pub fn is_halted(&self) -> bool {
+        self.backend.is_halted()
+    }
+
+    /// the reason the processor is halted, if it is
+    pub fn halt_reason(&self) -> Option<backend::HaltReason> {
+        self.backend.halt_reason()
+    }
+
+    /// attach a semihosting implementation to the backend
+    /// 
+    /// console input read by the firmware is given `tag`, like the reads
+    /// of a channel peripheral mapped with the same tag. to label it, the
+    /// semihosting input counter must be the one given to `enable_provenance`.
+    pub fn attach_semihosting(
+        &mut self,
+        semihosting: backend::semihosting::Semihosting,
+        tag: Option<T>,
+    ) -> Result<(), Error> {
+        self.backend.attach_semihosting(semihosting)?;
+        self.input_tag = tag;
+        Ok(())
+    }
+
+    /// set how system reset requests are handled by the backend
+    pub fn set_reset_policy(&mut self, policy: backend::ResetPolicy) {
+        self.backend.set_reset_policy(policy)
+    }
+
     /// process any pending backend events
     pub fn process_events(&mut self) -> Result<(), Error> {
-        self.backend.process_events().map_err(Error::from)
+        self.backend.process_events()?;
+        self._maybe_reset_shadow();
+        Ok(())
+    }
+
+    /// raise the architectural fault for a fault condition in the
+    /// current instruction, returns false if the backend doesn't fault
+    pub fn fault(&mut self, cause: backend::FaultCause) -> Result<bool, Error> {
+        self.backend.fault(cause).map_err(Error::from)
+    }
+
+    /// true if integer division by zero is a fault condition
+    pub fn traps_divide_by_zero(&self) -> bool {
+        self.backend.traps_divide_by_zero()
+    }
+
+    /// check that the instruction at the given address may be executed
+    pub fn check_fetch(&mut self, address: &Address) -> Result<(), Error> {
+        self.backend.check_fetch(address).map_err(Error::from)
     }
 
     /// fetch the lifted instruction at the given address
     pub fn fetch<'irb>(&mut self, address: impl Into<Address>, arena: &'irb IRBuilderArena) -> LiftResult<'irb> {
         let address = address.into();
-        self.request(CtxRequest::Fetch { address, arena }).into()
+        self.request(CtxRequest::Fetch { address, arena }).into_lift()
     }
 
     /// read a varnode
-    pub fn read(&mut self, vnd: &VarnodeData) -> Result<(BitVec, Tag), Error> {
-        self.request(CtxRequest::Read { vnd }).into()
+    pub fn read(&mut self, vnd: &VarnodeData) -> Result<(BitVec, T), Error> {
+        self.request(CtxRequest::Read { vnd }).into_value()
     }
 
     /// write a varnode
-    pub fn write(&mut self, vnd: &VarnodeData, val: &BitVec, tag: &Tag) -> Result<(), Error> {
-        self.request(CtxRequest::Write { vnd, val, tag }).into()
+    pub fn write(&mut self, vnd: &VarnodeData, val: &BitVec, tag: &T) -> Result<(), Error> {
+        self.request(CtxRequest::Write { vnd, val, tag }).into_unit()
     }
 
     /// read the current pc address
-    pub fn read_pc(&mut self) -> Result<(Address, Tag), Error> {
-        self.request(CtxRequest::ReadPc).into()
+    pub fn read_pc(&mut self) -> Result<(Address, T), Error> {
+        self.request(CtxRequest::ReadPc).into_address()
     }
 
     /// write an address to the pc
-    pub fn write_pc(&mut self, address: impl Into<Address>, tag: &Tag) -> Result<(), Error> {
+    pub fn write_pc(&mut self, address: impl Into<Address>, tag: &T) -> Result<(), Error> {
         let address = address.into();
-        self.request(CtxRequest::WritePc { address, tag }).into()
+        self.request(CtxRequest::WritePc { address, tag }).into_unit()
     }
 
     /// read the current stack pointer address
-    pub fn read_sp(&mut self) -> Result<(Address, Tag), Error> {
-        self.request(CtxRequest::ReadSp).into()
+    pub fn read_sp(&mut self) -> Result<(Address, T), Error> {
+        self.request(CtxRequest::ReadSp).into_address()
     }
 
     /// write an address the the active stack pointer
-    pub fn write_sp(&mut self, address: impl Into<Address>, tag: &Tag) -> Result<(), Error> {
+    pub fn write_sp(&mut self, address: impl Into<Address>, tag: &T) -> Result<(), Error> {
         let address = address.into();
-        self.request(CtxRequest::WriteSp { address, tag }).into()
+        self.request(CtxRequest::WriteSp { address, tag }).into_unit()
     }
 
     /// load a value from mapped memory
-    pub fn load(&mut self, address: impl Into<Address>, size: usize) -> Result<(BitVec, Tag), Error> {
+    pub fn load(&mut self, address: impl Into<Address>, size: usize) -> Result<(BitVec, T), Error> {
         let address = address.into();
-        self.request(CtxRequest::Load { address, size }).into()
+        self.request(CtxRequest::Load { address, size }).into_value()
     }
 
     /// store a value in mapped memory
-    pub fn store(&mut self, address: impl Into<Address>, val: &BitVec, tag: &Tag) -> Result<(), Error> {
+    pub fn store(&mut self, address: impl Into<Address>, val: &BitVec, tag: &T) -> Result<(), Error> {
         let address = address.into();
-        self.request(CtxRequest::Store { address, val, tag }).into()
+        self.request(CtxRequest::Store { address, val, tag }).into_unit()
     }
 
     /// load bytes from mapped memory into a destination buffer
-    pub fn load_bytes(&mut self, address: impl Into<Address>, dst: &mut [u8]) -> Result<Tag, Error> {
+    pub fn load_bytes(&mut self, address: impl Into<Address>, dst: &mut [u8]) -> Result<T, Error> {
         let address = address.into();
-        self.request(CtxRequest::LoadBytes { address, dst }).into()
+        self.request(CtxRequest::LoadBytes { address, dst }).into_tag()
     }
 
     /// store bytes from a source buffer into mapped memory
-    pub fn store_bytes<'a>(&mut self, address: impl Into<Address>, bytes: &'a [u8], tag: &Tag) -> Result<(), Error> {
+    pub fn store_bytes<'a>(&mut self, address: impl Into<Address>, bytes: &'a [u8], tag: &T) -> Result<(), Error> {
         let address = address.into();
-        self.request(CtxRequest::StoreBytes { address, bytes, tag }).into()
+        self.request(CtxRequest::StoreBytes { address, bytes, tag }).into_unit()
     }
 
-    pub fn view_tags(&mut self, address: impl Into<Address>, size: usize) -> Result<&[Tag], Error> {
+    pub fn view_tags(&mut self, address: impl Into<Address>, size: usize) -> Result<&[T], Error> {
         let address = address.into();
         self.shadow.view_mem_tags(&address, size)
             .map_err(Error::from)
     }
 
-    pub fn write_tags(&mut self, address: impl Into<Address>, size: usize, tag: impl Into<Tag>) -> Result<(), Error> {
+    pub fn write_tags(&mut self, address: impl Into<Address>, size: usize, tag: impl Into<T>) -> Result<(), Error> {
         let address = address.into();
         let tag = tag.into();
         self.shadow.write_mem_tags(&address, size, &tag)
             .map_err(Error::from)
     }
 
+    /// get the input offsets that influence a varnode
+    /// 
+    /// empty if provenance tracking is disabled
+    pub fn labels_of(&self, vnd: &VarnodeData) -> LabelSet {
+        self.shadow.provenance()
+            .map(|provenance| LabelSet::union_all(provenance.read(vnd).iter()))
+            .unwrap_or_default()
+    }
+
+    /// get the input offsets that influence a memory range
+    /// 
+    /// empty if provenance tracking is disabled
+    pub fn mem_labels(&self, address: impl Into<Address>, size: usize) -> LabelSet {
+        let address = address.into();
+        self.shadow.provenance()
+            .map(|provenance| provenance.read_mem(&address, size))
+            .unwrap_or_default()
+    }
+
+    /// get the input offsets that influenced the last policy-violating value
+    pub fn violation_labels(&self) -> Option<&LabelSet> {
+        self.shadow.provenance()
+            .and_then(|provenance| provenance.violation())
+    }
+
+    /// the input offset of the next input byte to be read
+    pub fn input_offset(&self) -> Option<Label> {
+        self.shadow.provenance()
+            .map(|provenance| provenance.input_offset())
+    }
+
+    /// get the labels of each byte of a varnode
+    pub fn byte_labels(&self, vnd: &VarnodeData) -> Vec<LabelSet> {
+        self.shadow.provenance()
+            .map(|provenance| provenance.read(vnd))
+            .unwrap_or_default()
+    }
+
+    /// set the labels of each byte of a varnode
+    pub fn write_byte_labels(&mut self, vnd: &VarnodeData, labels: &[LabelSet]) {
+        if let Some(provenance) = self.shadow.provenance_mut() {
+            provenance.write(vnd, labels);
+        }
+    }
+
+    /// get the taint mask of a varnode with the given tag
+    /// 
+    /// bytes without a stored mask are fully tainted if the tag is tainted,
+    /// so the mask is all ones for a tainted varnode if the bit shadow is disabled.
+    pub fn bit_mask(&self, vnd: &VarnodeData, tag: &T) -> BitVec {
+        if vnd.space().is_constant() || !tag.is_tainted() {
+            return BitVec::from_u64(0, vnd.bits());
+        }
+        let bytes: Vec<u8> = match self.shadow.bits() {
+            Some(bits) => {
+                bits.read(vnd).into_iter()
+                    .map(|mask| mask.unwrap_or(0xff))
+                    .collect()
+            }
+            None => { vec![0xff; vnd.size()] }
+        };
+        if self.lang().translator().is_big_endian() {
+            BitVec::from_be_bytes(&bytes)
+        } else {
+            BitVec::from_le_bytes(&bytes)
+        }
+    }
+
+    /// set the taint mask of a varnode
+    pub fn write_bit_mask(&mut self, vnd: &VarnodeData, mask: &BitVec) {
+        let big_endian = self.lang().translator().is_big_endian();
+        let Some(bits) = self.shadow.bits_mut() else {
+            return;
+        };
+        let mut bytes = vec![0u8; vnd.size()];
+        if big_endian {
+            mask.to_be_bytes(&mut bytes);
+        } else {
+            mask.to_le_bytes(&mut bytes);
+        }
+        bits.write(vnd, &bytes);
+    }
+
+    /// record the labels of a value that violated the taint policy
+    pub fn set_violation_labels(&mut self, labels: LabelSet) {
+        if let Some(provenance) = self.shadow.provenance_mut() {
+            provenance.set_violation(labels);
+        }
+    }
+
     /// call a user-defined pcode operation
     /// 
     /// on succes, returns a Location (from an address) if the userop performs a branch.
@@ -329,17 +572,95 @@ impl<'backend> Context<'backend> {
         // this is kept as a request despite being more of an evaluator 
         // operation because userops more often than not have 
         // side-effects that modify the system state
-        self.request(CtxRequest::CallOther { output, inputs }).into()
+        self.request(CtxRequest::CallOther { output, inputs }).into_location()
     }
 }
 
-impl<'backend> Context<'backend> {
-    fn request<'irb>(&mut self, req: CtxRequest<'irb>) -> CtxResponse<'irb> {
+impl<'backend, T: TaintTag> Context<'backend, T> {
+    /// the number of input bytes taken so far, if provenance is enabled
+    fn _input_consumed(&self) -> Option<Label> {
+        self.shadow.provenance()
+            .map(|provenance| provenance.counter().get())
+    }
+
+    /// the number of input bytes taken since the count was `consumed`
+    fn _input_taken(&self, consumed: Option<Label>) -> Option<usize> {
+        let (Some(consumed), Some(count)) = (consumed, self._input_consumed()) else {
+            return None;
+        };
+        Some(count.wrapping_sub(consumed) as usize)
+    }
+
+    /// label the bytes of a memory read with the input it consumed
+    /// 
+    /// `consumed` is the input count before the read. the bytes taken
+    /// since then are labelled in order if the read is tainted, and
+    /// only advance the input offset otherwise.
+    fn _label_input(&mut self, vnd: &VarnodeData, tag: &T, consumed: Option<Label>) {
+        let Some(taken) = self._input_taken(consumed) else {
+            return;
+        };
+        // memory keeps its labels, but mmio reads without input have none
+        if taken == 0 && !self._is_mmio(vnd) {
+            return;
+        }
+        self._write_input_labels(vnd, tag, taken);
+    }
+
+    fn _write_input_labels(&mut self, vnd: &VarnodeData, tag: &T, taken: usize) {
+        let provenance = self.shadow.provenance_mut().unwrap();
+        let labels = if tag.is_tainted() {
+            provenance.next_labels(taken)
+        } else {
+            provenance.skip(taken);
+            vec![]
+        };
+        provenance.write(vnd, &labels);
+    }
+
+    /// reset the shadow state if the backend was reset,
+    /// since register and memory taint don't survive a system reset
+    fn _maybe_reset_shadow(&mut self) {
+        if self.backend.take_reset() {
+            self.shadow.reset();
+        }
+    }
+
+    /// taint the locations the backend wrote with input bytes,
+    /// labelling them with the input taken since `consumed`
+    fn _taint_input_writes(&mut self, consumed: Option<Label>) -> Result<(), Error> {
+        let writes = self.backend.take_input_writes();
+        if writes.is_empty() {
+            return Ok(());
+        }
+        let tag = self.input_tag.clone().unwrap_or_else(T::accessed);
+        let mut taken = self._input_taken(consumed);
+        for vnd in writes.iter() {
+            self.shadow.write_tag(vnd, &tag)?;
+            if let Some(ref mut taken) = taken {
+                let n = vnd.size().min(*taken);
+                *taken -= n;
+                self._write_input_labels(vnd, &tag, n);
+            }
+        }
+        Ok(())
+    }
+
+    fn _is_mmio(&self, vnd: &VarnodeData) -> bool {
+        let address = Address::from(vnd.offset());
+        self.backend.mmap().mapped().any(|range| match range {
+            MappedRange::Mmio(range) => { range.contains(&address) }
+            MappedRange::Mem(_) => { false }
+        })
+    }
+
+    fn request<'irb>(&mut self, req: CtxRequest<'irb, T>) -> CtxResponse<'irb, T> {
         match req {
             CtxRequest::Fetch { address, arena } => {
                 CtxResponse::Fetch { result: self.backend.fetch(&address, arena) }
             }
             CtxRequest::Read { vnd } => {
+                let consumed = self._input_consumed();
                 let backend_result = self.backend.read(vnd);
                 if let Err(err) = backend_result {
                     return CtxResponse::Read { result: Err(err.into()) };
@@ -350,6 +671,9 @@ impl<'backend> Context<'backend> {
                     return CtxResponse::Read { result: Err(err.into()) };
                 };
                 let tag = shadow_result.unwrap();
+                if vnd.space().is_default() {
+                    self._label_input(vnd, &tag, consumed);
+                }
                 CtxResponse::Read { result: Ok((bv, tag)) }
             }
             CtxRequest::Write { vnd, val, tag } => {
@@ -361,6 +685,11 @@ impl<'backend> Context<'backend> {
                 if let Err(err) = shadow_result {
                     return CtxResponse::Write { result: Err(err.into()) }
                 }
+                if !tag.is_tainted() {
+                    if let Some(provenance) = self.shadow.provenance_mut() {
+                        provenance.clear(vnd);
+                    }
+                }
                 CtxResponse::Write { result: Ok(()) }
             }
             CtxRequest::Load { address, size } => {
@@ -385,6 +714,11 @@ impl<'backend> Context<'backend> {
                 if let Err(err) = shadow_result {
                     return CtxResponse::Store { result: Err(err.into()) }
                 }
+                if !tag.is_tainted() {
+                    if let Some(provenance) = self.shadow.provenance_mut() {
+                        provenance.clear_mem(&address, val.bytes());
+                    }
+                }
                 CtxResponse::Store { result: Ok(()) }
             }
             CtxRequest::LoadBytes { address, dst } => {
@@ -408,6 +742,11 @@ impl<'backend> Context<'backend> {
                 if let Err(err) = shadow_result {
                     return CtxResponse::StoreBytes { result: Err(err.into()) }
                 }
+                if !tag.is_tainted() {
+                    if let Some(provenance) = self.shadow.provenance_mut() {
+                        provenance.clear_mem(&address, bytes.len());
+                    }
+                }
                 CtxResponse::StoreBytes { result: Ok(()) }
             }
             CtxRequest::ReadPc => {
@@ -459,46 +798,48 @@ impl<'backend> Context<'backend> {
                 CtxResponse::WriteSp { result: Ok(()) }
             }
             CtxRequest::CallOther { output, inputs } => {
-                CtxResponse::CallOther { 
-                    result: self.backend.userop(output, inputs)
-                        .map_err(|e| e.into())
+                let consumed = self._input_consumed();
+                let backend_result = self.backend.userop(output, inputs);
+                if let Err(err) = backend_result {
+                    return CtxResponse::CallOther { result: Err(err.into()) }
                 }
+                let result = self._taint_input_writes(consumed)
+                    .map(|_| backend_result.unwrap());
+                CtxResponse::CallOther { result }
             }
         }
     }
 }
 
 
-impl<'irb> Into<LiftResult<'irb>> for CtxResponse<'irb> {
-    fn into(self) -> LiftResult<'irb> {
+// response conversions
+// 
+// these are inherent rather than `Into` impls since `Into` impls over
+// the generic tag type would overlap with each other
+impl<'irb, T: TaintTag> CtxResponse<'irb, T> {
+    fn into_lift(self) -> LiftResult<'irb> {
         match self {
             CtxResponse::Fetch { result } => { result }
             _ => { panic!("expected Fetch response! got: {self:?}") }
         }
     }
-}
 
-impl<'irb> Into<Result<(BitVec, Tag), Error>> for CtxResponse<'irb> {
-    fn into(self) -> Result<(BitVec, Tag), Error> {
+    fn into_value(self) -> Result<(BitVec, T), Error> {
         match self {
             CtxResponse::Load { result } => { result.map_err(|e| e.into()) }
             CtxResponse::Read { result } => { result.map_err(|e| e.into()) }
             _ => { panic!("expected Load or Read response! got: {self:?}") }
         }
     }
-}
 
-impl <'irb> Into<Result<Tag, Error>> for CtxResponse<'irb> {
-    fn into(self) -> Result<Tag, Error> {
+    fn into_tag(self) -> Result<T, Error> {
         match self {
             CtxResponse::LoadBytes { result } => { result.map_err(|e| e.into()) }
             _ => { panic!("expected LoadBytes response! got: {self:?}") }
         }
     }
-}
 
-impl<'irb> Into<Result<(), Error>> for CtxResponse<'irb> {
-    fn into(self) -> Result<(), Error> {
+    fn into_unit(self) -> Result<(), Error> {
         match self {
             CtxResponse::Store { result } => { result.map_err(|e| e.into()) }
             CtxResponse::Write { result } => { result.map_err(|e| e.into()) }
@@ -508,20 +849,16 @@ impl<'irb> Into<Result<(), Error>> for CtxResponse<'irb> {
             _ => { panic!("expected Store or Write response! got: {self:?}") }
         }
     }
-}
 
-impl<'irb> Into<Result<(Address, Tag), Error>> for CtxResponse<'irb> {
-    fn into(self) -> Result<(Address, Tag), Error> {
+    fn into_address(self) -> Result<(Address, T), Error> {
         match self {
             CtxResponse::ReadPc { result } => { result.map_err(|e| e.into()) }
             CtxResponse::ReadSp { result } => { result.map_err(|e| e.into()) }
             _ => { panic!("expected ReadPc response! got: {self:?}") }
         }
     }
-}
 
-impl<'irb> Into<Result<Option<Location>, Error>> for CtxResponse<'irb> {
-    fn into(self) -> Result<Option<Location>, Error> {
+    fn into_location(self) -> Result<Option<Location>, Error> {
         match self {
             CtxResponse::CallOther { result } => { result.map_err(|e| e.into()) }
             _ => { panic!("expected CallOther response! got: {self:?}") }
@@ -3,9 +3,13 @@
 //! armv7m plugin
 
 use super::*;
+use crate::backend::armv7m::{FPRegs, FP_FRAME_OFFSET};
 
 #[derive(Debug, Clone, Default)]
-pub struct Armv7m;
+pub struct Armv7m {
+    /// the fp registers, looked up on first use
+    fp: Option<FPRegs>,
+}
 
 
 impl Armv7m {
@@ -13,15 +17,12 @@ impl Armv7m {
     /// primarily associated with pushing stack
     /// returns the tag value of the exception vector read from the vector table
     /// see B1.5.8
-    fn exception_entry(
+    fn exception_entry<T: TaintTag>(
         &mut self,
-        shadow: &mut ShadowState,
+        shadow: &mut ShadowState<T>,
         ctx_switch: &backend::ThreadSwitch,
         nofpext: bool,
-    ) -> Result<Tag, Error> {
-        // to support floating point, need to do extended stack
-        assert_eq!(nofpext, true, "floating point not supported");
-        // let framesize = 0x20;
+    ) -> Result<T, Error> {
         let frame_address = ctx_switch.new_frame_address;
         assert!(ctx_switch.return_address.is_some(),
             "exception entry must have associated return address");
@@ -40,16 +41,22 @@ impl Armv7m {
             });
         for (i, reg) in push_regs.enumerate() {
             let tag = shadow.read_tag(&reg)?;
-            shadow.write_mem_tags(&(frame_address + i * 4), 4, tag)?;
+            shadow.write_mem_tags(&(frame_address + i * 4), 4, &tag)?;
         }
 
         // push return address tag
         let return_address_tag = shadow.read_mem_tags(return_address, 4)?;
-        shadow.write_mem_tags(&(frame_address + 0x18u64), 4, return_address_tag)?;
+        shadow.write_mem_tags(&(frame_address + 0x18u64), 4, &return_address_tag)?;
         // push xpsr tag (always clean)
-        shadow.write_mem_tags(&(frame_address + 0x1Cu64), 4, Tag::from(tag::ACCESSED))?;
+        shadow.write_mem_tags(&(frame_address + 0x1Cu64), 4, &T::accessed())?;
+
+        if !nofpext {
+            // the fp registers keep their values until lazily preserved,
+            // so their tags can be pushed whether or not the values were
+            self.push_fp_context(shadow, &(frame_address + FP_FRAME_OFFSET))?;
+        }
 
-        shadow.write_tag(&lr_vnd, &Tag::from(tag::ACCESSED))?;
+        shadow.write_tag(&lr_vnd, &T::accessed())?;
 
         // get target address tag from exception typ
         assert_ne!(ctx_switch.typ, 0, "exception entry cannot have exception number 0");
@@ -62,14 +69,12 @@ impl Armv7m {
     /// returns the tag value of the stack location where the 
     /// return address was stored
     /// see B1.5.8
-    fn exception_return(
+    fn exception_return<T: TaintTag>(
         &mut self,
-        shadow: &mut ShadowState,
+        shadow: &mut ShadowState<T>,
         ctx_switch: &backend::ThreadSwitch,
         nofpext: bool,
-    ) -> Result<Tag, Error> {
-        assert_eq!(nofpext, true, "floating point not supported");
-        // let framesize = 0x20
+    ) -> Result<T, Error> {
         // might want to check the frame address for taint, but incorporate that later...
         let frame_address = ctx_switch.old_frame_address;
         assert!(ctx_switch.return_address.is_none(),
@@ -93,22 +98,66 @@ impl Armv7m {
         let _psr_tag = shadow
             .read_mem_tags(&(frame_address + 0x1Cu64), 4)?;
 
+        if !nofpext {
+            // if the context was never preserved, the frame still holds
+            // the tags pushed on entry, which are the registers' tags
+            self.pop_fp_context(shadow, &(frame_address + FP_FRAME_OFFSET))?;
+        }
+
         Ok(target_address_tag)
     }
+
+    /// push s0-s15 and fpscr tags to an extended frame
+    fn push_fp_context<T: TaintTag>(
+        &mut self,
+        shadow: &mut ShadowState<T>,
+        address: &Address,
+    ) -> Result<(), Error> {
+        let fp = self.fp_regs(shadow)?;
+        for (i, reg) in fp.context().enumerate() {
+            let tag = shadow.read_tag(reg)?;
+            shadow.write_mem_tags(&(*address + i as u64 * 4), 4, &tag)?;
+        }
+        Ok(())
+    }
+
+    /// pop s0-s15 and fpscr tags from an extended frame
+    fn pop_fp_context<T: TaintTag>(
+        &mut self,
+        shadow: &mut ShadowState<T>,
+        address: &Address,
+    ) -> Result<(), Error> {
+        let fp = self.fp_regs(shadow)?;
+        for (i, reg) in fp.context().enumerate() {
+            let tag = shadow.read_mem_tags(&(*address + i as u64 * 4), 4)?;
+            shadow.write_tag(reg, &tag)?;
+        }
+        Ok(())
+    }
+
+    /// the fp registers of the shadow's language
+    fn fp_regs<T: TaintTag>(&mut self, shadow: &ShadowState<T>) -> Result<&FPRegs, Error> {
+        if self.fp.is_none() {
+            let fp = FPRegs::new_from(shadow.lang.translator())
+                .ok_or(Error::MissingRegisters("fp extension"))?;
+            self.fp = Some(fp);
+        }
+        Ok(self.fp.as_ref().unwrap())
+    }
 }
 
-impl ArchPlugin for Armv7m {
+impl<T: TaintTag> ArchPlugin<T> for Armv7m {
     fn maybe_thread_switch(
         &mut self,
-        shadow: &mut ShadowState,
+        shadow: &mut ShadowState<T>,
         ctx_switch: &backend::ThreadSwitch,
-    ) -> Result<Tag, Error> {
+    ) -> Result<T, Error> {
         if ctx_switch.return_address.is_some() {
             // switch to handler
-            self.exception_entry(shadow, ctx_switch, true)
+            self.exception_entry(shadow, ctx_switch, !ctx_switch.fp_frame)
         } else {
             // return from handler
-            self.exception_return(shadow, ctx_switch, true)
+            self.exception_return(shadow, ctx_switch, !ctx_switch.fp_frame)
         }
     }
 }
\ No newline at end of file
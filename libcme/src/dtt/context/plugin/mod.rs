@@ -12,29 +12,29 @@ pub mod armv7m;
 
 /// plugins for handling tag propagation that are tied
 /// to arch-specific operations.
-pub trait ArchPlugin: DynClone + fmt::Debug {
+pub trait ArchPlugin<T: TaintTag = Tag>: DynClone + fmt::Debug {
     /// performs tag propagation on thread context switch
     /// and returns the tag of the target address
     fn maybe_thread_switch(
         &mut self,
-        shadow: &mut ShadowState,
+        shadow: &mut ShadowState<T>,
         ctx_switch: &backend::ThreadSwitch,
-    ) -> Result<Tag, Error>;
+    ) -> Result<T, Error>;
 }
-clone_trait_object!(ArchPlugin);
+clone_trait_object!(<T> ArchPlugin<T> where T: TaintTag);
 
 
-impl<'plugin> ArchPlugin for Box<dyn ArchPlugin + 'plugin> {
+impl<'plugin, T: TaintTag> ArchPlugin<T> for Box<dyn ArchPlugin<T> + 'plugin> {
     fn maybe_thread_switch(
         &mut self,
-        shadow: &mut ShadowState,
+        shadow: &mut ShadowState<T>,
         ctx_switch: &backend::ThreadSwitch,
-    ) -> Result<Tag, Error> {
+    ) -> Result<T, Error> {
         (**self).maybe_thread_switch(shadow, ctx_switch)
     }
 }
 
-pub fn plugin_from<'plugin>(arch: &ArchitectureDef) -> Box<dyn ArchPlugin + 'plugin> {
+pub fn plugin_from<'plugin, T: TaintTag>(arch: &ArchitectureDef) -> Box<dyn ArchPlugin<T> + 'plugin> {
     let details = (
         arch.processor(),
         arch.endian(),
@@ -43,7 +43,7 @@ pub fn plugin_from<'plugin>(arch: &ArchitectureDef) -> Box<dyn ArchPlugin + 'plu
     );
     match details {
         ("ARM", Endian::Little, 32, "Cortex") => {
-            Box::new(armv7m::Armv7m)
+            Box::new(armv7m::Armv7m::default())
         }
         _ => {
             panic!("arch shadow plugin not implemented for {arch}")
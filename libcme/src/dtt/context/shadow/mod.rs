@@ -11,11 +11,15 @@ use fugue_ir::{Address, VarnodeData};
 use fugue_core::language::Language;
 
 // use crate::backend;
+use crate::utils::{DirtyPages, next_snapshot_id};
+use crate::peripheral::channel::InputCounter;
 
 use super::tag::{
-    self,
     Tag,
+    TaintTag,
     state::{FixedTagState, FixedTagStateError},
+    provenance::ProvenanceState,
+    bits::BitShadow,
 };
 
 /// shadow state errors
@@ -35,52 +39,115 @@ pub enum Error {
 
 
 
+/// a mapped shadow memory region
+#[derive(Clone)]
+struct TagRegion<T: TaintTag> {
+    tags: FixedTagState<T>,
+    /// the tag the region was mapped with
+    tag: T,
+    /// pages written since the last snapshot or restore
+    dirty: DirtyPages,
+}
+
 /// a shadow state for pcode context
 #[derive(Clone)]
-pub struct ShadowState {
+pub struct ShadowState<T: TaintTag = Tag> {
     pub lang: Language,
-    regs: FixedTagState,
-    tmps: FixedTagState,
-    mmap: IntervalMap<u64, FixedTagState>,
+    regs: FixedTagState<T>,
+    tmps: FixedTagState<T>,
+    mmap: IntervalMap<u64, TagRegion<T>>,
+    /// id of the snapshot the dirty pages are tracked against
+    snapshot_id: Option<usize>,
+    /// input byte labels, if provenance tracking is enabled
+    provenance: Option<ProvenanceState>,
+    /// bit-precise taint masks, if enabled
+    bits: Option<BitShadow>,
+}
+
+/// a snapshot of the shadow state
+/// 
+/// register and temporary tags are small enough that they are
+/// always restored in full, memory tags only restore dirty pages.
+#[derive(Clone)]
+pub struct ShadowSnapshot<T: TaintTag = Tag> {
+    id: usize,
+    regs: FixedTagState<T>,
+    tmps: FixedTagState<T>,
+    mmap: IntervalMap<u64, TagRegion<T>>,
+    provenance: Option<ProvenanceState>,
+    bits: Option<BitShadow>,
 }
 
-impl ShadowState {
+impl<T: TaintTag> ShadowState<T> {
     pub fn new_with(lang: Language) -> Self {
         let t = lang.translator();
         let regs = FixedTagState::new(t.register_space_size());
         let tmps = FixedTagState::new(t.unique_space_size());
         let mmap = IntervalMap::default();
+        let snapshot_id = None;
+        let provenance = None;
+        let bits = None;
 
-        Self { lang, regs, tmps, mmap }
+        Self { lang, regs, tmps, mmap, snapshot_id, provenance, bits }
+    }
+
+    /// start tracking input byte labels
+    pub fn enable_provenance(&mut self, consumed: InputCounter) {
+        self.provenance = Some(ProvenanceState::new(consumed));
+    }
+
+    pub fn provenance(&self) -> Option<&ProvenanceState> {
+        self.provenance.as_ref()
+    }
+
+    pub fn provenance_mut(&mut self) -> Option<&mut ProvenanceState> {
+        self.provenance.as_mut()
+    }
+
+    /// start tracking bit-precise taint masks
+    pub fn enable_bits(&mut self) {
+        self.bits = Some(BitShadow::new());
+    }
+
+    pub fn bits(&self) -> Option<&BitShadow> {
+        self.bits.as_ref()
+    }
+
+    pub fn bits_mut(&mut self) -> Option<&mut BitShadow> {
+        self.bits.as_mut()
     }
 
     #[inline(always)]
-    pub fn get_pc_tag(&self) -> Result<Tag, Error> {
+    pub fn get_pc_tag(&self) -> Result<T, Error> {
         let pc_vnd = self._pc_vnd();
         self.regs.read_tag(pc_vnd.offset() as usize, pc_vnd.size())
             .map_err(|e| e.into())
     }
 
     #[inline(always)]
-    pub fn set_pc_tag(&mut self, tag: impl AsRef<Tag>) -> Result<(), Error> {
-        let tag = tag.as_ref();
-        let pc_vnd = self._pc_vnd();
-        self.regs.write_tag(pc_vnd.offset() as usize, pc_vnd.size(), *tag)
+    pub fn set_pc_tag(&mut self, tag: &T) -> Result<(), Error> {
+        let pc_vnd = self.lang.translator().program_counter();
+        if let Some(bits) = self.bits.as_mut() {
+            bits.clear(pc_vnd);
+        }
+        self.regs.write_tag(pc_vnd.offset() as usize, pc_vnd.size(), tag)
             .map_err(|e| e.into())
     }
 
     #[inline(always)]
-    pub fn get_sp_tag(&self) -> Result<Tag, Error> {
+    pub fn get_sp_tag(&self) -> Result<T, Error> {
         let sp_vnd = self._sp_vnd();
         self.regs.read_tag(sp_vnd.offset() as usize, sp_vnd.size())
             .map_err(|e| e.into())
     }
 
     #[inline(always)]
-    pub fn set_sp_tag(&mut self, tag: impl AsRef<Tag>) -> Result<(), Error> {
-        let tag = tag.as_ref();
-        let sp_vnd = self._sp_vnd();
-        self.regs.write_tag(sp_vnd.offset() as usize, sp_vnd.size(), *tag)
+    pub fn set_sp_tag(&mut self, tag: &T) -> Result<(), Error> {
+        let sp_vnd = self.lang.convention().stack_pointer().varnode();
+        if let Some(bits) = self.bits.as_mut() {
+            bits.clear(sp_vnd);
+        }
+        self.regs.write_tag(sp_vnd.offset() as usize, sp_vnd.size(), tag)
             .map_err(|e| e.into())
     }
 
@@ -88,9 +155,9 @@ impl ShadowState {
     pub fn map_mem(&mut self,
         base: impl Into<Address>,
         size: usize,
-        tag: Option<Tag>,
+        tag: Option<T>,
     ) -> Result<(), Error> {
-        let tag = tag.unwrap_or(Tag::from(tag::UNACCESSED)).into();
+        let tag = tag.unwrap_or_default();
         let base: Address = base.into();
         // mapped memory must be word-aligned
         assert_eq!(base.offset() & 0b11, 0, "base {base:#x?} is not word-aligned!");
@@ -102,16 +169,17 @@ impl ShadowState {
             return Err(Error::MapConflict(range, colliding));
         }
 
-        let mem = FixedTagState::new_with(size, tag);
-        self.mmap.insert(range, mem);
+        let tags = FixedTagState::new_with(size, tag.clone());
+        let dirty = DirtyPages::new(size);
+        self.mmap.insert(range, TagRegion { tags, tag, dirty });
 
         Ok(())
     }
 
-    pub fn read_tag(&self, vnd: &VarnodeData) -> Result<Tag, Error> {
+    pub fn read_tag(&self, vnd: &VarnodeData) -> Result<T, Error> {
         let spc = vnd.space();
         if spc.is_constant() {
-            Ok(Tag::from(tag::UNACCESSED))
+            Ok(T::default())
         } else if spc.is_register() {
             self.regs.read_tag(vnd.offset() as usize, vnd.size())
                 .map_err(|e| e.into())
@@ -125,8 +193,11 @@ impl ShadowState {
         }
     }
 
-    pub fn write_tag(&mut self, vnd: &VarnodeData, tag: &Tag) -> Result<(), Error> {
+    pub fn write_tag(&mut self, vnd: &VarnodeData, tag: &T) -> Result<(), Error> {
         let spc = vnd.space();
+        if let Some(bits) = self.bits.as_mut().filter(|_| !spc.is_default()) {
+            bits.clear(vnd);
+        }
         if spc.is_register() {
             self.regs.write_tag(vnd.offset() as usize, vnd.size(), tag)
                 .map_err(|e| e.into())
@@ -142,12 +213,12 @@ impl ShadowState {
         }
     }
 
-    pub fn read_mem_tags(&self, address: impl AsRef<Address>, size: usize) -> Result<Tag, Error> {
+    pub fn read_mem_tags(&self, address: impl AsRef<Address>, size: usize) -> Result<T, Error> {
         let tag_mem = self.view_mem_tags(address, size)?;
-        Ok(tag_mem.iter().fold(Tag::new(), |result, t| result | t))
+        Ok(tag_mem.iter().fold(T::default(), |result, t| result.join(t)))
     }
 
-    pub fn view_mem_tags(&self, address: impl AsRef<Address>, size: usize) -> Result<&[Tag], Error> {
+    pub fn view_mem_tags(&self, address: impl AsRef<Address>, size: usize) -> Result<&[T], Error> {
         let address = address.as_ref();
         let (range, mem) = self._get_mem_tagstate(address)?;
         let offset = (address.offset() - range.start) as usize;
@@ -155,27 +226,104 @@ impl ShadowState {
             .map_err(|e| e.into())
     }
 
-    pub fn write_mem_tags(&mut self, address: impl AsRef<Address>, size: usize, tag: impl AsRef<Tag>) -> Result<(), Error> {
-        let tag = tag.as_ref();
+    pub fn write_mem_tags(&mut self, address: impl AsRef<Address>, size: usize, tag: &T) -> Result<(), Error> {
+        if let Some(bits) = self.bits.as_mut() {
+            bits.clear_mem(&address, size);
+        }
         let tag_mem = self.view_mem_tags_mut(address, size)?;
         for t in tag_mem.iter_mut() {
-            t.set_raw(tag.get_raw());
+            *t = tag.clone();
         }
         Ok(())
     } 
 
-    pub fn view_mem_tags_mut(&mut self, address: impl AsRef<Address>, size: usize) -> Result<&mut [Tag], Error> {
+    pub fn view_mem_tags_mut(&mut self, address: impl AsRef<Address>, size: usize) -> Result<&mut [T], Error> {
         let address = address.as_ref();
-        let (range, mem) = self._get_mem_tagstate_mut(address)?;
+        let (range, region) = self._get_mem_tagstate_mut(address)?;
         let offset = (address.offset() - range.start) as usize;
-        mem.view_mut(offset, size)
-            .map_err(|e| e.into())
+        let tags = region.tags.view_mut(offset, size)?;
+        region.dirty.mark(offset, size);
+        Ok(tags)
+    }
+
+    /// take a snapshot of the shadow state
+    /// 
+    /// dirty page tracking is reset relative to the new snapshot
+    pub fn snapshot(&mut self) -> ShadowSnapshot<T> {
+        let id = next_snapshot_id();
+        for (_range, region) in self.mmap.iter_mut(..) {
+            region.dirty.clear();
+        }
+        self.snapshot_id = Some(id);
+        ShadowSnapshot {
+            id,
+            regs: self.regs.clone(),
+            tmps: self.tmps.clone(),
+            mmap: self.mmap.clone(),
+            provenance: self.provenance.clone(),
+            bits: self.bits.clone(),
+        }
+    }
+
+    /// reset all tags to the ones they were mapped with, e.g. on a
+    /// system reset. provenance labels and taint masks start over.
+    pub fn reset(&mut self) {
+        self.regs.backing.fill(T::default());
+        self.tmps.backing.fill(T::default());
+        for (_range, region) in self.mmap.iter_mut(..) {
+            region.tags.backing.fill(region.tag.clone());
+            region.dirty.mark_all();
+        }
+        if let Some(provenance) = self.provenance.as_mut() {
+            *provenance = ProvenanceState::new(provenance.counter().clone());
+        }
+        if let Some(bits) = self.bits.as_mut() {
+            *bits = BitShadow::new();
+        }
+    }
+
+    /// restore the shadow state from a snapshot
+    /// 
+    /// if the snapshot is the one dirty pages are being tracked against,
+    /// only the dirty pages are copied. otherwise all tags are copied.
+    /// provenance labels and taint masks are always restored in full.
+    pub fn restore(&mut self, snapshot: &ShadowSnapshot<T>) -> Result<(), Error> {
+        self.regs.backing.clone_from_slice(&snapshot.regs.backing);
+        self.tmps.backing.clone_from_slice(&snapshot.tmps.backing);
+        self.provenance = match (&self.provenance, &snapshot.provenance) {
+            (Some(_), Some(saved)) => { Some(saved.clone()) }
+            // enabled after the snapshot was taken, so start over
+            (Some(current), None) => { Some(ProvenanceState::new(current.counter().clone())) }
+            (None, _) => { None }
+        };
+        self.bits = match (&self.bits, &snapshot.bits) {
+            (Some(_), Some(saved)) => { Some(saved.clone()) }
+            (Some(_), None) => { Some(BitShadow::new()) }
+            (None, _) => { None }
+        };
+        if self.snapshot_id != Some(snapshot.id) || self.mmap.len() != snapshot.mmap.len() {
+            self.mmap = snapshot.mmap.clone();
+            self.snapshot_id = Some(snapshot.id);
+            return Ok(());
+        }
+        let saved_regions = snapshot.mmap.iter(..);
+        for ((range, region), (saved_range, saved)) in self.mmap.iter_mut(..).zip(saved_regions) {
+            if range != saved_range {
+                return Err(Error::MapConflict(range, saved_range));
+            }
+            for dirty in region.dirty.ranges() {
+                region.tags.backing[dirty.clone()]
+                    .clone_from_slice(&saved.tags.backing[dirty]);
+            }
+            region.dirty.clear();
+        }
+        Ok(())
     }
 }
 
 
 
-impl ShadowState {
+impl<T: TaintTag> ShadowState<T> {
     fn _pc_vnd(&self) -> &VarnodeData {
         self.lang.translator().program_counter()
     }
@@ -184,17 +332,17 @@ impl ShadowState {
         self.lang.convention().stack_pointer().varnode()
     }
 
-    fn _get_mem_tagstate(&self, address: &Address) -> Result<(Range<u64>, &FixedTagState), Error> {
+    fn _get_mem_tagstate(&self, address: &Address) -> Result<(Range<u64>, &FixedTagState<T>), Error> {
         let mut overlaps = self.mmap.overlap(address.offset());
-        let (range, mem) = overlaps.next()
+        let (range, region) = overlaps.next()
             .ok_or(Error::Unmapped(address.offset()))?;
         if let Some((conflict, _)) = overlaps.next() {
             return Err(Error::MapConflict(range, conflict));
         }
-        Ok((range, mem))
+        Ok((range, &region.tags))
     }
 
-    fn _get_mem_tagstate_mut(&mut self, address: &Address) -> Result<(Range<u64>, &mut FixedTagState), Error> {
+    fn _get_mem_tagstate_mut(&mut self, address: &Address) -> Result<(Range<u64>, &mut TagRegion<T>), Error> {
         let mut overlaps = self.mmap.overlap_mut(address.offset());
         let (range, mem) = overlaps.next()
             .ok_or(Error::Unmapped(address.offset()))?;
@@ -206,7 +354,7 @@ impl ShadowState {
 }
 
 
-impl fmt::Debug for ShadowState {
+impl<T: TaintTag> fmt::Debug for ShadowState<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "ShadowState {{ {:?} }}", self.lang.translator().architecture())
     }
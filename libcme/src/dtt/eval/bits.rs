@@ -0,0 +1,127 @@
+//! bits.rs
+//!
+//! bit-precise taint propagation rules
+//!
+//! each rule takes the values and taint masks of an operation's inputs
+//! and returns the taint mask of its output. operations without a rule
+//! return None, meaning every output bit is tainted if any input is.
+use fugue_bv::BitVec;
+use fugue_ir::disassembly::Opcode;
+
+/// the taint mask of a binary integer operation's output
+pub(super) fn int2_mask(
+    opcode: &Opcode,
+    lhs: (&BitVec, &BitVec),
+    rhs: (&BitVec, &BitVec),
+) -> Option<BitVec> {
+    let (lval, lmask) = (lhs.0.clone().unsigned(), lhs.1.clone().unsigned());
+    let (rval, rmask) = (rhs.0.clone().unsigned(), rhs.1.clone().unsigned());
+    let size = lval.bits().max(rval.bits());
+    let (lval, lmask) = (lval.cast(size), lmask.cast(size));
+    let (rval, rmask) = (rval.cast(size), rmask.cast(size));
+    match opcode {
+        Opcode::IntAnd => {
+            // an untainted zero bit in either input forces the output bit
+            let both = lmask.clone() & rmask.clone();
+            Some(both | (lmask & rval) | (rmask & lval))
+        }
+        Opcode::IntOr => {
+            // an untainted one bit in either input forces the output bit
+            let both = lmask.clone() & rmask.clone();
+            Some(both | (lmask & !rval) | (rmask & !lval))
+        }
+        Opcode::IntXor => {
+            Some(lmask | rmask)
+        }
+        Opcode::IntLShift
+        | Opcode::IntRShift
+        | Opcode::IntSRShift => {
+            // a tainted shift amount can move any bit anywhere
+            if !rmask.is_zero() {
+                return None;
+            }
+            let bits = lhs.0.bits();
+            let lmask = lhs.1.clone().unsigned().cast(bits);
+            let amount = rhs.0.to_u64()
+                .map(|amount| amount.min(bits as u64) as u32)
+                .unwrap_or(bits as u32);
+            Some(_shift(opcode, lmask, amount, bits as u32))
+        }
+        _ => { None }
+    }
+}
+
+/// the taint mask of a unary integer operation's output
+pub(super) fn int1_mask(
+    opcode: &Opcode,
+    rhs: &BitVec,
+    bits: usize,
+) -> Option<BitVec> {
+    match opcode {
+        Opcode::IntZExt => { Some(rhs.clone().unsigned().cast(bits)) }
+        // the extended bits are copies of the sign bit, and so is their taint
+        Opcode::IntSExt => { Some(rhs.clone().signed().cast(bits).unsigned()) }
+        Opcode::IntNot => { Some(rhs.clone().unsigned()) }
+        _ => { None }
+    }
+}
+
+/// shift a mask, where an arithmetic right shift copies the taint of
+/// the sign bit into the bits it fills
+fn _shift(opcode: &Opcode, mask: BitVec, amount: u32, bits: u32) -> BitVec {
+    match opcode {
+        _ if amount == 0 => { mask }
+        Opcode::IntLShift if amount >= bits => { BitVec::from_u64(0, bits as usize) }
+        Opcode::IntLShift => { mask << amount }
+        Opcode::IntRShift if amount >= bits => { BitVec::from_u64(0, bits as usize) }
+        Opcode::IntRShift => { mask >> amount }
+        _ => {
+            let amount = amount.min(bits - 1);
+            (mask.signed() >> amount).unsigned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bv(val: u32) -> BitVec {
+        BitVec::from_u32(val, 32)
+    }
+
+    #[test]
+    fn test_int2_masks() {
+        let tainted = (&bv(0x1234_5678), &bv(0xffff_ffff));
+        let mask = int2_mask(&Opcode::IntAnd, tainted, (&bv(0), &bv(0)));
+        assert_eq!(mask, Some(bv(0)), "and with untainted zero");
+        let mask = int2_mask(&Opcode::IntAnd, tainted, (&bv(0xff), &bv(0)));
+        assert_eq!(mask, Some(bv(0xff)), "and with untainted byte mask");
+        let mask = int2_mask(&Opcode::IntOr, tainted, (&bv(0xffff_0000), &bv(0)));
+        assert_eq!(mask, Some(bv(0x0000_ffff)), "or with untainted ones");
+        let mask = int2_mask(&Opcode::IntRShift, tainted, (&bv(24), &bv(0)));
+        assert_eq!(mask, Some(bv(0xff)), "shift right");
+        let mask = int2_mask(&Opcode::IntLShift, tainted, (&bv(32), &bv(0)));
+        assert_eq!(mask, Some(bv(0)), "shift out every bit");
+
+        let sign = (&bv(0x8000_0000), &bv(0x8000_0000));
+        let mask = int2_mask(&Opcode::IntSRShift, sign, (&bv(31), &bv(0)));
+        assert_eq!(mask, Some(bv(0xffff_ffff)), "arithmetic shift copies sign taint");
+        let mask = int2_mask(&Opcode::IntRShift, sign, (&bv(31), &bv(0)));
+        let mask = mask.map(|mask| int2_mask(&Opcode::IntAnd, (&bv(1), &mask), (&bv(0), &bv(0))));
+        assert_eq!(mask, Some(Some(bv(0))), "(tainted >> 31) & 0");
+
+        let mask = int2_mask(&Opcode::IntLShift, tainted, (&bv(4), &bv(1)));
+        assert_eq!(mask, None, "tainted shift amount");
+        let mask = int2_mask(&Opcode::IntAdd, tainted, (&bv(1), &bv(0)));
+        assert_eq!(mask, None, "no rule for add");
+    }
+
+    #[test]
+    fn test_int1_masks() {
+        let mask = BitVec::from_u32(0x80, 8);
+        assert_eq!(int1_mask(&Opcode::IntZExt, &mask, 32), Some(bv(0x80)));
+        assert_eq!(int1_mask(&Opcode::IntSExt, &mask, 32), Some(bv(0xffff_ff80)));
+        assert_eq!(int1_mask(&Opcode::IntNeg, &mask, 8), None);
+    }
+}
@@ -0,0 +1,201 @@
+//! float.rs
+//!
+//! floating point pcode semantics
+//!
+//! float varnodes hold ieee-754 single or double precision values.
+//! operations are evaluated with the host's floating point arithmetic,
+//! which rounds to nearest even, the same as the fpscr's default mode.
+use fugue_bv::BitVec;
+use fugue_ir::disassembly::Opcode;
+
+use crate::utils::bool2bv;
+
+/// an ieee-754 value of a float varnode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Float {
+    Single(f32),
+    Double(f64),
+}
+
+impl Float {
+    /// interpret a bitvector as a float of the same size
+    pub fn from_bv(val: &BitVec) -> Option<Self> {
+        let raw = val.clone().unsigned().to_u64()?;
+        match val.bits() {
+            32 => { Some(Self::Single(f32::from_bits(raw as u32))) }
+            64 => { Some(Self::Double(f64::from_bits(raw))) }
+            _ => { None }
+        }
+    }
+
+    /// convert a value to a float of the given size
+    pub fn from_f64(val: f64, bits: usize) -> Option<Self> {
+        match bits {
+            32 => { Some(Self::Single(val as f32)) }
+            64 => { Some(Self::Double(val)) }
+            _ => { None }
+        }
+    }
+
+    /// convert a signed integer to a float of the given size,
+    /// rounding once to the target precision
+    pub fn from_i64(val: i64, bits: usize) -> Option<Self> {
+        match bits {
+            32 => { Some(Self::Single(val as f32)) }
+            64 => { Some(Self::Double(val as f64)) }
+            _ => { None }
+        }
+    }
+
+    pub fn to_bv(self) -> BitVec {
+        match self {
+            Self::Single(val) => { BitVec::from_u32(val.to_bits(), 32) }
+            Self::Double(val) => { BitVec::from_u64(val.to_bits(), 64) }
+        }
+    }
+
+    /// widen to double precision, which is exact
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Self::Single(val) => { val as f64 }
+            Self::Double(val) => { val }
+        }
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.to_f64().is_nan()
+    }
+
+    /// truncate toward zero to a signed integer of the given size,
+    /// saturating out of range values and converting nan to 0 like vcvt
+    pub fn to_int(self, bits: usize) -> BitVec {
+        let val = self.to_f64();
+        let int = match bits {
+            8 => { val as i8 as i64 }
+            16 => { val as i16 as i64 }
+            32 => { val as i32 as i64 }
+            _ => { val as i64 }
+        };
+        BitVec::from_u64(int as u64, bits)
+    }
+
+    fn _apply1(self, single: fn(f32) -> f32, double: fn(f64) -> f64) -> Self {
+        match self {
+            Self::Single(val) => { Self::Single(single(val)) }
+            Self::Double(val) => { Self::Double(double(val)) }
+        }
+    }
+}
+
+/// the result of a binary float operation, or None if the operand
+/// sizes aren't supported
+pub(super) fn float2(opcode: &Opcode, lhs: &BitVec, rhs: &BitVec) -> Option<BitVec> {
+    let (lhs, rhs) = (Float::from_bv(lhs)?, Float::from_bv(rhs)?);
+    let result = match (opcode, lhs, rhs) {
+        (Opcode::FloatEq, lhs, rhs) => { return Some(bool2bv(lhs.to_f64() == rhs.to_f64())) }
+        (Opcode::FloatNotEq, lhs, rhs) => { return Some(bool2bv(lhs.to_f64() != rhs.to_f64())) }
+        (Opcode::FloatLess, lhs, rhs) => { return Some(bool2bv(lhs.to_f64() < rhs.to_f64())) }
+        (Opcode::FloatLessEq, lhs, rhs) => { return Some(bool2bv(lhs.to_f64() <= rhs.to_f64())) }
+        (op, Float::Single(lhs), Float::Single(rhs)) => {
+            Float::Single(match op {
+                Opcode::FloatAdd => { lhs + rhs }
+                Opcode::FloatSub => { lhs - rhs }
+                Opcode::FloatMul => { lhs * rhs }
+                Opcode::FloatDiv => { lhs / rhs }
+                _ => { return None }
+            })
+        }
+        (op, Float::Double(lhs), Float::Double(rhs)) => {
+            Float::Double(match op {
+                Opcode::FloatAdd => { lhs + rhs }
+                Opcode::FloatSub => { lhs - rhs }
+                Opcode::FloatMul => { lhs * rhs }
+                Opcode::FloatDiv => { lhs / rhs }
+                _ => { return None }
+            })
+        }
+        // mixed precision arithmetic isn't valid pcode
+        _ => { return None }
+    };
+    Some(result.to_bv())
+}
+
+/// the result of a unary float operation with an output of `bits` bits,
+/// or None if the input or output size isn't supported
+pub(super) fn float1(opcode: &Opcode, rhs: &BitVec, bits: usize) -> Option<BitVec> {
+    if let Opcode::FloatOfInt = opcode {
+        let val = rhs.clone().signed().cast(64).to_i64()?;
+        return Float::from_i64(val, bits).map(Float::to_bv);
+    }
+    let val = Float::from_bv(rhs)?;
+    let result = match opcode {
+        Opcode::FloatIsNaN => { return Some(bool2bv(val.is_nan())) }
+        Opcode::FloatTruncate => { return Some(val.to_int(bits)) }
+        Opcode::FloatOfFloat => { Float::from_f64(val.to_f64(), bits)? }
+        Opcode::FloatNeg => { val._apply1(|val| -val, |val| -val) }
+        Opcode::FloatAbs => { val._apply1(f32::abs, f64::abs) }
+        Opcode::FloatSqrt => { val._apply1(f32::sqrt, f64::sqrt) }
+        Opcode::FloatCeiling => { val._apply1(f32::ceil, f64::ceil) }
+        Opcode::FloatFloor => { val._apply1(f32::floor, f64::floor) }
+        // p-code ROUND rounds half up, i.e. floor(x + 0.5), not away from zero
+        Opcode::FloatRound => {
+            val._apply1(|val| (val as f64 + 0.5).floor() as f32, |val| (val + 0.5).floor())
+        }
+        _ => { return None }
+    };
+    Some(result.to_bv())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn single(val: f32) -> BitVec {
+        Float::Single(val).to_bv()
+    }
+
+    fn double(val: f64) -> BitVec {
+        Float::Double(val).to_bv()
+    }
+
+    #[test]
+    fn test_float2() {
+        let sum = float2(&Opcode::FloatAdd, &single(0.1), &single(0.2));
+        assert_eq!(sum, Some(single(0.1f32 + 0.2f32)), "single precision rounding");
+        let sum = float2(&Opcode::FloatAdd, &double(0.1), &double(0.2));
+        assert_eq!(sum, Some(double(0.1f64 + 0.2f64)), "double precision rounding");
+        let quot = float2(&Opcode::FloatDiv, &single(1.0), &single(0.0));
+        assert_eq!(quot, Some(single(f32::INFINITY)));
+
+        let nan = single(f32::NAN);
+        assert_eq!(float2(&Opcode::FloatEq, &nan, &nan), Some(bool2bv(false)), "nan is unordered");
+        assert_eq!(float2(&Opcode::FloatNotEq, &nan, &nan), Some(bool2bv(true)));
+        assert_eq!(float2(&Opcode::FloatLess, &single(-1.0), &single(1.0)), Some(bool2bv(true)));
+        assert_eq!(float2(&Opcode::FloatMul, &single(1.0), &double(1.0)), None, "mixed sizes");
+    }
+
+    #[test]
+    fn test_float1() {
+        let int = BitVec::from_u32(-3i32 as u32, 32);
+        assert_eq!(float1(&Opcode::FloatOfInt, &int, 64), Some(double(-3.0)));
+        assert_eq!(float1(&Opcode::FloatOfFloat, &single(1.5), 64), Some(double(1.5)));
+        assert_eq!(float1(&Opcode::FloatOfFloat, &double(1.5), 32), Some(single(1.5)));
+        assert_eq!(float1(&Opcode::FloatTruncate, &single(-2.75), 32), Some(BitVec::from_u32(-2i32 as u32, 32)));
+        assert_eq!(float1(&Opcode::FloatTruncate, &single(1e20), 32), Some(BitVec::from_u32(i32::MAX as u32, 32)), "saturate");
+        assert_eq!(float1(&Opcode::FloatTruncate, &single(f32::NAN), 32), Some(BitVec::from_u32(0, 32)));
+        assert_eq!(float1(&Opcode::FloatIsNaN, &double(f64::NAN), 8), Some(bool2bv(true)));
+        assert_eq!(float1(&Opcode::FloatAbs, &single(-0.5), 32), Some(single(0.5)));
+        assert_eq!(float1(&Opcode::FloatSqrt, &double(2.0), 64), Some(double(2f64.sqrt())));
+        assert_eq!(float1(&Opcode::FloatNeg, &BitVec::from_u16(0, 16), 16), None, "half precision");
+    }
+
+    #[test]
+    fn test_float_round() {
+        assert_eq!(float1(&Opcode::FloatRound, &single(2.5), 32), Some(single(3.0)));
+        assert_eq!(float1(&Opcode::FloatRound, &single(-2.5), 32), Some(single(-2.0)), "half rounds up");
+        assert_eq!(float1(&Opcode::FloatRound, &double(-0.5), 64), Some(double(0.0)));
+        assert_eq!(float1(&Opcode::FloatRound, &double(-1.5), 64), Some(double(-1.0)));
+        assert_eq!(float1(&Opcode::FloatRound, &double(-1.6), 64), Some(double(-2.0)));
+        assert_eq!(float1(&Opcode::FloatRound, &single(1.4), 32), Some(single(1.0)));
+    }
+}
@@ -0,0 +1,200 @@
+//! implicit.rs
+//!
+//! implicit flow tracking
+//!
+//! a value assigned under a tainted branch depends on the branch condition
+//! even though no data flows from the condition to the value, e.g. in
+//! table lookups and switch-based parsers. when implicit flows are tracked,
+//! a tainted conditional branch opens a control scope that lasts until
+//! execution reconverges at the branch's immediate post-dominator, and the
+//! condition's tag is joined into every assignment made within the scope.
+//!
+//! post-dominators come from the cfg explored so far. until both sides of
+//! a branch and the code after them have been explored, a branch has no
+//! known post-dominator. its scope is resolved again as the cfg grows,
+//! and lasts until its function returns if none is found.
+use crate::dtt::tag::{TaintTag, LabelSet};
+
+/// a control-dependence scope opened by a tainted conditional branch
+#[derive(Debug, Clone)]
+struct ControlScope<T: TaintTag> {
+    /// the address of the branch and its successors
+    branch: (u64, [u64; 2]),
+    /// the address execution reconverges at
+    reconverge: Option<u64>,
+    /// the call depth of the branch
+    depth: isize,
+    /// the tag of the branch condition
+    tag: T,
+    /// the provenance labels of the branch condition
+    labels: LabelSet,
+}
+
+/// implicit flow state for the evaluator
+#[derive(Debug, Clone)]
+pub struct ImplicitFlows<T: TaintTag> {
+    /// calls minus returns since tracking started,
+    /// negative once execution returns past where it started
+    depth: isize,
+    scopes: Vec<ControlScope<T>>,
+    /// scopes of the threads interrupted by exceptions
+    saved: Vec<(isize, Vec<ControlScope<T>>)>,
+    /// the tainted branch in the current instruction and its taken target
+    pending: Option<(T, LabelSet, u64)>,
+}
+
+impl<T: TaintTag> Default for ImplicitFlows<T> {
+    fn default() -> Self {
+        Self {
+            depth: 0,
+            scopes: vec![],
+            saved: vec![],
+            pending: None,
+        }
+    }
+}
+
+impl<T: TaintTag> ImplicitFlows<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// true if any control scope is open
+    pub fn is_active(&self) -> bool {
+        !self.scopes.is_empty()
+    }
+
+    /// the joined tag of all open scopes
+    pub fn tag(&self) -> Option<T> {
+        let (first, rest) = self.scopes.split_first()?;
+        Some(rest.iter().fold(first.tag.clone(), |tag, scope| tag.join(&scope.tag)))
+    }
+
+    /// the union of the condition labels of all open scopes
+    pub fn labels(&self) -> LabelSet {
+        LabelSet::union_all(self.scopes.iter().map(|scope| &scope.labels))
+    }
+
+    /// record a tainted conditional branch in the current instruction
+    pub fn branch(&mut self, tag: T, labels: LabelSet, target: u64) {
+        self.pending = Some((tag, labels, target));
+    }
+
+    /// take the tainted branch recorded in the current instruction
+    pub fn take_pending(&mut self) -> Option<(T, LabelSet, u64)> {
+        self.pending.take()
+    }
+
+    /// open a control scope for a branch at the current call depth
+    pub fn push(
+        &mut self,
+        tag: T,
+        labels: LabelSet,
+        branch: (u64, [u64; 2]),
+        reconverge: Option<u64>,
+    ) {
+        let depth = self.depth;
+        self.scopes.push(ControlScope { branch, reconverge, depth, tag, labels });
+    }
+
+    /// look up the reconvergence points of open scopes that don't have one
+    pub fn resolve(&mut self, mut reconvergence_point: impl FnMut(u64, [u64; 2]) -> Option<u64>) {
+        for scope in self.scopes.iter_mut().filter(|scope| scope.reconverge.is_none()) {
+            let (branch, successors) = scope.branch;
+            scope.reconverge = reconvergence_point(branch, successors);
+        }
+    }
+
+    pub fn call(&mut self) {
+        self.depth += 1;
+    }
+
+    pub fn ret(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// close the scopes that end at `address`, either because execution
+    /// reconverged or because the function containing the branch returned
+    pub fn update(&mut self, address: u64) {
+        let depth = self.depth;
+        self.scopes.retain(|scope| {
+            let returned = depth < scope.depth;
+            let reconverged = depth == scope.depth && scope.reconverge == Some(address & !1);
+            !(returned || reconverged)
+        });
+    }
+
+    /// exception handlers aren't control dependent on the code they
+    /// interrupt, so they start with no open scopes
+    pub fn exception_entry(&mut self) {
+        let scopes = std::mem::take(&mut self.scopes);
+        self.saved.push((self.depth, scopes));
+        self.depth = 0;
+    }
+
+    /// restore the scopes of the interrupted thread
+    pub fn exception_return(&mut self) {
+        (self.depth, self.scopes) = self.saved.pop().unwrap_or_default();
+    }
+
+    /// close all scopes
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtt::tag::{Tag, ACCESSED, TAINTED_VAL};
+
+    #[test]
+    fn test_control_scopes() {
+        let tainted = Tag::from(TAINTED_VAL);
+        let mut flows = ImplicitFlows::<Tag>::new();
+        assert!(flows.tag().is_none(), "no open scopes");
+
+        // branch reconverges at 0x20
+        flows.branch(tainted, LabelSet::single(3), 0x10);
+        let (tag, labels, _target) = flows.take_pending().unwrap();
+        flows.push(tag, labels, (0x0c, [0x10, 0x0e]), Some(0x20));
+        flows.update(0x10);
+        assert_eq!(flows.tag(), Some(tainted), "scope open in branch arm");
+        assert!(flows.labels().contains(3));
+
+        // a call in the arm stays under the scope
+        flows.call();
+        flows.update(0x100);
+        assert!(flows.is_active(), "scope open in callee");
+        flows.ret();
+        flows.update(0x14);
+        assert!(flows.is_active(), "scope open after callee returns");
+
+        // exception handlers start without scopes
+        flows.exception_entry();
+        assert!(!flows.is_active(), "handler isn't control dependent");
+        flows.exception_return();
+        assert!(flows.is_active(), "scope restored after handler");
+
+        flows.update(0x21);
+        assert!(!flows.is_active(), "scope closed at reconvergence");
+
+        // a scope without a reconvergence point picks one up later
+        flows.push(Tag::from(ACCESSED), LabelSet::new(), (0x30, [0x34, 0x32]), None);
+        flows.resolve(|_, _| None);
+        flows.update(0x38);
+        assert!(flows.is_active(), "scope open without reconvergence point");
+        flows.resolve(|branch, successors| {
+            assert_eq!((branch, successors), (0x30, [0x34, 0x32]));
+            Some(0x38)
+        });
+        flows.update(0x38);
+        assert!(!flows.is_active(), "scope closed at resolved reconvergence");
+
+        // a scope without a reconvergence point closes when its function returns
+        flows.push(Tag::from(ACCESSED), LabelSet::new(), (0x30, [0x34, 0x32]), None);
+        flows.ret();
+        flows.update(0x40);
+        assert!(!flows.is_active(), "scope closed on return");
+    }
+}
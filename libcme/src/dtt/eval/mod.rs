@@ -9,6 +9,7 @@ use fugue_core::prelude::*;
 use fugue_core::ir::Location;
 use fugue_ir::disassembly::{Opcode, VarnodeData, PCodeData};
 
+use crate::backend::{self, FaultCause, ThreadSwitch};
 use crate::dtt::context::{self, Context};
 use crate::programdb::{self, ProgramDB};
 use crate::types::*;
@@ -23,9 +24,16 @@ use super::policy::{
 use super::tag::{
     self,
     Tag,
+    TaintTag,
+    LabelSet,
 };
 use super::EvalPlugin;
 
+mod bits;
+mod float;
+mod implicit;
+use implicit::ImplicitFlows;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("invalid address: {0:x}")]
@@ -34,6 +42,8 @@ pub enum Error {
     DivideByZero(Address),
     #[error("unsupported opcode: {0:?}")]
     Unsupported(Opcode),
+    #[error("unsupported float sizes for {0:?}: {1} -> {2} bits")]
+    FloatSize(Opcode, usize, usize),
     #[error(transparent)]
     Context(#[from] context::Error),
     #[error(transparent)]
@@ -60,11 +70,15 @@ impl From<plugin::Error> for Error {
 
 /// concrete pcode evaluator
 #[derive(Debug)]
-pub struct Evaluator<'policy, 'plugin> {
+pub struct Evaluator<'policy, 'plugin, T: TaintTag = Tag> {
     pub pc: Location,
-    pub pc_tag: Tag,
-    pub policy: EvalPolicy<'policy>,
-    plugin: EvaluatorPlugin<'plugin>,
+    pub pc_tag: T,
+    pub policy: EvalPolicy<'policy, T>,
+    plugin: EvaluatorPlugin<'plugin, T>,
+    /// control scopes, if implicit flows are tracked
+    implicit: Option<ImplicitFlows<T>>,
+    /// the thread switch taken at the start of the last step
+    thread_switch: Option<ThreadSwitch>,
 }
 
 impl<'policy, 'plugin> Default for Evaluator<'policy, 'plugin> {
@@ -73,8 +87,10 @@ impl<'policy, 'plugin> Default for Evaluator<'policy, 'plugin> {
         Self {
             pc: Location::default(),
             pc_tag: Tag::from(tag::ACCESSED),
-            policy: EvalPolicy { inner },
+            policy: EvalPolicy::new_with(inner),
             plugin: EvaluatorPlugin::default(),
+            implicit: None,
+            thread_switch: None,
         }
     }
 }
@@ -83,49 +99,124 @@ impl<'policy, 'plugin> Evaluator<'policy, 'plugin> {
     pub fn new() -> Self {
         Self::default()
     }
+}
 
-    pub fn new_with_policy(policy: Box<dyn TaintPolicy + 'policy>) -> Self {
+impl<'policy, 'plugin, T: TaintTag> Evaluator<'policy, 'plugin, T> {
+    pub fn new_with_policy(policy: Box<dyn TaintPolicy<T> + 'policy>) -> Self {
         Self {
             pc: Location::default(),
-            pc_tag: Tag::from(tag::ACCESSED),
-            policy: EvalPolicy { inner: policy },
+            pc_tag: T::accessed(),
+            policy: EvalPolicy::new_with(policy),
             plugin: EvaluatorPlugin::default(),
+            implicit: None,
+            thread_switch: None,
         }
     }
 
-    pub fn add_plugin(&mut self, plugin:  Box<dyn EvalPlugin + 'plugin>) {
+    pub fn add_plugin(&mut self, plugin:  Box<dyn EvalPlugin<T> + 'plugin>) {
         self.plugin.add_plugin(plugin)
     }
+
+    /// start tracking implicit flows
+    /// 
+    /// values assigned under a tainted conditional branch are joined with
+    /// the condition's tag until execution reconverges after the branch.
+    pub fn enable_implicit_flows(&mut self) {
+        self.implicit = Some(ImplicitFlows::new());
+    }
+
+    /// refine the policy's tags with bit-precise taint masks
+    /// (see `EvalPolicy::bit_precise`)
+    pub fn enable_bit_precise(&mut self) {
+        self.policy.bit_precise = true;
+    }
+
+    pub fn implicit_flows_enabled(&self) -> bool {
+        self.implicit.is_some()
+    }
+
+    /// close any open control scopes, should be called when the
+    /// context is restored
+    pub fn reset_implicit_flows(&mut self) {
+        if let Some(implicit) = self.implicit.as_mut() {
+            implicit.reset();
+        }
+    }
+
+    /// clear the per-run state of all plugins, should be called when
+    /// the context is restored
+    pub fn reset_plugins(&mut self) {
+        self.plugin.reset();
+    }
+
+    /// the thread switch taken at the start of the last step, if any
+    /// 
+    /// exception entry switches show which exceptions were taken,
+    /// e.g. a HardFault raised by the previous instruction.
+    pub fn last_thread_switch(&self) -> Option<&ThreadSwitch> {
+        self.thread_switch.as_ref()
+    }
 }
 
-impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
+impl<'irb, 'policy, 'backend, 'plugin, T: TaintTag> Evaluator<'policy, 'plugin, T> {
     #[instrument(skip_all)]
     pub fn step(&mut self,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         // need to eventually decide how long a thread switch should take
         // right now there is no latency, so it'll look instantaneous
+        self.thread_switch = None;
+        if context.is_halted() {
+            // nothing executes while halted
+            return Ok(());
+        }
+        if context.is_sleeping() {
+            // skip idle cycles up to the next wakeup
+            let cycles = context.fast_forward()?;
+            debug!("slept for {cycles} cycles");
+            if context.is_sleeping() {
+                return Ok(());
+            }
+        }
         if let Some((thread_switch, target_tag)) = context.maybe_thread_switch()? {
             // for different architectures, target may not be 32 bits, which could be an issue.
             let target = BitVec::from_u32(thread_switch.target_address.offset() as u32, 32);
             let val = (target, target_tag);
             self.policy.inner.check_assign(context.lang().translator().program_counter(), &val)?;
             self.pc = thread_switch.target_address.into();
-            self.pc_tag = target_tag;
+            self.pc_tag = val.1;
+            if let Some(implicit) = self.implicit.as_mut() {
+                // typ is the exception number, or 0 on exception return
+                match thread_switch.typ {
+                    0 => { implicit.exception_return(); }
+                    _ => { implicit.exception_entry(); }
+                }
+            }
+            self.thread_switch = Some(thread_switch);
         } else {
             let (pc, tag) = context.read_pc()?;
             self.pc = pc.into();
             self.pc_tag = tag;
         }
 
+        if self.policy.bit_precise && !context.bit_shadow_enabled() {
+            context.enable_bit_shadow();
+        }
+
         // tick processor clock
         context.tick()?;
 
         let address = self.pc.address();
 
         // let insn = context.fetch(address, pdb.arena)?;
-        let insn = pdb.fetch(address, context.backend_mut())?;
+        if let Err(err) = context.check_fetch(&address) {
+            return self._fault(Error::from(err), address, context)
+        }
+        let insn = match pdb.fetch(address, context.backend_mut()) {
+            Ok(insn) => { insn }
+            Err(err) => { return self._fault(Error::from(err), address, context) }
+        };
         debug!("pc @ {:#010x} (tag={}): {}", address.offset(), &self.pc_tag, insn.disasm_str());
         self.plugin.pre_insn_cb(&self.pc, insn.as_ref(), context, pdb)?;
 
@@ -138,7 +229,10 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
             let op = &pcode.operations[pos];
 
             self.plugin.pre_pcode_cb(&self.pc, op, context, pdb)?;
-            flow = self._evaluate(op, context, pdb)?;
+            flow = match self._evaluate(op, context, pdb) {
+                Ok(flow) => { flow }
+                Err(err) => { return self._fault(err, address, context) }
+            };
             self.plugin.post_pcode_cb(&self.pc, op, context, pdb)?;
 
             match flow.flowtype {
@@ -169,6 +263,22 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
                 pdb.add_edge(address, self.pc.address(), flow.flowtype)?;
             }
         }
+        if let Some(implicit) = self.implicit.as_mut() {
+            match flow.flowtype {
+                FlowType::Call | FlowType::ICall => { implicit.call(); }
+                FlowType::Return => { implicit.ret(); }
+                _ => {  }
+            }
+            if let Some((tag, labels, target)) = implicit.take_pending() {
+                let fall = (address + pcode.len()).offset();
+                let branch = (address.offset(), [target, fall]);
+                let reconverge = pdb.reconvergence_point(branch.0, branch.1);
+                implicit.push(tag, labels, branch, reconverge);
+            }
+            // scopes opened before their post-dominator was explored
+            implicit.resolve(|branch, successors| pdb.reconvergence_point(branch, successors));
+            implicit.update(self.pc.address().offset());
+        }
         context.write_pc(self.pc.address(), &self.pc_tag)?;
 
         // handle events after pc is written
@@ -180,13 +290,40 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
     }
 }
 
-impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
+impl<'irb, 'policy, 'backend, 'plugin, T: TaintTag> Evaluator<'policy, 'plugin, T> {
+    /// raise the architectural fault for an error in the instruction
+    /// at `address`, or return the error if it isn't a fault condition
+    /// 
+    /// the faulting instruction is abandoned with the pc left at its
+    /// address so that the fault is taken at the start of the next step.
+    /// pcode operations evaluated before the fault are not undone.
+    fn _fault(&mut self,
+        err: Error,
+        address: Address,
+        context: &mut Context<'backend, T>,
+    ) -> Result<(), Error> {
+        let Some(cause) = _fault_cause(&err) else {
+            return Err(err);
+        };
+        if !context.fault(cause)? {
+            return Err(err);
+        }
+        info!("fault @ {:#010x}: {err}", address.offset());
+        if let Some(implicit) = self.implicit.as_mut() {
+            implicit.take_pending();
+        }
+        self.pc = Location::from(address);
+        context.write_pc(address, &self.pc_tag)?;
+        context.process_events()?;
+        Ok(())
+    }
+
     /// evaluate a single pcode operation
     #[instrument(skip_all)]
     fn _evaluate(
         &mut self,
         operation: &PCodeData,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<Flow, Error> {
         let loc = self.pc.clone();
@@ -195,7 +332,9 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
         match operation.opcode {
             Opcode::Copy => {
                 let (val, tag) = context.read(&operation.inputs[0])?;
-                self._assign(operation.output.as_ref().unwrap(), val, tag, context)?;
+                let labels = self._labels(&operation.inputs[0], context);
+                let mask = self._mask(&operation.inputs[0], &tag, context);
+                self._assign(operation.output.as_ref().unwrap(), val, tag, labels, mask, context)?;
             }
             Opcode::Load => {
                 let dst = operation.output.as_ref().unwrap();
@@ -206,12 +345,17 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
                 self.plugin.pre_mem_access_cb(&self.pc, &loc.0, lsz, Permission::R, context, pdb)?;
                 let val = self._read_mem(&loc.0, lsz, context)?;
 
-                let tag = self.policy.inner.propagate_load(dst, &val, &loc, context)?;
+                let tag = self.policy.inner.propagate_load(dst, &val, &loc, context)
+                    .map_err(|err| _violation(err, context.labels_of(src), context))?;
+                let mem = self._mem_vnd(&loc.0, lsz, context);
+                let labels = self._moved_labels(&mem, src, &tag, context);
+                let mask = self._mask(&mem, &val.1, context)
+                    .filter(|_| !loc.1.is_tainted());
                 let mem_size = val.0.bytes();
                 let mut value = (val.0, tag);
                 self.plugin.mem_access_cb(&self.pc, &loc.0, mem_size, Permission::R, &mut value, context, pdb)?;
                 let (val, tag) = value;
-                self._assign(dst, val, tag, context)?;
+                self._assign(dst, val, tag, labels, mask, context)?;
             }
             Opcode::Store => {
                 let dst = &operation.inputs[1];
@@ -220,13 +364,17 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
                 let val = context.read(&src)?;
                 let loc = self._read_addr(dst, context)?;
 
-                let tag = self.policy.inner.propagate_store(dst, &val, &loc, context)?;
+                let tag = self.policy.inner.propagate_store(dst, &val, &loc, context)
+                    .map_err(|err| _violation(err, context.labels_of(dst), context))?;
+                let labels = self._moved_labels(src, dst, &tag, context);
+                let mask = self._mask(src, &val.1, context)
+                    .filter(|_| !loc.1.is_tainted());
                 let mem_size = val.0.bytes();
                 let mut value = (val.0, tag);
                 self.plugin.pre_mem_access_cb(&self.pc, &loc.0, mem_size, Permission::W, context, pdb)?;
                 self.plugin.mem_access_cb(&self.pc, &loc.0, mem_size, Permission::W, &mut value, context, pdb)?;
                 let (val, tag) = value;
-                self._write_mem(&loc.0, &val, &tag, context)?;
+                self._write_mem(&loc.0, &val, &tag, labels, mask, context)?;
             }
             Opcode::IntAdd => {
                 self._apply_unsigned_int2(operation, |lhs, rhs| Ok(lhs + rhs), context)?;
@@ -238,36 +386,40 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
                 self._apply_unsigned_int2(operation, |lhs, rhs| Ok(lhs * rhs), context)?;
             }
             Opcode::IntDiv => {
+                let trap = context.traps_divide_by_zero();
                 self._apply_unsigned_int2(operation, |lhs, rhs| {
                     if rhs.is_zero() {
-                        Err(Error::DivideByZero(loc.address()))
+                        _divide_by_zero(trap, loc.address(), lhs.bits())
                     } else {
                         Ok(lhs / rhs)
                     }
                 }, context)?;
             }
             Opcode::IntSDiv => {
+                let trap = context.traps_divide_by_zero();
                 self._apply_signed_int2(operation, |lhs, rhs| {
                     if rhs.is_zero() {
-                        Err(Error::DivideByZero(loc.address()))
+                        _divide_by_zero(trap, loc.address(), lhs.bits())
                     } else {
                         Ok(lhs / rhs)
                     }
                 }, context)?;
             }
             Opcode::IntRem => {
+                let trap = context.traps_divide_by_zero();
                 self._apply_unsigned_int2(operation, |lhs, rhs| {
                     if rhs.is_zero() {
-                        Err(Error::DivideByZero(loc.address()))
+                        _divide_by_zero(trap, loc.address(), lhs.bits())
                     } else {
                         Ok(lhs % rhs)
                     }
                 }, context)?;
             }
             Opcode::IntSRem => {
+                let trap = context.traps_divide_by_zero();
                 self._apply_signed_int2(operation, |lhs, rhs| {
                     if rhs.is_zero() {
-                        Err(Error::DivideByZero(loc.address()))
+                        _divide_by_zero(trap, loc.address(), lhs.bits())
                     } else {
                         Ok(lhs % rhs)
                     }
@@ -355,6 +507,28 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
             Opcode::Subpiece => {
                 self._subpiece(operation, context)?;
             }
+            Opcode::FloatEq
+            | Opcode::FloatNotEq
+            | Opcode::FloatLess
+            | Opcode::FloatLessEq
+            | Opcode::FloatAdd
+            | Opcode::FloatSub
+            | Opcode::FloatMul
+            | Opcode::FloatDiv => {
+                self._apply_float2(operation, context)?;
+            }
+            Opcode::FloatIsNaN
+            | Opcode::FloatNeg
+            | Opcode::FloatAbs
+            | Opcode::FloatSqrt
+            | Opcode::FloatCeiling
+            | Opcode::FloatFloor
+            | Opcode::FloatRound
+            | Opcode::FloatOfInt
+            | Opcode::FloatOfFloat
+            | Opcode::FloatTruncate => {
+                self._apply_float1(operation, context)?;
+            }
             Opcode::Branch => {
                 let target = _absolute_loc(loc.address(), operation.inputs[0], loc.position());
                 // no taint check is needed on a constant direct branch (constants never tainted)
@@ -362,15 +536,25 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
             }
             Opcode::CBranch => {
                 let bool_val = self._read_bool(&operation.inputs[1], context)?;
-                self.policy.inner.check_cond_branch(&operation.opcode, &bool_val)?;
+                self.policy.inner.check_cond_branch(&operation.opcode, &bool_val)
+                    .map_err(|err| _violation(err, context.labels_of(&operation.inputs[1]), context))?;
+                let target = _absolute_loc(loc.address(), operation.inputs[0], loc.position());
+                // branches within an instruction don't leave it, so only
+                // branches to other instructions open a control scope
+                if let Some(implicit) = self.implicit.as_mut()
+                    .filter(|_| bool_val.1.is_tainted() && target.address() != loc.address())
+                {
+                    let labels = context.labels_of(&operation.inputs[1]);
+                    implicit.branch(bool_val.1.clone(), labels, target.address().offset());
+                }
                 if bool_val.0 {
-                    let target = _absolute_loc(loc.address(), operation.inputs[0], loc.position());
                     return Ok(FlowType::CBranch.target(target));
                 }
             }
             Opcode::IBranch => {
                 let target = self._read_addr(&operation.inputs[0], context)?;
-                self.policy.inner.check_branch(&operation.opcode, &target)?;
+                self.policy.inner.check_branch(&operation.opcode, &target)
+                    .map_err(|err| _violation(err, context.labels_of(&operation.inputs[0]), context))?;
                 return Ok(FlowType::IBranch.target(target.0.into()));
             }
             Opcode::Call => {
@@ -380,12 +564,14 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
             }
             Opcode::ICall => {
                 let target = self._read_addr(&operation.inputs[0], context)?;
-                self.policy.inner.check_branch(&operation.opcode, &target)?;
+                self.policy.inner.check_branch(&operation.opcode, &target)
+                    .map_err(|err| _violation(err, context.labels_of(&operation.inputs[0]), context))?;
                 return Ok(FlowType::Call.target(target.0.into()));
             }
             Opcode::Return => {
                 let target = self._read_addr(&operation.inputs[0], context)?;
-                self.policy.inner.check_branch(&operation.opcode, &target)?;
+                self.policy.inner.check_branch(&operation.opcode, &target)
+                    .map_err(|err| _violation(err, context.labels_of(&operation.inputs[0]), context))?;
                 return Ok(FlowType::Return.target(target.0.into()));
             }
             Opcode::CallOther => {
@@ -410,71 +596,204 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
     }
 }
 
-impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
+impl<'irb, 'policy, 'backend, 'plugin, T: TaintTag> Evaluator<'policy, 'plugin, T> {
 
     fn _read_bool(&self,
         vnd: &VarnodeData,
-        context: &mut Context<'backend>,
-    ) -> Result<(bool, Tag), Error> {
+        context: &mut Context<'backend, T>,
+    ) -> Result<(bool, T), Error> {
         let (val, tag) = context.read(vnd)?;
-        Ok((!val.is_zero(), tag))
+        let mask = self._mask(vnd, &tag, context);
+        Ok((!val.is_zero(), self._refine(tag, mask.as_ref())))
     }
 
     fn _read_addr(&self,
         vnd: &VarnodeData,
-        context: &mut Context<'backend>,
-    ) -> Result<(Address, Tag), Error> {
+        context: &mut Context<'backend, T>,
+    ) -> Result<(Address, T), Error> {
         let (val, tag) = context.read(vnd)?;
+        let mask = self._mask(vnd, &tag, context);
+        let tag = self._refine(tag, mask.as_ref());
         val.to_u64()
             .map(Address::from)
             .ok_or_else(|| Error::InvalidAddress(val))
             .map(|address| (address, tag))
     }
 
-    fn _read_mem(&mut self,
+    fn _mem_vnd(&self,
         address: &Address,
         size: usize,
-        context: &mut Context<'backend>,
-    ) -> Result<(BitVec, Tag), Error> {
+        context: &Context<'backend, T>,
+    ) -> VarnodeData {
         let spc = context.lang()
             .translator()
             .manager()
             .default_space();
-        let mem = VarnodeData::new(spc.as_ref(), address.offset(), size);
+        VarnodeData::new(spc.as_ref(), address.offset(), size)
+    }
+
+    fn _read_mem(&mut self,
+        address: &Address,
+        size: usize,
+        context: &mut Context<'backend, T>,
+    ) -> Result<(BitVec, T), Error> {
+        let mem = self._mem_vnd(address, size, context);
         Ok(context.read(&mem)?)
     }
 
     fn _write_mem(&mut self,
         address: &Address,
         val: &BitVec,
-        tag: &Tag,
-        context: &mut Context<'backend>,
+        tag: &T,
+        labels: Option<Vec<LabelSet>>,
+        mask: Option<BitVec>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error> {
-        let spc = context.lang()
-            .translator()
-            .manager()
-            .default_space();
-        let mem = VarnodeData::new(spc.as_ref(), address.offset(), val.bytes());
-        let value = (val, tag);
-        self.policy.inner.check_write_mem(address, value)?;
-        Ok(context.write(&mem, val, tag)?)
+        let mem = self._mem_vnd(address, val.bytes(), context);
+        let tag = self._refine(tag.clone(), mask.as_ref());
+        let (tag, labels, mask) = self._control_dependence(&mem, tag, labels, mask, context);
+        let value = (val, &tag);
+        self.policy.inner.check_write_mem(address, value)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
+        context.write(&mem, val, &tag)?;
+        self._write_labels(&mem, labels, &tag, context);
+        self._write_mask(&mem, mask, &tag, context);
+        Ok(())
     }
 
     fn _assign(&mut self,
         vnd: &VarnodeData,
         val: BitVec,
-        tag: Tag,
-        context: &mut Context<'backend>,
+        tag: T,
+        labels: Option<Vec<LabelSet>>,
+        mask: Option<BitVec>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error> {
+        let tag = self._refine(tag, mask.as_ref());
+        let (tag, labels, mask) = self._control_dependence(vnd, tag, labels, mask, context);
         let val = (val, tag);
-        self.policy.inner.check_assign(vnd, &val)?;
-        context.write(vnd, &val.0.cast(vnd.bits()), &val.1)
-            .map_err(Error::from)
+        self.policy.inner.check_assign(vnd, &val)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
+        context.write(vnd, &val.0.cast(vnd.bits()), &val.1)?;
+        self._write_labels(vnd, labels, &val.1, context);
+        self._write_mask(vnd, mask, &val.1, context);
+        Ok(())
+    }
+
+    /// bit-precise taint mask of a varnode, if the policy is bit-precise
+    fn _mask(&self,
+        vnd: &VarnodeData,
+        tag: &T,
+        context: &Context<'backend, T>,
+    ) -> Option<BitVec> {
+        self.policy.bit_precise
+            .then(|| context.bit_mask(vnd, tag))
+    }
+
+    /// join the open control scopes into a value assigned under them
+    /// 
+    /// the program counter is left alone, since a branch under a tainted
+    /// condition isn't itself a tainted jump.
+    fn _control_dependence(&self,
+        vnd: &VarnodeData,
+        tag: T,
+        labels: Option<Vec<LabelSet>>,
+        mask: Option<BitVec>,
+        context: &Context<'backend, T>,
+    ) -> (T, Option<Vec<LabelSet>>, Option<BitVec>) {
+        let Some(implicit) = self.implicit.as_ref() else {
+            return (tag, labels, mask);
+        };
+        let Some(control) = implicit.tag() else {
+            return (tag, labels, mask);
+        };
+        if vnd == context.lang().translator().program_counter() {
+            return (tag, labels, mask);
+        }
+        let control_labels = implicit.labels();
+        let labels = labels.map(|labels| {
+            labels.iter().map(|labels| labels.union(&control_labels)).collect()
+        });
+        // every bit of the value depends on the branch
+        let mask = mask.filter(|_| !control.is_tainted());
+        (tag.join(&control), labels, mask)
+    }
+
+    /// untaint a tag if none of its value's bits are tainted
+    fn _refine(&self, tag: T, mask: Option<&BitVec>) -> T {
+        match mask {
+            Some(mask) if tag.is_tainted() && mask.is_zero() => { tag.untainted() }
+            _ => { tag }
+        }
+    }
+
+    /// masks only need to be stored for tainted values.
+    /// the context write has already cleared the previous masks
+    fn _write_mask(&self,
+        vnd: &VarnodeData,
+        mask: Option<BitVec>,
+        tag: &T,
+        context: &mut Context<'backend, T>,
+    ) {
+        if let Some(mask) = mask.filter(|_| tag.is_tainted()) {
+            context.write_bit_mask(vnd, &mask.unsigned().cast(vnd.bits()));
+        }
+    }
+
+    /// per-byte provenance labels of a varnode, if provenance is enabled
+    fn _labels(&self,
+        vnd: &VarnodeData,
+        context: &Context<'backend, T>,
+    ) -> Option<Vec<LabelSet>> {
+        context.provenance_enabled()
+            .then(|| context.byte_labels(vnd))
+    }
+
+    /// the union of the input labels for every byte of the output
+    fn _union_labels(&self,
+        inputs: &[VarnodeData],
+        size: usize,
+        context: &Context<'backend, T>,
+    ) -> Option<Vec<LabelSet>> {
+        context.provenance_enabled().then(|| {
+            let labels = inputs.iter()
+                .fold(LabelSet::new(), |labels, vnd| labels.union(&context.labels_of(vnd)));
+            vec![labels; size]
+        })
+    }
+
+    /// labels of a value moved to or from memory,
+    /// including the address labels if the policy tainted its location
+    fn _moved_labels(&self,
+        val: &VarnodeData,
+        address: &VarnodeData,
+        tag: &T,
+        context: &Context<'backend, T>,
+    ) -> Option<Vec<LabelSet>> {
+        let labels = self._labels(val, context)?;
+        if !tag.tainted_loc() {
+            return Some(labels);
+        }
+        let address_labels = context.labels_of(address);
+        Some(labels.iter().map(|labels| labels.union(&address_labels)).collect())
+    }
+
+    /// labels only stick to tainted values.
+    /// untainted writes have already cleared them in the context
+    fn _write_labels(&self,
+        vnd: &VarnodeData,
+        labels: Option<Vec<LabelSet>>,
+        tag: &T,
+        context: &mut Context<'backend, T>,
+    ) {
+        if let Some(labels) = labels.filter(|_| tag.is_tainted()) {
+            context.write_byte_labels(vnd, &labels);
+        }
     }
 
     fn _subpiece(&mut self,
         operation: &PCodeData,
-        context: &mut Context<'backend>
+        context: &mut Context<'backend, T>
     ) -> Result<(), Error> {
         let (src, tag) = context.read(&operation.inputs[0])?;
         let src_size = src.bits();
@@ -482,7 +801,8 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
         let dst = operation.output.as_ref().unwrap();
         let dst_size = dst.bits();
         let trunc_size = src_size.saturating_sub(offset);
-        let trunc = if dst_size > trunc_size {
+        // taint masks are truncated the same way as values
+        let truncate = |src: BitVec| if dst_size > trunc_size {
             // extract high + expand
             if trunc_size >= src_size {
                 src
@@ -498,17 +818,23 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
             .unsigned()
             .cast(dst_size)
         };
+        let mask = self._mask(&operation.inputs[0], &tag, context)
+            .map(&truncate);
+        let trunc = truncate(src);
 
+        let labels = self._labels(&operation.inputs[0], context)
+            .map(|labels| labels.into_iter().skip(operation.inputs[1].offset() as usize).collect::<Vec<_>>());
         let val = (trunc, tag);
-        self.policy.inner.propagate_subpiece(&operation.opcode, dst, &val)?;
-        self._assign(dst, val.0, tag, context)
+        self.policy.inner.propagate_subpiece(&operation.opcode, dst, &val)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
+        self._assign(dst, val.0, val.1, labels, mask, context)
     }
 
     fn _apply_int2<F, G>(&mut self,
         operation: &PCodeData,
         cast: F,
         op: G,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where
         F: Fn(BitVec, u32) -> BitVec,
@@ -518,19 +844,27 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
         let rhs = context.read(&operation.inputs[1])?;
         let dst = operation.output.as_ref().unwrap();
 
+        let labels = self._union_labels(&operation.inputs, dst.size(), context);
+        let mask = self._mask(&operation.inputs[0], &lhs.1, context)
+            .zip(self._mask(&operation.inputs[1], &rhs.1, context))
+            .and_then(|(lmask, rmask)| {
+                bits::int2_mask(&operation.opcode, (&lhs.0, &lmask), (&rhs.0, &rmask))
+            });
+
         let tag = self.policy.inner
-            .propagate_int2(&operation.opcode, dst, &lhs, &rhs)?;
+            .propagate_int2(&operation.opcode, dst, &lhs, &rhs)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
 
         let size = lhs.0.bits().max(rhs.0.bits());
         let val = op(cast(lhs.0, size), cast(rhs.0, size))?;
 
-        self._assign(dst, val.cast(dst.bits()), tag, context)
+        self._assign(dst, val.cast(dst.bits()), tag, labels, mask, context)
     }
 
     fn _apply_signed_int2<F>(&mut self,
         operation: &PCodeData,
         op: F,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where
         F: FnOnce(BitVec, BitVec) -> Result<BitVec, Error>,
@@ -541,7 +875,7 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
     fn _apply_unsigned_int2<F>(&mut self,
         operation: &PCodeData,
         op: F,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where
         F: FnOnce(BitVec, BitVec) -> Result<BitVec, Error>,
@@ -553,7 +887,7 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
         operation: &PCodeData,
         cast: F,
         op: G,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where
         F: Fn(BitVec) -> BitVec,
@@ -562,18 +896,23 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
         let rhs = context.read(&operation.inputs[0])?;
         let dst = operation.output.as_ref().unwrap();
 
+        let labels = self._union_labels(&operation.inputs, dst.size(), context);
+        let mask = self._mask(&operation.inputs[0], &rhs.1, context)
+            .and_then(|mask| bits::int1_mask(&operation.opcode, &mask, dst.bits()));
+
         let tag = self.policy.inner
-            .propagate_int1(&operation.opcode, dst, &rhs)?;
+            .propagate_int1(&operation.opcode, dst, &rhs)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
 
         let val = op(cast(rhs.0))?;
 
-        self._assign(dst, val.cast(dst.bits()), tag, context)
+        self._assign(dst, val.cast(dst.bits()), tag, labels, mask, context)
     }
 
     fn _apply_signed_int1<F>(&mut self,
         operation: &PCodeData,
         op: F,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where 
         F: FnOnce(BitVec) -> Result<BitVec, Error>,
@@ -584,7 +923,7 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
     fn _apply_unsigned_int1<F>(&mut self,
         operation: &PCodeData,
         op: F,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where
         F: FnOnce(BitVec) -> Result<BitVec, Error>,
@@ -595,7 +934,7 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
     fn _apply_bool2<F>(&mut self,
         operation: &PCodeData,
         op: F,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where
         F: FnOnce(bool, bool) -> Result<bool, Error>,
@@ -604,18 +943,21 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
         let rhs = context.read(&operation.inputs[1])?;
         let dst = operation.output.as_ref().unwrap();
 
+        let labels = self._union_labels(&operation.inputs, dst.size(), context);
+
         let tag = self.policy.inner
-            .propagate_bool2(&operation.opcode, dst, &lhs, &rhs)?;
+            .propagate_bool2(&operation.opcode, dst, &lhs, &rhs)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
 
         let val = bool2bv(op(!lhs.0.is_zero(), !rhs.0.is_zero())?);
 
-        self._assign(dst, val.cast(dst.bits()), tag, context)
+        self._assign(dst, val.cast(dst.bits()), tag, labels, None, context)
     }
 
     fn _apply_bool1<F>(&mut self,
         operation: &PCodeData,
         op: F,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
     ) -> Result<(), Error>
     where 
         F: FnOnce(bool) -> Result<bool, Error>,
@@ -623,14 +965,118 @@ impl<'irb, 'policy, 'backend, 'plugin> Evaluator<'policy, 'plugin> {
         let rhs = context.read(&operation.inputs[0])?;
         let dst = operation.output.as_ref().unwrap();
         
+        let labels = self._union_labels(&operation.inputs, dst.size(), context);
+
         let tag = self.policy.inner
-            .propagate_bool1(&operation.opcode, dst, &rhs)?;
+            .propagate_bool1(&operation.opcode, dst, &rhs)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
 
         let val = bool2bv(op(!rhs.0.is_zero())?);
-        self._assign(dst, val.cast(dst.bits()), tag, context)
+        self._assign(dst, val.cast(dst.bits()), tag, labels, None, context)
+    }
+
+    fn _apply_float2(&mut self,
+        operation: &PCodeData,
+        context: &mut Context<'backend, T>,
+    ) -> Result<(), Error> {
+        let inputs = [
+            context.read(&operation.inputs[0])?,
+            context.read(&operation.inputs[1])?,
+        ];
+        let dst = operation.output.as_ref().unwrap();
+
+        let labels = self._union_labels(&operation.inputs, dst.size(), context);
+
+        let tag = self.policy.inner
+            .propagate_float(&operation.opcode, dst, &inputs)
+            .map_err(|err| _violation(err, _union(&labels), context))?;
+
+        let [(lhs, _), (rhs, _)] = &inputs;
+        let val = float::float2(&operation.opcode, lhs, rhs)
+            .ok_or(Error::FloatSize(operation.opcode, lhs.bits(), dst.bits()))?;
+
+        self._assign(dst, val.cast(dst.bits()), tag, labels, None, context)
+    }
+
+    fn _apply_float1(&mut self,
+        operation: &PCodeData,
+        context: &mut Context<'backend, T>,
+    ) -> Result<(), Error> {
+        let rhs = context.read(&operation.inputs[0])?;
+        let dst = operation.output.as_ref().unwrap();
+
+        let labels = self._union_labels(&operation.inputs, dst.size(), context);
+
+        let tag = self.policy.inner
+            .propagate_float(&operation.opcode, dst, std::slice::from_ref(&rhs))
+            .map_err(|err| _violation(err, _union(&labels), context))?;
+
+        let val = float::float1(&operation.opcode, &rhs.0, dst.bits())
+            .ok_or(Error::FloatSize(operation.opcode, rhs.0.bits(), dst.bits()))?;
+
+        self._assign(dst, val.cast(dst.bits()), tag, labels, None, context)
     }
 }
 
+/// record the labels of a policy-violating value in the context
+fn _violation<T: TaintTag>(err: policy::Error, labels: LabelSet, context: &mut Context<T>) -> Error {
+    context.set_violation_labels(labels);
+    err.into()
+}
+
+fn _union(labels: &Option<Vec<LabelSet>>) -> LabelSet {
+    labels.as_ref()
+        .map(|labels| LabelSet::union_all(labels.iter()))
+        .unwrap_or_default()
+}
+
+/// the result of an integer division by zero,
+/// which is 0 unless the context traps on it
+fn _divide_by_zero(trap: bool, address: Address, bits: usize) -> Result<BitVec, Error> {
+    if trap {
+        Err(Error::DivideByZero(address))
+    } else {
+        Ok(BitVec::from_u32(0, bits))
+    }
+}
+
+/// the fault condition an evaluation error corresponds to, if any
+fn _fault_cause(err: &Error) -> Option<FaultCause> {
+    match err {
+        Error::DivideByZero(_) => { Some(FaultCause::DivideByZero) }
+        Error::Context(context::Error::Backend(backend::Error::Unmapped(address)))
+        | Error::Context(context::Error::Backend(backend::Error::Unprivileged(address)))
+        | Error::Context(context::Error::Unmapped(address)) => {
+            Some(FaultCause::DataAccess(address.clone()))
+        }
+        Error::Context(context::Error::Backend(backend::Error::AccessViolation(address, access))) => {
+            Some(FaultCause::AccessViolation(address.clone(), *access))
+        }
+        Error::Context(context::Error::Backend(backend::Error::Breakpoint(address))) => {
+            Some(FaultCause::Breakpoint(address.clone()))
+        }
+        Error::Context(context::Error::Backend(backend::Error::Unaligned(address, _))) => {
+            Some(FaultCause::UnalignedAccess(address.clone()))
+        }
+        Error::Context(context::Error::Backend(backend::Error::CoprocessorAccess)) => {
+            Some(FaultCause::CoprocessorAccess)
+        }
+        Error::Context(context::Error::Backend(backend::Error::UndefinedInsn(_))) => {
+            Some(FaultCause::UndefinedInsn)
+        }
+        Error::Lift(err) => {
+            match err.as_ref() {
+                LiftError::IR(_) => { Some(FaultCause::UndefinedInsn) }
+                LiftError::Backend(err) => {
+                    matches!(err.downcast_ref::<backend::Error>(), Some(backend::Error::Unmapped(_)))
+                        .then_some(FaultCause::InsnFetch)
+                }
+                LiftError::AddressNotLifted(_) => { None }
+            }
+        }
+        _ => { None }
+    }
+}
 
 
 #[cfg(test)]
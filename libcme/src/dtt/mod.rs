@@ -13,7 +13,7 @@ pub mod policy;
 pub use context::Context;
 pub use eval::Evaluator;
 pub use plugin::EvalPlugin;
-pub use tag::Tag;
+pub use tag::{Tag, TaintTag};
 
 #[cfg(test)]
 pub(crate) mod test;
\ No newline at end of file
@@ -19,12 +19,12 @@ pub struct DummyEvalPlugin {
     pub userops_called: IntMap<usize, usize>,
 }
 
-impl EvalPlugin for DummyEvalPlugin {
+impl<T: TaintTag> EvalPlugin<T> for DummyEvalPlugin {
     #[instrument(skip_all)]
     fn post_thread_switch_cb<'irb, 'backend>(
         &mut self,
         _thd_switch: &ThreadSwitch,
-        _context: &mut Context<'backend>,
+        _context: &mut Context<'backend, T>,
         _pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         self.thread_switch_cnt += 1;
@@ -37,7 +37,7 @@ impl EvalPlugin for DummyEvalPlugin {
         &mut self,
         _loc: &Location,
         _insn: &Insn<'irb>,
-        _context: &mut Context<'backend>,
+        _context: &mut Context<'backend, T>,
         _pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         self.pre_insn_cnt += 1;
@@ -51,7 +51,7 @@ impl EvalPlugin for DummyEvalPlugin {
         _loc: &Location,
         _insn: &Insn<'irb>,
         _flow: &Flow,
-        _context: &mut Context<'backend>,
+        _context: &mut Context<'backend, T>,
         _pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         self.post_insn_cnt += 1;
@@ -64,7 +64,7 @@ impl EvalPlugin for DummyEvalPlugin {
         &mut self,
         _loc: &Location,
         _pcode: &PCodeData<'irb>,
-        _context: &mut Context<'backend>,
+        _context: &mut Context<'backend, T>,
         _pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         self.pre_pcode_cnt += 1;
@@ -77,7 +77,7 @@ impl EvalPlugin for DummyEvalPlugin {
         &mut self,
         _loc: &Location,
         _pcode: &PCodeData<'irb>,
-        _context: &mut Context<'backend>,
+        _context: &mut Context<'backend, T>,
         _pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         self.post_pcode_cnt += 1;
@@ -92,8 +92,8 @@ impl EvalPlugin for DummyEvalPlugin {
         _mem_address: &Address,
         _mem_size: usize,
         access_type: Permission,
-        _value: &mut (BitVec, Tag),
-        _context: &mut Context<'backend>,
+        _value: &mut (BitVec, T),
+        _context: &mut Context<'backend, T>,
         _pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         match access_type {
@@ -117,7 +117,7 @@ impl EvalPlugin for DummyEvalPlugin {
         index: usize,
         _inputs: &[VarnodeData],
         _output: Option<&VarnodeData>,
-        _context: &mut Context<'backend>,
+        _context: &mut Context<'backend, T>,
         _pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         info!("called userop: {}", index);
@@ -20,7 +20,7 @@ use fugue_ir::disassembly::{PCodeData, VarnodeData};
 use crate::backend::ThreadSwitch;
 use crate::types::*;
 use crate::dtt::Context;
-use crate::dtt::tag::Tag;
+use crate::dtt::tag::{Tag, TaintTag};
 use crate::programdb::ProgramDB;
 
 mod dummy;
@@ -32,12 +32,14 @@ pub struct Error(pub anyhow::Error);
 
 
 /// plugin trait for evaluator
+/// 
+/// plugins are generic over the context's tag type, which defaults to `Tag`
 #[allow(unused)]
-pub trait EvalPlugin: fmt::Debug {
+pub trait EvalPlugin<T: TaintTag = Tag>: fmt::Debug {
     fn post_thread_switch_cb<'irb, 'backend>(
         &mut self,
         thd_switch: &ThreadSwitch,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
 
@@ -45,7 +47,7 @@ pub trait EvalPlugin: fmt::Debug {
         &mut self,
         loc: &Location,
         insn: &Insn<'irb>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
     
@@ -54,7 +56,7 @@ pub trait EvalPlugin: fmt::Debug {
         loc: &Location,
         insn: &Insn<'irb>,
         flow: &Flow,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
 
@@ -62,7 +64,7 @@ pub trait EvalPlugin: fmt::Debug {
         &mut self,
         loc: &Location,
         pcode: &PCodeData<'irb>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
 
@@ -70,7 +72,7 @@ pub trait EvalPlugin: fmt::Debug {
         &mut self,
         loc: &Location,
         pcode: &PCodeData<'irb>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
 
@@ -80,7 +82,7 @@ pub trait EvalPlugin: fmt::Debug {
         mem_address: &Address,
         mem_size: usize,
         access_type: Permission,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
 
@@ -90,8 +92,8 @@ pub trait EvalPlugin: fmt::Debug {
         mem_address: &Address,
         mem_size: usize,
         access_type: Permission,
-        value: &mut (BitVec, Tag),
-        context: &mut Context<'backend>,
+        value: &mut (BitVec, T),
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
 
@@ -101,7 +103,7 @@ pub trait EvalPlugin: fmt::Debug {
         index: usize,
         inputs: &[VarnodeData],
         output: Option<&VarnodeData>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> { Ok(()) }
 
@@ -111,10 +113,13 @@ pub trait EvalPlugin: fmt::Debug {
         index: usize,
         inputs: &[VarnodeData],
         output: Option<&VarnodeData>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
         result: &Option<Location>,
     ) -> Result<(), Error> { Ok(()) }
+
+    /// clear any per-run state, called when the context is restored
+    fn reset(&mut self) {  }
 }
 
 
@@ -123,33 +128,39 @@ pub trait EvalPlugin: fmt::Debug {
 /// a wrapper for the plugin(s) that will be provided to the
 /// evaluator upon instantiation
 #[derive(Debug)]
-pub(crate) struct EvaluatorPlugin<'a> {
-    plugins: Vec<Box<dyn EvalPlugin + 'a>>,
+pub(crate) struct EvaluatorPlugin<'a, T: TaintTag = Tag> {
+    plugins: Vec<Box<dyn EvalPlugin<T> + 'a>>,
 }
 
-impl<'a> EvaluatorPlugin<'a> {
-    pub(crate) fn new_with(plugins: Vec<Box<dyn EvalPlugin + 'a>>) -> Self {
+impl<'a, T: TaintTag> EvaluatorPlugin<'a, T> {
+    pub(crate) fn new_with(plugins: Vec<Box<dyn EvalPlugin<T> + 'a>>) -> Self {
         Self { plugins }
     }
 
-    pub(crate) fn add_plugin(&mut self, plugin: Box<dyn EvalPlugin + 'a>) {
+    pub(crate) fn add_plugin(&mut self, plugin: Box<dyn EvalPlugin<T> + 'a>) {
         self.plugins.push(plugin)
     }
 }
 
-impl<'a> Default for EvaluatorPlugin<'a> {
+impl<'a, T: TaintTag> Default for EvaluatorPlugin<'a, T> {
     fn default() -> Self {
         Self::new_with(vec![])
     }
 }
 
-impl<'a> EvalPlugin for EvaluatorPlugin<'a> {
+impl<'a, T: TaintTag> EvalPlugin<T> for EvaluatorPlugin<'a, T> {
+
+    fn reset(&mut self) {
+        for plugin in self.plugins.iter_mut() {
+            plugin.as_mut().reset();
+        }
+    }
 
     fn pre_insn_cb<'irb, 'backend>(
         &mut self,
         loc: &Location,
         insn: &Insn<'irb>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         for plugin in self.plugins.iter_mut() {
@@ -163,7 +174,7 @@ impl<'a> EvalPlugin for EvaluatorPlugin<'a> {
         loc: &Location,
         insn: &Insn<'irb>,
         flow: &Flow,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         for plugin in self.plugins.iter_mut() {
@@ -176,7 +187,7 @@ impl<'a> EvalPlugin for EvaluatorPlugin<'a> {
         &mut self,
         loc: &Location,
         pcode: &PCodeData<'irb>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         for plugin in self.plugins.iter_mut() {
@@ -189,7 +200,7 @@ impl<'a> EvalPlugin for EvaluatorPlugin<'a> {
         &mut self,
         loc: &Location,
         pcode: &PCodeData<'irb>,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         for plugin in self.plugins.iter_mut() {
@@ -204,7 +215,7 @@ impl<'a> EvalPlugin for EvaluatorPlugin<'a> {
         mem_address: &Address,
         mem_size: usize,
         access_type: Permission,
-        context: &mut Context<'backend>,
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         for plugin in self.plugins.iter_mut() {
@@ -219,8 +230,8 @@ impl<'a> EvalPlugin for EvaluatorPlugin<'a> {
         mem_address: &Address,
         mem_size: usize,
         access_type: Permission,
-        value: &mut (BitVec, Tag),
-        context: &mut Context<'backend>,
+        value: &mut (BitVec, T),
+        context: &mut Context<'backend, T>,
         pdb: &mut ProgramDB<'irb>,
     ) -> Result<(), Error> {
         for plugin in self.plugins.iter_mut() {
@@ -9,7 +9,7 @@ use fugue_ir::disassembly::{
     Opcode,
 };
 use crate::dtt;
-use super::tag::{self, Tag};
+use super::tag::{Tag, TaintTag};
 
 pub mod jump;
 pub use jump::{
@@ -27,37 +27,52 @@ impl From<anyhow::Error> for Error {
 }
 
 #[derive(Debug)]
-pub struct EvalPolicy<'policy> {
-    pub inner: Box<dyn TaintPolicy + 'policy>,
+pub struct EvalPolicy<'policy, T: TaintTag = Tag> {
+    pub inner: Box<dyn TaintPolicy<T> + 'policy>,
+    /// refine the tags propagated by the policy with bit-precise taint
+    /// masks. `and`, `or`, `xor`, shifts, `subpiece`, and extensions
+    /// track taint per bit, so a result whose tainted bits were all masked
+    /// or shifted out is untainted before it is checked and assigned.
+    pub bit_precise: bool,
 }
 
-pub trait TaintPolicy: std::fmt::Debug {
+impl<'policy, T: TaintTag> EvalPolicy<'policy, T> {
+    pub fn new_with(inner: Box<dyn TaintPolicy<T> + 'policy>) -> Self {
+        Self { inner, bit_precise: false }
+    }
+}
+
+/// a taint policy
+/// 
+/// policies are generic over the tag type they propagate, so a policy can
+/// track a richer lattice than `Tag` by implementing this for its own tag.
+pub trait TaintPolicy<T: TaintTag = Tag>: std::fmt::Debug {
     /// check for policy violations on varnode assignment
     fn check_assign(
         &mut self,
         dst: &VarnodeData,
-        val: &(BitVec, Tag),
+        val: &(BitVec, T),
     ) -> Result<(), Error>;
 
     /// check for policy violations on memory write
     fn check_write_mem(
         &mut self,
         address: &Address,
-        val: (&BitVec, &Tag),
+        val: (&BitVec, &T),
     ) -> Result<(), Error>;
     
     /// check for policy violations on conditional branches
     fn check_cond_branch(
         &mut self,
         opcode: &Opcode,
-        cond: &(bool, Tag),
+        cond: &(bool, T),
     ) -> Result<(), Error>;
 
     /// check for policy violations on target branches
     fn check_branch(
         &mut self,
         opcode: &Opcode,
-        target: &(Address, Tag),
+        target: &(Address, T),
     ) -> Result<(), Error>;
 
     /// check for policy violations during subpiece operations.
@@ -66,8 +81,8 @@ pub trait TaintPolicy: std::fmt::Debug {
         &mut self,
         opcode: &Opcode,
         dst: &VarnodeData,
-        src: &(BitVec, Tag),
-    ) -> Result<Tag, Error>;
+        src: &(BitVec, T),
+    ) -> Result<T, Error>;
     
     /// check for policy violations during operations involving 2 integers.
     /// return error if violation detected, otherwise propagate taint
@@ -75,9 +90,9 @@ pub trait TaintPolicy: std::fmt::Debug {
         &mut self,
         opcode: &Opcode,
         dst: &VarnodeData,
-        lhs: &(BitVec, Tag),
-        rhs: &(BitVec, Tag),
-    ) -> Result<Tag, Error>;
+        lhs: &(BitVec, T),
+        rhs: &(BitVec, T),
+    ) -> Result<T, Error>;
     
     /// check for policy violations during operations involving 2 integer.
     /// return error if violation detected, otherwise propagate taint
@@ -85,8 +100,8 @@ pub trait TaintPolicy: std::fmt::Debug {
         &mut self,
         opcode: &Opcode,
         dst: &VarnodeData,
-        rhs: &(BitVec, Tag),
-    ) -> Result<Tag, Error>;
+        rhs: &(BitVec, T),
+    ) -> Result<T, Error>;
     
     /// check for policy violations during operations involving 2 booleans.
     /// return error if violation detected, otherwise propagate taint
@@ -94,9 +109,9 @@ pub trait TaintPolicy: std::fmt::Debug {
         &mut self,
         opcode: &Opcode,
         dst: &VarnodeData,
-        lhs: &(BitVec, Tag),
-        rhs: &(BitVec, Tag),
-    ) -> Result<Tag, Error>;
+        lhs: &(BitVec, T),
+        rhs: &(BitVec, T),
+    ) -> Result<T, Error>;
     
     /// check for policy violations during operations involving 1 boolean.
     /// return error if violation detected, otherwise propagate taint
@@ -104,28 +119,41 @@ pub trait TaintPolicy: std::fmt::Debug {
         &mut self,
         opcode: &Opcode,
         dst: &VarnodeData,
-        rhs: &(BitVec, Tag),
-    ) -> Result<Tag, Error>;
+        rhs: &(BitVec, T),
+    ) -> Result<T, Error>;
+
+    /// check for policy violations during floating point operations.
+    /// inputs are the raw ieee-754 bits, or an integer for `FloatOfInt`.
+    /// return error if violation detected, otherwise propagate taint.
+    /// by default the result is the join of the input tags.
+    fn propagate_float(
+        &mut self,
+        _opcode: &Opcode,
+        _dst: &VarnodeData,
+        inputs: &[(BitVec, T)],
+    ) -> Result<T, Error> {
+        Ok(inputs.iter().fold(T::default(), |tag, input| tag.join(&input.1)))
+    }
 
     /// check for policy violations during load operations
     /// return error if violation detected, otherwise propagate taint
     fn propagate_load<'a>(
         &mut self,
         dst: &VarnodeData,
-        val: &(BitVec, Tag),
-        loc: &(Address, Tag),
-        ctx: &dtt::Context<'a>,
-    ) -> Result<Tag, Error>;
+        val: &(BitVec, T),
+        loc: &(Address, T),
+        ctx: &dtt::Context<'a, T>,
+    ) -> Result<T, Error>;
 
     /// check for policy violations during store operations
     /// return error if violation detected, otherwise propagate taint
     fn propagate_store<'a>(
         &mut self,
         dst: &VarnodeData,
-        val: &(BitVec, Tag),
-        loc: &(Address, Tag),
-        ctx: &dtt::Context<'a>,
-    ) -> Result<Tag, Error>;
+        val: &(BitVec, T),
+        loc: &(Address, T),
+        ctx: &dtt::Context<'a, T>,
+    ) -> Result<T, Error>;
 }
 
 /// a dummy policy that has no violations and no propagation
@@ -134,97 +162,97 @@ pub trait TaintPolicy: std::fmt::Debug {
 pub struct NoPolicy;
 
 // #[allow(unused)]
-impl TaintPolicy for NoPolicy {
+impl<T: TaintTag> TaintPolicy<T> for NoPolicy {
 
     fn check_assign(
         &mut self,
         _dst: &VarnodeData,
-        _val: &(BitVec, Tag),
+        _val: &(BitVec, T),
     ) -> Result<(), Error> { Ok(()) }
 
     fn check_write_mem(
         &mut self,
         _address: &Address,
-        _val: (&BitVec, &Tag),
+        _val: (&BitVec, &T),
     ) -> Result<(), Error> { Ok(()) }
 
     fn check_cond_branch(
         &mut self,
         _opcode: &Opcode,
-        _cond: &(bool, Tag),
+        _cond: &(bool, T),
     ) -> Result<(), Error> { Ok(()) }
     
     fn check_branch(
         &mut self,
         _opcode: &Opcode,
-        _target: &(Address, Tag),
+        _target: &(Address, T),
     ) -> Result<(), Error> { Ok(()) }
 
     fn propagate_subpiece(
         &mut self,
         _opcode: &Opcode,
         _dst: &VarnodeData,
-        _src: &(BitVec, Tag),
-    ) -> Result<Tag, Error> {
-        Ok(Tag::from(tag::ACCESSED))
+        _src: &(BitVec, T),
+    ) -> Result<T, Error> {
+        Ok(T::accessed())
     }
     
     fn propagate_int2(
         &mut self,
         _opcode: &Opcode,
         _dst: &VarnodeData,
-        _lhs: &(BitVec, Tag),
-        _rhs: &(BitVec, Tag),
-    ) -> Result<Tag, Error> {
-        Ok(Tag::from(tag::ACCESSED))
+        _lhs: &(BitVec, T),
+        _rhs: &(BitVec, T),
+    ) -> Result<T, Error> {
+        Ok(T::accessed())
     }
     
     fn propagate_int1(
         &mut self,
         _opcode: &Opcode,
         _dst: &VarnodeData,
-        _rhs: &(BitVec, Tag)
-    ) -> Result<Tag, Error> {
-        Ok(Tag::from(tag::ACCESSED))
+        _rhs: &(BitVec, T)
+    ) -> Result<T, Error> {
+        Ok(T::accessed())
     }
     
     fn propagate_bool2(
         &mut self,
         _opcode: &Opcode,
         _dst: &VarnodeData,
-        _lhs: &(BitVec, Tag),
-        _rhs: &(BitVec, Tag),
-    ) -> Result<Tag, Error> {
-        Ok(Tag::from(tag::ACCESSED))
+        _lhs: &(BitVec, T),
+        _rhs: &(BitVec, T),
+    ) -> Result<T, Error> {
+        Ok(T::accessed())
     }
     
     fn propagate_bool1(
         &mut self,
         _opcode: &Opcode,
         _dst: &VarnodeData,
-        _rhs: &(BitVec, Tag),
-    ) -> Result<Tag, Error> {
-        Ok(Tag::from(tag::ACCESSED))
+        _rhs: &(BitVec, T),
+    ) -> Result<T, Error> {
+        Ok(T::accessed())
     }
 
     fn propagate_load<'a>(
         &mut self,
         _dst: &VarnodeData,
-        _val: &(BitVec, Tag),
-        _loc: &(Address, Tag),
-        _ctx: &dtt::Context<'a>,
-    ) -> Result<Tag, Error> {
-        Ok(Tag::from(tag::ACCESSED))
+        _val: &(BitVec, T),
+        _loc: &(Address, T),
+        _ctx: &dtt::Context<'a, T>,
+    ) -> Result<T, Error> {
+        Ok(T::accessed())
     }
     
     fn propagate_store<'a>(
         &mut self,
         _dst: &VarnodeData,
-        _val: &(BitVec, Tag),
-        _loc: &(Address, Tag),
-        _ctx: &dtt::Context<'a>,
-    ) -> Result<Tag, Error> {
-        Ok(Tag::from(tag::ACCESSED))
+        _val: &(BitVec, T),
+        _loc: &(Address, T),
+        _ctx: &dtt::Context<'a, T>,
+    ) -> Result<T, Error> {
+        Ok(T::accessed())
     }
 }
 
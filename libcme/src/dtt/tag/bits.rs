@@ -0,0 +1,105 @@
+//! bits.rs
+//!
+//! bit-precise taint masks
+//!
+//! tags are tracked per byte, so an operation that masks or shifts out
+//! every tainted bit of a value still produces a tainted result. when a
+//! bit-precise policy is in use, the bit shadow keeps a taint mask with one
+//! bit per data bit for bytes whose taint is known more precisely than
+//! their tag.
+//!
+//! a byte without a mask is fully tainted if its tag is tainted, so masks
+//! only need to be stored by the evaluator's precise propagation rules.
+//! any other tag write clears the masks of the bytes it covers.
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use fugue_ir::{Address, VarnodeData};
+
+/// per-byte taint masks for registers, temporaries, and memory
+#[derive(Debug, Clone, Default)]
+pub struct BitShadow {
+    regs: BTreeMap<u64, u8>,
+    tmps: BTreeMap<u64, u8>,
+    mem: BTreeMap<u64, u8>,
+}
+
+impl BitShadow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// get the mask of each byte of a varnode, if one is stored
+    pub fn read(&self, vnd: &VarnodeData) -> Vec<Option<u8>> {
+        let Some(map) = self._map(vnd) else {
+            return vec![None; vnd.size()];
+        };
+        let start = vnd.offset();
+        (start..start + vnd.size() as u64)
+            .map(|offset| map.get(&offset).copied())
+            .collect()
+    }
+
+    /// set the mask of each byte of a varnode
+    pub fn write(&mut self, vnd: &VarnodeData, masks: &[u8]) {
+        let start = vnd.offset();
+        let Some(map) = self._map_mut(vnd) else {
+            return;
+        };
+        for (i, mask) in masks.iter().take(vnd.size()).enumerate() {
+            map.insert(start + i as u64, *mask);
+        }
+    }
+
+    /// clear the masks of a varnode
+    pub fn clear(&mut self, vnd: &VarnodeData) {
+        let start = vnd.offset();
+        if let Some(map) = self._map_mut(vnd) {
+            _clear_range(map, start..start + vnd.size() as u64);
+        }
+    }
+
+    /// clear the masks in a memory range
+    pub fn clear_mem(&mut self, address: impl AsRef<Address>, size: usize) {
+        let start = address.as_ref().offset();
+        _clear_range(&mut self.mem, start..start + size as u64);
+    }
+}
+
+impl BitShadow {
+    fn _map(&self, vnd: &VarnodeData) -> Option<&BTreeMap<u64, u8>> {
+        let spc = vnd.space();
+        if spc.is_register() {
+            Some(&self.regs)
+        } else if spc.is_unique() {
+            Some(&self.tmps)
+        } else if spc.is_default() {
+            Some(&self.mem)
+        } else {
+            None
+        }
+    }
+
+    fn _map_mut(&mut self, vnd: &VarnodeData) -> Option<&mut BTreeMap<u64, u8>> {
+        let spc = vnd.space();
+        if spc.is_register() {
+            Some(&mut self.regs)
+        } else if spc.is_unique() {
+            Some(&mut self.tmps)
+        } else if spc.is_default() {
+            Some(&mut self.mem)
+        } else {
+            None
+        }
+    }
+}
+
+fn _clear_range(map: &mut BTreeMap<u64, u8>, range: Range<u64>) {
+    if map.range(range.clone()).next().is_none() {
+        return;
+    }
+    let keys: Vec<u64> = map.range(range).map(|(offset, _)| *offset).collect();
+    for offset in keys {
+        map.remove(&offset);
+    }
+}
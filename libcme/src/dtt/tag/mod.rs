@@ -1,12 +1,49 @@
 //! tag module
 //! 
 //! implement tags for dynamic flow tracking
+//! 
+//! the taint engine is generic over a `TaintTag`. `Tag` is the default and
+//! what the built-in policies use, but a policy can track richer state
+//! (e.g. a bitset of sources or confidentiality levels) by implementing
+//! the trait for its own tag type.
+use std::fmt;
 use std::ops;
 
 use bitfield_struct::bitfield;
 
 pub mod state;
 pub use state::FixedTagState;
+pub mod provenance;
+pub use provenance::{Label, LabelSet};
+pub mod bits;
+pub use bits::BitShadow;
+
+/// a tag type the taint engine can be parameterized over
+/// 
+/// the default value is the tag of data that has never been accessed,
+/// e.g. constants and freshly mapped memory.
+pub trait TaintTag: Clone + Default + PartialEq + fmt::Debug + fmt::Display + 'static {
+    /// the tag of data that has been accessed but is untainted
+    fn accessed() -> Self;
+
+    /// combine the tags of two values
+    fn join(&self, other: &Self) -> Self;
+
+    /// true if the tag carries any taint
+    fn is_tainted(&self) -> bool;
+
+    /// true if the location the tag's value was moved through is tainted,
+    /// e.g. a load through a tainted pointer
+    fn tainted_loc(&self) -> bool {
+        self.is_tainted()
+    }
+
+    /// the tag with its taint removed, used when bit-precise propagation
+    /// shows that none of a value's bits are tainted
+    fn untainted(&self) -> Self {
+        Self::accessed()
+    }
+}
 
 /// a data tag type
 /// meant to be used as a bitflag container
@@ -61,6 +98,33 @@ impl Tag {
     }
 }
 
+impl TaintTag for Tag {
+    #[inline(always)]
+    fn accessed() -> Self {
+        Tag::from(ACCESSED)
+    }
+
+    #[inline(always)]
+    fn join(&self, other: &Self) -> Self {
+        *self | other
+    }
+
+    #[inline(always)]
+    fn is_tainted(&self) -> bool {
+        Tag::is_tainted(self)
+    }
+
+    #[inline(always)]
+    fn tainted_loc(&self) -> bool {
+        Tag::tainted_loc(self)
+    }
+
+    #[inline(always)]
+    fn untainted(&self) -> Self {
+        *self & ACCESSED
+    }
+}
+
 pub const UNACCESSED:   u8 = 0;
 pub const ACCESSED:     u8 = 0b00000001;
 pub const TAINTED_VAL:  u8 = 0b00000010;
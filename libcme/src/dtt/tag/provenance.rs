@@ -0,0 +1,285 @@
+//! provenance.rs
+//!
+//! input byte provenance labels
+//!
+//! tags only say whether a value is tainted. when provenance tracking is
+//! enabled, every tainted byte taken from the input is also labelled with
+//! its offset in the input stream, and labels are unioned as taint
+//! propagates, so a tainted value can be traced back to the input bytes
+//! that produced it.
+//!
+//! input offsets follow the bytes actually taken from the input channel,
+//! counted by its readers, rather than the size of each access.
+//!
+//! labels are kept per byte in sparse maps alongside the tag shadow and
+//! never outlive taint: writing an untainted value clears its labels.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use fugue_ir::{Address, VarnodeData};
+
+use crate::peripheral::channel::InputCounter;
+
+/// an input byte offset
+pub type Label = u32;
+
+/// an immutable sorted set of labels
+///
+/// sets are shared by every byte they are written to, so cloning is cheap
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct LabelSet(Option<Arc<[Label]>>);
+
+impl LabelSet {
+    pub fn new() -> Self {
+        Self(None)
+    }
+
+    pub fn single(label: Label) -> Self {
+        Self(Some(Arc::from(vec![label])))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    pub fn as_slice(&self) -> &[Label] {
+        self.0.as_deref().unwrap_or(&[])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=Label> + '_ {
+        self.as_slice().iter().copied()
+    }
+
+    pub fn contains(&self, label: Label) -> bool {
+        self.as_slice().binary_search(&label).is_ok()
+    }
+
+    /// the union of two label sets
+    pub fn union(&self, other: &LabelSet) -> LabelSet {
+        match (&self.0, &other.0) {
+            (_, None) => { self.clone() }
+            (None, _) => { other.clone() }
+            (Some(lhs), Some(rhs)) if Arc::ptr_eq(lhs, rhs) => { self.clone() }
+            (Some(lhs), Some(rhs)) => {
+                let mut merged = Vec::with_capacity(lhs.len() + rhs.len());
+                let (mut i, mut j) = (0, 0);
+                while i < lhs.len() && j < rhs.len() {
+                    if lhs[i] < rhs[j] {
+                        merged.push(lhs[i]);
+                        i += 1;
+                    } else if rhs[j] < lhs[i] {
+                        merged.push(rhs[j]);
+                        j += 1;
+                    } else {
+                        merged.push(lhs[i]);
+                        i += 1;
+                        j += 1;
+                    }
+                }
+                merged.extend_from_slice(&lhs[i..]);
+                merged.extend_from_slice(&rhs[j..]);
+                if merged.len() == lhs.len() {
+                    self.clone()
+                } else {
+                    Self(Some(Arc::from(merged)))
+                }
+            }
+        }
+    }
+
+    /// the union of many label sets
+    pub fn union_all<'a>(sets: impl IntoIterator<Item=&'a LabelSet>) -> LabelSet {
+        sets.into_iter()
+            .fold(LabelSet::new(), |result, labels| result.union(labels))
+    }
+
+    /// the labels as contiguous ranges of input offsets
+    pub fn ranges(&self) -> Vec<Range<Label>> {
+        let mut ranges: Vec<Range<Label>> = vec![];
+        for label in self.iter() {
+            match ranges.last_mut() {
+                Some(range) if range.end == label => { range.end += 1; }
+                _ => { ranges.push(label..label + 1); }
+            }
+        }
+        ranges
+    }
+}
+
+impl FromIterator<Label> for LabelSet {
+    fn from_iter<T: IntoIterator<Item = Label>>(iter: T) -> Self {
+        let mut labels: Vec<Label> = iter.into_iter().collect();
+        if labels.is_empty() {
+            return Self(None);
+        }
+        labels.sort_unstable();
+        labels.dedup();
+        Self(Some(Arc::from(labels)))
+    }
+}
+
+impl fmt::Display for LabelSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, range) in self.ranges().into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            if range.len() == 1 {
+                write!(f, "{}", range.start)?;
+            } else {
+                write!(f, "{}-{}", range.start, range.end - 1)?;
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Debug for LabelSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LabelSet{self}")
+    }
+}
+
+/// per-byte provenance labels for registers, temporaries, and memory
+#[derive(Debug, Clone, Default)]
+pub struct ProvenanceState {
+    /// bytes taken from the input channel by its readers
+    consumed: InputCounter,
+    /// input offset of the next byte read
+    next: Label,
+    regs: BTreeMap<u64, LabelSet>,
+    tmps: BTreeMap<u64, LabelSet>,
+    mem: BTreeMap<u64, LabelSet>,
+    /// labels of the last value that violated the taint policy
+    violation: Option<LabelSet>,
+}
+
+impl ProvenanceState {
+    pub fn new(consumed: InputCounter) -> Self {
+        Self { consumed, ..Default::default() }
+    }
+
+    /// the counter of bytes taken from the input channel
+    pub fn counter(&self) -> &InputCounter {
+        &self.consumed
+    }
+
+    /// the input offset of the next byte to be read
+    pub fn input_offset(&self) -> Label {
+        self.next
+    }
+
+    /// consume `size` input bytes, returning a label for each
+    pub fn next_labels(&mut self, size: usize) -> Vec<LabelSet> {
+        let labels = (0..size as Label)
+            .map(|i| LabelSet::single(self.next + i))
+            .collect();
+        self.next += size as Label;
+        labels
+    }
+
+    /// consume `size` input bytes without labelling them
+    pub fn skip(&mut self, size: usize) {
+        self.next += size as Label;
+    }
+
+    /// get the labels of each byte of a varnode
+    pub fn read(&self, vnd: &VarnodeData) -> Vec<LabelSet> {
+        let Some(map) = self._map(vnd) else {
+            return vec![LabelSet::new(); vnd.size()];
+        };
+        let start = vnd.offset();
+        (start..start + vnd.size() as u64)
+            .map(|offset| map.get(&offset).cloned().unwrap_or_default())
+            .collect()
+    }
+
+    /// set the labels of each byte of a varnode
+    ///
+    /// bytes without a corresponding label set are cleared
+    pub fn write(&mut self, vnd: &VarnodeData, labels: &[LabelSet]) {
+        let start = vnd.offset();
+        let Some(map) = self._map_mut(vnd) else {
+            return;
+        };
+        for i in 0..vnd.size() {
+            match labels.get(i) {
+                Some(labels) if !labels.is_empty() => {
+                    map.insert(start + i as u64, labels.clone());
+                }
+                _ => { map.remove(&(start + i as u64)); }
+            }
+        }
+    }
+
+    /// clear the labels of a varnode
+    pub fn clear(&mut self, vnd: &VarnodeData) {
+        let start = vnd.offset();
+        if let Some(map) = self._map_mut(vnd) {
+            _clear_range(map, start..start + vnd.size() as u64);
+        }
+    }
+
+    /// the union of the labels in a memory range
+    pub fn read_mem(&self, address: impl AsRef<Address>, size: usize) -> LabelSet {
+        let start = address.as_ref().offset();
+        LabelSet::union_all(self.mem.range(start..start + size as u64).map(|(_, labels)| labels))
+    }
+
+    /// clear the labels in a memory range
+    pub fn clear_mem(&mut self, address: impl AsRef<Address>, size: usize) {
+        let start = address.as_ref().offset();
+        _clear_range(&mut self.mem, start..start + size as u64);
+    }
+
+    /// labels of the last value that violated the taint policy
+    pub fn violation(&self) -> Option<&LabelSet> {
+        self.violation.as_ref()
+    }
+
+    pub fn set_violation(&mut self, labels: LabelSet) {
+        self.violation = Some(labels);
+    }
+}
+
+impl ProvenanceState {
+    fn _map(&self, vnd: &VarnodeData) -> Option<&BTreeMap<u64, LabelSet>> {
+        let spc = vnd.space();
+        if spc.is_register() {
+            Some(&self.regs)
+        } else if spc.is_unique() {
+            Some(&self.tmps)
+        } else if spc.is_default() {
+            Some(&self.mem)
+        } else {
+            None
+        }
+    }
+
+    fn _map_mut(&mut self, vnd: &VarnodeData) -> Option<&mut BTreeMap<u64, LabelSet>> {
+        let spc = vnd.space();
+        if spc.is_register() {
+            Some(&mut self.regs)
+        } else if spc.is_unique() {
+            Some(&mut self.tmps)
+        } else if spc.is_default() {
+            Some(&mut self.mem)
+        } else {
+            None
+        }
+    }
+}
+
+fn _clear_range(map: &mut BTreeMap<u64, LabelSet>, range: Range<u64>) {
+    let keys: Vec<u64> = map.range(range).map(|(offset, _)| *offset).collect();
+    for offset in keys {
+        map.remove(&offset);
+    }
+}
@@ -3,7 +3,7 @@
 //! a simple fixed tag state struct
 use thiserror::Error;
 
-use super::Tag;
+use super::{Tag, TaintTag};
 
 #[derive(Clone, Debug, Error)]
 pub enum FixedTagStateError {
@@ -15,19 +15,19 @@ pub enum FixedTagStateError {
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[repr(transparent)]
-pub struct FixedTagState {
-    pub(crate) backing: Box<[Tag]>,
+pub struct FixedTagState<T: TaintTag = Tag> {
+    pub(crate) backing: Box<[T]>,
 }
 
-impl FixedTagState {
+impl<T: TaintTag> FixedTagState<T> {
     pub fn new(size: usize) -> Self {
         Self {
-            backing: vec![Tag::from(0); size].into_boxed_slice(),
+            backing: vec![T::default(); size].into_boxed_slice(),
         }
     }
 
-    /// create a fixed state initialized with the given Tag
-    pub fn new_with(size: usize, tag: Tag) -> Self {
+    /// create a fixed state initialized with the given tag
+    pub fn new_with(size: usize, tag: T) -> Self {
         Self {
             backing: vec![tag; size].into_boxed_slice(),
         }
@@ -43,11 +43,11 @@ impl FixedTagState {
         &self,
         offset: impl Into<usize>,
         size: usize,
-    ) -> Result<Tag, FixedTagStateError> {
+    ) -> Result<T, FixedTagStateError> {
         let (offset, end) = self._check_bounds(offset, size)?;
         Ok(self.backing[offset..end]
             .iter()
-            .fold(Tag::default(), |result, t| result | t))
+            .fold(T::default(), |result, t| result.join(t)))
     }
 
     #[inline(always)]
@@ -55,13 +55,12 @@ impl FixedTagState {
         &mut self,
         offset: impl Into<usize>,
         size: usize,
-        tag: impl AsRef<Tag>,
+        tag: &T,
     ) -> Result<(), FixedTagStateError> {
         let (offset, end) = self._check_bounds(offset, size)?;
-        let tag = tag.as_ref();
 
         for i in offset..end {
-            self.backing[i] = *tag;
+            self.backing[i] = tag.clone();
         }
 
         Ok(())
@@ -72,7 +71,7 @@ impl FixedTagState {
         &self,
         offset: impl Into<usize>,
         size: usize,
-    ) -> Result<&[Tag], FixedTagStateError> {
+    ) -> Result<&[T], FixedTagStateError> {
         let (offset, end) = self._check_bounds(offset, size)?;
         Ok(&self.backing[offset..end])
     }
@@ -82,13 +81,13 @@ impl FixedTagState {
         &mut self,
         offset: impl Into<usize>,
         size: usize,
-    ) -> Result<&mut [Tag], FixedTagStateError> {
+    ) -> Result<&mut [T], FixedTagStateError> {
         let (offset, end) = self._check_bounds(offset, size)?;
         Ok(&mut self.backing[offset..end])
     }
 }
 
-impl FixedTagState {
+impl<T: TaintTag> FixedTagState<T> {
     #[inline(always)]
     fn _check_bounds(
         &self,
@@ -106,8 +105,8 @@ impl FixedTagState {
     }
 }
 
-impl From<Vec<Tag>> for FixedTagState {
-    fn from(backing: Vec<Tag>) -> Self {
+impl<T: TaintTag> From<Vec<T>> for FixedTagState<T> {
+    fn from(backing: Vec<T>) -> Self {
         Self {
             backing: backing.into_boxed_slice(),
         }
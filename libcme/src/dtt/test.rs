@@ -49,6 +49,8 @@ fn test_smash_stack() -> Result<(), anyhow::Error> {
         fpu_present: false,
         nvic_prio_bits: 8,
         vendor_systick_config: false,
+        num_interrupts: None,
+        vtor_present: true,
         mem: vec![MemRegion {
             name: "memory".into(),
             address: 0x0u64.into(),
@@ -127,4 +129,432 @@ fn test_smash_stack() -> Result<(), anyhow::Error> {
 
     // expect a failure to occur
     Err(anyhow::Error::msg("expected a policy violation"))
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_snapshot_restore() -> Result<(), anyhow::Error> {
+    use fugue_core::prelude::*;
+    use crate::backend::armv7m;
+    use crate::dtt::{
+        self,
+        tag::{self, Tag},
+    };
+
+    let builder = LanguageBuilder::new("data/processors")?;
+    let backend = armv7m::Backend::new_with(&builder, None)?;
+    let mut context = dtt::Context::new_with(Box::new(backend));
+    context.map_mem(0x0u64, 0x1000)?;
+    context.map_mem(0x2000_0000u64, 0x1000)?;
+
+    context.store_bytes(0x0u64, &[0xaa; 0x10], &Tag::from(tag::ACCESSED))?;
+    context.write_pc(0x8u64, &Tag::from(tag::ACCESSED))?;
+    let snapshot = context.snapshot();
+    assert_eq!(context.backend().mmap().dirty_page_count(), 0);
+
+    info!("dirtying context...");
+    context.store_bytes(0x4u64, &[0x55; 4], &Tag::from(tag::TAINTED_VAL))?;
+    context.store_bytes(0x2000_0800u64, &[0x55; 4], &Tag::from(tag::TAINTED_VAL))?;
+    context.write_pc(0x20u64, &Tag::from(tag::TAINTED_VAL))?;
+    assert_eq!(context.backend().mmap().dirty_page_count(), 2);
+
+    info!("restoring context...");
+    context.restore(&snapshot)?;
+    assert_eq!(context.backend().mmap().dirty_page_count(), 0);
+
+    let mut bytes = [0u8; 4];
+    let tag = context.load_bytes(0x4u64, &mut bytes)?;
+    assert_eq!(bytes, [0xaa; 4]);
+    assert!(!tag.is_tainted(), "tag should be restored");
+
+    let tag = context.load_bytes(0x2000_0800u64, &mut bytes)?;
+    assert_eq!(bytes, [0x00; 4]);
+    assert!(!tag.is_tainted(), "tag should be restored");
+
+    let (pc, pc_tag) = context.read_pc()?;
+    assert_eq!(pc.offset(), 0x8);
+    assert!(!pc_tag.is_tainted(), "pc tag should be restored");
+
+    // restoring again should be idempotent
+    context.restore(&snapshot)?;
+    let tag = context.load_bytes(0x4u64, &mut bytes)?;
+    assert_eq!(bytes, [0xaa; 4]);
+    assert!(!tag.is_tainted(), "tag should be restored");
+
+    Ok(())
+}
+
+#[test]
+fn test_reset_clears_taint() -> Result<(), anyhow::Error> {
+    use fugue_core::prelude::*;
+    use crate::backend::{self, armv7m};
+    use crate::backend::armv7m::{AIRCR, SCRegType};
+    use crate::dtt::{
+        self,
+        tag::{self, Tag},
+    };
+
+    let builder = LanguageBuilder::new("data/processors")?;
+    let backend = armv7m::Backend::new_with(&builder, None)?;
+    let mut context = dtt::Context::new_with(Box::new(backend));
+    context.map_mem(0x0u64, 0x1000)?;
+    context.set_reset_policy(backend::ResetPolicy::Reset);
+
+    info!("tainting context...");
+    context.store_bytes(0x0u64, &u32::to_le_bytes(0x800), &Tag::from(tag::ACCESSED))?;
+    context.store_bytes(0x4u64, &u32::to_le_bytes(0x101), &Tag::from(tag::ACCESSED))?;
+    context.store_bytes(0x200u64, &[0x55; 4], &Tag::from(tag::TAINTED_VAL))?;
+    context.write_pc(0x20u64, &Tag::from(tag::TAINTED_VAL))?;
+
+    info!("requesting system reset...");
+    let aircr = AIRCR::new().with_vectkey_stat(0x05fa).with_sysresetreq(true);
+    context.backend_mut().store_bytes(&SCRegType::AIRCR.address(), &aircr.into_bits().to_le_bytes())?;
+    context.process_events()?;
+
+    let mut bytes = [0u8; 4];
+    let tag = context.load_bytes(0x200u64, &mut bytes)?;
+    assert_eq!(bytes, [0x55; 4], "memory survives reset");
+    assert!(!tag.is_tainted(), "memory taint cleared");
+
+    let (pc, pc_tag) = context.read_pc()?;
+    assert_eq!(pc.offset(), 0x100);
+    assert!(!pc_tag.is_tainted(), "register taint cleared");
+
+    Ok(())
+}
+
+#[test]
+fn test_provenance_labels() -> Result<(), anyhow::Error> {
+    use fugue_core::prelude::*;
+    use fugue_ir::VarnodeData;
+    use fugue_bv::BitVec;
+    use crate::backend::armv7m;
+    use crate::peripheral::channel::{ChannelPeripheral, GeneratedChannelPeripheral, EndOfInput};
+    use crate::dtt::{
+        self,
+        tag::{self, Tag, LabelSet},
+    };
+
+    let builder = LanguageBuilder::new("data/processors")?;
+    let backend = armv7m::Backend::new_with(&builder, None)?;
+    let mut context = dtt::Context::new_with(Box::new(backend));
+    context.map_mem(0x2000_0000u64, 0x1000)?;
+
+    let GeneratedChannelPeripheral {
+        access_log: _access_log,
+        read_src,
+        write_dst: _write_dst,
+        peripheral,
+    } = ChannelPeripheral::new(Address::from(0x4000_1000u32), 0x1000);
+    let peripheral = peripheral.with_end_of_input(EndOfInput::Fill(0).into());
+    context.enable_provenance(peripheral.input_counter());
+    context.map_mmio(peripheral.into(), Some(Tag::from(tag::TAINTED_VAL)))?;
+    for byte in 0..5u8 {
+        read_src.0.try_send(byte)?;
+    }
+    let snapshot = context.snapshot();
+
+    let spc = context.lang().translator().manager().default_space();
+    let rx = VarnodeData::new(spc.as_ref(), 0x4000_1000u64, 2);
+    let r0 = context.lang().translator().register_by_name("r0").unwrap();
+
+    info!("reading tainted mmio...");
+    let (_, tag) = context.read(&rx)?;
+    assert!(tag.is_tainted(), "source read should be tainted");
+    assert_eq!(context.labels_of(&rx), LabelSet::from_iter([0, 1]));
+    let (_, tag) = context.read(&rx)?;
+    assert!(tag.is_tainted(), "source read should be tainted");
+    assert_eq!(context.labels_of(&rx), LabelSet::from_iter([2, 3]));
+    assert_eq!(context.input_offset(), Some(4));
+
+    info!("propagating labels...");
+    let labels = context.byte_labels(&rx);
+    context.write_byte_labels(&r0, &labels);
+    assert_eq!(context.labels_of(&r0), LabelSet::from_iter([2, 3]));
+    let mem = VarnodeData::new(spc.as_ref(), 0x2000_0010u64, 2);
+    context.write(&mem, &BitVec::from_u32(0x0302, 16), &Tag::from(tag::TAINTED_VAL))?;
+    context.write_byte_labels(&mem, &labels);
+    assert_eq!(context.mem_labels(0x2000_0000u64, 0x20), LabelSet::from_iter([2, 3]));
+
+    info!("clearing labels with untainted writes...");
+    context.store_bytes(0x2000_0011u64, &[0x00], &Tag::from(tag::ACCESSED))?;
+    assert_eq!(context.mem_labels(0x2000_0000u64, 0x20), LabelSet::from_iter([2]));
+    context.write(&r0, &BitVec::from_u32(0, 32), &Tag::from(tag::ACCESSED))?;
+    assert!(context.labels_of(&r0).is_empty(), "untainted write should clear labels");
+
+    info!("labelling only the bytes taken from the input...");
+    let word = VarnodeData::new(spc.as_ref(), 0x4000_1004u64, 4);
+    context.read(&word)?;
+    assert_eq!(context.labels_of(&word), LabelSet::from_iter([4]));
+    assert_eq!(context.input_offset(), Some(5));
+    context.read(&word)?;
+    assert!(context.labels_of(&word).is_empty(), "filled bytes aren't input");
+    assert_eq!(context.input_offset(), Some(5));
+
+    info!("restoring context...");
+    context.restore(&snapshot)?;
+    assert_eq!(context.input_offset(), Some(0));
+    assert!(context.mem_labels(0x2000_0000u64, 0x20).is_empty(), "labels should be restored");
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_tag() -> Result<(), anyhow::Error> {
+    use std::fmt;
+    use fugue_core::prelude::*;
+    use fugue_ir::VarnodeData;
+    use crate::backend::armv7m;
+    use crate::peripheral::channel::{ChannelPeripheral, GeneratedChannelPeripheral};
+    use crate::dtt::{self, TaintTag};
+
+    /// a bitset of taint sources
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Sources(u8);
+
+    impl fmt::Display for Sources {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:#010b}", self.0)
+        }
+    }
+
+    impl TaintTag for Sources {
+        fn accessed() -> Self { Self(0) }
+        fn join(&self, other: &Self) -> Self { Self(self.0 | other.0) }
+        fn is_tainted(&self) -> bool { self.0 != 0 }
+    }
+
+    let builder = LanguageBuilder::new("data/processors")?;
+    let backend = armv7m::Backend::new_with(&builder, None)?;
+    let mut context = dtt::Context::<Sources>::new_tagged(Box::new(backend));
+    context.map_mem(0x2000_0000u64, 0x1000)?;
+
+    let mut read_srcs = vec![];
+    for (i, base) in [0x4000_1000u32, 0x4000_2000u32].into_iter().enumerate() {
+        let GeneratedChannelPeripheral {
+            access_log: _access_log,
+            read_src,
+            write_dst: _write_dst,
+            peripheral,
+        } = ChannelPeripheral::new(Address::from(base), 0x1000);
+        context.map_mmio(peripheral.into(), Some(Sources(1 << i)))?;
+        read_src.0.try_send(0x55)?;
+        read_srcs.push(read_src);
+    }
+
+    let spc = context.lang().translator().manager().default_space();
+    let uart = VarnodeData::new(spc.as_ref(), 0x4000_1000u64, 1);
+    let spi = VarnodeData::new(spc.as_ref(), 0x4000_2000u64, 1);
+    let (uart_val, uart_tag) = context.read(&uart)?;
+    let (spi_val, spi_tag) = context.read(&spi)?;
+    assert_eq!(uart_tag, Sources(0b01));
+    assert_eq!(spi_tag, Sources(0b10));
+
+    info!("joining tags of a multi-byte read...");
+    let lo = VarnodeData::new(spc.as_ref(), 0x2000_0000u64, 1);
+    let hi = VarnodeData::new(spc.as_ref(), 0x2000_0001u64, 1);
+    context.write(&lo, &uart_val, &uart_tag)?;
+    context.write(&hi, &spi_val, &spi_tag)?;
+    let word = VarnodeData::new(spc.as_ref(), 0x2000_0000u64, 2);
+    let (_, tag) = context.read(&word)?;
+    assert_eq!(tag, Sources(0b11));
+    assert!(tag.is_tainted());
+
+    Ok(())
+}
+
+#[test]
+fn test_bit_masks() -> Result<(), anyhow::Error> {
+    use fugue_core::prelude::*;
+    use fugue_ir::VarnodeData;
+    use fugue_bv::BitVec;
+    use crate::backend::armv7m;
+    use crate::dtt::{
+        self,
+        tag::{self, Tag},
+    };
+
+    let builder = LanguageBuilder::new("data/processors")?;
+    let backend = armv7m::Backend::new_with(&builder, None)?;
+    let mut context = dtt::Context::new_with(Box::new(backend));
+    context.map_mem(0x2000_0000u64, 0x1000)?;
+    context.enable_bit_shadow();
+
+    let spc = context.lang().translator().manager().default_space();
+    let mem = VarnodeData::new(spc.as_ref(), 0x2000_0010u64, 4);
+    let r0 = context.lang().translator().register_by_name("r0").unwrap();
+    let tainted = Tag::from(tag::TAINTED_VAL);
+
+    info!("masks default to the tag...");
+    context.write(&r0, &BitVec::from_u32(0x1234, 32), &tainted)?;
+    assert_eq!(context.bit_mask(&r0, &tainted), BitVec::from_u32(0xffff_ffff, 32));
+    assert!(context.bit_mask(&r0, &Tag::from(tag::ACCESSED)).is_zero());
+
+    info!("writing precise masks...");
+    context.write_bit_mask(&r0, &BitVec::from_u32(0x0000_ff00, 32));
+    assert_eq!(context.bit_mask(&r0, &tainted), BitVec::from_u32(0x0000_ff00, 32));
+    context.write(&mem, &BitVec::from_u32(0x1234, 32), &tainted)?;
+    context.write_bit_mask(&mem, &BitVec::from_u32(0x0000_00f0, 32));
+    let byte = VarnodeData::new(spc.as_ref(), 0x2000_0010u64, 1);
+    assert_eq!(context.bit_mask(&byte, &tainted), BitVec::from_u32(0xf0, 8));
+
+    info!("tag writes clear masks...");
+    context.write(&r0, &BitVec::from_u32(0x1234, 32), &tainted)?;
+    assert_eq!(context.bit_mask(&r0, &tainted), BitVec::from_u32(0xffff_ffff, 32));
+    context.store_bytes(0x2000_0010u64, &[0x00; 4], &tainted)?;
+    assert_eq!(context.bit_mask(&mem, &tainted), BitVec::from_u32(0xffff_ffff, 32));
+
+    Ok(())
+}
+
+#[test]
+fn test_exclusive_monitor() -> Result<(), anyhow::Error> {
+    use fugue_core::prelude::*;
+    use fugue_core::ir::Location;
+    use fugue_ir::disassembly::IRBuilderArena;
+    use fugue_bv::BitVec;
+    use crate::types::Permission;
+    use crate::programdb::{ProgramDB, MemRegion, Platform, Program};
+    use crate::backend::armv7m;
+    use crate::dtt::{
+        self,
+        Evaluator,
+        tag::{self, Tag},
+    };
+
+    let irb = IRBuilderArena::with_capacity(0x1000);
+    let program = Program::new_from_bytes(
+        irb.inner(),
+        0x0u64,
+        programs::EXCLUSIVE_MONITOR_TEST,
+    )?;
+    let platform = Platform {
+        name: "dummy".into(),
+        cpu_name: "CM3".into(),
+        cpu_revision: "".into(),
+        cpu_endian: Endian::Little,
+        mpu_present: false,
+        fpu_present: false,
+        nvic_prio_bits: 8,
+        vendor_systick_config: false,
+        num_interrupts: None,
+        vtor_present: true,
+        mem: vec![MemRegion {
+            name: "memory".into(),
+            address: 0x0u64.into(),
+            size: 0x1000,
+            perms: Permission::R | Permission::W,
+            description: "".into(),
+        }],
+        mmio: vec![],
+    };
+    let builder = LanguageBuilder::new("data/processors")?;
+    let mut pdb = ProgramDB::new_with(&builder, program, platform, &irb);
+
+    let backend = armv7m::Backend::new_with(&builder, None)?;
+    let mut context = dtt::Context::new_with(Box::new(backend));
+    context.map_mem(0x0u64, 0x1000)?;
+    context.store_bytes(0x0u64, programs::EXCLUSIVE_MONITOR_TEST, &Tag::from(tag::ACCESSED))?;
+
+    let reg = |name: &str| context.lang().translator().register_by_name(name).unwrap();
+    let (r1, r2) = (reg("r1"), reg("r2"));
+    let results = ["r3", "r4", "r5", "r6", "r7"].map(reg);
+    context.write(&r1, &BitVec::from_u32(0x800, 32), &Tag::from(tag::ACCESSED))?;
+    context.write(&r2, &BitVec::from_u32(5, 32), &Tag::from(tag::ACCESSED))?;
+    context.write_sp(0x1000u64, &Tag::from(tag::ACCESSED))?;
+    context.write_pc(0x40u64, &Tag::from(tag::ACCESSED))?;
+
+    info!("executing program...");
+    let mut evaluator = Evaluator::new();
+    (evaluator.pc, evaluator.pc_tag) = context.read_pc()
+        .map(|(pc, tag)| (Location::from(pc), tag))?;
+    let exit = Address::from(0x68u64);
+    let mut cycles = 0;
+    while evaluator.pc.address() != exit {
+        assert!(cycles < 100, "expected to reach exit");
+        evaluator.step(&mut context, &mut pdb)?;
+        cycles += 1;
+    }
+
+    // strex writes 0 on success and 1 on failure
+    let [intervening_store, success, clrex, entry, ret] = results
+        .map(|vnd| context.read(&vnd).map(|(val, _)| val));
+    assert_eq!(intervening_store?, BitVec::from_u32(1, 32), "store clears the monitor");
+    assert_eq!(success?, BitVec::from_u32(0, 32), "monitor should be set");
+    assert_eq!(clrex?, BitVec::from_u32(1, 32), "clrex clears the monitor");
+    assert_eq!(entry?, BitVec::from_u32(1, 32), "exception entry clears the monitor");
+    assert_eq!(ret?, BitVec::from_u32(1, 32), "exception return clears the monitor");
+
+    Ok(())
+}
+
+#[test]
+fn test_armv6m_undefined() -> Result<(), anyhow::Error> {
+    use fugue_core::prelude::*;
+    use fugue_core::ir::Location;
+    use fugue_ir::disassembly::IRBuilderArena;
+    use fugue_bv::BitVec;
+    use crate::types::Permission;
+    use crate::programdb::{ProgramDB, MemRegion, Platform, Program};
+    use crate::dtt::{
+        self,
+        Evaluator,
+        tag::{self, Tag},
+    };
+
+    let irb = IRBuilderArena::with_capacity(0x1000);
+    let program = Program::new_from_bytes(
+        irb.inner(),
+        0x0u64,
+        programs::ARMV6M_UNDEFINED_TEST,
+    )?;
+    let platform = Platform {
+        name: "dummy".into(),
+        cpu_name: "CM0".into(),
+        cpu_revision: "".into(),
+        cpu_endian: Endian::Little,
+        mpu_present: false,
+        fpu_present: false,
+        nvic_prio_bits: 2,
+        vendor_systick_config: false,
+        num_interrupts: None,
+        vtor_present: false,
+        mem: vec![MemRegion {
+            name: "memory".into(),
+            address: 0x0u64.into(),
+            size: 0x1000,
+            perms: Permission::R | Permission::W,
+            description: "".into(),
+        }],
+        mmio: vec![],
+    };
+    let builder = LanguageBuilder::new("data/processors")?;
+    let mut pdb = ProgramDB::new_with(&builder, program, platform, &irb);
+
+    let backend = pdb.backend_armv6m(&builder)?;
+    let mut context = dtt::Context::from_backend(backend)?;
+    context.store_bytes(0x0u64, programs::ARMV6M_UNDEFINED_TEST, &Tag::from(tag::ACCESSED))?;
+
+    let reg = |name: &str| context.lang().translator().register_by_name(name).unwrap();
+    let (r1, r2, r3) = (reg("r1"), reg("r2"), reg("r3"));
+    context.write(&r1, &BitVec::from_u32(10, 32), &Tag::from(tag::ACCESSED))?;
+    context.write(&r2, &BitVec::from_u32(5, 32), &Tag::from(tag::ACCESSED))?;
+    context.write_sp(0x1000u64, &Tag::from(tag::ACCESSED))?;
+    context.write_pc(0x40u64, &Tag::from(tag::ACCESSED))?;
+
+    info!("executing program...");
+    let mut evaluator = Evaluator::new();
+    (evaluator.pc, evaluator.pc_tag) = context.read_pc()
+        .map(|(pc, tag)| (Location::from(pc), tag))?;
+    let exit = Address::from(0x4cu64);
+    let mut cycles = 0;
+    while evaluator.pc.address() != exit {
+        assert!(cycles < 100, "expected to reach exit");
+        evaluator.step(&mut context, &mut pdb)?;
+        cycles += 1;
+    }
+
+    let (faults, _) = context.read(&r3)?;
+    assert_eq!(faults, BitVec::from_u32(2, 32), "sdiv and msr BASEPRI should fault");
+
+    Ok(())
+}
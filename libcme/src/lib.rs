@@ -23,7 +23,7 @@ pub mod prelude {
         Peripheral,
         PeripheralState,
     };
-    pub use super::backend::{self, armv7m, Backend};
+    pub use super::backend::{self, armv7m, armv6m, Backend};
     pub use super::dtt::{
         self,
         policy,
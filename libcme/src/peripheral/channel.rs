@@ -3,7 +3,10 @@
 //! a peripheral implementation that sends and receives
 //! via crossbeam channel
 use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
 use anyhow;
+use parking_lot::RwLock;
 use thiserror::Error;
 use crossbeam::channel::{
     unbounded, Receiver, Sender, TryRecvError, TrySendError
@@ -37,6 +40,85 @@ impl From<Access> for (Address, usize, bool) {
     }
 }
 
+/// what a channel-backed peripheral does when its read source runs dry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EndOfInput {
+    /// stop execution normally
+    Exit,
+    /// read the given fill byte
+    Fill(u8),
+    /// report that no data is available and let the firmware idle
+    Stall,
+    /// stop execution with a timeout
+    #[default]
+    Timeout,
+}
+
+/// a shared end-of-input policy
+/// 
+/// the same policy should be given to the peripherals and the executor
+/// that feeds them, so that both agree on how a dry channel is handled.
+/// peripherals record stalled reads here, since a stalled read can
+/// otherwise look like any other.
+#[derive(Debug, Clone, Default)]
+pub struct EndOfInputPolicy {
+    end_of_input: Arc<RwLock<EndOfInput>>,
+    /// the first read that stalled since the last `take_stall`
+    stall: Arc<RwLock<Option<Address>>>,
+}
+
+impl EndOfInputPolicy {
+    pub fn new(end_of_input: EndOfInput) -> Self {
+        let end_of_input = Arc::new(RwLock::new(end_of_input));
+        Self { end_of_input, stall: Arc::default() }
+    }
+
+    pub fn get(&self) -> EndOfInput {
+        *self.end_of_input.read()
+    }
+
+    pub fn set(&self, end_of_input: EndOfInput) {
+        *self.end_of_input.write() = end_of_input;
+    }
+
+    /// record a read at the given address that stalled
+    pub fn stall(&self, address: Address) {
+        self.stall.write().get_or_insert(address);
+    }
+
+    /// the address of the first read that stalled since the last call
+    pub fn take_stall(&self) -> Option<Address> {
+        self.stall.write().take()
+    }
+}
+
+impl From<EndOfInput> for EndOfInputPolicy {
+    fn from(end_of_input: EndOfInput) -> Self {
+        Self::new(end_of_input)
+    }
+}
+
+/// the number of bytes taken from an input channel
+/// 
+/// shared by every reader of the channel, so that the number of input
+/// bytes an access actually consumed can be told apart from its size.
+#[derive(Debug, Clone, Default)]
+pub struct InputCounter(Arc<AtomicU32>);
+
+impl InputCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn add(&self, n: u32) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ChannelStateError {
     #[error("log error: {0:?}")]
@@ -60,6 +142,8 @@ pub struct ChannelPeripheral {
     access_log: Sender<Access>,
     read_src: Receiver<u8>,
     write_dst: Sender<u8>,
+    end_of_input: EndOfInputPolicy,
+    input_count: InputCounter,
 }
 
 #[derive(Clone)]
@@ -79,7 +163,27 @@ impl ChannelPeripheral {
         write_dst: Sender<u8>,
     ) -> Self {
         let base = base.into();
-        Self { base, size, access_log, read_src, write_dst }
+        let end_of_input = EndOfInputPolicy::default();
+        let input_count = InputCounter::default();
+        Self { base, size, access_log, read_src, write_dst, end_of_input, input_count }
+    }
+
+    /// set the policy for reads after the read source runs dry
+    pub fn with_end_of_input(mut self, end_of_input: EndOfInputPolicy) -> Self {
+        self.end_of_input = end_of_input;
+        self
+    }
+
+    /// count the bytes taken from the read source with the given counter
+    pub fn with_input_counter(mut self, input_count: InputCounter) -> Self {
+        self.input_count = input_count;
+        self
+    }
+
+    /// the counter of bytes taken from the read source,
+    /// shared with every peripheral cloned from this one
+    pub fn input_counter(&self) -> InputCounter {
+        self.input_count.clone()
     }
 
     /// creates a new channel peripheral and returns the other side of the 
@@ -151,11 +255,28 @@ impl PeripheralState for ChannelPeripheral {
                 ChannelStateError::Log(address.clone(), err)
             })?;
         for i in 0..dst.len() {
-            dst[i] = self.read_src.try_recv()
-                .map_err(|err| {
+            dst[i] = match self.read_src.try_recv() {
+                Ok(byte) => { self.input_count.add(1); byte }
+                Err(TryRecvError::Empty) => {
+                    match self.end_of_input.get() {
+                        EndOfInput::Fill(byte) => { byte }
+                        // a plain channel has no status to report, so the
+                        // stall is recorded for the executor instead
+                        EndOfInput::Stall => {
+                            self.end_of_input.stall(*address + i as u64);
+                            0
+                        }
+                        EndOfInput::Exit | EndOfInput::Timeout => {
+                            let addr = *address + i as u64;
+                            return Err(ChannelStateError::Recv(addr, TryRecvError::Empty).into());
+                        }
+                    }
+                }
+                Err(err) => {
                     let addr = *address + i as u64;
-                    ChannelStateError::Recv(addr, err)
-                })?;
+                    return Err(ChannelStateError::Recv(addr, err).into());
+                }
+            };
         }
         Ok(())
     }
@@ -215,7 +336,7 @@ mod test {
             write_dst,
             peripheral
         } = ChannelPeripheral::new(base, size);
-        backend.map_mmio(peripheral.into())?;
+        backend.map_mmio(peripheral.clone().into())?;
 
         // initializing data for peripheral byte reads
         let bytes: [u8; 4] = [0x01, 0x02, 0x03, 0x04];
@@ -264,6 +385,26 @@ mod test {
                 return Err(anyhow::Error::msg(msg));
             }
         }
+
+        info!("testing end of input fill...");
+        let end_of_input = EndOfInputPolicy::new(EndOfInput::Fill(0xaa));
+        let fill_base = Address::from(0x40003000u32);
+        let fill_peripheral = peripheral.clone_with(fill_base, size)
+            .with_end_of_input(end_of_input.clone());
+        backend.map_mmio(fill_peripheral.into())?;
+        read_src.0.try_send(0x01)?;
+        backend.load_bytes(&fill_base, &mut dst)?;
+        assert_eq!(dst, [0x01, 0xaa, 0xaa, 0xaa],
+            "read bytes do not match expected: {:#x?}", dst);
+
+        assert_eq!(end_of_input.take_stall(), None, "fill does not stall");
+
+        end_of_input.set(EndOfInput::Stall);
+        backend.load_bytes(&fill_base, &mut dst)?;
+        assert_eq!(dst, [0; 4],
+            "read bytes do not match expected: {:#x?}", dst);
+        assert_eq!(end_of_input.take_stall(), Some(fill_base));
+        assert_eq!(end_of_input.take_stall(), None);
         Ok(())
     }
 }
\ No newline at end of file
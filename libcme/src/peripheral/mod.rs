@@ -66,6 +66,13 @@ pub trait PeripheralState: DynClone {
     fn write_bytes(&mut self, address: &Address, src: &[u8], events: &mut VecDeque<Event>) -> Result<(), Error>;
     /// increment time for peripheral
     fn tick(&mut self) -> Result<Option<Event>, Error> { Ok(None) }
+    /// cycles until the next tick that may return an event, or None if
+    /// ticks never do. a time-dependent peripheral must implement this
+    /// and `skip`, since a sleeping processor skips the ticks in between.
+    fn next_event(&self) -> Option<usize> { None }
+    /// advance time by the given number of cycles, which end before
+    /// the next event
+    fn skip(&mut self, _cycles: usize) -> Result<(), Error> { Ok(()) }
 }
 clone_trait_object!(PeripheralState);
 
@@ -94,6 +101,14 @@ impl Peripheral {
         self.state.tick()
     }
 
+    pub fn next_event(&self) -> Option<usize> {
+        self.state.next_event()
+    }
+
+    pub fn skip(&mut self, cycles: usize) -> Result<(), Error> {
+        self.state.skip(cycles)
+    }
+
     pub fn read_bytes(&mut self,
         address: &Address,
         dst: &mut [u8],
@@ -10,11 +10,13 @@ use std::hash::BuildHasherDefault;
 // use itertools::Itertools;
 use thiserror::Error;
 use ahash;
-use nohash::IntMap;
+use nohash::{IntMap, IntSet};
 use iset::IntervalMap;
 use petgraph::{
     Directed,
-    graphmap::GraphMap,
+    Direction,
+    algo::dominators,
+    graphmap::{DiGraphMap, GraphMap},
 };
 
 pub use fugue_core::prelude::*;
@@ -26,6 +28,9 @@ use super::types::*;
 
 type AHashState = BuildHasherDefault<ahash::AHasher>;
 
+/// pseudo-node that function exits lead to in post-dominator analysis
+const EXIT: u64 = u64::MAX;
+
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -190,6 +195,53 @@ impl<'arena> CFGraph<'arena> {
         let (_, blk_address) = self.blkmap.overlap(address).next()?;
         self.blocks.get_mut(blk_address)
     }
+
+    /// get the immediate post-dominator of a block within its function,
+    /// with additional edges from the block to `successors`
+    /// 
+    /// only the part of the function reachable from the block is
+    /// considered. calls continue at their return site and returns leave
+    /// the function. nodes without successors are treated as exits, so a
+    /// block that can reach an unexplored part of the function has no
+    /// post-dominator.
+    pub fn immediate_post_dominator(&self, block: u64, successors: &[u64]) -> Option<u64> {
+        let mut reversed: DiGraphMap<u64, ()> = DiGraphMap::new();
+        let mut visited: IntSet<u64> = IntSet::default();
+        let mut worklist = vec![block];
+        visited.insert(block);
+        while let Some(node) = worklist.pop() {
+            let mut local_successors = self._local_successors(node);
+            if node == block {
+                local_successors.extend_from_slice(successors);
+            }
+            if local_successors.is_empty() {
+                reversed.add_edge(EXIT, node, ());
+            }
+            for child in local_successors {
+                reversed.add_edge(child, node, ());
+                if child != EXIT && visited.insert(child) {
+                    worklist.push(child);
+                }
+            }
+        }
+        dominators::simple_fast(&reversed, EXIT)
+            .immediate_dominator(block)
+            .filter(|&node| node != EXIT)
+    }
+
+    /// successors of a node within its function, with `EXIT` for
+    /// leaving the function
+    fn _local_successors(&self, node: u64) -> Vec<u64> {
+        self.graph.edges_directed(node, Direction::Outgoing)
+            .filter_map(|(_, child, flowtype)| match flowtype {
+                FlowType::Call | FlowType::ICall => {
+                    self.blocks.get(&node).map(|block| block.range().end & !1)
+                }
+                FlowType::Return => { Some(EXIT) }
+                _ => { Some(child) }
+            })
+            .collect()
+    }
 }
 
 impl<'arena> fmt::Debug for CFGraph<'arena> {
@@ -9,13 +9,14 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use petgraph::Direction;
+use nohash::IntMap;
 
 // use fugue_core::prelude::*;
 use fugue_ir::disassembly::{ Opcode, IRBuilderArena };
 
 use crate::types::*;
 use crate::utils::*;
-use crate::backend::Backend;
+use crate::backend::{armv6m, Backend};
 
 pub mod plugin;
 pub use plugin::*;
@@ -61,6 +62,9 @@ pub struct ProgramDB<'irb> {
     pub(crate) arena: &'irb IRBuilderArena,
     pub(crate) cfg: CFGraph<'irb>,
     plugin: PDBPlugin,
+    /// reconvergence points of conditional branches and the
+    /// cfg size they were computed at
+    reconvergence: ((usize, usize), IntMap<u64, Option<u64>>),
 }
 
 
@@ -81,8 +85,9 @@ impl<'irb> ProgramDB<'irb> {
         let cache = Arc::new(RwLock::new(TranslationCache::default()));
         let cfg = CFGraph::new_with(arena.inner());
         let plugin = PDBPlugin::default();
+        let reconvergence = Default::default();
 
-        Self { lang, platform, program, cache, arena, cfg, plugin }
+        Self { lang, platform, program, cache, arena, cfg, plugin, reconvergence }
     }
 
     pub fn add_plugin(&mut self, plugin: Box<dyn AnalysisPlugin>) {
@@ -109,6 +114,36 @@ impl<'irb> ProgramDB<'irb> {
             .map_err(Error::from)
     }
 
+    /// get the address execution reconverges at after a conditional branch
+    /// 
+    /// this is the immediate post-dominator of the branch's block within its
+    /// function in the cfg explored so far, with edges added to both of the
+    /// branch's successors. None if no post-dominator is known yet, e.g. if
+    /// a successor leads to code that hasn't been explored.
+    /// results are cached until the cfg grows.
+    pub fn reconvergence_point(&mut self, branch: impl Into<u64>, successors: [u64; 2]) -> Option<u64> {
+        let branch = branch.into();
+        let graph = self.cfg.graph();
+        let size = (graph.node_count(), graph.edge_count());
+        if self.reconvergence.0 != size {
+            self.reconvergence = (size, IntMap::default());
+        }
+        if let Some(point) = self.reconvergence.1.get(&branch) {
+            return *point;
+        }
+        let block_base = |address: u64| {
+            self.cfg.get_block(address & !1)
+                .map(|block| block.address() & !1)
+                .unwrap_or(address & !1)
+        };
+        let point = self.cfg.immediate_post_dominator(
+            block_base(branch),
+            &successors.map(block_base),
+        );
+        self.reconvergence.1.insert(branch, point);
+        point
+    }
+
     pub fn is_block_end(&self, address: impl Into<u64>) -> bool {
         let address = address.into();
         let Some(block) = self.cfg.get_block(address) else {
@@ -135,6 +170,10 @@ impl<'irb> ProgramDB<'irb> {
     pub fn backend(&self, builder: &LanguageBuilder) -> Result<impl Backend, Error> {
         self.platform.backend(builder).map_err(Error::from)
     }
+
+    pub fn backend_armv6m(&self, builder: &LanguageBuilder) -> Result<armv6m::Backend, Error> {
+        self.platform.backend_armv6m(builder).map_err(Error::from)
+    }
 }
 
 impl<'irb> ProgramDB<'irb> {
@@ -15,7 +15,7 @@ use fugue_core::prelude::*;
 use fugue_core::language::*;
 
 use crate::backend::armv7m::SysCtrlConfig;
-use crate::backend::{self, armv7m, Backend};
+use crate::backend::{self, armv7m, armv6m, Backend};
 use crate::types::Permission;
 use crate::utils::*;
 
@@ -73,6 +73,10 @@ pub struct Platform {
     pub(crate) fpu_present: bool,
     pub(crate) nvic_prio_bits: u8,
     pub(crate) vendor_systick_config: bool,
+    /// number of external interrupts, if given
+    pub(crate) num_interrupts: Option<usize>,
+    /// VTOR is implemented (only optional on armv6m)
+    pub(crate) vtor_present: bool,
     pub(crate) mem: Vec<MemRegion>,
     pub(crate) mmio: Vec<MmioRegion>,
 }
@@ -94,7 +98,7 @@ impl Platform {
     pub fn from_yaml(yaml: Yaml) -> Result<Self, Error> {
         let name = yaml["name"].as_str()
             .ok_or(Error::InvalidField("name"))?.into();
-        let cpu_name = yaml["cpu"]["name"].as_str()
+        let cpu_name: String = yaml["cpu"]["name"].as_str()
             .ok_or(Error::InvalidField("cpu_name"))?.into();
         let cpu_revision = yaml["cpu"]["revision"].as_str()
             .ok_or(Error::InvalidField("cpu_revision"))?.into();
@@ -129,6 +133,24 @@ impl Platform {
                 return Err(Error::InvalidField("vendor_sytick_config"));
             }
         };
+        let num_interrupts = match &yaml["cpu"]["deviceNumInterrupts"] {
+            Yaml::Integer(val) => { Some(*val as usize) }
+            Yaml::BadValue => { None }
+            variant => {
+                error!("invalid field {:?}", variant);
+                return Err(Error::InvalidField("device_num_interrupts"));
+            }
+        };
+        let vtor_present = match &yaml["cpu"]["vtorPresent"] {
+            Yaml::Integer(val) => { !(*val == 0) }
+            Yaml::Boolean(val) => { *val }
+            // cortex-m0 has no VTOR, it's implemented by default otherwise
+            Yaml::BadValue => { cpu_name != "CM0" }
+            variant => {
+                error!("invalid field {:?}", variant);
+                return Err(Error::InvalidField("vtor_present"));
+            }
+        };
 
         let mem_regions = yaml["mem"].as_hash()
             .ok_or(Error::InvalidField("mem"))?;
@@ -161,6 +183,8 @@ impl Platform {
             fpu_present,
             nvic_prio_bits,
             vendor_systick_config,
+            num_interrupts,
+            vtor_present,
             mem,
             mmio,
         })
@@ -169,7 +193,8 @@ impl Platform {
 
     pub fn lang(&self, builder: &LanguageBuilder) -> Result<Language, Error> {
         match self.cpu_name.as_str() {
-            "CM3" | "CM4" if self.cpu_endian.is_little() => {
+            "CM3" | "CM4" | "CM0" | "CM0PLUS" | "CM0+" | "CM1"
+            if self.cpu_endian.is_little() => {
                 builder.build("ARM:LE:32:Cortex", "default")
                     .map_err(Error::from)
             }
@@ -182,21 +207,41 @@ impl Platform {
             "CM3" | "CM4" if self.cpu_endian.is_little() => {
                 let scs_config = self._generate_scs_config();
                 let mut backend = armv7m::Backend::new_with(builder, scs_config)?;
-                for MemRegion {
-                    name: _,
-                    address,
-                    size,
-                    perms: _,
-                    description: _,
-                } in self.mem.iter() {
-                    backend.map_mem(address, *size)?;
-                }
+                self._map_mem(&mut backend)?;
                 Ok(backend)
             }
-            _ => { panic!("unsupported cpu: {}", self.cpu_name) }
+            _ => { Err(Error::Unsupported(format!("cpu: {}", self.cpu_name))) }
         }
     }
 
+    /// the backend for cortex-m0/m0+/m1 platforms
+    pub fn backend_armv6m(&self, builder: &LanguageBuilder) -> Result<armv6m::Backend, Error> {
+        match self.cpu_name.as_str() {
+            "CM0" | "CM0PLUS" | "CM0+" | "CM1" if self.cpu_endian.is_little() => {
+                let scs_config = self._generate_scs_config();
+                let mut backend = armv6m::Backend::new_with(builder, scs_config)?
+                    .with_vtor(self.vtor_present)
+                    .with_mpu(self.mpu_present);
+                self._map_mem(&mut backend)?;
+                Ok(backend)
+            }
+            _ => { Err(Error::Unsupported(format!("cpu: {}", self.cpu_name))) }
+        }
+    }
+
+    fn _map_mem(&self, backend: &mut impl Backend) -> Result<(), Error> {
+        for MemRegion {
+            name: _,
+            address,
+            size,
+            perms: _,
+            description: _,
+        } in self.mem.iter() {
+            backend.map_mem(address, *size)?;
+        }
+        Ok(())
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -229,6 +274,14 @@ impl Platform {
         self.vendor_systick_config
     }
 
+    pub fn num_interrupts(&self) -> Option<usize> {
+        self.num_interrupts
+    }
+
+    pub fn vtor_present(&self) -> bool {
+        self.vtor_present
+    }
+
     pub fn mem(&self) -> &[MemRegion] {
         &self.mem[..]
     }
@@ -240,8 +293,12 @@ impl Platform {
 
 impl Platform {
     fn _generate_scs_config(&self) -> Option<SysCtrlConfig> {
-        // TODO
-        None
+        // TODO: remaining implementation-defined reset values
+        let num_interrupts = self.num_interrupts?;
+        let ictr = armv7m::ICTR::from_num_interrupts(num_interrupts);
+        let config = SysCtrlConfig::default()
+            .with_reset(armv7m::SCRegType::ICTR, ictr.into_bits());
+        Some(config)
     }
 }
 
@@ -100,4 +100,91 @@ pub mod programs {
         0xde, 0xc0, 0xad, 0x0b, // 40: .word 0x0badc0de 
     ];
 
+    // exclusive loads and stores around a supervisor call,
+    // with the monitored address in r1 and the stored value in r2
+    pub(crate) static EXCLUSIVE_MONITOR_TEST: &[u8] = &[
+        // 00000000 <vectors>:
+        0x00, 0x10, 0x00, 0x00,  //  0: .word 0x1000 (sp)
+        0x41, 0x00, 0x00, 0x00,  //  4: .word 0x41 (reset)
+        0x00, 0x00, 0x00, 0x00,  //  8
+        0x00, 0x00, 0x00, 0x00,  //  c
+        0x00, 0x00, 0x00, 0x00,  // 10
+        0x00, 0x00, 0x00, 0x00,  // 14
+        0x00, 0x00, 0x00, 0x00,  // 18
+        0x00, 0x00, 0x00, 0x00,  // 1c
+        0x00, 0x00, 0x00, 0x00,  // 20
+        0x00, 0x00, 0x00, 0x00,  // 24
+        0x00, 0x00, 0x00, 0x00,  // 28
+        0x6d, 0x00, 0x00, 0x00,  // 2c: .word 0x6d (svcall)
+        0x00, 0x00, 0x00, 0x00,  // 30
+        0x00, 0x00, 0x00, 0x00,  // 34
+        0x00, 0x00, 0x00, 0x00,  // 38
+        0x00, 0x00, 0x00, 0x00,  // 3c
+
+        // 00000040 <_start>:
+        0x51, 0xe8, 0x00, 0x0f,  // 40: ldrex r0, [r1]
+        0x0a, 0x60,              // 44: str   r2, [r1, #0]
+        0x41, 0xe8, 0x00, 0x23,  // 46: strex r3, r2, [r1]
+        0x51, 0xe8, 0x00, 0x0f,  // 4a: ldrex r0, [r1]
+        0x41, 0xe8, 0x00, 0x24,  // 4e: strex r4, r2, [r1]
+        0x51, 0xe8, 0x00, 0x0f,  // 52: ldrex r0, [r1]
+        0xbf, 0xf3, 0x2f, 0x8f,  // 56: clrex
+        0x41, 0xe8, 0x00, 0x25,  // 5a: strex r5, r2, [r1]
+        0x51, 0xe8, 0x00, 0x0f,  // 5e: ldrex r0, [r1]
+        0x00, 0xdf,              // 62: svc   0
+        0x41, 0xe8, 0x00, 0x27,  // 64: strex r7, r2, [r1]
+
+        // 00000068 <exit>:
+        0xfe, 0xe7,              // 68: b.n   68 <exit>
+        0x00, 0xbf,              // 6a: nop
+
+        // 0000006c <svc_handler>:
+        0x41, 0xe8, 0x00, 0x26,  // 6c: strex r6, r2, [r1]
+        0x51, 0xe8, 0x00, 0x0f,  // 70: ldrex r0, [r1]
+        0x70, 0x47,              // 74: bx    lr
+    ];
+
+    /// armv7m-only instructions and BASEPRI writes are undefined on armv6m,
+    /// the hardfault handler counts them in r3 and skips over them
+    pub(crate) static ARMV6M_UNDEFINED_TEST: &[u8] = &[
+        // 00000000 <vectors>:
+        0x00, 0x10, 0x00, 0x00,  //  0: .word 0x1000 (sp)
+        0x41, 0x00, 0x00, 0x00,  //  4: .word 0x41 (reset)
+        0x00, 0x00, 0x00, 0x00,  //  8
+        0x61, 0x00, 0x00, 0x00,  //  c: .word 0x61 (hardfault)
+        0x00, 0x00, 0x00, 0x00,  // 10
+        0x00, 0x00, 0x00, 0x00,  // 14
+        0x00, 0x00, 0x00, 0x00,  // 18
+        0x00, 0x00, 0x00, 0x00,  // 1c
+        0x00, 0x00, 0x00, 0x00,  // 20
+        0x00, 0x00, 0x00, 0x00,  // 24
+        0x00, 0x00, 0x00, 0x00,  // 28
+        0x00, 0x00, 0x00, 0x00,  // 2c
+        0x00, 0x00, 0x00, 0x00,  // 30
+        0x00, 0x00, 0x00, 0x00,  // 34
+        0x00, 0x00, 0x00, 0x00,  // 38
+        0x00, 0x00, 0x00, 0x00,  // 3c
+
+        // 00000040 <_start>:
+        0x00, 0x23,              // 40: movs  r3, #0
+        0x91, 0xfb, 0xf2, 0xf0,  // 42: sdiv  r0, r1, r2
+        0x20, 0x21,              // 46: movs  r1, #32
+        0x81, 0xf3, 0x11, 0x88,  // 48: msr   BASEPRI, r1
+
+        // 0000004c <exit>:
+        0xfe, 0xe7,              // 4c: b.n   4c <exit>
+        0x00, 0xbf,              // 4e: nop
+        0x00, 0x00, 0x00, 0x00,  // 50
+        0x00, 0x00, 0x00, 0x00,  // 54
+        0x00, 0x00, 0x00, 0x00,  // 58
+        0x00, 0x00, 0x00, 0x00,  // 5c
+
+        // 00000060 <hardfault_handler>:
+        0x01, 0x33,              // 60: adds  r3, #1
+        0x06, 0x98,              // 62: ldr   r0, [sp, #24]
+        0x04, 0x30,              // 64: adds  r0, #4
+        0x06, 0x90,              // 66: str   r0, [sp, #24]
+        0x70, 0x47,              // 68: bx    lr
+    ];
+
 }
\ No newline at end of file
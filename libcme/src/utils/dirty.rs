@@ -0,0 +1,81 @@
+//! dirty.rs
+//!
+//! dirty page tracking utilities for snapshot/restore
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// size of a tracked page in bytes
+pub const PAGE_SIZE: usize = 0x400;
+
+static SNAPSHOT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// get a unique id for a new snapshot
+///
+/// ids are used to check that dirty pages are being restored
+/// from the same snapshot they were tracked against.
+pub fn next_snapshot_id() -> usize {
+    SNAPSHOT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// tracks the pages of a fixed-size region that have been
+/// written since the last snapshot or restore
+#[derive(Debug, Clone, Default)]
+pub struct DirtyPages {
+    len: usize,
+    flags: Box<[bool]>,
+    pages: Vec<usize>,
+}
+
+impl DirtyPages {
+    pub fn new(len: usize) -> Self {
+        let npages = len.div_ceil(PAGE_SIZE);
+        let flags = vec![false; npages].into_boxed_slice();
+        Self { len, flags, pages: vec![] }
+    }
+
+    /// mark the pages overlapping [offset; size] as dirty
+    #[inline(always)]
+    pub fn mark(&mut self, offset: usize, size: usize) {
+        if size == 0 {
+            return;
+        }
+        let first = offset / PAGE_SIZE;
+        let last = ((offset + size - 1) / PAGE_SIZE).min(self.flags.len().saturating_sub(1));
+        for page in first..=last {
+            if !self.flags[page] {
+                self.flags[page] = true;
+                self.pages.push(page);
+            }
+        }
+    }
+
+    /// mark every page as dirty
+    pub fn mark_all(&mut self) {
+        self.mark(0, self.len);
+    }
+
+    /// clear all dirty pages
+    pub fn clear(&mut self) {
+        for page in self.pages.drain(..) {
+            self.flags[page] = false;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    pub fn count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// iterate over the byte ranges of the dirty pages
+    pub fn ranges(&self) -> impl Iterator<Item=Range<usize>> + use<'_> {
+        self.pages.iter()
+            .map(|&page| {
+                let start = page * PAGE_SIZE;
+                let end = (start + PAGE_SIZE).min(self.len);
+                start..end
+            })
+    }
+}
@@ -13,4 +13,6 @@ pub use trace::*;
 pub mod iter;
 pub use iter::*;
 pub mod render;
-pub use render::*;
\ No newline at end of file
+pub use render::*;
+pub mod dirty;
+pub use dirty::*;
\ No newline at end of file
@@ -0,0 +1,113 @@
+//! common.rs
+//!
+//! setup shared by the uart-jump demos
+//!
+//! builds the programdb and context for the uart-jump sample, maps the
+//! nrf52 peripherals it touches, and initializes the context from the
+//! vector table. the demos differ only in how they drive the fuzzer.
+use std::fs;
+
+use anyhow;
+use crossbeam::channel::{unbounded, Receiver, Sender};
+use libafl::executors::ExitKind;
+
+use libcme::prelude::*;
+use libcme::peripheral::channel::{Access, EndOfInputPolicy};
+use ttff::prelude::*;
+
+use crate::{ficr, uicr, uart, gpio};
+
+/// the uart-jump sample binary
+pub const UART_JUMP_ELF: &str = "examples/samples/uart-jump/uart-jump.elf";
+
+/// a loaded and initialized uart-jump target
+pub struct UartJumpTarget<'irb> {
+    pub pdb: ProgramDB<'irb>,
+    pub context: dtt::Context<'static>,
+    pub access_log: (Sender<Access>, Receiver<Access>),
+    pub rx_channel: (Sender<u8>, Receiver<u8>),
+    pub tx_channel: (Sender<u8>, Receiver<u8>),
+}
+
+/// load the uart-jump sample with hitcount coverage written to `covmap`
+/// and uart reads tainted
+pub fn uart_jump_target<'irb>(
+    irb: &'irb IRBuilderArena,
+    covmap: CovMap,
+    end_of_input: EndOfInputPolicy,
+) -> Result<UartJumpTarget<'irb>, anyhow::Error> {
+    info!("reading program binary...");
+    let bytes = fs::read(UART_JUMP_ELF)?;
+    let elf_bytes = ElfBytes::minimal_parse(bytes.as_slice())?;
+    let program = Program::new_from_elf(irb.inner(), elf_bytes)?;
+
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new("data/processors")?;
+
+    info!("building programdb...");
+    let platform = Platform::from_path("data/nrf52/nrf52.yml")?;
+    let mut pdb = ProgramDB::new_with(&builder, program, platform, irb);
+
+    let hc_plugin = HcPlugin::new(covmap);
+    pdb.add_plugin(Box::new(hc_plugin));
+
+    info!("building context...");
+    let backend = pdb.backend(&builder)?;
+    let mut context = dtt::Context::from_backend(backend)?;
+
+    info!("mapping peripherals...");
+    let ficr_peripheral = ficr::FICRState::new_with(ficr::FICR_BASE);
+    let uicr_peripheral = uicr::UICRState::new_with(uicr::UICR_BASE);
+    let gpio_peripheral = gpio::GPIOState::new_with(gpio::P0_BASE);
+    context.map_mmio(Peripheral::new_with(Box::new(ficr_peripheral)), None)?;
+    context.map_mmio(Peripheral::new_with(Box::new(uicr_peripheral)), None)?;
+    context.map_mmio(Peripheral::new_with(Box::new(gpio_peripheral)), None)?;
+
+    let access_log = unbounded();
+    let tx_channel = unbounded();
+    let rx_channel = unbounded();
+    let uart_peripheral = uart::UARTState::new_with(
+        access_log.clone(), rx_channel.clone(), tx_channel.clone())
+        .with_end_of_input(end_of_input);
+    context.map_mmio(
+        Peripheral::new_with(Box::new(uart_peripheral)),
+        Some(dtt::Tag::from(tag::TAINTED_VAL)),
+    )?;
+
+    for mapped_range in context.backend().mmap().mapped() {
+        match mapped_range {
+            MappedRange::Mem(range) => {
+                info!("mapped mem: [{:#x}, {:#x}]",
+                    range.start.offset(), range.end.offset());
+            }
+            MappedRange::Mmio(range) => {
+                info!("mapped mmio: [{:#x}, {:#x}]",
+                    range.start.offset(), range.end.offset());
+            }
+        }
+    }
+
+    info!("loading program binary...");
+    setup::load_program(&mut context, pdb.program())?;
+
+    info!("initializing context...");
+    setup::init_from_vector_table(&mut context)?;
+
+    Ok(UartJumpTarget { pdb, context, access_log, rx_channel, tx_channel })
+}
+
+/// halt at `_exit`
+///
+/// we can locate obvious exit functions statically as self loops.
+/// a more sophisticated method would be to check if interrupts are
+/// disabled and only halt then, but this sample has no interrupts.
+pub fn halt_on_exit(
+    evaluator: &dtt::Evaluator,
+    _pdb: &ProgramDB,
+    _context: &mut dtt::Context,
+) -> Option<ExitKind> {
+    match evaluator.pc.address().offset() {
+        0xb1c => { info!("_exit reached"); Some(ExitKind::Ok) }
+        _ => { None }
+    }
+}
@@ -2,7 +2,6 @@
 //! 
 use std::fs;
 use std::io::BufWriter;
-use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -59,62 +58,6 @@ pub mod gpio;
 const COVMAP_SIZE: usize = 0x2000;
 static mut COVMAP: [u8; COVMAP_SIZE] = [0u8; COVMAP_SIZE];
 
-use crossbeam::channel::{
-    Sender,
-    Receiver,
-};
-use ttff::policy::address::{FrameUpdate, FrameStart};
-
-#[derive(Debug)]
-pub struct CallStackPlugin {
-    pub callstack: VecDeque<Address>,
-    pub call_channel: (Sender<FrameUpdate>, Receiver<FrameUpdate>),
-}
-
-impl Default for CallStackPlugin {
-    fn default() -> Self {
-        let callstack = VecDeque::default();
-        let call_channel = unbounded();
-        Self { callstack, call_channel }
-    }
-}
-
-impl EvalPlugin for CallStackPlugin {
-
-    #[instrument(skip_all)]
-    fn post_insn_cb<'irb, 'backend>(
-        &mut self,
-        _loc: &Location,
-        _insn: &Insn<'irb>,
-        flow: &Flow,
-        context: &mut dtt::Context<'backend>,
-        _pdb: &mut ProgramDB<'irb>,
-    ) -> Result<(), dtt::plugin::Error> {
-        match flow.flowtype {
-            FlowType::Call
-            | FlowType::ICall => {
-                let target = flow.target.unwrap().address();
-                debug!("calling {:#x} from {:#x}",
-                    target.offset(),
-                    self.callstack.back().unwrap_or(&0u64.into()).offset());
-                self.callstack.push_back(target);
-                let frame_start = FrameStart {
-                    pc: target,
-                    sp: context.backend().read_sp().unwrap(),
-                };
-                let _ = self.call_channel.0.try_send(FrameUpdate::Call(frame_start));
-                Ok(())
-            }
-            FlowType::Return => {
-                self.callstack.pop_back().unwrap();
-                let _ = self.call_channel.0.try_send(FrameUpdate::Return);
-                Ok(())
-            }
-            _ => { Ok(()) }
-        }
-    }
-}
-
 pub fn main() -> Result<(), anyhow::Error> {
     let (global_sub, _guard) = compact_file_logger(
         "examples/uart-address/uart-address.log",
@@ -254,7 +197,7 @@ pub fn main() -> Result<(), anyhow::Error> {
     let dump_cfg = &mut |
         _evaluator: &mut dtt::Evaluator,
         pdb: &mut ProgramDB,
-        _context: dtt::Context,
+        _context: &mut dtt::Context,
         result: Result<ExitKind, libafl::Error>,
     | {
         let path = format!("examples/uart-address/uart-address.simple-cfg.json");
@@ -232,7 +232,7 @@ pub fn main() -> Result<(), anyhow::Error> {
     let dump_cfg = &mut |
         _evaluator: &mut dtt::Evaluator,
         pdb: &mut ProgramDB,
-        _context: dtt::Context,
+        _context: &mut dtt::Context,
         result: Result<ExitKind, libafl::Error>,
     | {
         let path = format!("examples/uart-int-overflow/uart-int-overflow.simple-cfg.json");
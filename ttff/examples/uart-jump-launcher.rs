@@ -0,0 +1,205 @@
+//! uart-jump-launcher demo
+//!
+//! multi-process version of the uart-jump demo.
+//!
+//! each libafl client builds its own arena, programdb, context, and
+//! shared memory coverage map inside the client closure, so nothing is
+//! shared between clients except through the llmp broker.
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow;
+use libafl_bolts::{
+    core_affinity::Cores,
+    rands::StdRand,
+    shmem::{ShMemProvider, StdShMemProvider},
+    tuples::tuple_list,
+    nonzero,
+};
+use libafl::{
+    self,
+    corpus::{Corpus, InMemoryCorpus, OnDiskCorpus},
+    events::{
+        ClientDescription,
+        EventConfig,
+        Launcher,
+    },
+    executors::{ExitKind, WithObservers},
+    feedback_and_fast, feedback_or, feedback_or_fast,
+    feedbacks::{CrashFeedback, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
+    fuzzer::{Fuzzer, StdFuzzer},
+    generators::RandBytesGenerator,
+    inputs::BytesInput,
+    monitors::MultiMonitor,
+    mutators::{
+        havoc_mutations::havoc_mutations,
+        scheduled::StdScheduledMutator,
+    },
+    observers::{
+        CanTrack,
+        TimeObserver,
+    },
+    schedulers::QueueScheduler,
+    stages::StdMutationalStage,
+    state::{HasCorpus, StdState},
+};
+
+use libcme::prelude::*;
+use libcme::peripheral::channel::EndOfInputPolicy;
+use ttff::prelude::*;
+
+pub mod ficr;
+pub mod uicr;
+pub mod uart;
+pub mod gpio;
+mod common;
+
+const COVMAP_SIZE: usize = 0x2000;
+const BROKER_PORT: u16 = 1337;
+
+
+/// convert errors from client setup into libafl errors
+fn client_err(err: impl fmt::Debug) -> libafl::Error {
+    libafl::Error::unknown(format!("{err:?}"))
+}
+
+pub fn main() -> Result<(), anyhow::Error> {
+    // configure test fuzz run limits
+    let limit = Some(1000000 as usize);
+    let cores = Cores::from_cmdline("all")?;
+
+    let shmem_provider = StdShMemProvider::new()?;
+    let monitor = MultiMonitor::new(|s| println!("{s}"));
+
+    let mut run_client = |
+        state: Option<_>,
+        mut manager,
+        client: ClientDescription,
+    | -> Result<(), libafl::Error> {
+        // each client runs in its own process, so each needs its own logger
+        let (global_sub, _guard) = compact_file_logger(
+            &format!("examples/uart-jump/uart-jump-launcher.{}.log", client.id()),
+            Level::INFO,
+        );
+        set_global_default(global_sub).map_err(client_err)?;
+
+        let irb = IRBuilderArena::with_capacity(0x10000);
+        let mut shmem_provider = StdShMemProvider::new()?;
+        let mut covmap = ShMemCovMap::new(&mut shmem_provider, COVMAP_SIZE)?;
+
+        let common::UartJumpTarget {
+            pdb,
+            mut context,
+            access_log,
+            rx_channel,
+            tx_channel,
+        } = common::uart_jump_target(&irb, covmap.covmap(), EndOfInputPolicy::default())
+            .map_err(client_err)?;
+
+        info!("building taint policy...");
+        let lang = Arc::new(pdb.lang().clone());
+        let policy = ttff::policy::TaintedJumpPolicy::new_with(lang);
+
+        info!("building evaluator...");
+        let mut evaluator = dtt::Evaluator::new_with_policy(Box::new(policy));
+        (evaluator.pc, evaluator.pc_tag) = context.read_pc()
+            .map(|(pc, tag)| (Location::from(pc), tag))
+            .map_err(client_err)?;
+
+        info!("building dtt executor...");
+        let halt_on_exit = &mut common::halt_on_exit;
+        let halt_cb = Some(sc::HaltCallback {
+            callback: halt_on_exit,
+        });
+
+        let mut dtt_executor = sc::DttExecutor::new_with(
+            evaluator,
+            context,
+            pdb,
+            limit,
+            None,
+            halt_cb,
+            None,
+            None,
+            access_log.clone(),
+            rx_channel.clone(),
+            tx_channel.clone(),
+        );
+
+        info!("running to fuzzing entry point...");
+        dtt_executor.run_to_entry(&EntryPoint::from("main"), limit)
+            .map_err(client_err)?;
+
+        info!("building libafl observers, feedbacks, and objective...");
+        let edges_observer = covmap.observer("edges").track_indices();
+        let time_observer = TimeObserver::new("time");
+        let map_feedback = MaxMapFeedback::new(&edges_observer);
+        let mut feedback = feedback_or!(
+            // maximize coverage
+            map_feedback,
+            TimeFeedback::new(&time_observer),
+        );
+        let exit_cause_observer = dtt_executor.exit_cause_observer("exit_cause");
+        let mut objective = feedback_and_fast!(
+            feedback_or_fast!(
+                CrashFeedback::new(),
+                TimeoutFeedback::new(),
+            ),
+            // only keep one objective per distinct exit cause
+            ExitCauseFeedback::new_unique(&exit_cause_observer),
+        );
+
+        info!("building stages...");
+        let mutator = StdScheduledMutator::new(havoc_mutations());
+        let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+        info!("building libafl state...");
+        // the queue is kept in memory since testcases are shared through the broker
+        let crash_corpus_path = PathBuf::from("examples/uart-jump/crashes");
+        let mut state = match state {
+            Some(state) => { state }
+            None => {
+                StdState::new(
+                    StdRand::with_seed(client.id() as u64),
+                    InMemoryCorpus::<BytesInput>::new(),
+                    OnDiskCorpus::new(crash_corpus_path)?,
+                    &mut feedback,
+                    &mut objective,
+                )?
+            }
+        };
+
+        info!("building scheduler, and fuzzer...");
+        let scheduler = QueueScheduler::new();
+        let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+
+        let mut executor = WithObservers::new(
+            dtt_executor,
+            tuple_list!(edges_observer, time_observer, exit_cause_observer),
+        );
+
+        if state.corpus().count() < 1 {
+            let mut generator = RandBytesGenerator::new(nonzero!(0x10000));
+            state.generate_initial_inputs(
+                &mut fuzzer, &mut executor, &mut generator, &mut manager, 8)?;
+        }
+
+        fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut manager)
+    };
+
+    match Launcher::builder()
+        .shmem_provider(shmem_provider)
+        .configuration(EventConfig::from_name("default"))
+        .monitor(monitor)
+        .run_client(&mut run_client)
+        .cores(&cores)
+        .broker_port(BROKER_PORT)
+        .build()
+        .launch()
+    {
+        Err(libafl::Error::ShuttingDown) => { println!("fuzzer stopped by user."); Ok(()) }
+        Err(err) => { Err(anyhow::Error::msg(format!("failed to run launcher: {err:?}"))) }
+        _ => { Ok(()) }
+    }
+}
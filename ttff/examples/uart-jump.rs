@@ -7,7 +7,6 @@ use std::sync::Arc;
 use std::io::BufWriter;
 
 use anyhow;
-use crossbeam::channel::unbounded;
 use libafl_bolts::{
     rands::StdRand,
     tuples::tuple_list,
@@ -24,7 +23,7 @@ use libafl::{
         // ShutdownSignalData,
     },
     executors::{ExitKind, WithObservers},
-    feedback_or, feedback_or_fast,
+    feedback_and_fast, feedback_or, feedback_or_fast,
     feedbacks::{CrashFeedback, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
     fuzzer::{Fuzzer, StdFuzzer},
     generators::RandBytesGenerator,
@@ -49,12 +48,14 @@ use libafl::{
 };
 
 use libcme::prelude::*;
+use libcme::peripheral::channel::{EndOfInput, EndOfInputPolicy};
 use ttff::prelude::*;
 
 pub mod ficr;
 pub mod uicr;
 pub mod uart;
 pub mod gpio;
+mod common;
 
 const COVMAP_SIZE: usize = 0x2000;
 static mut COVMAP: [u8; COVMAP_SIZE] = [0u8; COVMAP_SIZE];
@@ -114,76 +115,15 @@ pub fn main() -> Result<(), anyhow::Error> {
         COVMAP_SIZE,
     );
 
-    info!("reading program binary...");
-    let path = "examples/samples/uart-jump/uart-jump.elf";
-    let bytes = fs::read(path)?;
-    let elf_bytes = ElfBytes::minimal_parse(bytes.as_slice())?;
-    let program = Program::new_from_elf(irb.inner(), elf_bytes)?;
-
-    info!("creating language builder...");
-    let builder = LanguageBuilder::new("data/processors")?;
-    
-    info!("building programdb...");
-    let platform = Platform::from_path("data/nrf52/nrf52.yml")?;
-    let mut pdb = ProgramDB::new_with(&builder, program, platform, &irb);
-
-    let hc_plugin = HcPlugin::new(covmap);
-    pdb.add_plugin(Box::new(hc_plugin));
-
-    info!("building context...");
-    let backend = pdb.backend(&builder)?;
-    let mut context = dtt::Context::from_backend(backend)?;
-
-    info!("mapping peripherals...");
-    let ficr_peripheral = ficr::FICRState::new_with(ficr::FICR_BASE);
-    let uicr_peripheral = uicr::UICRState::new_with(uicr::UICR_BASE);
-    let gpio_peripheral = gpio::GPIOState::new_with(gpio::P0_BASE);
-    context.map_mmio(Peripheral::new_with(Box::new(ficr_peripheral)), None)?;
-    context.map_mmio(Peripheral::new_with(Box::new(uicr_peripheral)), None)?;
-    context.map_mmio(Peripheral::new_with(Box::new(gpio_peripheral)), None)?;
-    
-    let access_log = unbounded();
-    let tx_channel = unbounded();
-    let rx_channel = unbounded();
-    let uart_peripheral = uart::UARTState::new_with(
-        access_log.clone(), rx_channel.clone(), tx_channel.clone());
-    context.map_mmio(
-        Peripheral::new_with(Box::new(uart_peripheral)),
-        Some(dtt::Tag::from(tag::TAINTED_VAL)),
-    )?;
-
-    for mapped_range in context.backend().mmap().mapped() {
-        match mapped_range {
-            MappedRange::Mem(range) => {
-                info!("mapped mem: [{:#x}, {:#x}]",
-                    range.start.offset(), range.end.offset());
-            }
-            MappedRange::Mmio(range) => {
-                info!("mapped mmio: [{:#x}, {:#x}]",
-                    range.start.offset(), range.end.offset());
-            }
-        }
-    }
-
-    info!("loading program binary...");
-    for segment in pdb.program().loadable_segments() {
-        context.store_bytes(
-            segment.p_paddr(),
-            segment.data(),
-            &dtt::Tag::from(tag::UNACCESSED),
-        )?;
-    }
-
-    info!("initializing context...");
-    let mut stack_bytes = [0; 4];
-    context.load_bytes(0u64, &mut stack_bytes)?;
-    let stack_top = u32::from_le_bytes(stack_bytes);
-    context.write_sp(stack_top, &dtt::Tag::from(tag::ACCESSED))?;
-
-    let mut entry_bytes = [0u8; 4];
-    context.load_bytes(4u64, &mut entry_bytes)?;
-    let entry = u32::from_le_bytes(entry_bytes);
-    context.write_pc(entry, &dtt::Tag::from(tag::ACCESSED))?;
+    // inputs that run out are finished, not hung
+    let end_of_input = EndOfInputPolicy::new(EndOfInput::Exit);
+    let common::UartJumpTarget {
+        pdb,
+        mut context,
+        access_log,
+        rx_channel,
+        tx_channel,
+    } = common::uart_jump_target(&irb, covmap, end_of_input.clone())?;
 
     info!("building taint policy...");
     let lang = Arc::new(pdb.lang().clone());
@@ -197,19 +137,7 @@ pub fn main() -> Result<(), anyhow::Error> {
         .map(|(pc, tag)| (Location::from(pc), tag))?;
 
     info!("building dtt executor...");
-    let halt_on_exit = &mut |
-        evaluator: &dtt::Evaluator,
-        _pdb: &ProgramDB,
-        _context: &mut dtt::Context,
-    | {
-        match evaluator.pc.address().offset() {
-            // we can locate obvious exit functions statically as self loops.
-            // a more sophisticated method would be to check if interrupts are
-            // disabled and only halt then, but this sample has no interrupts.
-            0xb1c => { info!("_exit reached"); Some(ExitKind::Ok) }
-            _ => { None }
-        }
-    };
+    let halt_on_exit = &mut common::halt_on_exit;
     let halt_cb = Some(sc::HaltCallback {
         callback: halt_on_exit,
     });
@@ -232,7 +160,7 @@ pub fn main() -> Result<(), anyhow::Error> {
     let dump_cfg = &mut |
         _evaluator: &mut dtt::Evaluator,
         pdb: &mut ProgramDB,
-        _context: dtt::Context,
+        _context: &mut dtt::Context,
         result: Result<ExitKind, libafl::Error>,
     | {
         let path = format!("examples/uart-jump/uart-jump.simple-cfg.json");
@@ -249,7 +177,7 @@ pub fn main() -> Result<(), anyhow::Error> {
     };
     let post_exec_cb = Some(sc::PostExecCallback { callback: dump_cfg });
 
-    let dtt_executor = sc::DttExecutor::new_with(
+    let mut dtt_executor = sc::DttExecutor::new_with(
         evaluator,
         context,
         pdb,
@@ -261,7 +189,11 @@ pub fn main() -> Result<(), anyhow::Error> {
         access_log.clone(),
         rx_channel.clone(),
         tx_channel.clone(),
-    );
+    ).with_end_of_input(end_of_input);
+
+    info!("running to fuzzing entry point...");
+    // skip SystemInit and start fuzzing from main
+    dtt_executor.run_to_entry(&EntryPoint::from("main"), limit)?;
 
     info!("building libafl observers, feedbacks, and objective...");
     let edges_observer = unsafe {
@@ -276,9 +208,14 @@ pub fn main() -> Result<(), anyhow::Error> {
         map_feedback,
         TimeFeedback::new(&time_observer),
     );
-    let mut objective = feedback_or_fast!(
-        CrashFeedback::new(),
-        TimeoutFeedback::new(),
+    let exit_cause_observer = dtt_executor.exit_cause_observer("exit_cause");
+    let mut objective = feedback_and_fast!(
+        feedback_or_fast!(
+            CrashFeedback::new(),
+            TimeoutFeedback::new(),
+        ),
+        // only keep one objective per distinct exit cause
+        ExitCauseFeedback::new_unique(&exit_cause_observer),
     );
 
     info!("building stages...");
@@ -313,7 +250,7 @@ pub fn main() -> Result<(), anyhow::Error> {
 
     let mut executor = WithObservers::new(
         dtt_executor,
-        tuple_list!(edges_observer, time_observer),
+        tuple_list!(edges_observer, time_observer, exit_cause_observer),
     );
 
     let mut generator = RandBytesGenerator::new(nonzero!(0x10000));
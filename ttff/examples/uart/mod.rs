@@ -5,7 +5,12 @@
 use std::fmt;
 use std::collections::VecDeque;
 
-use libcme::peripheral::channel::Access;
+use libcme::peripheral::channel::{
+    Access,
+    ChannelStateError,
+    EndOfInput,
+    EndOfInputPolicy,
+};
 use thiserror::Error;
 use bitfield_struct::bitfield;
 use crossbeam::channel::{
@@ -59,6 +64,7 @@ pub struct UARTState {
     access_log: (Sender<Access>, Receiver<Access>),
     rx_channel: (Sender<u8>, Receiver<u8>),
     tx_channel: (Sender<u8>, Receiver<u8>),
+    end_of_input: EndOfInputPolicy,
     // rxd_buf: [u8; 6]
 }
 
@@ -127,11 +133,18 @@ impl UARTState {
             access_log,
             rx_channel,
             tx_channel,
+            end_of_input: EndOfInputPolicy::default(),
             // rxd_buf,
         };
         state.reset()
     }
 
+    /// set the policy for RXD reads after the rx channel runs dry
+    pub fn with_end_of_input(mut self, end_of_input: EndOfInputPolicy) -> Self {
+        self.end_of_input = end_of_input;
+        self
+    }
+
     pub fn reset(mut self) -> Self {
         self.backing = Box::new([0u32; 0x400]);
         // self.rxd_buf = [0; 6];
@@ -223,11 +236,29 @@ impl UARTState {
                         size: 1,
                         is_write: false,
                     }).expect("failed to send over access log");
-                    let val = self.rx_channel.1.try_recv()
-                        .map_err(|e| {
+                    let val = match self.rx_channel.1.try_recv() {
+                        Ok(val) => { val }
+                        Err(TryRecvError::Empty) => {
+                            match self.end_of_input.get() {
+                                EndOfInput::Fill(val) => { val }
+                                EndOfInput::Stall => {
+                                    // leave rxdrdy clear so no data is reported
+                                    trace!("UART RXD stalled");
+                                    self.end_of_input.stall(address.into());
+                                    return Ok(());
+                                }
+                                EndOfInput::Exit | EndOfInput::Timeout => {
+                                    let err = ChannelStateError::Recv(
+                                        address.into(), TryRecvError::Empty);
+                                    return Err(err.into());
+                                }
+                            }
+                        }
+                        Err(e) => {
                             let e = UartError::from(e);
-                            peripheral::Error::State(e.into())
-                        })?;
+                            return Err(peripheral::Error::State(e.into()));
+                        }
+                    };
                     debug!("read byte {val:#x} from UART RXD");
                     dst[0] = val;
                     self.get_events_rxdrdy_mut().set_events_rxdrdy(true);
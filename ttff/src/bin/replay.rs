@@ -0,0 +1,291 @@
+//! replay.rs
+//!
+//! replay a single input through the dtt executor
+//!
+//! runs exactly one execution and prints the instruction trace,
+//! tainted locations, mmio access log, and exit reason.
+//!
+//! usage: replay <elf> <platform> <policy> <input> [options]
+//!
+//! options:
+//!   --source <name>        platform peripheral to treat as a taint source (repeatable)
+//!   --halt <symbol|addr>   exit normally when pc reaches this address (repeatable)
+//!   --entry <symbol|addr>  run to this entry point before replaying the input
+//!   --limit <cycles>       cycle limit (default: 1000000)
+//!   --end-of-input <eoi>   exit, timeout, stall, or fill:<byte> (default: timeout)
+//!   --bit-precise          track taint per bit through masking and shifts
+//!   --implicit-flows       taint values assigned under tainted branches
+//!   --hardfault-handler    run the firmware's hardfault handler instead of exiting
+//!   --semihosting          handle semihosting calls, reading from the input
+//!   --sandbox <dir>        directory for semihosting file i/o (implies --semihosting)
+//!   --processors <dir>     sleigh processor directory (default: data/processors)
+//!   --log <path>           write a trace level log to the given file
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow;
+use libafl_bolts::rands::StdRand;
+use libafl::{
+    corpus::InMemoryCorpus,
+    events::NopEventManager,
+    executors::{Executor, ExitKind},
+    feedbacks::ConstFeedback,
+    fuzzer::NopFuzzer,
+    inputs::BytesInput,
+    state::StdState,
+};
+
+use libcme::prelude::*;
+use libcme::peripheral::channel::{ChannelPeripheral, EndOfInputPolicy, GeneratedChannelPeripheral};
+use ttff::prelude::*;
+use ttff::harness::setup;
+
+const USAGE: &str = "usage: replay <elf> <platform> <policy> <input> \
+    [--source <name>]... [--halt <symbol|addr>]... [--entry <symbol|addr>] \
+    [--limit <cycles>] [--end-of-input <eoi>] [--bit-precise] [--implicit-flows] \
+    [--hardfault-handler] [--semihosting] [--sandbox <dir>] [--processors <dir>] [--log <path>]";
+
+#[derive(Debug)]
+struct Args {
+    elf: String,
+    platform: String,
+    policy: PolicyKind,
+    input: String,
+    sources: Vec<String>,
+    halts: Vec<EntryPoint>,
+    entry: Option<EntryPoint>,
+    limit: Option<usize>,
+    end_of_input: String,
+    bit_precise: bool,
+    implicit_flows: bool,
+    hardfault_handler: bool,
+    semihosting: bool,
+    sandbox: Option<PathBuf>,
+    processors: String,
+    log: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, anyhow::Error> {
+        let mut positional = vec![];
+        let mut sources = vec![];
+        let mut halts = vec![];
+        let mut entry = None;
+        let mut limit = Some(1000000);
+        let mut end_of_input = "timeout".to_string();
+        let mut bit_precise = false;
+        let mut implicit_flows = false;
+        let mut hardfault_handler = false;
+        let mut semihosting = false;
+        let mut sandbox = None;
+        let mut processors = "data/processors".to_string();
+        let mut log = None;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next().ok_or_else(|| anyhow::anyhow!("missing value for {arg}\n{USAGE}"))
+            };
+            match arg.as_str() {
+                "--source" => { sources.push(value()?); }
+                "--halt" => { halts.push(value()?.parse()?); }
+                "--entry" => { entry = Some(value()?.parse()?); }
+                "--limit" => { limit = Some(value()?.parse()?); }
+                "--end-of-input" => { end_of_input = value()?; }
+                "--bit-precise" => { bit_precise = true; }
+                "--implicit-flows" => { implicit_flows = true; }
+                "--hardfault-handler" => { hardfault_handler = true; }
+                "--semihosting" => { semihosting = true; }
+                "--sandbox" => {
+                    semihosting = true;
+                    sandbox = Some(value()?.into());
+                }
+                "--processors" => { processors = value()?; }
+                "--log" => { log = Some(value()?); }
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => { positional.push(arg); }
+            }
+        }
+        let [elf, platform, policy, input]: [String; 4] = positional.try_into()
+            .map_err(|_| anyhow::anyhow!("{USAGE}"))?;
+        let policy = policy.parse()?;
+        Ok(Self {
+            elf, platform, policy, input,
+            sources, halts, entry, limit, end_of_input, bit_precise, implicit_flows,
+            hardfault_handler, semihosting, sandbox, processors, log,
+        })
+    }
+}
+
+pub fn main() -> Result<(), anyhow::Error> {
+    let args = Args::parse()?;
+
+    let _guard = match args.log {
+        Some(ref path) => {
+            let (global_sub, guard) = compact_file_logger(path, Level::TRACE);
+            set_global_default(global_sub)?;
+            Some(guard)
+        }
+        None => { None }
+    };
+
+    let irb = IRBuilderArena::with_capacity(0x10000);
+
+    info!("reading program binary...");
+    let bytes = fs::read(&args.elf)?;
+    let elf_bytes = ElfBytes::minimal_parse(bytes.as_slice())?;
+    let program = Program::new_from_elf(irb.inner(), elf_bytes)?;
+
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new(&args.processors)?;
+
+    info!("building programdb...");
+    let platform = Platform::from_path(&args.platform)?;
+    let pdb = ProgramDB::new_with(&builder, program, platform, &irb);
+
+    info!("building context...");
+    let backend = pdb.backend(&builder)?;
+    let mut context = dtt::Context::from_backend(backend)?;
+
+    info!("mapping channel peripherals...");
+    let end_of_input = EndOfInputPolicy::new(setup::parse_end_of_input(&args.end_of_input)?);
+    let GeneratedChannelPeripheral {
+        access_log,
+        read_src,
+        write_dst,
+        peripheral,
+    } = ChannelPeripheral::new(Address::default(), 0x1000);
+    let peripheral = peripheral.with_end_of_input(end_of_input.clone());
+    let mappings = setup::mmio_mappings(pdb.platform(), None, &args.sources)?;
+    setup::map_channel_peripherals(&mut context, &peripheral, &mappings)?;
+    if args.semihosting {
+        setup::attach_semihosting(
+            &mut context,
+            &peripheral,
+            &read_src.1,
+            &end_of_input,
+            args.sandbox.as_deref(),
+        )?;
+    }
+
+    info!("loading program binary...");
+    setup::load_program(&mut context, pdb.program())?;
+
+    info!("initializing context...");
+    setup::init_from_vector_table(&mut context)?;
+
+    info!("building taint policy...");
+    let lang = Arc::new(pdb.lang().clone());
+    let (policy, policy_plugin) = args.policy.build(lang);
+
+    info!("building evaluator...");
+    let insn_trace = crossbeam::channel::unbounded();
+    let tainted = crossbeam::channel::unbounded();
+    let mut evaluator = dtt::Evaluator::new_with_policy(policy);
+    if let Some(plugin) = policy_plugin {
+        evaluator.add_plugin(plugin);
+    }
+    if args.implicit_flows {
+        evaluator.enable_implicit_flows();
+    }
+    if args.bit_precise {
+        evaluator.enable_bit_precise();
+    }
+    evaluator.add_plugin(Box::new(InsnTracePlugin::new_with(insn_trace.0.clone())));
+    evaluator.add_plugin(Box::new(TaintTracePlugin::new_with(tainted.0.clone())));
+    (evaluator.pc, evaluator.pc_tag) = context.read_pc()
+        .map(|(pc, tag)| (Location::from(pc), tag))?;
+
+    let halts = args.halts.iter()
+        .map(|halt| halt.resolve(pdb.program()).map(|address| address.offset()))
+        .collect::<Result<Vec<u64>, _>>()?;
+    let halt_on_exit = &mut |
+        evaluator: &dtt::Evaluator,
+        _pdb: &ProgramDB,
+        _context: &mut dtt::Context,
+    | {
+        let pc = evaluator.pc.address().offset();
+        halts.contains(&pc).then(|| {
+            info!("halt address {pc:#x} reached");
+            ExitKind::Ok
+        })
+    };
+    let halt_cb = Some(sc::HaltCallback {
+        callback: halt_on_exit,
+    });
+
+    info!("building dtt executor...");
+    let mut executor = sc::DttExecutor::new_with(
+        evaluator,
+        context,
+        pdb,
+        args.limit,
+        None,
+        halt_cb,
+        None,
+        None,
+        access_log.clone(),
+        read_src.clone(),
+        write_dst.clone(),
+    )
+    .with_end_of_input(end_of_input)
+    .with_crash_on_hardfault(!args.hardfault_handler);
+
+    if let Some(ref entry) = args.entry {
+        info!("running to entry point...");
+        executor.run_to_entry(entry, args.limit)?;
+        // only report what happens during the replayed execution
+        while let Ok(_) = insn_trace.1.try_recv() {}
+        while let Ok(_) = tainted.1.try_recv() {}
+    }
+    let exit_observer = executor.exit_cause_observer("exit_cause");
+
+    info!("replaying input...");
+    let input = BytesInput::new(fs::read(&args.input)?);
+    let mut state = StdState::new(
+        StdRand::new(),
+        InMemoryCorpus::<BytesInput>::new(),
+        InMemoryCorpus::new(),
+        &mut ConstFeedback::new(false),
+        &mut ConstFeedback::new(false),
+    )?;
+    let result = executor.run_target(
+        &mut NopFuzzer::new(),
+        &mut state,
+        &mut NopEventManager::new(),
+        &input,
+    );
+
+    println!("== instruction trace ==");
+    for (address, disasm) in insn_trace.1.try_iter() {
+        println!("{:#010x}: {disasm}", address.offset());
+    }
+
+    println!("== tainted locations ==");
+    for loc in tainted.1.try_iter() {
+        println!("{:#010x}-{}", loc.address().offset(), loc.position());
+    }
+
+    println!("== mmio accesses ==");
+    for access in access_log.1.try_iter() {
+        let rw = if access.is_write { "W" } else { "R" };
+        println!("{rw} {:#010x} [{}]", access.address.offset(), access.size);
+    }
+
+    println!("== exit ==");
+    match result {
+        Ok(kind) => { println!("exit kind: {kind:?}"); }
+        Err(err) => { println!("executor error: {err:?}"); }
+    }
+    match exit_observer.metadata() {
+        Some(metadata) => {
+            println!("exit cause @ {:#010x}: {:?}", metadata.pc, metadata.cause);
+        }
+        None => { println!("exit cause: unknown"); }
+    }
+    Ok(())
+}
@@ -0,0 +1,424 @@
+//! config.rs
+//!
+//! fuzzer configuration files
+//!
+//! a config file describes everything that differs between harnesses:
+//! the firmware, the platform, which peripherals are mapped and which are
+//! taint sources, the policy, halt points, limits, and output paths.
+//!
+//! ```yaml
+//! firmware: examples/samples/uart-jump/uart-jump.elf
+//! platform: data/nrf52/nrf52.yml
+//! processors: data/processors     # optional
+//! peripherals: [uart0, gpio]      # optional, maps all platform peripherals if omitted
+//! sources: [uart0]
+//! policy: jump                    # jump, address, or overflow
+//! bit_precise: true               # optional, track taint per bit
+//! implicit_flows: true            # optional, taint values assigned under tainted branches
+//! entry: main                     # optional, symbol or address
+//! halt: [0xb1c]                   # symbols or addresses
+//! end_of_input: exit              # optional, exit, timeout, stall, or fill:<byte>
+//! crash_on_hardfault: false       # optional, run the firmware's hardfault handler instead
+//! semihosting: { sandbox: tmp }   # optional, true or a directory for file i/o
+//! system_reset: halt              # optional, halt or reset on a firmware reset request
+//! hang:                           # optional, each detection is off unless given an exit kind
+//!   self_loop: ok
+//!   sleep: ok
+//!   repeated_state: { window: 100000, exit: timeout }
+//! limits:
+//!   cycles: 1000000               # optional
+//!   executions: 5                 # optional
+//! seed: 42                        # optional
+//! output:
+//!   queue: out/queue
+//!   crashes: out/crashes
+//!   log: out/fuzz.log             # optional
+//! ```
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use thiserror::Error;
+use yaml_rust2::{Yaml, YamlLoader, ScanError};
+
+use libcme::prelude::*;
+use libcme::peripheral::channel::EndOfInput;
+
+use super::{EntryPoint, PolicyKind, HangDetector};
+use super::hang::parse_exit_kind;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("no yaml documents parsed")]
+    NoDocs,
+    #[error("multiple yaml documents parsed: {0:?} documents")]
+    MultipleDocs(usize),
+    #[error("invalid field value: {0}")]
+    InvalidField(&'static str),
+    #[error(transparent)]
+    Yaml(#[from] ScanError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Harness(#[from] super::Error),
+}
+
+/// fuzzer configuration
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    /// path to the firmware elf
+    pub firmware: PathBuf,
+    /// path to the platform description
+    pub platform: PathBuf,
+    /// path to the sleigh processor directory
+    pub processors: PathBuf,
+    /// platform peripherals to map, or all if None
+    pub peripherals: Option<Vec<String>>,
+    /// platform peripherals whose reads are tainted
+    pub sources: Vec<String>,
+    pub policy: PolicyKind,
+    /// track taint per bit through masking and shifts
+    pub bit_precise: bool,
+    /// track implicit flows through tainted conditional branches
+    pub implicit_flows: bool,
+    /// where to start fuzzing, or the reset vector if None
+    pub entry: Option<EntryPoint>,
+    /// points at which an execution exits normally
+    pub halts: Vec<EntryPoint>,
+    pub end_of_input: EndOfInput,
+    /// end runs with a crash on entry to the HardFault handler
+    pub crash_on_hardfault: bool,
+    /// handle semihosting calls, reading from the fuzz input
+    pub semihosting: bool,
+    /// directory semihosting file operations are confined to
+    pub sandbox: Option<PathBuf>,
+    /// how firmware system reset requests are handled
+    pub reset_policy: backend::ResetPolicy,
+    /// terminal state detection
+    pub hang: HangDetector,
+    pub cycle_limit: Option<usize>,
+    pub exec_limit: Option<usize>,
+    pub seed: u64,
+    pub queue_dir: PathBuf,
+    pub crashes_dir: PathBuf,
+    pub log_file: Option<PathBuf>,
+}
+
+impl FuzzConfig {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    #[instrument(skip_all)]
+    pub fn from_yaml(yaml: Yaml) -> Result<Self, Error> {
+        let firmware = yaml["firmware"].as_str()
+            .ok_or(Error::InvalidField("firmware"))?.into();
+        let platform = yaml["platform"].as_str()
+            .ok_or(Error::InvalidField("platform"))?.into();
+        let processors = match &yaml["processors"] {
+            Yaml::String(path) => { path.into() }
+            Yaml::BadValue => { "data/processors".into() }
+            _ => { return Err(Error::InvalidField("processors")) }
+        };
+        let peripherals = match &yaml["peripherals"] {
+            Yaml::BadValue => { None }
+            yaml => { Some(_str_list(yaml).ok_or(Error::InvalidField("peripherals"))?) }
+        };
+        let sources = match &yaml["sources"] {
+            Yaml::BadValue => { vec![] }
+            yaml => { _str_list(yaml).ok_or(Error::InvalidField("sources"))? }
+        };
+        let policy = yaml["policy"].as_str()
+            .ok_or(Error::InvalidField("policy"))?
+            .parse()?;
+        let bit_precise = match &yaml["bit_precise"] {
+            Yaml::Boolean(bit_precise) => { *bit_precise }
+            Yaml::BadValue => { false }
+            _ => { return Err(Error::InvalidField("bit_precise")) }
+        };
+        let implicit_flows = match &yaml["implicit_flows"] {
+            Yaml::Boolean(implicit_flows) => { *implicit_flows }
+            Yaml::BadValue => { false }
+            _ => { return Err(Error::InvalidField("implicit_flows")) }
+        };
+        let entry = match &yaml["entry"] {
+            Yaml::BadValue => { None }
+            yaml => { Some(_entry_point(yaml).ok_or(Error::InvalidField("entry"))??) }
+        };
+        let halts = match &yaml["halt"] {
+            Yaml::BadValue => { vec![] }
+            Yaml::Array(halts) => {
+                halts.iter()
+                    .map(|halt| _entry_point(halt).ok_or(Error::InvalidField("halt"))?
+                        .map_err(Error::from))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            yaml => { vec![_entry_point(yaml).ok_or(Error::InvalidField("halt"))??] }
+        };
+        let end_of_input = match &yaml["end_of_input"] {
+            Yaml::String(eoi) => { super::setup::parse_end_of_input(eoi)? }
+            Yaml::BadValue => { EndOfInput::default() }
+            _ => { return Err(Error::InvalidField("end_of_input")) }
+        };
+        let crash_on_hardfault = match &yaml["crash_on_hardfault"] {
+            Yaml::Boolean(crash_on_hardfault) => { *crash_on_hardfault }
+            Yaml::BadValue => { true }
+            _ => { return Err(Error::InvalidField("crash_on_hardfault")) }
+        };
+        let (semihosting, sandbox) = match &yaml["semihosting"] {
+            Yaml::Boolean(semihosting) => { (*semihosting, None) }
+            Yaml::BadValue => { (false, None) }
+            Yaml::Hash(_) => {
+                let sandbox = yaml["semihosting"]["sandbox"].as_str()
+                    .ok_or(Error::InvalidField("semihosting.sandbox"))?;
+                (true, Some(sandbox.into()))
+            }
+            _ => { return Err(Error::InvalidField("semihosting")) }
+        };
+        let reset_policy = match &yaml["system_reset"] {
+            Yaml::String(policy) if policy == "halt" => { backend::ResetPolicy::Halt }
+            Yaml::String(policy) if policy == "reset" => { backend::ResetPolicy::Reset }
+            Yaml::BadValue => { backend::ResetPolicy::default() }
+            _ => { return Err(Error::InvalidField("system_reset")) }
+        };
+        let mut hang = HangDetector::new();
+        if let Some(kind) = yaml["hang"]["self_loop"].as_str() {
+            hang = hang.with_self_loop(parse_exit_kind(kind)?);
+        }
+        if let Some(kind) = yaml["hang"]["sleep"].as_str() {
+            hang = hang.with_sleep(parse_exit_kind(kind)?);
+        }
+        match &yaml["hang"]["repeated_state"] {
+            Yaml::BadValue => {  }
+            yaml => {
+                let window = yaml["window"].as_i64()
+                    .and_then(|window| usize::try_from(window).ok())
+                    .ok_or(Error::InvalidField("hang.repeated_state.window"))?;
+                let kind = yaml["exit"].as_str()
+                    .ok_or(Error::InvalidField("hang.repeated_state.exit"))?;
+                hang = hang.with_repeated_state(window, parse_exit_kind(kind)?);
+            }
+        }
+        let cycle_limit = _limit(&yaml["limits"]["cycles"])
+            .ok_or(Error::InvalidField("limits.cycles"))?;
+        let exec_limit = _limit(&yaml["limits"]["executions"])
+            .ok_or(Error::InvalidField("limits.executions"))?;
+        let seed = match &yaml["seed"] {
+            Yaml::Integer(seed) => { *seed as u64 }
+            Yaml::BadValue => { 42 }
+            _ => { return Err(Error::InvalidField("seed")) }
+        };
+        let queue_dir = yaml["output"]["queue"].as_str()
+            .ok_or(Error::InvalidField("output.queue"))?.into();
+        let crashes_dir = yaml["output"]["crashes"].as_str()
+            .ok_or(Error::InvalidField("output.crashes"))?.into();
+        let log_file = match &yaml["output"]["log"] {
+            Yaml::String(path) => { Some(path.into()) }
+            Yaml::BadValue => { None }
+            _ => { return Err(Error::InvalidField("output.log")) }
+        };
+
+        Ok(Self {
+            firmware,
+            platform,
+            processors,
+            peripherals,
+            sources,
+            policy,
+            bit_precise,
+            implicit_flows,
+            entry,
+            halts,
+            end_of_input,
+            crash_on_hardfault,
+            semihosting,
+            sandbox,
+            reset_policy,
+            hang,
+            cycle_limit,
+            exec_limit,
+            seed,
+            queue_dir,
+            crashes_dir,
+            log_file,
+        })
+    }
+}
+
+impl FromStr for FuzzConfig {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut docs = YamlLoader::load_from_str(s)?;
+        if docs.len() == 0 {
+            return Err(Error::NoDocs);
+        } else if docs.len() > 1 {
+            return Err(Error::MultipleDocs(docs.len()));
+        }
+        FuzzConfig::from_yaml(docs.swap_remove(0))
+    }
+}
+
+/// a single string or a list of strings
+fn _str_list(yaml: &Yaml) -> Option<Vec<String>> {
+    match yaml {
+        Yaml::String(s) => { Some(vec![s.clone()]) }
+        Yaml::Array(list) => {
+            list.iter()
+                .map(|s| s.as_str().map(String::from))
+                .collect()
+        }
+        _ => { None }
+    }
+}
+
+/// an address or a symbol name
+fn _entry_point(yaml: &Yaml) -> Option<Result<EntryPoint, super::Error>> {
+    match yaml {
+        Yaml::Integer(address) => { Some(Ok(EntryPoint::from(*address as u64))) }
+        Yaml::String(s) => { Some(s.parse()) }
+        _ => { None }
+    }
+}
+
+/// an optional limit, where a missing or null limit is no limit
+fn _limit(yaml: &Yaml) -> Option<Option<usize>> {
+    match yaml {
+        Yaml::Integer(limit) if *limit >= 0 => { Some(Some(*limit as usize)) }
+        Yaml::BadValue | Yaml::Null => { Some(None) }
+        _ => { None }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_minimal_config() -> Result<(), Error> {
+        let config: FuzzConfig = r#"
+firmware: fw.elf
+platform: nrf52.yml
+policy: jump
+output:
+  queue: out/queue
+  crashes: out/crashes
+"#.parse()?;
+        assert_eq!(config.firmware, PathBuf::from("fw.elf"));
+        assert_eq!(config.platform, PathBuf::from("nrf52.yml"));
+        assert_eq!(config.processors, PathBuf::from("data/processors"));
+        assert!(config.peripherals.is_none());
+        assert!(config.sources.is_empty());
+        assert_eq!(config.policy, PolicyKind::Jump);
+        assert!(!config.bit_precise);
+        assert!(!config.implicit_flows);
+        assert!(config.entry.is_none());
+        assert!(config.halts.is_empty());
+        assert_eq!(config.end_of_input, EndOfInput::Timeout);
+        assert!(config.crash_on_hardfault);
+        assert!(!config.semihosting);
+        assert!(config.sandbox.is_none());
+        assert_eq!(config.reset_policy, backend::ResetPolicy::default());
+        assert_eq!(config.cycle_limit, None);
+        assert_eq!(config.exec_limit, None);
+        assert_eq!(config.seed, 42);
+        assert_eq!(config.queue_dir, PathBuf::from("out/queue"));
+        assert_eq!(config.crashes_dir, PathBuf::from("out/crashes"));
+        assert!(config.log_file.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_full_config() -> Result<(), Error> {
+        let config: FuzzConfig = r#"
+firmware: fw.elf
+platform: nrf52.yml
+processors: sleigh
+peripherals: [uart0, gpio]
+sources: uart0
+policy: overflow
+bit_precise: true
+implicit_flows: true
+entry: main
+halt: [0xb1c, exit]
+end_of_input: fill:0x41
+crash_on_hardfault: false
+semihosting: { sandbox: tmp }
+system_reset: reset
+hang:
+  self_loop: ok
+  sleep: ok
+  repeated_state: { window: 100000, exit: timeout }
+limits:
+  cycles: 1000000
+  executions: 5
+seed: 7
+output:
+  queue: out/queue
+  crashes: out/crashes
+  log: out/fuzz.log
+"#.parse()?;
+        assert_eq!(config.processors, PathBuf::from("sleigh"));
+        assert_eq!(config.peripherals, Some(vec!["uart0".to_string(), "gpio".to_string()]));
+        assert_eq!(config.sources, vec!["uart0".to_string()]);
+        assert_eq!(config.policy, PolicyKind::Overflow);
+        assert!(config.bit_precise);
+        assert!(config.implicit_flows);
+        assert_eq!(config.entry, Some(EntryPoint::from("main")));
+        assert_eq!(config.halts, vec![EntryPoint::from(0xb1cu64), EntryPoint::from("exit")]);
+        assert_eq!(config.end_of_input, EndOfInput::Fill(0x41));
+        assert!(!config.crash_on_hardfault);
+        assert!(config.semihosting);
+        assert_eq!(config.sandbox, Some(PathBuf::from("tmp")));
+        assert_eq!(config.reset_policy, backend::ResetPolicy::Reset);
+        assert_eq!(config.cycle_limit, Some(1000000));
+        assert_eq!(config.exec_limit, Some(5));
+        assert_eq!(config.seed, 7);
+        assert_eq!(config.log_file, Some(PathBuf::from("out/fuzz.log")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let result = r#"
+firmware: fw.elf
+platform: nrf52.yml
+policy: jump
+bit_precise: yes please
+output:
+  queue: out/queue
+  crashes: out/crashes
+"#.parse::<FuzzConfig>();
+        assert!(matches!(result, Err(Error::InvalidField("bit_precise"))));
+
+        let result = r#"
+firmware: fw.elf
+platform: nrf52.yml
+policy: jump
+hang:
+  repeated_state: { window: -1, exit: timeout }
+output:
+  queue: out/queue
+  crashes: out/crashes
+"#.parse::<FuzzConfig>();
+        assert!(matches!(result, Err(Error::InvalidField("hang.repeated_state.window"))));
+
+        let result = r#"
+firmware: fw.elf
+platform: nrf52.yml
+policy: jump
+"#.parse::<FuzzConfig>();
+        assert!(matches!(result, Err(Error::InvalidField("output.queue"))));
+
+        let result = r#"
+firmware: fw.elf
+platform: nrf52.yml
+policy: taint-everything
+output:
+  queue: out/queue
+  crashes: out/crashes
+"#.parse::<FuzzConfig>();
+        assert!(matches!(result, Err(Error::Harness(super::super::Error::UnknownPolicy(_)))));
+    }
+}
@@ -0,0 +1,190 @@
+//! entry.rs
+//!
+//! deferred fuzzing entry points
+//!
+//! rather than fuzzing from the reset vector, firmware can be run
+//! until some point after initialization (e.g. `main`) and the
+//! resulting state used as the base state for fuzzing.
+use std::str::FromStr;
+
+use libcme::{
+    prelude::*,
+    programdb::ProgramDB,
+};
+
+/// a fuzzing entry point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryPoint {
+    /// a symbol in the program's symbol table
+    Symbol(String),
+    /// an instruction address
+    Address(Address),
+}
+
+impl EntryPoint {
+    /// resolve the entry point to an address in the given program
+    pub fn resolve(&self, program: &Program) -> Result<Address, super::Error> {
+        match self {
+            EntryPoint::Symbol(name) => {
+                let symbol = program.symtab()
+                    .get(name.as_str())
+                    .ok_or_else(|| super::Error::SymbolNotFound(name.clone()))?;
+                // thumb function symbols have the low bit set
+                Ok(Address::from(symbol.st_value & !1))
+            }
+            EntryPoint::Address(address) => { Ok(*address) }
+        }
+    }
+}
+
+impl FromStr for EntryPoint {
+    type Err = super::Error;
+
+    /// parse an address (decimal or `0x` hex) or a symbol name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(super::Error::Parse("empty entry point".to_string()));
+        }
+        match parse_uint(s) {
+            Some(address) => { Ok(Self::from(address)) }
+            None => { Ok(Self::from(s)) }
+        }
+    }
+}
+
+/// parse a decimal or `0x`-prefixed hex integer
+pub fn parse_uint(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x") {
+        Some(hex) => { u64::from_str_radix(hex, 16).ok() }
+        None => { s.parse::<u64>().ok() }
+    }
+}
+
+impl From<&str> for EntryPoint {
+    fn from(name: &str) -> Self {
+        Self::Symbol(name.to_string())
+    }
+}
+
+impl From<u64> for EntryPoint {
+    fn from(address: u64) -> Self {
+        Self::Address(Address::from(address))
+    }
+}
+
+impl From<Address> for EntryPoint {
+    fn from(address: Address) -> Self {
+        Self::Address(address)
+    }
+}
+
+/// step the context until the evaluator reaches the entry address
+///
+/// returns the number of cycles executed. on success, the evaluator
+/// pc and pc_tag are at the entry point and the context is in the
+/// state it would be in just before the entry instruction executes.
+#[instrument(skip_all)]
+pub fn run_to_entry(
+    evaluator: &mut dtt::Evaluator,
+    context: &mut dtt::Context,
+    pdb: &mut ProgramDB,
+    entry: Address,
+    limit: Option<usize>,
+) -> Result<usize, super::Error> {
+    (evaluator.pc, evaluator.pc_tag) = context.read_pc()
+        .map(|(pc, tag)| (Location::from(pc), tag))
+        .map_err(dtt::eval::Error::from)?;
+
+    let mut cycles: usize = 0;
+    while (evaluator.pc.address().offset() & !1) != entry.offset() {
+        if limit.is_some_and(|limit| cycles >= limit) {
+            error!("entry point {:#x} not reached after {cycles} cycles", entry.offset());
+            return Err(super::Error::EntryNotReached(entry, cycles));
+        }
+        evaluator.step(context, pdb)?;
+        cycles += 1;
+    }
+    info!("entry point {:#x} reached after {cycles} cycles", entry.offset());
+    Ok(cycles)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use super::*;
+    use crate::harness::{Error, setup};
+
+    // blinky-o0: Reset_Handler @ 0x2b0, main @ 0x608
+    const FIRMWARE: &str = "data/nrf52/blinky-o0.elf";
+    const RESET_HANDLER: u64 = 0x2b0;
+    const MAIN: u64 = 0x608;
+
+    #[test]
+    fn test_parse_uint() {
+        assert_eq!(parse_uint("0x608"), Some(0x608));
+        assert_eq!(parse_uint("1544"), Some(1544));
+        assert_eq!(parse_uint("0xzz"), None);
+        assert_eq!(parse_uint("main"), None);
+    }
+
+    #[test]
+    fn test_entry_from_str() -> Result<(), Error> {
+        assert_eq!(EntryPoint::from_str("main")?, EntryPoint::Symbol("main".into()));
+        assert_eq!(EntryPoint::from_str("0x608")?, EntryPoint::Address(Address::from(MAIN)));
+        assert_eq!(EntryPoint::from_str("1544")?, EntryPoint::Address(Address::from(MAIN)));
+        assert!(matches!(EntryPoint::from_str(""), Err(Error::Parse(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_resolve() -> Result<(), anyhow::Error> {
+        let irb = IRBuilderArena::with_capacity(0x1000);
+        let bytes = fs::read(FIRMWARE)?;
+        let elf_bytes = ElfBytes::minimal_parse(bytes.as_slice())?;
+        let program = Program::new_from_elf(irb.inner(), elf_bytes)?;
+
+        // the thumb bit is cleared from function symbols
+        let address = EntryPoint::from("main").resolve(&program)?;
+        assert_eq!(address.offset(), MAIN);
+        let address = EntryPoint::from(MAIN).resolve(&program)?;
+        assert_eq!(address.offset(), MAIN);
+
+        let result = EntryPoint::from("not_a_symbol").resolve(&program);
+        assert!(matches!(result, Err(Error::SymbolNotFound(name)) if name == "not_a_symbol"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_to_entry() -> Result<(), anyhow::Error> {
+        let irb = IRBuilderArena::with_capacity(0x10000);
+        let bytes = fs::read(FIRMWARE)?;
+        let elf_bytes = ElfBytes::minimal_parse(bytes.as_slice())?;
+        let program = Program::new_from_elf(irb.inner(), elf_bytes)?;
+        let builder = LanguageBuilder::new("data/processors")?;
+        let platform = Platform::from_path("data/nrf52/nrf52.yml")?;
+        let mut pdb = ProgramDB::new_with(&builder, program, platform, &irb);
+
+        let backend = pdb.backend(&builder)?;
+        let mut context = dtt::Context::from_backend(backend)?;
+        setup::load_program(&mut context, pdb.program())?;
+        setup::init_from_vector_table(&mut context)?;
+        let mut evaluator = dtt::Evaluator::new();
+
+        // already at the entry point
+        let entry = Address::from(RESET_HANDLER);
+        let cycles = run_to_entry(&mut evaluator, &mut context, &mut pdb, entry, Some(0))?;
+        assert_eq!(cycles, 0);
+
+        // one instruction into the reset handler
+        let entry = Address::from(RESET_HANDLER + 2);
+        let cycles = run_to_entry(&mut evaluator, &mut context, &mut pdb, entry, Some(10))?;
+        assert_eq!(cycles, 1);
+        assert_eq!(evaluator.pc.address().offset(), RESET_HANDLER + 2);
+
+        // the cycle limit is reached before main
+        let entry = Address::from(MAIN);
+        let result = run_to_entry(&mut evaluator, &mut context, &mut pdb, entry, Some(0));
+        assert!(matches!(result, Err(Error::EntryNotReached(address, 0)) if address == entry));
+        Ok(())
+    }
+}
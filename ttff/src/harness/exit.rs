@@ -0,0 +1,360 @@
+//! exit.rs
+//!
+//! exit cause tracking for crash triage
+//!
+//! the executor records why each run ended in an `ExitCauseObserver`,
+//! and the `ExitCauseFeedback` attaches that cause to testcases as
+//! `ExitCauseMetadata` so that the objective corpus can be triaged and
+//! deduplicated by cause.
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use ahash::AHasher;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use libcme::prelude::*;
+use libafl::{
+    corpus::Testcase,
+    executors::ExitKind,
+    feedbacks::{Feedback, StateInitializer},
+    observers::Observer,
+    HasNamedMetadata,
+};
+use libafl_bolts::{
+    Named,
+    impl_serdeany,
+    tuples::{Handle, Handled, MatchNameRef},
+};
+
+use super::hang::Hang;
+use crate::policy::{
+    JumpPolicyViolation,
+    AddressPolicyViolation,
+    OverflowPolicyViolation,
+};
+
+/// the reason an execution ended
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExitCause {
+    /// a halt or step callback ended the run,
+    /// or the processor halted in debug state
+    Halt,
+    /// the firmware exited through semihosting
+    /// with a reason code and subcode
+    Exit { reason: u32, code: u32 },
+    /// the firmware executed a breakpoint instruction
+    Breakpoint { imm: u32 },
+    /// the firmware requested a system reset
+    Reset,
+    /// the cycle limit was reached
+    CycleLimit,
+    /// a terminal state was detected
+    Hang { hang: Hang },
+    /// the firmware entered its HardFault handler,
+    /// with the return address stacked for the fault
+    HardFault { address: u64 },
+    /// the firmware read past the end of the input
+    InputExhausted { address: u64 },
+    /// a taint policy violation
+    Policy { violation: String, message: String },
+    /// an access to unmapped memory
+    Unmapped { address: u64 },
+    /// a peripheral error other than running out of input
+    Peripheral { error: String },
+    /// any other backend error
+    Backend { kind: String, error: String },
+    /// any other evaluator error
+    Eval { kind: String, error: String },
+}
+
+impl ExitCause {
+    /// classify an evaluator error
+    pub fn from_eval_error(err: &dtt::eval::Error) -> Self {
+        match err {
+            dtt::eval::Error::Policy(err) => {
+                let violation = _policy_violation(err);
+                let message = format!("{err}");
+                Self::Policy { violation, message }
+            }
+            dtt::eval::Error::Context(dtt::context::Error::Backend(err)) => {
+                Self::from_backend_error(err)
+            }
+            dtt::eval::Error::Context(dtt::context::Error::Unmapped(address)) => {
+                Self::Unmapped { address: address.offset() }
+            }
+            dtt::eval::Error::Context(dtt::context::Error::Peripheral(error)) => {
+                Self::Peripheral { error: error.clone() }
+            }
+            err => {
+                let kind = match err {
+                    dtt::eval::Error::InvalidAddress(_) => { "InvalidAddress" }
+                    dtt::eval::Error::DivideByZero(_) => { "DivideByZero" }
+                    dtt::eval::Error::Unsupported(_) => { "Unsupported" }
+                    dtt::eval::Error::FloatSize(..) => { "FloatSize" }
+                    dtt::eval::Error::Context(_) => { "Context" }
+                    dtt::eval::Error::Lift(_) => { "Lift" }
+                    dtt::eval::Error::ProgramDB(_) => { "ProgramDB" }
+                    dtt::eval::Error::Policy(_) => { "Policy" }
+                    dtt::eval::Error::Plugin(_) => { "Plugin" }
+                };
+                Self::Eval { kind: kind.to_string(), error: format!("{err}") }
+            }
+        }
+    }
+
+    /// classify a backend error
+    pub fn from_backend_error(err: &backend::Error) -> Self {
+        match err {
+            backend::Error::Unmapped(address) => {
+                Self::Unmapped { address: address.offset() }
+            }
+            backend::Error::Peripheral(err) => {
+                if let peripheral::Error::State(err) = err.as_ref() {
+                    if let Some(peripheral::channel::ChannelStateError::Recv(address, _)) = err.downcast_ref() {
+                        return Self::InputExhausted { address: address.offset() };
+                    }
+                }
+                Self::Peripheral { error: format!("{err}") }
+            }
+            err => {
+                let kind = match err {
+                    backend::Error::Lift(_) => { "Lift".to_string() }
+                    backend::Error::State(_) => { "State".to_string() }
+                    backend::Error::AddressInvalid(_) => { "AddressInvalid".to_string() }
+                    backend::Error::AddressNotLifted(_) => { "AddressNotLifted".to_string() }
+                    backend::Error::MapConflict(_, _) => { "MapConflict".to_string() }
+                    backend::Error::Arch(arch, _) => { format!("Arch({arch})") }
+                    backend::Error::LangBuilder(_) => { "LangBuilder".to_string() }
+                    backend::Error::Unmapped(_) => { "Unmapped".to_string() }
+                    backend::Error::AccessViolation(..) => { "AccessViolation".to_string() }
+                    backend::Error::Breakpoint(_) => { "Breakpoint".to_string() }
+                    backend::Error::Unaligned(..) => { "Unaligned".to_string() }
+                    backend::Error::Unprivileged(_) => { "Unprivileged".to_string() }
+                    backend::Error::CoprocessorAccess => { "CoprocessorAccess".to_string() }
+                    backend::Error::Peripheral(_) => { "Peripheral".to_string() }
+                };
+                Self::Backend { kind, error: format!("{err}") }
+            }
+        }
+    }
+
+    /// the exit cause for a halted processor, and whether the run
+    /// should be considered a crash
+    pub fn from_halt_reason(reason: &backend::HaltReason) -> (Self, ExitKind) {
+        match reason {
+            backend::HaltReason::Debug => { (Self::Halt, ExitKind::Ok) }
+            backend::HaltReason::Breakpoint(imm) => {
+                (Self::Breakpoint { imm: *imm }, ExitKind::Crash)
+            }
+            &backend::HaltReason::Exit { reason, code } => {
+                let kind = if reason == backend::semihosting::ADP_STOPPED_APPLICATION_EXIT
+                    && code == 0
+                {
+                    ExitKind::Ok
+                } else {
+                    ExitKind::Crash
+                };
+                (Self::Exit { reason, code }, kind)
+            }
+            backend::HaltReason::Reset => { (Self::Reset, ExitKind::Ok) }
+        }
+    }
+
+    /// the cause without any run-specific detail,
+    /// used to bucket exits
+    pub fn kind(&self) -> Cow<'_, str> {
+        match self {
+            Self::Halt => { "Halt".into() }
+            Self::Exit { reason, .. } => { format!("Exit({reason:#x})").into() }
+            Self::Breakpoint { .. } => { "Breakpoint".into() }
+            Self::Reset => { "Reset".into() }
+            Self::CycleLimit => { "CycleLimit".into() }
+            Self::Hang { hang } => { format!("Hang({hang:?})").into() }
+            Self::HardFault { .. } => { "HardFault".into() }
+            Self::InputExhausted { .. } => { "InputExhausted".into() }
+            Self::Policy { violation, .. } => { format!("Policy({violation})").into() }
+            Self::Unmapped { .. } => { "Unmapped".into() }
+            Self::Peripheral { .. } => { "Peripheral".into() }
+            Self::Backend { kind, .. } => { format!("Backend({kind})").into() }
+            Self::Eval { kind, .. } => { format!("Eval({kind})").into() }
+        }
+    }
+}
+
+/// get the name of a policy violation for the policies we know about
+fn _policy_violation(err: &anyhow::Error) -> String {
+    if let Some(violation) = err.downcast_ref::<JumpPolicyViolation>() {
+        format!("{violation:?}")
+    } else if let Some(violation) = err.downcast_ref::<AddressPolicyViolation>() {
+        format!("{violation:?}")
+    } else if let Some(violation) = err.downcast_ref::<OverflowPolicyViolation>() {
+        format!("{violation:?}")
+    } else if let Some(violation) = err.downcast_ref::<dtt::policy::jump::PolicyViolation>() {
+        format!("{violation:?}")
+    } else {
+        "Unknown".to_string()
+    }
+}
+
+/// exit cause testcase metadata
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ExitCauseMetadata {
+    /// the pc at exit
+    pub pc: u64,
+    /// the reason for the exit
+    pub cause: ExitCause,
+}
+impl_serdeany!(ExitCauseMetadata);
+
+impl ExitCauseMetadata {
+    pub fn new(pc: u64, cause: ExitCause) -> Self {
+        Self { pc, cause }
+    }
+
+    /// a hash of the exit pc and cause kind for deduplication
+    pub fn signature(&self) -> u64 {
+        let mut hasher = AHasher::default();
+        self.pc.hash(&mut hasher);
+        self.cause.kind().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// shared exit cause slot written by the executor
+pub type ExitCauseRecord = Arc<RwLock<Option<ExitCauseMetadata>>>;
+
+/// an observer for the cause of each execution's exit
+///
+/// get one from the executor with `DttExecutor::exit_cause_observer`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExitCauseObserver {
+    name: Cow<'static, str>,
+    #[serde(skip)]
+    record: ExitCauseRecord,
+}
+
+impl ExitCauseObserver {
+    pub fn new(name: &'static str, record: ExitCauseRecord) -> Self {
+        Self { name: Cow::from(name), record }
+    }
+
+    /// the exit cause of the last execution
+    pub fn metadata(&self) -> Option<ExitCauseMetadata> {
+        self.record.read().clone()
+    }
+}
+
+impl Named for ExitCauseObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for ExitCauseObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        *self.record.write() = None;
+        Ok(())
+    }
+}
+
+/// the exit cause signatures a unique `ExitCauseFeedback` has seen
+///
+/// kept in the state as named metadata so that it is serialized with
+/// the state and survives client restarts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExitCauseFeedbackMetadata {
+    pub seen: HashSet<u64>,
+}
+
+impl_serdeany!(ExitCauseFeedbackMetadata);
+
+/// a feedback that attaches the exit cause to testcases
+///
+/// on its own it never considers an input interesting. when built with
+/// `new_unique` it is only interesting for exit causes it hasn't seen
+/// before, so that and-ing it with an objective deduplicates the
+/// objective corpus by cause.
+#[derive(Debug)]
+pub struct ExitCauseFeedback {
+    name: Cow<'static, str>,
+    observer_handle: Handle<ExitCauseObserver>,
+    unique: bool,
+}
+
+impl ExitCauseFeedback {
+    pub fn new(observer: &ExitCauseObserver) -> Self {
+        Self {
+            name: Cow::from("ExitCauseFeedback"),
+            observer_handle: observer.handle(),
+            unique: false,
+        }
+    }
+
+    pub fn new_unique(observer: &ExitCauseObserver) -> Self {
+        Self { unique: true, ..Self::new(observer) }
+    }
+}
+
+impl Named for ExitCauseFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<S> StateInitializer<S> for ExitCauseFeedback
+where
+    S: HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), libafl::Error> {
+        if self.unique {
+            state.named_metadata_or_insert_with(&self.name, ExitCauseFeedbackMetadata::default);
+        }
+        Ok(())
+    }
+}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for ExitCauseFeedback
+where
+    OT: MatchNameRef,
+    S: HasNamedMetadata,
+{
+    fn is_interesting(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, libafl::Error> {
+        if !self.unique {
+            return Ok(false);
+        }
+        let observer = observers.get(&self.observer_handle)
+            .ok_or_else(|| libafl::Error::key_not_found("ExitCauseObserver not found"))?;
+        let Some(metadata) = observer.metadata() else {
+            return Ok(false);
+        };
+        let meta = state.named_metadata_or_insert_with(
+            &self.name, ExitCauseFeedbackMetadata::default);
+        Ok(meta.seen.insert(metadata.signature()))
+    }
+
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<I>,
+    ) -> Result<(), libafl::Error> {
+        let observer = observers.get(&self.observer_handle)
+            .ok_or_else(|| libafl::Error::key_not_found("ExitCauseObserver not found"))?;
+        if let Some(metadata) = observer.metadata() {
+            info!("exit cause @ {:#x}: {:?}", metadata.pc, metadata.cause);
+            testcase.add_metadata(metadata);
+        }
+        Ok(())
+    }
+}
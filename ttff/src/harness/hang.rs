@@ -0,0 +1,169 @@
+//! hang.rs
+//!
+//! idle-loop and hang detection
+//!
+//! firmware often ends up in a state it can never leave, e.g. a
+//! branch-to-self in an exit or fault handler, or a WFI with nothing
+//! left to wake it. rather than relying on halt callbacks matching known
+//! addresses or burning through the cycle limit, the detector recognizes
+//! these terminal states and ends the run with a configurable exit kind.
+use std::hash::{Hash, Hasher};
+
+use ahash::{AHashMap, AHasher};
+use serde::{Deserialize, Serialize};
+
+use libcme::prelude::*;
+use libafl::executors::ExitKind;
+
+use fugue_ir::VarnodeData;
+
+/// registers included in the architectural state hash
+const STATE_REGS: &[&str] = &[
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7",
+    "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "NG", "ZR", "CY", "OV",
+];
+
+/// a detected terminal state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Hang {
+    /// a branch to self that no interrupt can break out of
+    SelfLoop,
+    /// a WFI or WFE that no interrupt can wake the processor from
+    Sleep,
+    /// the architectural state has only revisited previous states
+    /// for the configured window of cycles
+    RepeatedState,
+}
+
+/// detects terminal states during execution
+///
+/// each detection is disabled unless given an exit kind.
+/// the repeated state detection only hashes registers, so busy-wait loops
+/// that count in memory look the same as hung loops. its window should be
+/// longer than any delay loop the firmware is expected to finish.
+#[derive(Debug, Clone, Default)]
+pub struct HangDetector {
+    self_loop: Option<ExitKind>,
+    sleep: Option<ExitKind>,
+    repeated_state: Option<(usize, ExitKind)>,
+    /// registers hashed for repeated state detection
+    regs: Vec<VarnodeData>,
+    /// pc after the previous step
+    last_pc: Option<u64>,
+    /// the last cycle each recent state was seen
+    seen: AHashMap<u64, usize>,
+    /// consecutive cycles spent in previously seen states
+    repeats: usize,
+}
+
+impl HangDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// exit with `kind` on a branch to self while interrupts are masked
+    /// or when no interrupt can occur
+    pub fn with_self_loop(mut self, kind: ExitKind) -> Self {
+        self.self_loop = Some(kind);
+        self
+    }
+
+    /// exit with `kind` when the processor sleeps and no interrupt can wake it
+    pub fn with_sleep(mut self, kind: ExitKind) -> Self {
+        self.sleep = Some(kind);
+        self
+    }
+
+    /// exit with `kind` when every state in the last `window` cycles
+    /// had already been seen within the window before it
+    pub fn with_repeated_state(mut self, window: usize, kind: ExitKind) -> Self {
+        self.repeated_state = Some((window, kind));
+        self
+    }
+
+    /// clear per-run state, should be called before each run
+    pub fn reset(&mut self) {
+        self.last_pc = None;
+        self.seen.clear();
+        self.repeats = 0;
+    }
+
+    /// check for a terminal state after a step
+    ///
+    /// `cycles` is the number of cycles executed so far.
+    #[instrument(skip_all)]
+    pub fn check(
+        &mut self,
+        cycles: usize,
+        evaluator: &dtt::Evaluator,
+        context: &mut dtt::Context,
+    ) -> Result<Option<(Hang, ExitKind)>, dtt::context::Error> {
+        let pc = evaluator.pc.address().offset();
+        let last_pc = self.last_pc.replace(pc);
+
+        if let Some(kind) = self.self_loop {
+            // sleeping has its own detection, since a sleeping
+            // processor stays at the same pc until it wakes
+            if last_pc == Some(pc)
+                && !context.is_sleeping()
+                && (context.interrupts_masked() || !context.interrupts_possible())
+            {
+                debug!("self loop @ {pc:#x} with no reachable interrupts");
+                return Ok(Some((Hang::SelfLoop, kind)));
+            }
+        }
+
+        if let Some(kind) = self.sleep {
+            if context.is_sleeping() && !context.interrupts_possible() {
+                debug!("sleeping @ {pc:#x} with no reachable interrupts");
+                return Ok(Some((Hang::Sleep, kind)));
+            }
+        }
+
+        if let Some((window, kind)) = self.repeated_state {
+            let hash = self._state_hash(pc, context)?;
+            let repeated = self.seen.get(&hash)
+                .is_some_and(|&seen| cycles.saturating_sub(seen) <= window);
+            self.repeats = if repeated { self.repeats + 1 } else { 0 };
+            self.seen.insert(hash, cycles);
+            if self.repeats >= window {
+                debug!("state repeated for {window} cycles @ {pc:#x}");
+                return Ok(Some((Hang::RepeatedState, kind)));
+            }
+            // keep only the states seen within the window
+            if self.seen.len() > 2 * window {
+                self.seen.retain(|_, seen| cycles.saturating_sub(*seen) <= window);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn _state_hash(&mut self, pc: u64, context: &mut dtt::Context) -> Result<u64, dtt::context::Error> {
+        if self.regs.is_empty() {
+            let t = context.lang().translator();
+            self.regs = STATE_REGS.iter()
+                .filter_map(|name| t.register_by_name(name))
+                .collect();
+        }
+        let mut hasher = AHasher::default();
+        pc.hash(&mut hasher);
+        for vnd in self.regs.iter() {
+            let (val, _tag) = context.read(vnd)?;
+            val.to_u64().unwrap_or_default().hash(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+}
+
+/// parse an exit kind (`ok`, `crash`, `timeout`, or `oom`)
+pub fn parse_exit_kind(s: &str) -> Result<ExitKind, super::Error> {
+    match s {
+        "ok" => { Ok(ExitKind::Ok) }
+        "crash" => { Ok(ExitKind::Crash) }
+        "timeout" => { Ok(ExitKind::Timeout) }
+        "oom" => { Ok(ExitKind::Oom) }
+        _ => { Err(super::Error::Parse(format!("exit kind: {s}"))) }
+    }
+}
@@ -0,0 +1,132 @@
+//! interrupt.rs
+//!
+//! fuzzer-driven interrupt injection
+//!
+//! firmware logic in interrupt handlers is only reached when a peripheral
+//! model fires the interrupt. the injector instead pends enabled external
+//! interrupts according to a schedule taken from the fuzz input, so that
+//! handlers are exercised even if no peripheral model ever raises them.
+use libcme::prelude::*;
+
+/// size of an encoded schedule entry in bytes
+pub const ENTRY_SIZE: usize = 3;
+
+/// where the interrupt schedule comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptSource {
+    /// the first `n` bytes of each input are the schedule,
+    /// the rest are sent to the input channel
+    InputPrefix(usize),
+    /// the schedule is loaded separately with `InterruptInjector::load_schedule`,
+    /// e.g. from another part of a multipart input.
+    /// the same schedule is replayed on every run until another is loaded.
+    External,
+}
+
+/// a single scheduled injection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Injection {
+    /// cycles to wait after the previous injection
+    pub delay: u16,
+    /// selects which of the enabled interrupts to pend
+    pub selector: u8,
+}
+
+impl Injection {
+    /// decode an entry from little endian `[delay: u16, selector: u8]`
+    pub fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> Self {
+        let delay = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let selector = bytes[2];
+        Self { delay, selector }
+    }
+}
+
+/// pends enabled external interrupts according to a schedule
+///
+/// the selector of each entry indexes into the set of external interrupts
+/// that are enabled in the nvic at the time of injection, so every
+/// injection hits an interrupt the firmware is actually prepared to handle.
+/// if no interrupts are enabled yet, the entry waits until one is.
+#[derive(Debug, Clone)]
+pub struct InterruptInjector {
+    source: InterruptSource,
+    schedule: Vec<Injection>,
+    /// index of the next injection in the schedule
+    cursor: usize,
+    /// cycle of the last injection
+    last: usize,
+}
+
+impl InterruptInjector {
+    pub fn new_with(source: InterruptSource) -> Self {
+        Self { source, schedule: vec![], cursor: 0, last: 0 }
+    }
+
+    pub fn source(&self) -> InterruptSource {
+        self.source
+    }
+
+    /// prepare the injector for a run on the given input bytes
+    ///
+    /// if the schedule comes from the input, it is split off and loaded.
+    /// returns the remaining bytes, which are meant for the input channel.
+    pub fn load_from_input<'a>(&mut self, bytes: &'a [u8]) -> &'a [u8] {
+        match self.source {
+            InterruptSource::InputPrefix(n) => {
+                let (schedule, bytes) = bytes.split_at(n.min(bytes.len()));
+                self.load_schedule(schedule);
+                bytes
+            }
+            InterruptSource::External => { bytes }
+        }
+    }
+
+    /// replace the current schedule with one decoded from bytes
+    ///
+    /// trailing bytes that don't make up a whole entry are ignored
+    pub fn load_schedule(&mut self, bytes: &[u8]) {
+        self.schedule = bytes.chunks_exact(ENTRY_SIZE)
+            .map(|chunk| Injection::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        self.reset();
+    }
+
+    /// rewind to the start of the schedule for a new run
+    pub fn reset(&mut self) {
+        self.cursor = 0;
+        self.last = 0;
+    }
+
+    /// number of injections left in the schedule
+    pub fn remaining(&self) -> usize {
+        self.schedule.len() - self.cursor
+    }
+
+    /// pend the next scheduled interrupt if it is due
+    ///
+    /// should be called before each evaluator step with the number of
+    /// cycles executed so far. returns the interrupt number if one was pended.
+    #[instrument(skip_all)]
+    pub fn maybe_inject(
+        &mut self,
+        cycles: usize,
+        context: &mut dtt::Context,
+    ) -> Result<Option<u32>, dtt::context::Error> {
+        let Some(next) = self.schedule.get(self.cursor) else {
+            return Ok(None);
+        };
+        if cycles < self.last + next.delay as usize {
+            return Ok(None);
+        }
+        let enabled = context.enabled_interrupts();
+        if enabled.is_empty() {
+            return Ok(None);
+        }
+        let int_num = enabled[next.selector as usize % enabled.len()];
+        debug!("injecting interrupt {int_num} @ cycle {cycles}");
+        context.fire_interrupt(int_num)?;
+        self.cursor += 1;
+        self.last = cycles;
+        Ok(Some(int_num))
+    }
+}
@@ -4,11 +4,43 @@
 //! with libcme dft
 use thiserror::Error;
 
+use libcme::prelude::*;
+
 /// harness error
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("error loading input")]
     Input,
+    #[error("symbol not found: {0}")]
+    SymbolNotFound(String),
+    #[error("unknown policy: {0}")]
+    UnknownPolicy(String),
+    #[error("unknown peripheral: {0}")]
+    UnknownPeripheral(String),
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("entry point {0:#x?} not reached after {1} cycles")]
+    EntryNotReached(Address, usize),
+    #[error(transparent)]
+    Eval(#[from] dtt::eval::Error),
 }
 
-pub mod sc;
\ No newline at end of file
+pub mod sc;
+pub mod entry;
+pub use entry::EntryPoint;
+pub mod exit;
+pub use exit::{
+    ExitCause,
+    ExitCauseMetadata,
+    ExitCauseObserver,
+    ExitCauseFeedback,
+    ExitCauseFeedbackMetadata,
+};
+pub mod interrupt;
+pub use interrupt::{InterruptInjector, InterruptSource};
+pub mod hang;
+pub use hang::{Hang, HangDetector};
+pub mod setup;
+pub use setup::PolicyKind;
+pub mod config;
+pub use config::FuzzConfig;
\ No newline at end of file
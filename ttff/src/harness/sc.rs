@@ -11,7 +11,7 @@ use libcme::{
     self,
     prelude::*,
     programdb::ProgramDB,
-    peripheral::channel::Access,
+    peripheral::channel::{Access, EndOfInput, EndOfInputPolicy},
 };
 
 use libafl::{
@@ -20,6 +20,15 @@ use libafl::{
     inputs::HasTargetBytes,
 };
 
+use super::interrupt::InterruptInjector;
+use super::hang::HangDetector;
+use super::exit::{
+    ExitCause,
+    ExitCauseMetadata,
+    ExitCauseObserver,
+    ExitCauseRecord,
+};
+
 pub type HaltCallbackFn = dyn FnMut(
     &dtt::Evaluator,
     &ProgramDB,
@@ -33,7 +42,7 @@ pub type StepCallbackFn = dyn FnMut(
 pub type PostExecCallbackFn = dyn FnMut(
     &mut dtt::Evaluator,
     &mut ProgramDB,
-    dtt::Context,
+    &mut dtt::Context,
     Result<ExitKind, libafl::Error>,
 ) -> Result<ExitKind, libafl::Error>;
 
@@ -53,6 +62,10 @@ pub struct PostExecCallback<'a> {
 /// 
 /// the base_context should be initialized at the point where
 /// fuzzing should begin, so it must already be initialized for execution.
+/// it is snapshotted on construction and restored before every run,
+/// so only memory dirtied by the previous run is copied.
+/// alternatively, use `run_to_entry` to advance the base context to
+/// a later entry point before fuzzing.
 /// 
 /// if cycle limit is None, then there is no limit.
 pub struct DttExecutor<'policy, 'backend, 'irb, 'plugin> {
@@ -69,6 +82,20 @@ pub struct DttExecutor<'policy, 'backend, 'irb, 'plugin> {
     post_exec_cb: Option<PostExecCallback<'plugin>>,
     evaluator: dtt::Evaluator<'policy, 'plugin>,
     base_context: dtt::Context<'backend>,
+    /// snapshot of the base context to restore before each run
+    snapshot: dtt::context::Snapshot,
+    /// evaluator pc and pc tag at the base state
+    base_pc: (Location, dtt::Tag),
+    /// why the last execution ended
+    exit_cause: ExitCauseRecord,
+    /// an optional fuzzer-driven interrupt injector
+    interrupts: Option<InterruptInjector>,
+    /// an optional terminal state detector
+    hangs: Option<HangDetector>,
+    /// how to end a run when the input channel runs dry
+    end_of_input: EndOfInputPolicy,
+    /// end runs with a crash when the firmware enters its HardFault handler
+    crash_on_hardfault: bool,
     pdb: ProgramDB<'irb>,
     access_log: (Sender<Access>, Receiver<Access>),
     read_src: (Sender<u8>, Receiver<u8>),
@@ -78,7 +105,7 @@ pub struct DttExecutor<'policy, 'backend, 'irb, 'plugin> {
 impl<'policy, 'backend, 'irb, 'plugin> DttExecutor<'policy, 'backend, 'irb, 'plugin> {
     pub fn new_with(
         evaluator: dtt::Evaluator<'policy, 'plugin>,
-        base_context: dtt::Context<'backend>,
+        mut base_context: dtt::Context<'backend>,
         pdb: programdb::ProgramDB<'irb>,
         limit: Option<usize>,
         exc_limit: Option<usize>,
@@ -89,9 +116,18 @@ impl<'policy, 'backend, 'irb, 'plugin> DttExecutor<'policy, 'backend, 'irb, 'plu
         read_src: (Sender<u8>, Receiver<u8>),
         write_dst: (Sender<u8>, Receiver<u8>),
     ) -> Self {
+        let snapshot = base_context.snapshot();
+        let base_pc = (evaluator.pc.clone(), evaluator.pc_tag);
         Self {
             evaluator,
             base_context,
+            snapshot,
+            base_pc,
+            exit_cause: ExitCauseRecord::default(),
+            interrupts: None,
+            hangs: None,
+            end_of_input: EndOfInputPolicy::default(),
+            crash_on_hardfault: true,
             pdb,
             limit,
             exc_limit,
@@ -104,13 +140,58 @@ impl<'policy, 'backend, 'irb, 'plugin> DttExecutor<'policy, 'backend, 'irb, 'plu
         }
     }
 
+    /// inject interrupts during execution according to a schedule
+    /// taken from the fuzz input
+    pub fn with_interrupt_injector(mut self, injector: InterruptInjector) -> Self {
+        self.interrupts = Some(injector);
+        self
+    }
+
+    /// end runs that reach a terminal state detected by the given detector
+    pub fn with_hang_detector(mut self, detector: HangDetector) -> Self {
+        self.hangs = Some(detector);
+        self
+    }
+
+    /// set whether entering the HardFault handler ends a run with a crash
+    /// 
+    /// faults are architectural exceptions, so a faulting firmware runs
+    /// its HardFault handler, which usually spins or resets.
+    /// by default, entering the handler is treated as the crash.
+    pub fn with_crash_on_hardfault(mut self, crash: bool) -> Self {
+        self.crash_on_hardfault = crash;
+        self
+    }
+
+    /// set the end-of-input policy
+    /// 
+    /// this should be the same policy given to the peripherals reading
+    /// from the input channel. by default, running out of input
+    /// is treated as a timeout.
+    pub fn with_end_of_input(mut self, end_of_input: EndOfInputPolicy) -> Self {
+        self.end_of_input = end_of_input;
+        self
+    }
+
+    /// get the interrupt injector, e.g. to load a schedule
+    /// from a separate input stream before each run
+    pub fn interrupt_injector_mut(&mut self) -> Option<&mut InterruptInjector> {
+        self.interrupts.as_mut()
+    }
+
+    /// load an input, splitting off the interrupt schedule
+    /// if interrupt injection is enabled
     #[instrument(skip_all)]
     pub fn load_input<I>(&mut self, input: &I) -> Result<(), super::Error>
     where
         I: HasTargetBytes,
     {
         let slice = input.target_bytes();
-        for (i, byte) in slice.iter().cloned().enumerate() {
+        let bytes = match self.interrupts {
+            Some(ref mut injector) => { injector.load_from_input(&slice[..]) }
+            None => { &slice[..] }
+        };
+        for (i, byte) in bytes.iter().cloned().enumerate() {
             match self.read_src.0.try_send(byte) {
                 Err(TrySendError::Disconnected(_)) => {
                     error!("failed to send byte #{i}: disconnected!");
@@ -126,14 +207,77 @@ impl<'policy, 'backend, 'irb, 'plugin> DttExecutor<'policy, 'backend, 'irb, 'plu
         Ok(())
     }
 
+    /// run the base context until the entry point is reached and
+    /// use the resulting context and evaluator pc as the new base state.
+    /// 
+    /// no input is loaded while running to the entry point, so it must
+    /// be reachable without reading from the input channel.
+    /// returns the number of cycles executed.
+    #[instrument(skip_all)]
+    pub fn run_to_entry(
+        &mut self,
+        entry: &super::EntryPoint,
+        limit: Option<usize>,
+    ) -> Result<usize, super::Error> {
+        let address = entry.resolve(self.pdb.program())?;
+        info!("running to entry point {entry:?} @ {:#x}...", address.offset());
+
+        self.base_context.restore(&self.snapshot)
+            .map_err(dtt::eval::Error::from)?;
+        while let Ok(_byte) = self.read_src.1.try_recv() {}
+
+        let cycles = super::entry::run_to_entry(
+            &mut self.evaluator,
+            &mut self.base_context,
+            &mut self.pdb,
+            address,
+            limit,
+        )?;
+
+        self.snapshot = self.base_context.snapshot();
+        self.base_pc = (self.evaluator.pc.clone(), self.evaluator.pc_tag);
+        Ok(cycles)
+    }
+
+    /// get an observer for the cause of each execution's exit
+    pub fn exit_cause_observer(&self, name: &'static str) -> ExitCauseObserver {
+        ExitCauseObserver::new(name, self.exit_cause.clone())
+    }
+
+    #[inline]
+    fn record_exit(&mut self, cause: ExitCause) {
+        // the handler address is the same for every hardfault,
+        // so hardfaults are recorded at the faulting instruction
+        let pc = match cause {
+            ExitCause::HardFault { address } => { address }
+            _ => { self.evaluator.pc.address().offset() }
+        };
+        *self.exit_cause.write() = Some(ExitCauseMetadata::new(pc, cause));
+    }
+
+    /// the return address of a HardFault taken in the last step,
+    /// if hardfaults are treated as crashes
+    fn _hardfault_entry(&self) -> Option<u64> {
+        if !self.crash_on_hardfault {
+            return None;
+        }
+        let hardfault = u32::from(&armv7m::ExceptionType::HardFault);
+        self.evaluator.last_thread_switch()
+            .filter(|switch| switch.typ == hardfault)
+            .and_then(|switch| switch.return_address.as_ref())
+            .map(|address| address.offset())
+    }
+
     #[inline]
     fn post_exec(
         &mut self,
-        context: dtt::Context<'backend>,
+        cause: ExitCause,
         result: Result<ExitKind, libafl::Error>,
     ) -> Result<ExitKind, libafl::Error> {
+        self.record_exit(cause);
         if let Some(ref mut post_exec_cb) = self.post_exec_cb {
-            return (post_exec_cb.callback)(&mut self.evaluator, &mut self.pdb, context, result);
+            return (post_exec_cb.callback)(
+                &mut self.evaluator, &mut self.pdb, &mut self.base_context, result);
         }
         result
     }
@@ -164,7 +308,16 @@ where
         *state.executions_mut() += 1;
         info!("EXECUTION COUNT: {}", *state.executions());
 
-        let mut context = self.base_context.clone();
+        self.base_context.restore(&self.snapshot)
+            .map_err(|err| {
+                libafl::Error::unknown(format!("failed to restore snapshot: {err:?}"))
+            })?;
+        (self.evaluator.pc, self.evaluator.pc_tag) = self.base_pc.clone();
+        self.evaluator.reset_implicit_flows();
+        self.evaluator.reset_plugins();
+        if let Some(ref mut injector) = self.interrupts {
+            injector.reset();
+        }
 
         // flush channels
         while let Ok(_access) = self.access_log.1.try_recv() {}
@@ -175,14 +328,33 @@ where
             .map_err(|err| {
                 libafl::Error::unknown(format!("{err:?}"))
             })?;
+        if let Some(ref mut detector) = self.hangs {
+            detector.reset();
+        }
+        self.end_of_input.take_stall();
 
+        // the first read that stalled on the exhausted input, after which
+        // the firmware idling is expected rather than a timeout
+        let mut stall: Option<Address> = None;
         let mut cycles: usize = 0;
         while self.limit.is_none() || cycles < self.limit.unwrap() {
-            let result = self.evaluator.step(&mut context, &mut self.pdb);
+            if let Some(ref mut injector) = self.interrupts {
+                if let Err(err) = injector.maybe_inject(cycles, &mut self.base_context) {
+                    error!("execution {:>4}: interrupt injection failed: {err:?}",
+                        *state.executions());
+                    let err = dtt::eval::Error::from(err);
+                    let cause = ExitCause::from_eval_error(&err);
+                    return self.post_exec(cause, Ok(ExitKind::Crash));
+                }
+            }
+            let result = self.evaluator.step(&mut self.base_context, &mut self.pdb);
+            let cause = result.as_ref().err()
+                .map(ExitCause::from_eval_error)
+                .unwrap_or(ExitCause::Halt);
             if let Some(ref mut step_cb) = self.step_cb {
                 match (step_cb.callback)(&result) {
-                    Ok(Some(kind)) => { return self.post_exec(context, Ok(kind)); }
-                    Err(err) => { return self.post_exec(context, Err(err)); }
+                    Ok(Some(kind)) => { return self.post_exec(cause, Ok(kind)); }
+                    Err(err) => { return self.post_exec(cause, Err(err)); }
                     Ok(None) => {  }
                 }
             }
@@ -191,44 +363,96 @@ where
                     // policy violation
                     error!("execution {:>4}: policy violation: {err:#x?}",
                         *state.executions());
-                    return self.post_exec(context, Ok(ExitKind::Crash));
+                    return self.post_exec(cause, Ok(ExitKind::Crash));
                 }
                 Err(dtt::eval::Error::Context(
                     dtt::context::Error::Backend(
                         backend::Error::Peripheral(err)
                 ))) => {
                     let peripheral::Error::State(err) = err.as_ref() else {
-                        return self.post_exec(context, Ok(ExitKind::Crash));
+                        return self.post_exec(cause, Ok(ExitKind::Crash));
                     };
                     if let Some(peripheral::channel::ChannelStateError::Recv(addr, err)) = err.downcast_ref() {
+                        if err.is_empty() && self.end_of_input.get() == EndOfInput::Exit {
+                            info!("execution {:>4}: input exhausted at {:#x}",
+                                *state.executions(),
+                                addr.offset());
+                            return self.post_exec(cause, Ok(ExitKind::Ok));
+                        }
                         error!("execution {:>4}: channel error on read at {}: {:?}",
                             *state.executions(),
                             addr.offset(),
                             err);
-                        return self.post_exec(context, Ok(ExitKind::Timeout));
+                        return self.post_exec(cause, Ok(ExitKind::Timeout));
                     } else {
-                        return self.post_exec(context, Ok(ExitKind::Crash));
+                        return self.post_exec(cause, Ok(ExitKind::Crash));
                     }
                 }
                 Err(err) => {
                     // other evaluation/emulation error
                     error!("execution {:>4}: other error: {err:#x?}",
                         *state.executions());
-                    return self.post_exec(context, Ok(ExitKind::Crash));
+                    return self.post_exec(cause, Ok(ExitKind::Crash));
                 }
                 _ => {
                     cycles += 1;
+                    if stall.is_none() {
+                        stall = self.end_of_input.take_stall();
+                        if let Some(address) = stall {
+                            info!("execution {:>4}: input exhausted at {:#x}, stalling",
+                                *state.executions(),
+                                address.offset());
+                        }
+                    }
+                    if let Some(address) = self._hardfault_entry() {
+                        error!("execution {:>4}: hardfault raised at {address:#x}",
+                            *state.executions());
+                        return self.post_exec(ExitCause::HardFault { address }, Ok(ExitKind::Crash));
+                    }
+                    if let Some(reason) = self.base_context.halt_reason() {
+                        info!("execution {:>4}: halted: {reason:?}",
+                            *state.executions());
+                        let (cause, kind) = ExitCause::from_halt_reason(&reason);
+                        return self.post_exec(cause, Ok(kind));
+                    }
                     if let Some(ref mut halt_cb) = self.halt_cb {
                         if let Some(kind) = (halt_cb.callback)(
-                            &mut self.evaluator, &mut self.pdb, &mut context)
+                            &mut self.evaluator, &mut self.pdb, &mut self.base_context)
                         {
-                            return self.post_exec(context, Ok(kind));
+                            return self.post_exec(cause, Ok(kind));
+                        }
+                    }
+                    if let Some(ref mut detector) = self.hangs {
+                        match detector.check(cycles, &self.evaluator, &mut self.base_context) {
+                            Ok(Some((hang, kind))) => {
+                                info!("execution {:>4}: {hang:?} detected after {cycles} cycles",
+                                    *state.executions());
+                                if let Some(address) = stall {
+                                    let cause = ExitCause::InputExhausted { address: address.offset() };
+                                    return self.post_exec(cause, Ok(ExitKind::Ok));
+                                }
+                                return self.post_exec(ExitCause::Hang { hang }, Ok(kind));
+                            }
+                            Err(err) => {
+                                error!("execution {:>4}: hang detection failed: {err:?}",
+                                    *state.executions());
+                                let err = dtt::eval::Error::from(err);
+                                let cause = ExitCause::from_eval_error(&err);
+                                return self.post_exec(cause, Ok(ExitKind::Crash));
+                            }
+                            Ok(None) => {  }
                         }
                     }
                 }
             }
         }
+        if let Some(address) = stall {
+            info!("cycle limit hit while stalled on exhausted input");
+            self.record_exit(ExitCause::InputExhausted { address: address.offset() });
+            return Ok(ExitKind::Ok);
+        }
         error!("cycle limit hit! exiting with timeout...");
+        self.record_exit(ExitCause::CycleLimit);
         Ok(ExitKind::Timeout)
     }
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+//! setup.rs
+//!
+//! shared helpers for building a harness from a platform description
+//!
+//! these cover the setup that every harness repeats: loading the program
+//! into a context, initializing it from the vector table, mapping the
+//! platform's peripherals as channel peripherals, and building a taint
+//! policy by name.
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crossbeam::channel::Receiver;
+use fugue_core::language::Language;
+
+use libcme::{
+    prelude::*,
+    backend::semihosting::Semihosting,
+    dtt::policy::TaintPolicy,
+    peripheral::channel::{ChannelPeripheral, EndOfInput, EndOfInputPolicy},
+};
+
+use crate::policy::{
+    TaintedJumpPolicy,
+    TaintedAddressPolicy,
+    TaintedOverflowPolicy,
+};
+use crate::instrumentation::CallStackPlugin;
+
+/// a platform peripheral region to be mapped as a channel peripheral
+#[derive(Debug, Clone)]
+pub struct MmioMapping {
+    pub name: String,
+    pub base: Address,
+    pub size: usize,
+    pub tag: dtt::Tag,
+}
+
+/// get the platform's peripheral regions, tainting those named in `sources`
+///
+/// if `peripherals` is given, only the named regions are included.
+/// overlapping peripheral regions are merged and their tags or'd together,
+/// and names that aren't platform peripherals are an error.
+pub fn mmio_mappings(
+    platform: &Platform,
+    peripherals: Option<&[String]>,
+    sources: &[String],
+) -> Result<Vec<MmioMapping>, super::Error> {
+    let names = peripherals.into_iter().flatten().chain(sources.iter());
+    for name in names {
+        if !platform.mmio().iter().any(|region| &region.name == name) {
+            return Err(super::Error::UnknownPeripheral(name.clone()));
+        }
+    }
+
+    let mut regions: Vec<MmioMapping> = vec![];
+    for &MmioRegion {
+        ref name,
+        base,
+        blocksize,
+        perms: _,
+        description: _,
+    } in platform.mmio().iter() {
+        if peripherals.is_some_and(|peripherals| !peripherals.contains(name)) {
+            continue;
+        }
+        let tag: dtt::Tag = if sources.contains(name) {
+            tag::TAINTED_VAL.into()
+        } else {
+            tag::ACCESSED.into()
+        };
+        regions.push(MmioMapping { name: name.clone(), base, size: blocksize, tag });
+    }
+
+    // in address order, a region only overlaps the mapping before it,
+    // which may already have absorbed several others
+    regions.sort_by_key(|region| region.base.offset());
+    let mut mappings: Vec<MmioMapping> = vec![];
+    for region in regions {
+        let region_end = region.base.offset() + region.size as u64;
+        match mappings.last_mut() {
+            Some(mapping) if region.base.offset() < mapping.base.offset() + mapping.size as u64 => {
+                let end = std::cmp::max(region_end, mapping.base.offset() + mapping.size as u64);
+                mapping.size = (end - mapping.base.offset()) as usize;
+                mapping.tag |= region.tag;
+            }
+            _ => { mappings.push(region) }
+        }
+    }
+    Ok(mappings)
+}
+
+/// map each region with a clone of the given channel peripheral
+pub fn map_channel_peripherals(
+    context: &mut dtt::Context,
+    peripheral: &ChannelPeripheral,
+    mappings: &[MmioMapping],
+) -> Result<(), dtt::context::Error> {
+    for mapping in mappings {
+        info!("mapping peripheral {} @ [{:#x}; {:#x}]",
+            mapping.name, mapping.base.offset(), mapping.size);
+        let new_peripheral = peripheral.clone_with(mapping.base, mapping.size);
+        context.map_mmio(new_peripheral.into(), Some(mapping.tag))?;
+    }
+    Ok(())
+}
+
+/// attach semihosting to the context so that semihosting reads consume
+/// the same input as the channel peripherals. console input is tainted
+/// like a read from a source peripheral and counted with `peripheral`.
+///
+/// file operations are confined to the sandbox directory, if given,
+/// and fail otherwise. writes are kept in memory and undone on restore,
+/// so runs and clients never change the directory.
+pub fn attach_semihosting(
+    context: &mut dtt::Context,
+    peripheral: &ChannelPeripheral,
+    read_src: &Receiver<u8>,
+    end_of_input: &EndOfInputPolicy,
+    sandbox: Option<&Path>,
+) -> Result<(), dtt::context::Error> {
+    let mut semihosting = Semihosting::new()
+        .with_stdin(read_src.clone())
+        .with_end_of_input(end_of_input.clone())
+        .with_input_counter(peripheral.input_counter());
+    if let Some(sandbox) = sandbox {
+        info!("semihosting sandbox: {}", sandbox.display());
+        semihosting = semihosting.with_sandbox(sandbox);
+    }
+    context.attach_semihosting(semihosting, Some(tag::TAINTED_VAL.into()))
+}
+
+/// store the program's loadable segments in the context
+pub fn load_program(
+    context: &mut dtt::Context,
+    program: &Program,
+) -> Result<(), dtt::context::Error> {
+    for segment in program.loadable_segments() {
+        context.store_bytes(
+            segment.p_paddr(),
+            segment.data(),
+            &dtt::Tag::from(tag::UNACCESSED),
+        )?;
+    }
+    Ok(())
+}
+
+/// initialize the stack pointer and pc from the vector table at address 0
+pub fn init_from_vector_table(context: &mut dtt::Context) -> Result<(), dtt::context::Error> {
+    let mut stack_bytes = [0u8; 4];
+    context.load_bytes(0u64, &mut stack_bytes)?;
+    let stack_top = u32::from_le_bytes(stack_bytes);
+    context.write_sp(stack_top, &dtt::Tag::from(tag::ACCESSED))?;
+
+    let mut entry_bytes = [0u8; 4];
+    context.load_bytes(4u64, &mut entry_bytes)?;
+    let entry = u32::from_le_bytes(entry_bytes);
+    context.write_pc(entry, &dtt::Tag::from(tag::ACCESSED))?;
+    Ok(())
+}
+
+/// the taint policies a harness can be built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyKind {
+    Jump,
+    Address,
+    Overflow,
+}
+
+impl FromStr for PolicyKind {
+    type Err = super::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jump" => { Ok(Self::Jump) }
+            "address" => { Ok(Self::Address) }
+            "overflow" => { Ok(Self::Overflow) }
+            _ => { Err(super::Error::UnknownPolicy(s.to_string())) }
+        }
+    }
+}
+
+impl PolicyKind {
+    /// build the policy along with any plugin it depends on
+    pub fn build(
+        &self,
+        lang: Arc<Language>,
+    ) -> (Box<dyn TaintPolicy>, Option<Box<dyn EvalPlugin>>) {
+        match self {
+            PolicyKind::Jump => {
+                let policy = TaintedJumpPolicy::new_with(lang);
+                (Box::new(policy), None)
+            }
+            PolicyKind::Address => {
+                // the address policy needs frame information from a callstack plugin
+                let callstack_plugin = CallStackPlugin::default();
+                let call_channel = callstack_plugin.call_channel.clone();
+                let policy = TaintedAddressPolicy::new_with(lang, call_channel);
+                (Box::new(policy), Some(Box::new(callstack_plugin)))
+            }
+            PolicyKind::Overflow => {
+                let policy = TaintedOverflowPolicy::new_with(lang);
+                (Box::new(policy), None)
+            }
+        }
+    }
+}
+
+/// parse an end-of-input policy (`exit`, `timeout`, `stall`, or `fill:<byte>`)
+pub fn parse_end_of_input(s: &str) -> Result<EndOfInput, super::Error> {
+    match s {
+        "exit" => { Ok(EndOfInput::Exit) }
+        "timeout" => { Ok(EndOfInput::Timeout) }
+        "stall" => { Ok(EndOfInput::Stall) }
+        s => {
+            let byte = s.strip_prefix("fill:")
+                .and_then(|byte| super::entry::parse_uint(byte))
+                .and_then(|byte| u8::try_from(byte).ok())
+                .ok_or_else(|| super::Error::Parse(format!("end of input: {s}")))?;
+            Ok(EndOfInput::Fill(byte))
+        }
+    }
+}
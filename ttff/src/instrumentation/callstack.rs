@@ -0,0 +1,80 @@
+//! callstack.rs
+//! 
+//! call stack tracking plugin for the tainted address policy
+use std::collections::VecDeque;
+
+use crossbeam::channel::{
+    unbounded,
+    Sender,
+    Receiver,
+};
+
+use libcme::prelude::*;
+
+use crate::policy::address::{FrameUpdate, FrameStart};
+
+#[derive(Debug)]
+pub struct CallStackPlugin {
+    pub callstack: VecDeque<Address>,
+    pub call_channel: (Sender<FrameUpdate>, Receiver<FrameUpdate>),
+}
+
+impl Default for CallStackPlugin {
+    fn default() -> Self {
+        let callstack = VecDeque::default();
+        let call_channel = unbounded();
+        Self { callstack, call_channel }
+    }
+}
+
+impl EvalPlugin for CallStackPlugin {
+
+    #[instrument(skip_all)]
+    fn post_insn_cb<'irb, 'backend>(
+        &mut self,
+        _loc: &Location,
+        _insn: &Insn<'irb>,
+        flow: &Flow,
+        context: &mut dtt::Context<'backend>,
+        _pdb: &mut ProgramDB<'irb>,
+    ) -> Result<(), dtt::plugin::Error> {
+        let err = |e: backend::Error| dtt::plugin::Error(e.into());
+        match flow.flowtype {
+            FlowType::Call
+            | FlowType::ICall => {
+                // the call has executed, so the pc is the target
+                // when the flow doesn't know it
+                let target = match flow.target {
+                    Some(ref target) => { target.address() }
+                    None => { context.backend().read_pc().map_err(err)? }
+                };
+                debug!("calling {:#x} from {:#x}",
+                    target.offset(),
+                    self.callstack.back().unwrap_or(&0u64.into()).offset());
+                self.callstack.push_back(target);
+                let frame_start = FrameStart {
+                    pc: target,
+                    sp: context.backend().read_sp().map_err(err)?,
+                };
+                let _ = self.call_channel.0.try_send(FrameUpdate::Call(frame_start));
+                Ok(())
+            }
+            FlowType::Return => {
+                // returns from frames entered before tracking started,
+                // e.g. the entry function, have nothing to pop
+                if self.callstack.pop_back().is_none() {
+                    debug!("return with empty call stack");
+                    return Ok(());
+                }
+                let _ = self.call_channel.0.try_send(FrameUpdate::Return);
+                Ok(())
+            }
+            _ => { Ok(()) }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.callstack.clear();
+        let _ = self.call_channel.0.try_send(FrameUpdate::Reset);
+    }
+}
@@ -1,6 +1,342 @@
 //! cmplog.rs
-//! 
+//!
 //! CmpLog implementation
-//! 
-//! should be built from the libafl CmpObserver, but there's quite a bit of 
-//! background work that needs to be done to set that up.
+//!
+//! the plugin records the operands of integer comparisons and the
+//! arguments of known comparison functions during execution. the observer
+//! then publishes them as `CmpValuesMetadata` in the fuzzer state after
+//! each run, which is where input-to-state mutators like `I2SRandReplace`
+//! look for them.
+//!
+//! only `CmpValuesMetadata` is produced. there is no `CmpMap` or
+//! `AFLppCmpValuesMetadata`, so the AFL++ redqueen and colorization
+//! stages can't use this observer.
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use fugue_ir::disassembly::{Opcode, PCodeData};
+use fugue_ir::VarnodeData;
+
+use libcme::prelude::*;
+use libafl::{
+    executors::ExitKind,
+    observers::{CmpValues, CmpValuesMetadata, CmplogBytes, Observer},
+    HasMetadata,
+};
+use libafl_bolts::Named;
+
+/// maximum number of comparisons logged per execution
+pub const CMPLOG_MAX_ENTRIES: usize = 0x1000;
+
+/// maximum number of bytes logged per comparison function operand
+const CMPLOG_MAX_BYTES: usize = 32;
+
+/// shared comparison log written by the plugin
+pub type CmpLog = Arc<RwLock<Vec<CmpValues>>>;
+
+/// how a comparison function's operands are bounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpFn {
+    /// (a, b, n) compares exactly n bytes
+    Mem,
+    /// (a, b) compares up to a nul terminator
+    Str,
+    /// (a, b, n) compares up to n bytes or a nul terminator
+    StrN,
+}
+
+/// comparison functions whose arguments are logged
+const CMP_FUNCTIONS: &[(&str, CmpFn)] = &[
+    ("memcmp", CmpFn::Mem),
+    ("bcmp", CmpFn::Mem),
+    ("strcmp", CmpFn::Str),
+    ("strcasecmp", CmpFn::Str),
+    ("strncmp", CmpFn::StrN),
+    ("strncasecmp", CmpFn::StrN),
+];
+
+/// cmplog eval plugin
+#[derive(Debug, Default)]
+pub struct CmpLogPlugin {
+    pub log: CmpLog,
+    /// resolved comparison function entry addresses
+    functions: Option<Vec<(u64, CmpFn)>>,
+    /// argument registers r0-r2
+    args: Vec<VarnodeData>,
+}
+
+impl CmpLogPlugin {
+    pub fn new_with(log: CmpLog) -> Self {
+        Self { log, ..Default::default() }
+    }
+
+    /// get an observer for this plugin's log
+    pub fn observer(&self, name: &'static str) -> CmpLogObserver {
+        CmpLogObserver::new(name, self.log.clone())
+    }
+
+    fn _push(&self, values: CmpValues) {
+        let mut log = self.log.write();
+        if log.len() < CMPLOG_MAX_ENTRIES {
+            log.push(values);
+        }
+    }
+
+    fn _resolve_functions(&mut self, pdb: &ProgramDB, context: &dtt::Context) {
+        let symtab = pdb.program().symtab();
+        let functions = CMP_FUNCTIONS.iter()
+            .filter_map(|(name, cmp_fn)| {
+                // thumb function symbols have the low bit set
+                symtab.get(*name).map(|symbol| (symbol.st_value & !1, *cmp_fn))
+            })
+            .collect::<Vec<_>>();
+        debug!("logging comparison functions: {functions:#x?}");
+        self.functions = Some(functions);
+
+        let t = context.lang().translator();
+        self.args = ["r0", "r1", "r2"].iter()
+            .map(|name| t.register_by_name(name).unwrap())
+            .collect();
+    }
+
+    /// read up to `len` bytes of a comparison function operand,
+    /// only reading from memory so that peripherals aren't disturbed
+    fn _read_operand(
+        &self,
+        context: &mut dtt::Context,
+        address: u64,
+        len: usize,
+        nul_terminated: bool,
+    ) -> Result<Option<CmplogBytes>, dtt::context::Error> {
+        let len = len.min(CMPLOG_MAX_BYTES);
+        let start = Address::from(address);
+        let end = Address::from(address + len as u64);
+        let in_mem = context.backend().mmap().mapped().any(|range| match range {
+            MappedRange::Mem(range) => { range.start <= start && end <= range.end }
+            MappedRange::Mmio(_) => { false }
+        });
+        if !in_mem {
+            return Ok(None);
+        }
+        let mut buf = [0u8; CMPLOG_MAX_BYTES];
+        context.load_bytes(start, &mut buf[..len])?;
+        let len = match nul_terminated {
+            true => { buf[..len].iter().position(|&b| b == 0).unwrap_or(len) }
+            false => { len }
+        };
+        Ok(Some(CmplogBytes::from_buf_and_len(buf, len as u8)))
+    }
+
+    fn _read_arg(&self, context: &mut dtt::Context, i: usize) -> Result<u64, dtt::context::Error> {
+        let (val, _tag) = context.read(&self.args[i])?;
+        Ok(val.to_u64().unwrap_or_default())
+    }
+}
+
+impl EvalPlugin for CmpLogPlugin {
+
+    #[instrument(skip_all)]
+    fn pre_insn_cb<'irb, 'backend>(
+        &mut self,
+        loc: &Location,
+        _insn: &Insn<'irb>,
+        context: &mut dtt::Context<'backend>,
+        pdb: &mut ProgramDB<'irb>,
+    ) -> Result<(), dtt::plugin::Error> {
+        if self.functions.is_none() {
+            self._resolve_functions(pdb, context);
+        }
+        let address = loc.address().offset();
+        let Some(&(_, cmp_fn)) = self.functions.as_ref().unwrap().iter()
+            .find(|(entry, _)| *entry == address)
+        else {
+            return Ok(());
+        };
+
+        let err = |e: dtt::context::Error| dtt::plugin::Error(e.into());
+        let a = self._read_arg(context, 0).map_err(err)?;
+        let b = self._read_arg(context, 1).map_err(err)?;
+        let (len, nul_terminated) = match cmp_fn {
+            CmpFn::Mem => { (self._read_arg(context, 2).map_err(err)? as usize, false) }
+            CmpFn::Str => { (CMPLOG_MAX_BYTES, true) }
+            CmpFn::StrN => { (self._read_arg(context, 2).map_err(err)? as usize, true) }
+        };
+        let lhs = self._read_operand(context, a, len, nul_terminated).map_err(err)?;
+        let rhs = self._read_operand(context, b, len, nul_terminated).map_err(err)?;
+        if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+            debug!("{cmp_fn:?} comparison @ {address:#x}: {lhs:?} {rhs:?}");
+            self._push(CmpValues::Bytes((lhs, rhs)));
+        }
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    fn pre_pcode_cb<'irb, 'backend>(
+        &mut self,
+        _loc: &Location,
+        pcode: &PCodeData<'irb>,
+        context: &mut dtt::Context<'backend>,
+        _pdb: &mut ProgramDB<'irb>,
+    ) -> Result<(), dtt::plugin::Error> {
+        match pcode.opcode {
+            Opcode::IntEq
+            | Opcode::IntNotEq
+            | Opcode::IntLess
+            | Opcode::IntSLess
+            | Opcode::IntLessEq
+            | Opcode::IntSLessEq => {  }
+            _ => { return Ok(()) }
+        }
+        let (mut lhs_vnd, mut rhs_vnd) = (&pcode.inputs[0], &pcode.inputs[1]);
+        // comparisons of two constants can't be influenced by the input
+        if lhs_vnd.space().is_constant() && rhs_vnd.space().is_constant() {
+            return Ok(());
+        }
+        // operands may be swapped by sleigh, keep any constant second
+        if lhs_vnd.space().is_constant() {
+            std::mem::swap(&mut lhs_vnd, &mut rhs_vnd);
+        }
+        let err = |e: dtt::context::Error| dtt::plugin::Error(e.into());
+        let (lhs, _) = context.read(lhs_vnd).map_err(err)?;
+        let (rhs, _) = context.read(rhs_vnd).map_err(err)?;
+        let (Some(lhs), Some(rhs)) = (lhs.to_u64(), rhs.to_u64()) else {
+            return Ok(());
+        };
+        let is_const = rhs_vnd.space().is_constant();
+        let values = match lhs_vnd.size() {
+            1 => { CmpValues::U8((lhs as u8, rhs as u8, is_const)) }
+            2 => { CmpValues::U16((lhs as u16, rhs as u16, is_const)) }
+            4 => { CmpValues::U32((lhs as u32, rhs as u32, is_const)) }
+            8 => { CmpValues::U64((lhs, rhs, is_const)) }
+            _ => { return Ok(()) }
+        };
+        self._push(values);
+        Ok(())
+    }
+}
+
+/// an observer that publishes the comparison log as state metadata
+///
+/// get one from the plugin with `CmpLogPlugin::observer`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CmpLogObserver {
+    name: Cow<'static, str>,
+    #[serde(skip)]
+    log: CmpLog,
+}
+
+impl CmpLogObserver {
+    pub fn new(name: &'static str, log: CmpLog) -> Self {
+        Self { name: Cow::from(name), log }
+    }
+}
+
+impl Named for CmpLogObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, S> Observer<I, S> for CmpLogObserver
+where
+    S: HasMetadata,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), libafl::Error> {
+        self.log.write().clear();
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), libafl::Error> {
+        let meta = state.metadata_or_insert_with(CmpValuesMetadata::new);
+        meta.list.clear();
+        meta.list.extend(self.log.read().iter().cloned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use libafl::{
+        corpus::InMemoryCorpus,
+        inputs::BytesInput,
+        mutators::{MutationResult, Mutator, token_mutations::I2SRandReplace},
+        state::StdState,
+    };
+    use libafl_bolts::rands::StdRand;
+    use super::*;
+    use crate::harness::setup;
+
+    #[test]
+    fn test_cmplog_metadata() -> Result<(), anyhow::Error> {
+        let irb = IRBuilderArena::with_capacity(0x10000);
+        let bytes = fs::read("data/nrf52/blinky-o0.elf")?;
+        let elf_bytes = ElfBytes::minimal_parse(bytes.as_slice())?;
+        let program = Program::new_from_elf(irb.inner(), elf_bytes)?;
+        let builder = LanguageBuilder::new("data/processors")?;
+        let platform = Platform::from_path("data/nrf52/nrf52.yml")?;
+        let mut pdb = ProgramDB::new_with(&builder, program, platform, &irb);
+
+        let backend = pdb.backend(&builder)?;
+        let mut context = dtt::Context::from_backend(backend)?;
+        setup::load_program(&mut context, pdb.program())?;
+        setup::init_from_vector_table(&mut context)?;
+
+        let plugin = CmpLogPlugin::default();
+        let mut observer = plugin.observer("cmplog");
+        let mut evaluator = dtt::Evaluator::new();
+        evaluator.add_plugin(Box::new(plugin));
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut (),
+            &mut (),
+        )?;
+        let input = BytesInput::new(vec![]);
+        observer.pre_exec(&mut state, &input)?;
+
+        // Reset_Handler compares the end of the data section with its start
+        (evaluator.pc, evaluator.pc_tag) = context.read_pc()
+            .map(|(pc, tag)| (Location::from(pc), tag))?;
+        for _ in 0..5 {
+            evaluator.step(&mut context, &mut pdb)?;
+        }
+        observer.post_exec(&mut state, &input, &ExitKind::Ok)?;
+
+        let meta = state.metadata::<CmpValuesMetadata>()?;
+        assert!(!meta.list.is_empty(), "comparison should be logged");
+        assert!(meta.list.iter().any(|values| matches!(values, CmpValues::U32(_))));
+
+        // the input-to-state mutator replaces logged operands in the input
+        let operands = meta.list.iter()
+            .filter_map(|values| match values {
+                CmpValues::U32((lhs, rhs, _)) => { Some([*lhs, *rhs]) }
+                _ => { None }
+            })
+            .flatten()
+            .flat_map(u32::to_le_bytes)
+            .collect::<Vec<u8>>();
+        let mut i2s = I2SRandReplace::new();
+        let mutated = (0..0x1000).any(|_| {
+            let mut input = BytesInput::new(operands.clone());
+            matches!(i2s.mutate(&mut state, &mut input), Ok(MutationResult::Mutated))
+        });
+        assert!(mutated, "logged operands should be replaced");
+
+        // the log is cleared before the next run
+        observer.pre_exec(&mut state, &input)?;
+        observer.post_exec(&mut state, &input, &ExitKind::Ok)?;
+        assert!(state.metadata::<CmpValuesMetadata>()?.list.is_empty());
+        Ok(())
+    }
+}
@@ -4,6 +4,8 @@
 
 use std::ops::{Index, IndexMut};
 
+use libafl::observers::{HitcountsMapObserver, StdMapObserver};
+use libafl_bolts::shmem::{ShMem, ShMemProvider};
 
 
 #[derive(Debug, Clone)]
@@ -48,4 +50,49 @@ impl AsMut<[u8]> for CovMap {
     fn as_mut(&mut self) -> &mut [u8] {
         unsafe { std::slice::from_raw_parts_mut(self.ptr, self.size) }
     }
-}
\ No newline at end of file
+}
+
+/// a coverage map backed by shared memory
+/// 
+/// each libafl client should own its own coverage map
+/// rather than sharing a `static mut` across clients.
+#[derive(Debug)]
+pub struct ShMemCovMap<SHM> {
+    shmem: SHM,
+}
+
+impl<SHM: ShMem> ShMemCovMap<SHM> {
+    pub fn new<SP>(shmem_provider: &mut SP, size: usize) -> Result<Self, libafl::Error>
+    where
+        SP: ShMemProvider<ShMem = SHM>,
+    {
+        if !size.is_power_of_two() {
+            return Err(libafl::Error::illegal_argument(
+                format!("coverage map size must be a power of 2: {size:#x}")));
+        }
+        let shmem = shmem_provider.new_shmem(size)?;
+        Ok(Self { shmem })
+    }
+
+    pub fn size(&self) -> usize {
+        self.shmem.len()
+    }
+
+    /// get a raw coverage map for instrumentation plugins
+    /// 
+    /// the returned map must not outlive the shared memory
+    pub fn covmap(&mut self) -> CovMap {
+        let size = self.size();
+        CovMap::new(&mut *self.shmem as *mut [u8], size)
+    }
+
+    /// get a hitcounts edge observer over the coverage map
+    pub fn observer<'a>(
+        &'a mut self,
+        name: &'static str,
+    ) -> HitcountsMapObserver<StdMapObserver<'a, u8, false>> {
+        // the observer borrows the shared memory, so it can't outlive it
+        let observer = unsafe { StdMapObserver::new(name, &mut *self.shmem) };
+        HitcountsMapObserver::new(observer)
+    }
+}
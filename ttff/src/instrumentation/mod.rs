@@ -9,9 +9,12 @@ pub mod hc;
 pub mod csbc;
 pub mod cmplog;
 pub mod ttrace;
+pub mod callstack;
 
-pub use covmap::CovMap;
+pub use covmap::{CovMap, ShMemCovMap};
 pub use hc::HcPlugin;
 pub use csbc::CsbcPlugin;
+pub use cmplog::{CmpLogPlugin, CmpLogObserver};
 pub use mem::{MemCallback, MemInterceptPlugin};
-pub use ttrace::TaintTracePlugin;
\ No newline at end of file
+pub use ttrace::{TaintTracePlugin, InsnTracePlugin};
+pub use callstack::CallStackPlugin;
\ No newline at end of file
@@ -1,12 +1,24 @@
 //! ttrace.rs
-//! 
+//!
 //! taint trace plugin
+use crossbeam::channel::Sender;
+
 use libcme::prelude::*;
 
 use fugue_ir::disassembly::PCodeData;
 
 #[derive(Debug, Default)]
-pub struct TaintTracePlugin {}
+pub struct TaintTracePlugin {
+    /// an optional sink for tainted locations
+    pub locations: Option<Sender<Location>>,
+}
+
+impl TaintTracePlugin {
+    /// also send each tainted location over the given channel
+    pub fn new_with(locations: Sender<Location>) -> Self {
+        Self { locations: Some(locations) }
+    }
+}
 
 impl EvalPlugin for TaintTracePlugin {
 
@@ -37,7 +49,39 @@ impl EvalPlugin for TaintTracePlugin {
         if is_tainted {
             warn!("TAINTED LOCATION: {:#010x}-{}",
                 loc.address().offset(), loc.position());
+            if let Some(ref locations) = self.locations {
+                let _ = locations.try_send(loc.clone());
+            }
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// instruction trace plugin
+///
+/// sends the address and disassembly of every executed instruction
+#[derive(Debug)]
+pub struct InsnTracePlugin {
+    pub trace: Sender<(Address, String)>,
+}
+
+impl InsnTracePlugin {
+    pub fn new_with(trace: Sender<(Address, String)>) -> Self {
+        Self { trace }
+    }
+}
+
+impl EvalPlugin for InsnTracePlugin {
+
+    #[instrument(skip_all)]
+    fn pre_insn_cb<'irb, 'backend>(
+        &mut self,
+        loc: &Location,
+        insn: &Insn<'irb>,
+        _context: &mut dtt::Context<'backend>,
+        _pdb: &mut ProgramDB<'irb>,
+    ) -> Result<(), dtt::plugin::Error> {
+        let _ = self.trace.try_send((loc.address(), insn.disasm_str()));
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+//! main.rs
+//!
+//! config-driven fuzzer front end
+//!
+//! usage: ttff <config.yml>
+//!
+//! see `harness::config` for the config file format.
+use std::fs;
+use std::sync::Arc;
+
+use anyhow;
+use libafl_bolts::{
+    rands::StdRand,
+    shmem::{ShMemProvider, StdShMemProvider},
+    tuples::tuple_list,
+    nonzero,
+};
+use libafl::{
+    self,
+    corpus::OnDiskCorpus,
+    events::SimpleEventManager,
+    executors::{ExitKind, WithObservers},
+    feedback_and_fast, feedback_or, feedback_or_fast,
+    feedbacks::{CrashFeedback, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
+    fuzzer::{Fuzzer, StdFuzzer},
+    generators::RandBytesGenerator,
+    inputs::BytesInput,
+    monitors::MultiMonitor,
+    mutators::{
+        havoc_mutations::havoc_mutations,
+        scheduled::StdScheduledMutator,
+        token_mutations::I2SRandReplace,
+    },
+    observers::{
+        CanTrack,
+        TimeObserver,
+    },
+    schedulers::QueueScheduler,
+    stages::{
+        AflStatsStage,
+        StdMutationalStage,
+    },
+    state::StdState,
+};
+
+use libcme::prelude::*;
+use libcme::peripheral::channel::{ChannelPeripheral, EndOfInputPolicy, GeneratedChannelPeripheral};
+use ttff::prelude::*;
+use ttff::harness::setup;
+
+const COVMAP_SIZE: usize = 0x2000;
+
+pub fn main() -> Result<(), anyhow::Error> {
+    let path = std::env::args().nth(1)
+        .ok_or_else(|| anyhow::anyhow!("usage: ttff <config.yml>"))?;
+    let config = FuzzConfig::from_path(&path)?;
+
+    let _guard = match config.log_file {
+        Some(ref path) => {
+            let (global_sub, guard) = compact_file_logger(path, Level::TRACE);
+            set_global_default(global_sub)?;
+            Some(guard)
+        }
+        None => { None }
+    };
+    info!("loaded config {path}: {config:#?}");
+
+    let irb = IRBuilderArena::with_capacity(0x10000);
+
+    let mut shmem_provider = StdShMemProvider::new()?;
+    let mut covmap = ShMemCovMap::new(&mut shmem_provider, COVMAP_SIZE)?;
+
+    info!("reading program binary...");
+    let bytes = fs::read(&config.firmware)?;
+    let elf_bytes = ElfBytes::minimal_parse(bytes.as_slice())?;
+    let program = Program::new_from_elf(irb.inner(), elf_bytes)?;
+
+    info!("creating language builder...");
+    let builder = LanguageBuilder::new(&config.processors)?;
+
+    info!("building programdb...");
+    let platform = Platform::from_path(&config.platform)?;
+    let mut pdb = ProgramDB::new_with(&builder, program, platform, &irb);
+
+    let hc_plugin = HcPlugin::new(covmap.covmap());
+    pdb.add_plugin(Box::new(hc_plugin));
+
+    info!("building context...");
+    let backend = pdb.backend(&builder)?;
+    let mut context = dtt::Context::from_backend(backend)?;
+
+    info!("mapping peripherals...");
+    let end_of_input = EndOfInputPolicy::new(config.end_of_input);
+    let GeneratedChannelPeripheral {
+        access_log,
+        read_src,
+        write_dst,
+        peripheral,
+    } = ChannelPeripheral::new(Address::default(), 0x1000);
+    let peripheral = peripheral.with_end_of_input(end_of_input.clone());
+    let mappings = setup::mmio_mappings(
+        pdb.platform(),
+        config.peripherals.as_deref(),
+        &config.sources,
+    )?;
+    setup::map_channel_peripherals(&mut context, &peripheral, &mappings)?;
+    context.set_reset_policy(config.reset_policy);
+    if config.semihosting {
+        setup::attach_semihosting(
+            &mut context,
+            &peripheral,
+            &read_src.1,
+            &end_of_input,
+            config.sandbox.as_deref(),
+        )?;
+    }
+
+    info!("loading program binary...");
+    setup::load_program(&mut context, pdb.program())?;
+
+    info!("initializing context...");
+    setup::init_from_vector_table(&mut context)?;
+
+    info!("building taint policy...");
+    let lang = Arc::new(pdb.lang().clone());
+    let (policy, policy_plugin) = config.policy.build(lang);
+
+    info!("building evaluator...");
+    let mut evaluator = dtt::Evaluator::new_with_policy(policy);
+    if let Some(plugin) = policy_plugin {
+        evaluator.add_plugin(plugin);
+    }
+    if config.implicit_flows {
+        evaluator.enable_implicit_flows();
+    }
+    if config.bit_precise {
+        evaluator.enable_bit_precise();
+    }
+    evaluator.add_plugin(Box::new(TaintTracePlugin::default()));
+    let cmplog_plugin = CmpLogPlugin::default();
+    let cmplog_observer = cmplog_plugin.observer("cmplog");
+    evaluator.add_plugin(Box::new(cmplog_plugin));
+    (evaluator.pc, evaluator.pc_tag) = context.read_pc()
+        .map(|(pc, tag)| (Location::from(pc), tag))?;
+
+    info!("building dtt executor...");
+    let halts = config.halts.iter()
+        .map(|halt| halt.resolve(pdb.program()).map(|address| address.offset()))
+        .collect::<Result<Vec<u64>, _>>()?;
+    let halt_on_exit = &mut |
+        evaluator: &dtt::Evaluator,
+        _pdb: &ProgramDB,
+        _context: &mut dtt::Context,
+    | {
+        let pc = evaluator.pc.address().offset();
+        halts.contains(&pc).then(|| {
+            info!("halt address {pc:#x} reached");
+            ExitKind::Ok
+        })
+    };
+    let halt_cb = Some(sc::HaltCallback {
+        callback: halt_on_exit,
+    });
+
+    let mut dtt_executor = sc::DttExecutor::new_with(
+        evaluator,
+        context,
+        pdb,
+        config.cycle_limit,
+        config.exec_limit,
+        halt_cb,
+        None,
+        None,
+        access_log.clone(),
+        read_src.clone(),
+        write_dst.clone(),
+    )
+    .with_end_of_input(end_of_input)
+    .with_crash_on_hardfault(config.crash_on_hardfault)
+    .with_hang_detector(config.hang.clone());
+
+    if let Some(ref entry) = config.entry {
+        info!("running to fuzzing entry point...");
+        dtt_executor.run_to_entry(entry, config.cycle_limit)?;
+    }
+
+    info!("building libafl observers, feedbacks, and objective...");
+    let edges_observer = covmap.observer("edges").track_indices();
+    let time_observer = TimeObserver::new("time");
+    let map_feedback = MaxMapFeedback::new(&edges_observer);
+    let mut feedback = feedback_or!(
+        // maximize coverage
+        map_feedback,
+        TimeFeedback::new(&time_observer),
+    );
+    let exit_cause_observer = dtt_executor.exit_cause_observer("exit_cause");
+    let mut objective = feedback_and_fast!(
+        feedback_or_fast!(
+            CrashFeedback::new(),
+            TimeoutFeedback::new(),
+        ),
+        // only keep one objective per distinct exit cause
+        ExitCauseFeedback::new_unique(&exit_cause_observer),
+    );
+
+    info!("building stages...");
+    let mutator = StdScheduledMutator::new(havoc_mutations());
+    // replace input bytes with comparison operands logged by the cmplog plugin
+    let i2s_mutator = StdScheduledMutator::new(tuple_list!(I2SRandReplace::new()));
+    let mut stages = tuple_list!(
+        StdMutationalStage::new(mutator),
+        StdMutationalStage::new(i2s_mutator),
+        AflStatsStage::builder()
+            .map_observer(&edges_observer)
+            .build()?,
+    );
+
+    info!("building libafl state...");
+    let mut state = StdState::new(
+        StdRand::with_seed(config.seed),
+        OnDiskCorpus::<BytesInput>::new(config.queue_dir.clone())?,
+        OnDiskCorpus::new(config.crashes_dir.clone())?,
+        &mut feedback,
+        &mut objective,
+    )?;
+
+    info!("building monitor...");
+    let monitor = MultiMonitor::new(|s| info!("{s}"));
+    let mut manager = SimpleEventManager::new(monitor);
+
+    info!("building scheduler, and fuzzer...");
+    let scheduler = QueueScheduler::new();
+    let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+
+    let mut executor = WithObservers::new(
+        dtt_executor,
+        tuple_list!(edges_observer, time_observer, exit_cause_observer, cmplog_observer),
+    );
+
+    let mut generator = RandBytesGenerator::new(nonzero!(0x10000));
+    match state.generate_initial_inputs(
+        &mut fuzzer, &mut executor, &mut generator, &mut manager, 8)
+    {
+        Err(libafl::Error::ShuttingDown) => { info!("fuzzer stopped by user."); return Ok(()) }
+        Err(err) => {
+            error!("failed to generate initial corpus: {err:?}");
+            return Err(err.into())
+        }
+        _ => {  }
+    }
+
+    match fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut manager) {
+        Err(libafl::Error::ShuttingDown) => { info!("fuzzer stopped by user."); Ok(()) }
+        Err(err) => {
+            error!("fuzz loop failed: {err:?}");
+            Err(err.into())
+        }
+        _ => { Ok(()) }
+    }
+}
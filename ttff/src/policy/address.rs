@@ -51,6 +51,8 @@ pub struct FrameStart { pub pc: Address, pub sp: Address }
 pub enum FrameUpdate {
     Call(FrameStart),
     Return,
+    /// the context was restored, so all frames are gone
+    Reset,
 }
 
 /// a control flow integrity policy to catch tainted PC writes
@@ -78,6 +80,9 @@ impl TaintedAddressPolicy {
                 FrameUpdate::Return => {
                     self.stack.pop_back();
                 }
+                FrameUpdate::Reset => {
+                    self.stack.clear();
+                }
             }
         }
     }